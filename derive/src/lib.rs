@@ -0,0 +1,82 @@
+//! Procedural macros for thespis_remote. Use them through the `derive` feature of thespis_remote,
+//! which re-exports them in `thespis_remote::derive`.
+//!
+//! - `#[derive(Service)]` implements `thespis_remote::Service` or `thespis_remote::StreamService`.
+//! - `#[service_map]` turns an inline module into a service map for a list of types.
+//
+#![ forbid ( unsafe_code                             ) ]
+#![ warn   ( rust_2018_idioms, unused_qualifications ) ]
+
+extern crate proc_macro;
+
+mod service     ;
+mod service_map ;
+
+use proc_macro::TokenStream;
+
+
+/// Implement `thespis_remote::Service` for a message type, or `thespis_remote::StreamService` with
+/// the `stream` flag. The type must implement `Message`, `Serialize` and `Deserialize`.
+///
+/// ```ignore
+/// #[ derive( Serialize, Deserialize, Service ) ]
+/// #[ service( namespace = "myns" ) ]
+/// //
+/// struct Add( i64 );
+///
+/// #[ derive( Serialize, Deserialize, Service ) ]
+/// #[ service( namespace = "myns", stream ) ]
+/// //
+/// struct Count( u32 );
+/// ```
+///
+/// Options of the `service` attribute:
+///
+/// - `namespace = "myns"`: the sid is a hash of "myns::Type", like for `service_map!`. Required
+///   unless `name` is set.
+/// - `name = "other::Name"`: hash this name instead.
+/// - `sid = 0x1234`: use this number as the sid. Set either `name` or `sid`, not both. Not allowed
+///   on generic types.
+/// - `stream`: implement `StreamService`. The `Return` type of the message must be a `Stream`.
+/// - `fallible`: the `Return` type of the message is a `Result` and errors are sent to the caller as
+///   `PeerErr::Application`. See `thespis_remote::Fallible`.
+///
+/// For a generic type the type arguments are hashed with the name, eg. "myns::Echo<alloc::string::String>",
+/// so every instance has it's own sid.
+//
+#[ proc_macro_derive( Service, attributes( service ) ) ]
+//
+pub fn derive_service( input: TokenStream ) -> TokenStream
+{
+	let input = syn::parse_macro_input!( input as syn::DeriveInput );
+
+	service::expand( input ).unwrap_or_else( |e| e.to_compile_error() ).into()
+}
+
+
+/// Turn an inline module into a service map for the listed services. The types are resolved
+/// inside the module, which imports everything from it's parent.
+///
+/// ```ignore
+/// #[ service_map( wire_format = ThesWF, services( Add, Echo<String> ), streams( Count ) ) ]
+/// //
+/// mod remotes {}
+/// ```
+///
+/// This generates in the module:
+///
+/// - `Services`: a `ServiceMap` that only accepts handlers for the listed services.
+/// - `RemoteAddr`: an alias for `thespis_remote::ServiceAddr` with the wire format of the map.
+///
+/// `wire_format` is optional and defaults to `ThesWF`. The services must implement
+/// `thespis_remote::Service` and the streams `thespis_remote::StreamService`.
+//
+#[ proc_macro_attribute ]
+//
+pub fn service_map( args: TokenStream, input: TokenStream ) -> TokenStream
+{
+	let args   = syn::parse_macro_input!( args  as service_map::Args );
+	let module = syn::parse_macro_input!( input as syn::ItemMod     );
+
+	service_map::expand( args, module ).unwrap_or_else( |e| e.to_compile_error() ).into()
+}
@@ -0,0 +1,270 @@
+use
+{
+	proc_macro2 :: { Span, TokenStream                                               } ,
+	quote       :: { quote, quote_spanned                                            } ,
+	syn         :: { DeriveInput, Error, Lit, Meta, NestedMeta, Result, spanned::Spanned } ,
+};
+
+
+// The options of the `service` attribute.
+//
+#[ derive( Default ) ]
+//
+struct Opts
+{
+	namespace: Option<String> ,
+	name     : Option<String> ,
+	sid      : Option<u64>    ,
+
+	// The flags are the span of where they are set, so errors about them point there.
+	//
+	stream   : Option<Span>   ,
+	fallible : Option<Span>   ,
+}
+
+
+impl Opts
+{
+	fn parse( input: &DeriveInput ) -> Result<Self>
+	{
+		let mut opts = Self::default();
+
+		for attr in input.attrs.iter().filter( |a| a.path.is_ident( "service" ) )
+		{
+			let list = match attr.parse_meta()?
+			{
+				Meta::List( list ) => list,
+				other              => return Err( Error::new_spanned( other, "expected #[ service( namespace = \"...\" ) ]" ) ),
+			};
+
+			for nested in list.nested
+			{
+				match nested
+				{
+					NestedMeta::Meta( Meta::Path( path ) ) if path.is_ident( "stream" ) =>
+					{
+						opts.stream = Some( path.span() );
+					}
+
+					NestedMeta::Meta( Meta::Path( path ) ) if path.is_ident( "fallible" ) =>
+					{
+						opts.fallible = Some( path.span() );
+					}
+
+					NestedMeta::Meta( Meta::NameValue( nv ) ) if nv.path.is_ident( "namespace" ) =>
+					{
+						opts.namespace = Some( string( &nv.lit )? );
+					}
+
+					NestedMeta::Meta( Meta::NameValue( nv ) ) if nv.path.is_ident( "name" ) =>
+					{
+						opts.name = Some( string( &nv.lit )? );
+					}
+
+					NestedMeta::Meta( Meta::NameValue( nv ) ) if nv.path.is_ident( "sid" ) =>
+					{
+						opts.sid = match &nv.lit
+						{
+							Lit::Int( int ) => Some( int.base10_parse()? ),
+							other           => return Err( Error::new_spanned( other, "sid must be an integer" ) ),
+						};
+					}
+
+					other => return Err( Error::new_spanned
+					(
+						other,
+						"unknown service option, expected one of: namespace, name, sid, stream, fallible",
+					)),
+				}
+			}
+		}
+
+		if opts.name.is_some() && opts.sid.is_some()
+		{
+			return Err( Error::new_spanned( &input.ident, "set either name or sid on a service, not both" ) );
+		}
+
+		if opts.stream.is_some() && opts.fallible.is_some()
+		{
+			return Err( Error::new_spanned( &input.ident, "streaming services can't be fallible" ) );
+		}
+
+		if opts.sid.is_some() && input.generics.type_params().next().is_some()
+		{
+			return Err( Error::new_spanned( &input.ident, "a generic service can't have a fixed sid, all instances would share it" ) );
+		}
+
+		if opts.namespace.is_none() && opts.name.is_none()
+		{
+			return Err( Error::new_spanned( &input.ident, "missing #[ service( namespace = \"...\" ) ]" ) );
+		}
+
+		Ok( opts )
+	}
+}
+
+
+fn string( lit: &Lit ) -> Result<String>
+{
+	match lit
+	{
+		Lit::Str( s ) => Ok( s.value() ),
+		other         => Err( Error::new_spanned( other, "expected a string" ) ),
+	}
+}
+
+
+
+pub(crate) fn expand( input: DeriveInput ) -> Result<TokenStream>
+{
+	let opts  = Opts::parse( &input )?;
+	let ident = &input.ident;
+
+	let name = match ( &opts.name, &opts.namespace )
+	{
+		( Some(name), _         ) => name.clone(),
+		( None      , Some(ns)  ) => format!( "{}::{}", ns, ident ),
+		( None      , None      ) => unreachable!( "checked in Opts::parse" ),
+	};
+
+	let params: Vec<_> = input.generics.type_params().map( |p| &p.ident ).collect();
+
+	// Statics in a generic function are shared by all instances, so for generic types we hash
+	// the name with the type arguments every time.
+	//
+	let sid = match opts.sid
+	{
+		Some( sid ) => quote!{ ::thespis_remote::ServiceID::from( #sid ) },
+
+		None if !params.is_empty() => quote!
+		{
+			let args = [ #( ::std::any::type_name::<#params>() ),* ].join( ", " );
+
+			::thespis_remote::ServiceID::from_seed( format!( "{}<{}>", #name, args ).as_bytes() )
+		},
+
+		None => quote!
+		{
+			static SID: ::thespis_remote::external_deps::once_cell::sync::Lazy< ::thespis_remote::ServiceID > =
+
+				::thespis_remote::external_deps::once_cell::sync::Lazy::new( ||
+				{
+					::thespis_remote::ServiceID::from_seed( #name.as_bytes() )
+				});
+
+			*SID
+		},
+	};
+
+	let mut generics = input.generics.clone();
+	let (_, ty_generics, _) = input.generics.split_for_impl();
+
+	// Everything about the type is spanned on it, so errors like a missing impl point at the type
+	// rather than at the derive.
+	//
+	let span = ident.span();
+
+	let message   = |s: Span| quote_spanned!{ s=> ::thespis_remote::external_deps::thespis::Message            };
+	let serialize = |s: Span| quote_spanned!{ s=> ::thespis_remote::external_deps::serde::Serialize            };
+	let des_owned = |s: Span| quote_spanned!{ s=> ::thespis_remote::external_deps::serde::de::DeserializeOwned };
+	let stream    = |s: Span| quote_spanned!{ s=> ::thespis_remote::external_deps::futures::Stream             };
+
+	let mut bounds = Vec::new();
+
+	let (m, ser, des) = ( message( span ), serialize( span ), des_owned( span ) );
+
+	bounds.push( quote_spanned!{ span=> #ident #ty_generics: #m + #ser + #des } );
+
+	match opts.stream
+	{
+		Some( s ) =>
+		{
+			let (m, ser, des, st) = ( message( s ), serialize( s ), des_owned( s ), stream( s ) );
+
+			bounds.push( quote_spanned!{ s=> <#ident #ty_generics as #m>::Return: #st } );
+			bounds.push( quote_spanned!{ s=> <<#ident #ty_generics as #m>::Return as #st>::Item: #ser + #des + Send + 'static } );
+		}
+
+		None => bounds.push( quote_spanned!{ span=> <#ident #ty_generics as #m>::Return: #ser + #des } ),
+	}
+
+	if let Some( s ) = opts.fallible
+	{
+		let m = message( s );
+
+		bounds.push( quote_spanned!{ s=> <#ident #ty_generics as #m>::Return: ::thespis_remote::Fallible } );
+	}
+
+	let where_clause = generics.make_where_clause();
+
+	for bound in bounds
+	{
+		where_clause.predicates.push( syn::parse2( bound )? );
+	}
+
+	let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+	let reply = match opts.fallible
+	{
+		Some( s ) =>
+		{
+			let m = message( s );
+
+			quote_spanned!
+			{
+				s=>
+
+				fn reply( resp: <Self as #m>::Return )
+
+					-> Result< ::thespis_remote::Reply<<Self as #m>::Return>, ::thespis_remote::external_deps::serde_cbor::Error >
+				{
+					::thespis_remote::Reply::fallible( resp )
+				}
+			}
+		}
+
+		None => quote!{},
+	};
+
+	Ok( match opts.stream
+	{
+		Some( s ) =>
+		{
+			let (m, st) = ( message( s ), stream( s ) );
+
+			quote_spanned!
+			{
+				span=>
+
+				impl #impl_generics ::thespis_remote::StreamService for #ident #ty_generics #where_clause
+				{
+					type Item = <<Self as #m>::Return as #st>::Item;
+
+					const NAME: &'static str = #name;
+
+					fn sid() -> ::thespis_remote::ServiceID
+					{
+						#sid
+					}
+				}
+			}
+		}
+
+		None => quote_spanned!
+		{
+			span=>
+
+			impl #impl_generics ::thespis_remote::Service for #ident #ty_generics #where_clause
+			{
+				const NAME: &'static str = #name;
+
+				fn sid() -> ::thespis_remote::ServiceID
+				{
+					#sid
+				}
+
+				#reply
+			}
+		},
+	})
+}
@@ -0,0 +1,234 @@
+use
+{
+	proc_macro2 :: { TokenStream                                                     } ,
+	quote       :: { quote, quote_spanned                                            } ,
+	syn         :: { Error, Ident, ItemMod, Result, Token, Type                      } ,
+	syn         :: { parenthesized, parse_quote, punctuated::Punctuated              } ,
+	syn         :: { parse::{ Parse, ParseStream }, spanned::Spanned                 } ,
+};
+
+
+// The arguments of `#[service_map]`.
+//
+pub(crate) struct Args
+{
+	wire_format: Option<Type> ,
+	services   : Vec<Type>    ,
+	streams    : Vec<Type>    ,
+}
+
+
+impl Parse for Args
+{
+	fn parse( input: ParseStream<'_> ) -> Result<Self>
+	{
+		let mut args = Self { wire_format: None, services: Vec::new(), streams: Vec::new() };
+
+		while !input.is_empty()
+		{
+			let key: Ident = input.parse()?;
+
+			if key == "wire_format"
+			{
+				input.parse::<Token![=]>()?;
+				args.wire_format = Some( input.parse()? );
+			}
+
+			else if key == "services" || key == "streams"
+			{
+				let content;
+				parenthesized!( content in input );
+
+				let list = Punctuated::<Type, Token![,]>::parse_terminated( &content )?;
+
+				if key == "services" { args.services.extend( list ) }
+				else                 { args.streams .extend( list ) }
+			}
+
+			else
+			{
+				return Err( Error::new( key.span(), "unknown service_map option, expected one of: wire_format, services, streams" ) );
+			}
+
+			if !input.is_empty()
+			{
+				input.parse::<Token![,]>()?;
+			}
+		}
+
+		Ok( args )
+	}
+}
+
+
+
+pub(crate) fn expand( args: Args, mut module: ItemMod ) -> Result<TokenStream>
+{
+	let wf = args.wire_format.unwrap_or_else( || parse_quote!{ ::thespis_remote::ThesWF } );
+
+	let message   = quote!{ ::thespis_remote::external_deps::thespis::Message            };
+	let box_addr  = quote!{ ::thespis_remote::external_deps::thespis::BoxAddress         };
+	let thes_err  = quote!{ ::thespis_remote::external_deps::thespis_impl::ThesErr       };
+	let serialize = quote!{ ::thespis_remote::external_deps::serde::Serialize            };
+	let des_owned = quote!{ ::thespis_remote::external_deps::serde::de::DeserializeOwned };
+	let stream    = quote!{ ::thespis_remote::external_deps::futures::Stream             };
+	let future    = quote!{ ::thespis_remote::external_deps::futures::future::BoxFuture  };
+
+	// Spanned on the type so an error about a missing impl points at the type in the list.
+	//
+	let markers = args.services.iter().map( |ty| quote_spanned!{ ty.span() => impl MapService       for #ty {} } )
+		.chain(   args.streams .iter().map( |ty| quote_spanned!{ ty.span() => impl MapStreamService for #ty {} } ) );
+
+	let declares = args.services.iter().map( |ty| quote_spanned!{ ty.span() => inner.declare       ::<#ty>(); } )
+		.chain(    args.streams .iter().map( |ty| quote_spanned!{ ty.span() => inner.declare_stream::<#ty>(); } ) );
+
+	let mut items: Vec<syn::Item> = markers.map( |m| syn::parse2( m ) ).collect::<Result<_>>()?;
+
+	items.extend( vec!
+	[
+		parse_quote!
+		{
+			#[ allow( unused_imports ) ]
+			use super::*;
+		},
+
+		parse_quote!
+		{
+			/// Implemented by the services of this service map. [`Services::register_handler`] only accepts these.
+			//
+			pub trait MapService {}
+		},
+
+		parse_quote!
+		{
+			/// Implemented by the streaming services of this service map. [`Services::register_stream_handler`]
+			/// only accepts these.
+			//
+			pub trait MapStreamService {}
+		},
+
+		parse_quote!
+		{
+			/// Service map generated by `#[service_map]`. It only accepts handlers for the listed services.
+			/// See [`thespis_remote::ServiceRegistry`].
+			//
+			#[ derive( Clone ) ]
+			//
+			pub struct Services
+			{
+				inner: ::thespis_remote::ServiceRegistry< #wf >,
+			}
+		},
+
+		parse_quote!
+		{
+			impl Services
+			{
+				/// Create a service map without handlers.
+				//
+				pub fn new() -> Self
+				{
+					let mut inner = ::thespis_remote::ServiceRegistry::new();
+
+					#( #declares )*
+
+					Self { inner }
+				}
+
+
+				/// Register a handler for a service of this map. Calling this method twice for the same
+				/// type will override the first handler.
+				//
+				pub fn register_handler<S>( &mut self, handler: #box_addr<S, #thes_err> )
+
+					where  S                     : MapService + ::thespis_remote::Service + Send + 'static,
+					      <S as #message>::Return: #serialize + #des_owned + Send,
+				{
+					self.inner.register_handler::<S>( handler );
+				}
+
+
+				/// Register a handler for a streaming service of this map. Calling this method twice for the
+				/// same type will override the first handler.
+				//
+				pub fn register_stream_handler<S>( &mut self, handler: #box_addr<S, #thes_err> )
+
+					where  S                     : MapStreamService + ::thespis_remote::StreamService + Send + 'static,
+					      <S as #message>::Return: #stream< Item = <S as ::thespis_remote::StreamService>::Item > + Send + 'static,
+				{
+					self.inner.register_stream_handler::<S>( handler );
+				}
+			}
+		},
+
+		parse_quote!
+		{
+			impl Default for Services
+			{
+				fn default() -> Self
+				{
+					Self::new()
+				}
+			}
+		},
+
+		parse_quote!
+		{
+			impl ::std::fmt::Debug for Services
+			{
+				fn fmt( &self, f: &mut ::std::fmt::Formatter<'_> ) -> ::std::fmt::Result
+				{
+					::std::fmt::Debug::fmt( &self.inner, f )
+				}
+			}
+		},
+
+		parse_quote!
+		{
+			impl ::thespis_remote::ServiceMap< #wf > for Services
+			{
+				fn send_service( &self, msg: #wf, ctx: ::thespis_remote::PeerErrCtx )
+
+					-> Result< #future<'static, Result< ::thespis_remote::Response< #wf >, ::thespis_remote::PeerErr >>, ::thespis_remote::PeerErr >
+				{
+					::thespis_remote::ServiceMap::send_service( &self.inner, msg, ctx )
+				}
+
+
+				fn call_service( &self, msg: #wf, ctx: ::thespis_remote::PeerErrCtx )
+
+					-> Result< #future<'static, Result< ::thespis_remote::Response< #wf >, ::thespis_remote::PeerErr >>, ::thespis_remote::PeerErr >
+				{
+					::thespis_remote::ServiceMap::call_service( &self.inner, msg, ctx )
+				}
+
+
+				fn services( &self ) -> Box< dyn Iterator< Item = &::thespis_remote::ServiceID > + '_ >
+				{
+					::thespis_remote::ServiceMap::services( &self.inner )
+				}
+			}
+		},
+
+		parse_quote!
+		{
+			/// Call and send to the services of this map on a remote. See [`thespis_remote::ServiceAddr`].
+			//
+			pub type RemoteAddr = ::thespis_remote::ServiceAddr< #wf >;
+		},
+	]);
+
+
+	match &mut module.content
+	{
+		Some( (_, content) ) => content.extend( items ),
+
+		None => return Err( Error::new_spanned
+		(
+			&module,
+			"#[service_map] needs an inline module: `mod name {}`",
+		)),
+	}
+
+	Ok( quote!{ #module } )
+}
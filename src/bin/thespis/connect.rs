@@ -0,0 +1,96 @@
+use
+{
+	crate           :: { DynResult                                   } ,
+	async_executors :: { AsyncStd                                    } ,
+	async_std       :: { net::TcpStream                              } ,
+	std             :: { path::PathBuf, str::FromStr, time::Duration } ,
+	structopt       :: { StructOpt                                   } ,
+	thespis_impl    :: { Addr                                        } ,
+	thespis_remote  :: { Peer, PeerBuilder                           } ,
+};
+
+
+/// Where and how to connect to a peer.
+//
+#[ derive( Debug, StructOpt ) ]
+//
+pub(crate) struct Connect
+{
+	/// The address of the peer, "host:port" for TCP or "unix:/path" for a Unix socket.
+	//
+	target: Target,
+
+	/// The max_size of the remote, in bytes.
+	//
+	#[ structopt( long, default_value = "1048576" ) ]
+	//
+	max_size: usize,
+
+	/// How long to wait for a response, in seconds.
+	//
+	#[ structopt( long, default_value = "60" ) ]
+	//
+	timeout: u64,
+}
+
+
+impl Connect
+{
+	/// Connect and start a peer on the connection.
+	//
+	pub(crate) async fn connect( &self ) -> DynResult< Addr<Peer> >
+	{
+		let builder = PeerBuilder::new()
+
+			.name    ( "thespis cli"                        )
+			.max_size( self.max_size                        )
+			.timeout ( Duration::from_secs( self.timeout ) )
+		;
+
+		let peer = match &self.target
+		{
+			Target::Tcp( addr ) => builder.build_async_read( TcpStream::connect( addr ).await?, AsyncStd )?,
+
+			#[ cfg( unix ) ]
+			//
+			Target::Unix( path ) =>
+			{
+				let socket = async_std::os::unix::net::UnixStream::connect( path ).await?;
+
+				builder.build_async_read( socket, AsyncStd )?
+			}
+
+			#[ cfg( not( unix ) ) ]
+			//
+			Target::Unix(_) => return Err( "Unix sockets are not supported on this platform.".into() ),
+		};
+
+		Ok( peer )
+	}
+}
+
+
+
+#[ derive( Debug ) ]
+//
+enum Target
+{
+	Tcp ( String  ),
+	Unix( PathBuf ),
+}
+
+
+impl FromStr for Target
+{
+	type Err = String;
+
+	fn from_str( s: &str ) -> Result<Self, Self::Err>
+	{
+		match s.strip_prefix( "unix:" )
+		{
+			Some( path ) if path.is_empty() => Err( "unix: needs the path of the socket.".to_string() ),
+			Some( path )                    => Ok ( Target::Unix( path.into()    ) ),
+			None                            => Ok ( Target::Tcp ( s.to_string() ) ),
+		}
+	}
+}
@@ -0,0 +1,50 @@
+use
+{
+	crate          :: { DynResult, payload } ,
+	std            :: { io                 } ,
+	thespis_remote :: { *                  } ,
+};
+
+
+/// Print the frames of a capture, one line per frame followed by it's payload.
+///
+/// Service ids show the name of the service if it's registered. The payload of a connection
+/// error is shown as the error.
+//
+pub(crate) fn print( input: impl io::Read, max_size: usize ) -> DynResult<()>
+{
+	for frame in CaptureReader::new( input, max_size )?
+	{
+		let CapturedFrame{ direction, time, peer_id, frame } = frame?;
+
+		let arrow = match direction
+		{
+			Direction::Incoming => "<-",
+			Direction::Outgoing => "->",
+		};
+
+		println!
+		(
+			"{:>12.6}s  peer {}  {}  {:?}  sid: {}  cid: {}",
+			time.as_secs_f64(), peer_id, arrow, frame.kind(), frame.sid(), frame.cid(),
+		);
+
+		let msg = match frame.kind()
+		{
+			WireType::ConnectionError => match serde_cbor::from_slice::<ConnectionError>( frame.msg() )
+			{
+				Ok ( err ) => format!( "{:?}: {}", err, err ),
+				Err( _   ) => payload::pretty( frame.msg() ),
+			}
+
+			_ => payload::pretty( frame.msg() ),
+		};
+
+		for line in msg.lines()
+		{
+			println!( "    {}", line );
+		}
+	}
+
+	Ok(())
+}
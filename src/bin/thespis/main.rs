@@ -0,0 +1,262 @@
+//! Command line tool to inspect and drive thespis_remote traffic. Build it with the `cli` feature.
+//!
+//! ```text
+//! thespis services 127.0.0.1:8998
+//! thespis call     127.0.0.1:8998     myapp::Show null
+//! thespis send     unix:/run/app.sock myapp::Add  5
+//! thespis send     unix:/run/app.sock myapp::Add  '5' --diag
+//! thespis decode   capture.bin --service myapp::Add --service myapp::Show
+//! ```
+//!
+//! Services are given by name, "namespace::Type" as in `service_map!`, or by their number. Payloads are
+//! JSON by default, or CBOR diagnostic notation with `--diag`. Responses and the payloads of decoded frames
+//! are printed in CBOR diagnostic notation.
+//
+mod connect ;
+mod decode  ;
+mod payload ;
+
+use
+{
+	connect        :: { Connect                                     } ,
+	std            :: { error::Error, fs::File, path::PathBuf, process } ,
+	std            :: { io::{ self, Write, BufReader }               } ,
+	structopt      :: { StructOpt                                   } ,
+	thespis        :: { Address                                     } ,
+	thespis_impl   :: { Addr                                        } ,
+	thespis_remote :: { *                                           } ,
+};
+
+
+pub(crate) type DynResult<T> = Result< T, Box<dyn Error> >;
+
+
+/// Inspect, call and decode thespis_remote traffic.
+//
+#[ derive( Debug, StructOpt ) ]
+//
+#[ structopt( name = "thespis" ) ]
+//
+enum Opt
+{
+	/// List the services a peer exposes. Uses the reflection service of the peer.
+	//
+	Services
+	{
+		#[ structopt( flatten ) ]
+		//
+		conn: Connect,
+	},
+
+	/// Send a message to a service. Doesn't wait for a response.
+	//
+	Send
+	{
+		#[ structopt( flatten ) ]
+		//
+		conn: Connect,
+
+		/// The service, "namespace::Type" or the service id as a number.
+		//
+		#[ structopt( parse( from_str = parse_sid ) ) ]
+		//
+		service: ServiceID,
+
+		/// The message, JSON unless --diag is given.
+		//
+		payload: String,
+
+		/// The payload is in CBOR diagnostic notation.
+		//
+		#[ structopt( long ) ]
+		//
+		diag: bool,
+	},
+
+	/// Call a service and print the response.
+	//
+	Call
+	{
+		#[ structopt( flatten ) ]
+		//
+		conn: Connect,
+
+		/// The service, "namespace::Type" or the service id as a number.
+		//
+		#[ structopt( parse( from_str = parse_sid ) ) ]
+		//
+		service: ServiceID,
+
+		/// The message, JSON unless --diag is given.
+		//
+		payload: String,
+
+		/// The payload is in CBOR diagnostic notation.
+		//
+		#[ structopt( long ) ]
+		//
+		diag: bool,
+	},
+
+	/// Print the frames of a capture file.
+	//
+	Decode
+	{
+		/// The capture file. Reads stdin when not given or "-".
+		//
+		file: Option<PathBuf>,
+
+		/// The name of a service, "namespace::Type", to show instead of it's id. Can be repeated.
+		//
+		#[ structopt( long = "service", parse( from_str = parse_sid ) ) ]
+		//
+		services: Vec<ServiceID>,
+
+		/// Refuse frames bigger than this, in bytes.
+		//
+		#[ structopt( long, default_value = "1048576" ) ]
+		//
+		max_size: usize,
+	},
+}
+
+
+
+fn main()
+{
+	let opt = Opt::from_args();
+
+	if let Err( err ) = async_std::task::block_on( run( opt ) )
+	{
+		eprintln!( "error: {}", err );
+		process::exit( 1 );
+	}
+}
+
+
+
+async fn run( opt: Opt ) -> DynResult<()>
+{
+	match opt
+	{
+		Opt::Services{ conn } =>
+		{
+			let mut peer = conn.connect().await?;
+			let services = ServiceInfo::list_remote( &mut peer ).await;
+
+			close( peer ).await;
+
+			for service in services?
+			{
+				let name = service.name.as_deref().unwrap_or( "<unnamed>" );
+
+				match service.types
+				{
+					Some( types ) => println!( "{}  {}  ({} -> {})", service.sid, name, types.message, types.returns ),
+					None          => println!( "{}  {}"            , service.sid, name                                ),
+				}
+			}
+		}
+
+
+		Opt::Send{ conn, service, payload, diag } =>
+		{
+			let wf       = frame( service, &payload, diag )?;
+			let mut peer = conn.connect().await?;
+
+			// The peer sends out what is queued before closing the connection.
+			//
+			let res = peer.call( wf ).await;
+
+			close( peer ).await;
+
+			res??;
+		}
+
+
+		Opt::Call{ conn, service, payload, diag } =>
+		{
+			let wf       = frame( service, &payload, diag )?;
+			let mut peer = conn.connect().await?;
+
+			let res = match peer.call( Call::new( wf ) ).await
+			{
+				Ok ( Ok(rx) ) => rx.await,
+				Ok ( Err(e) ) => { close( peer ).await; return Err( e.into() ) }
+				Err(     e  ) => { close( peer ).await; return Err( e.into() ) }
+			};
+
+			close( peer ).await;
+
+			match res
+			{
+				Ok ( Ok ( resp ) ) => println!( "{}", payload::pretty( resp.msg() ) ),
+				Ok ( Err( err  ) ) => return Err( format!( "remote returned ConnectionError::{:?}: {}", err, err ).into() ),
+				Err( _           ) => return Err( "the connection closed before the response arrived".into() ),
+			}
+		}
+
+
+		Opt::Decode{ file, max_size, .. } =>
+		{
+			// The names of the services are registered by parse_sid.
+			//
+			match file
+			{
+				Some( path ) if path.as_os_str() != "-" => decode::print( BufReader::new( File::open( path )? ), max_size )?,
+				_                                       => decode::print( io::stdin()                         , max_size )?,
+			}
+		}
+	}
+
+	Ok(())
+}
+
+
+
+// A number is taken as the service id, anything else as the name of the service. Names are
+// registered, so they show up when the service id is printed.
+//
+fn parse_sid( s: &str ) -> ServiceID
+{
+	let num = match s.strip_prefix( "0x" )
+	{
+		Some( hex ) => u64::from_str_radix( hex, 16 ).ok(),
+		None        => s.parse().ok(),
+	};
+
+	if let Some( num ) = num
+	{
+		return ServiceID::from( num );
+	}
+
+	let sid = ServiceID::from_seed( s.as_bytes() );
+
+	ServiceID::register_service( sid, Box::leak( s.to_string().into_boxed_str() ) );
+
+	sid
+}
+
+
+
+// Build the frame for a send or a call.
+//
+fn frame( sid: ServiceID, payload: &str, diag: bool ) -> DynResult<ThesWF>
+{
+	let msg    = payload::encode( payload, diag )?;
+	let mut wf = ThesWF::with_capacity( msg.len() );
+
+	wf.set_sid( sid );
+	wf.write_all( &msg )?;
+
+	Ok( wf )
+}
+
+
+
+async fn close( mut peer: Addr<Peer> )
+{
+	// If the remote closed the connection, the peer is already gone.
+	//
+	let _ = peer.send( CloseConnection{ remote: false, reason: "thespis cli is done.".to_string() } ).await;
+}
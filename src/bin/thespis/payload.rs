@@ -0,0 +1,33 @@
+use crate::DynResult;
+
+
+/// Turn a payload from the command line into CBOR. It's either JSON or CBOR diagnostic notation.
+//
+pub(crate) fn encode( payload: &str, diag: bool ) -> DynResult< Vec<u8> >
+{
+	if diag
+	{
+		return Ok( cbor_diag::parse_diag( payload )?.to_bytes() );
+	}
+
+	let value: serde_json::Value = serde_json::from_str( payload )?;
+
+	Ok( serde_cbor::to_vec( &value )? )
+}
+
+
+/// Show a CBOR payload in diagnostic notation. Payloads that aren't CBOR are shown as hex.
+//
+pub(crate) fn pretty( bytes: &[u8] ) -> String
+{
+	if bytes.is_empty()
+	{
+		return "<empty>".to_string();
+	}
+
+	match cbor_diag::parse_bytes( bytes )
+	{
+		Ok ( item ) => item.to_diag_pretty(),
+		Err( _    ) => format!( "<not cbor> {:02x?}", bytes ),
+	}
+}
@@ -0,0 +1,107 @@
+//! Record the frames of a connection to a capture file and replay them later.
+//!
+//! ## Capture file format
+//!
+//! All integers are little endian. A capture starts with a header:
+//!
+//! ```text
+//! magic   : 8 bytes  "THESCAP\0"
+//! version : u16      currently 1
+//! start   : u64      wall clock time at which the recording started, in microseconds since the unix epoch
+//! ```
+//!
+//! The header is followed by one record per frame, until the end of the file:
+//!
+//! ```text
+//! direction: u8       0 for a frame received from the remote, 1 for a frame sent to the remote
+//! time     : u64      microseconds since the start of the recording
+//! peer_id  : u64      the id of the address of the peer that received or sent the frame
+//! length   : u64      the length of the frame in bytes
+//! frame    : length   the frame exactly as it goes over the wire, see [`ThesWF`](crate::ThesWF)
+//! ```
+//!
+//! Timestamps are 0 on targets that have no clock, like wasm.
+//
+    mod reader   ;
+    mod recorder ;
+    mod replay   ;
+
+pub use reader   :: { CaptureReader } ;
+pub use recorder :: { Recorder      } ;
+pub use replay   :: { Replay        } ;
+
+use crate::{ import::*, ThesWF };
+
+
+const MAGIC  : &[u8; 8] = b"THESCAP\0";
+const VERSION: u16      = 1;
+
+
+/// Whether a frame was received or sent by the peer that recorded it.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, Hash ) ]
+//
+pub enum Direction
+{
+	/// Received from the remote.
+	//
+	Incoming,
+
+	/// Sent to the remote.
+	//
+	Outgoing,
+}
+
+
+impl Direction
+{
+	fn to_byte( self ) -> u8
+	{
+		match self
+		{
+			Direction::Incoming => 0,
+			Direction::Outgoing => 1,
+		}
+	}
+
+
+	fn from_byte( byte: u8 ) -> io::Result<Self>
+	{
+		match byte
+		{
+			0 => Ok( Direction::Incoming ),
+			1 => Ok( Direction::Outgoing ),
+			_ => Err( invalid( "Capture: unknown direction." ) ),
+		}
+	}
+}
+
+
+/// A frame read from a capture file.
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct CapturedFrame
+{
+	/// Whether the frame was received or sent.
+	//
+	pub direction: Direction,
+
+	/// When the frame was recorded, since the start of the recording.
+	//
+	pub time: Duration,
+
+	/// The id of the address of the peer that recorded the frame.
+	//
+	pub peer_id: usize,
+
+	/// The frame.
+	//
+	pub frame: ThesWF,
+}
+
+
+fn invalid( msg: &str ) -> io::Error
+{
+	io::Error::new( io::ErrorKind::InvalidData, msg )
+}
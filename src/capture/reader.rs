@@ -0,0 +1,106 @@
+use crate::{ import::*, * };
+use super::{ MAGIC, VERSION, Direction, CapturedFrame, invalid };
+use byteorder::{ ReadBytesExt, LittleEndian };
+
+
+/// Reads the frames of a capture file written by a [`Recorder`]. It's an iterator over the records.
+///
+/// Frames bigger than `max_size` are refused, so a corrupt length can't make us allocate arbitrary
+/// amounts of memory.
+//
+#[ derive( Debug ) ]
+//
+pub struct CaptureReader<R>
+{
+	input   : R     ,
+	start   : u64   ,
+	max_size: usize ,
+	done    : bool  ,
+}
+
+
+impl<R: io::Read> CaptureReader<R>
+{
+	/// Read the header of the capture.
+	//
+	pub fn new( mut input: R, max_size: usize ) -> io::Result<Self>
+	{
+		let mut magic = [0u8; 8];
+
+		input.read_exact( &mut magic )?;
+
+		if &magic != MAGIC
+		{
+			return Err( invalid( "Capture: this is not a capture file." ) );
+		}
+
+		if input.read_u16::<LittleEndian>()? != VERSION
+		{
+			return Err( invalid( "Capture: unsupported version." ) );
+		}
+
+		let start = input.read_u64::<LittleEndian>()?;
+
+		Ok( Self{ input, start, max_size, done: false } )
+	}
+
+
+	/// When the recording started, in microseconds since the unix epoch. 0 when it was recorded without a clock.
+	//
+	pub fn start( &self ) -> u64
+	{
+		self.start
+	}
+
+
+	fn read_frame( &mut self ) -> io::Result< Option<CapturedFrame> >
+	{
+		// The end of the file is only valid between records.
+		//
+		let direction = match self.input.read_u8()
+		{
+			Ok ( byte ) => Direction::from_byte( byte )?,
+			Err( err  ) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok( None ),
+			Err( err  ) => return Err( err ),
+		};
+
+		let time    = Duration::from_micros( self.input.read_u64::<LittleEndian>()? );
+		let peer_id = self.input.read_u64::<LittleEndian>()? as usize;
+		let len     = self.input.read_u64::<LittleEndian>()? as usize;
+
+		if len > self.max_size
+		{
+			return Err( invalid( "Capture: frame bigger than max_size." ) );
+		}
+
+		let mut data = vec![ 0u8; len ];
+
+		self.input.read_exact( &mut data )?;
+
+		let frame = ThesWF::try_from( data ).map_err( |_| invalid( "Capture: invalid frame." ) )?;
+
+		Ok( Some( CapturedFrame{ direction, time, peer_id, frame } ) )
+	}
+}
+
+
+impl<R: io::Read> Iterator for CaptureReader<R>
+{
+	type Item = io::Result<CapturedFrame>;
+
+	fn next( &mut self ) -> Option<Self::Item>
+	{
+		if self.done { return None }
+
+		let res = self.read_frame().transpose();
+
+		// Stop after the end of the file or an error, we can't find the next record.
+		//
+		if !matches!( res, Some( Ok(_) ) )
+		{
+			self.done = true;
+		}
+
+		res
+	}
+}
@@ -0,0 +1,165 @@
+use crate::{ import::*, * };
+use super::{ MAGIC, VERSION, Direction };
+use byteorder::{ WriteBytesExt, LittleEndian };
+use std::time::{ Instant, SystemTime, UNIX_EPOCH };
+
+
+/// Records frames to a capture file. See the [`capture`](crate::capture) module for the format.
+///
+/// Wrap the transport of a peer with [`Recorder::wrap`], or build the peer with
+/// [`PeerBuilder::build_recorded`]. A recorder can be cloned to record several peers into the same file.
+/// The records of each peer can be told apart by their peer id.
+///
+/// Every record is written and flushed right away, so a capture is complete up to the last frame
+/// even when the process crashes. It's meant for debugging, as writing blocks the task that
+/// reads or writes the connection. Errors while writing the capture are logged and don't affect the
+/// connection.
+//
+#[ derive( Clone ) ]
+//
+pub struct Recorder
+{
+	inner: Arc<Mutex< Inner >>,
+}
+
+
+struct Inner
+{
+	out  : Box< dyn io::Write + Send > ,
+	start: Option<Instant>             ,
+}
+
+
+
+impl Recorder
+{
+	/// Create a recorder that writes to `out`. This writes the header of the capture.
+	//
+	pub fn new( mut out: impl io::Write + Send + 'static ) -> io::Result<Self>
+	{
+		out.write_all( MAGIC )?;
+		out.write_u16::<LittleEndian>( VERSION )?;
+		out.write_u64::<LittleEndian>( unix_micros() )?;
+		out.flush()?;
+
+		let inner = Inner { out: Box::new( out ), start: now() };
+
+		Ok( Self{ inner: Arc::new( Mutex::new( inner ) ) } )
+	}
+
+
+	/// Write one record.
+	//
+	pub fn record( &self, direction: Direction, peer_id: usize, frame: &ThesWF ) -> io::Result<()>
+	{
+		let mut inner = self.inner.lock();
+
+		let time = inner.start.map( |s| s.elapsed().as_micros() as u64 ).unwrap_or( 0 );
+		let buf  = frame.as_buf();
+
+		inner.out.write_u8                  ( direction.to_byte() )?;
+		inner.out.write_u64::<LittleEndian>( time                )?;
+		inner.out.write_u64::<LittleEndian>( peer_id as u64      )?;
+		inner.out.write_u64::<LittleEndian>( buf.len() as u64    )?;
+		inner.out.write_all( buf )?;
+		inner.out.flush()
+	}
+
+
+	/// Record all frames that pass through `incoming` and `outgoing`, the transport of the peer with `peer_id`.
+	//
+	pub fn wrap
+	(
+		&self                            ,
+		peer_id : usize                  ,
+		incoming: impl BoundsIn <ThesWF> ,
+		outgoing: impl BoundsOut<ThesWF> ,
+	)
+		-> ( impl BoundsIn<ThesWF>, impl BoundsOut<ThesWF> )
+
+	{
+		let rec_in  = self.clone();
+		let rec_out = self.clone();
+
+		let incoming = incoming.inspect( move |frame|
+		{
+			if let Ok( frame ) = frame
+			{
+				rec_in.log( Direction::Incoming, peer_id, frame );
+			}
+		});
+
+		let outgoing = outgoing.with( move |frame: ThesWF|
+		{
+			rec_out.log( Direction::Outgoing, peer_id, &frame );
+
+			futures::future::ready( Ok::<_, WireErr>( frame ) )
+		});
+
+		(incoming, outgoing)
+	}
+
+
+	fn log( &self, direction: Direction, peer_id: usize, frame: &ThesWF )
+	{
+		if let Err( err ) = self.record( direction, peer_id, frame )
+		{
+			warn!( "Recorder: failed to write to the capture: {}", err );
+		}
+	}
+}
+
+
+impl fmt::Debug for Recorder
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "Recorder" ).finish()
+	}
+}
+
+
+
+impl PeerBuilder<ThesWF>
+{
+	/// Build the peer and record all it's frames with `recorder`. See [`PeerBuilder::build`].
+	//
+	pub fn build_recorded
+	(
+		self                                    ,
+		incoming: impl BoundsIn <ThesWF>        ,
+		outgoing: impl BoundsOut<ThesWF>        ,
+		recorder: &Recorder                     ,
+		exec    : impl PeerExec<ThesWF> + Spawn ,
+	)
+		-> Result< Addr<Peer>, PeerErr >
+
+	{
+		self.build_with( exec, |addr| recorder.wrap( addr.id(), incoming, outgoing ) )
+	}
+}
+
+
+
+// Instant isn't available on wasm.
+//
+fn now() -> Option<Instant>
+{
+	#[ cfg(not( target_arch = "wasm32" )) ] { Some( Instant::now() ) }
+	#[ cfg(     target_arch = "wasm32"  ) ] { None                   }
+}
+
+
+fn unix_micros() -> u64
+{
+	#[ cfg(not( target_arch = "wasm32" )) ]
+	{
+		SystemTime::now()
+
+			.duration_since( UNIX_EPOCH )
+			.map( |d| d.as_micros() as u64 )
+			.unwrap_or( 0 )
+	}
+
+	#[ cfg( target_arch = "wasm32" ) ] { 0 }
+}
@@ -0,0 +1,130 @@
+use crate::{ import::*, *, peer::Response };
+use super::{ Direction, CapturedFrame };
+
+
+/// Feed the frames of a capture back into a [`Peer`] or a [`ServiceMap`] to reproduce what happened.
+///
+/// Only the incoming frames are replayed, in the order they were recorded. Timestamps are ignored, so
+/// the replay doesn't depend on timing. The outgoing frames of the capture are what the peer answered at
+/// the time, so you can compare them with what it answers now.
+///
+/// When a capture holds several peers, select one with [`Replay::peer`].
+//
+#[ derive( Debug, Clone, Default ) ]
+//
+pub struct Replay
+{
+	frames: Vec<CapturedFrame>,
+}
+
+
+impl Replay
+{
+	/// Read a whole capture. See [`CaptureReader`] for `max_size`.
+	//
+	pub fn read( input: impl io::Read, max_size: usize ) -> io::Result<Self>
+	{
+		let frames = CaptureReader::new( input, max_size )?.collect::< io::Result<_> >()?;
+
+		Ok( Self{ frames } )
+	}
+
+
+	/// All the frames of the capture.
+	//
+	pub fn frames( &self ) -> &[CapturedFrame]
+	{
+		&self.frames
+	}
+
+
+	/// Only keep the frames of the peer with `peer_id`.
+	//
+	pub fn peer( mut self, peer_id: usize ) -> Self
+	{
+		self.frames.retain( |f| f.peer_id == peer_id );
+		self
+	}
+
+
+	/// The frames that were received, in order.
+	//
+	pub fn incoming( &self ) -> impl Iterator< Item = &ThesWF > + '_
+	{
+		self.by_direction( Direction::Incoming )
+	}
+
+
+	/// The frames that were sent, in order.
+	//
+	pub fn outgoing( &self ) -> impl Iterator< Item = &ThesWF > + '_
+	{
+		self.by_direction( Direction::Outgoing )
+	}
+
+
+	fn by_direction( &self, direction: Direction ) -> impl Iterator< Item = &ThesWF > + '_
+	{
+		self.frames.iter().filter( move |f| f.direction == direction ).map( |f| &f.frame )
+	}
+
+
+	/// Build a peer with `builder` and feed it the incoming frames. Returns the address of the peer and
+	/// the frames it sends.
+	///
+	/// The connection stays open after the last frame, so the peer can finish processing. Send it
+	/// `CloseConnection` when you are done.
+	//
+	pub fn into_peer( self, builder: PeerBuilder, exec: impl PeerExec + Spawn )
+
+		-> Result< (Addr<Peer>, mpsc::UnboundedReceiver<ThesWF>), PeerErr >
+
+	{
+		let incoming: Vec<_> = self.incoming().cloned().map( Ok ).collect();
+
+		let (tx, rx) = mpsc::unbounded();
+
+		let incoming = futures::stream::iter( incoming ).chain( futures::stream::pending() );
+		let outgoing = tx.sink_map_err( |_| WireErr::Io{ kind: io::ErrorKind::BrokenPipe } );
+
+		let peer = builder.build( incoming, outgoing, exec )?;
+
+		Ok( (peer, rx) )
+	}
+
+
+	/// Deliver the incoming sends and calls to `sm`, one at a time, and return the result of each in order.
+	/// Other frames, like responses to calls the peer made, are skipped.
+	//
+	pub async fn into_service_map( self, sm: &dyn ServiceMap ) -> Vec< Result<Response<ThesWF>, PeerErr> >
+	{
+		let mut results = Vec::new();
+
+		for frame in self.incoming().cloned()
+		{
+			let ctx = PeerErrCtx::default()
+
+				.sid    ( frame.sid()                  )
+				.cid    ( frame.cid()                  )
+				.context( Some( "Replay".to_string() ) )
+			;
+
+			let task = match frame.kind()
+			{
+				WireType::IncomingSend => sm.send_service( frame, ctx ),
+				WireType::IncomingCall => sm.call_service( frame, ctx ),
+				_                      => continue,
+			};
+
+			let res = match task
+			{
+				Ok ( task ) => task.await,
+				Err( err  ) => Err( err ),
+			};
+
+			results.push( res );
+		}
+
+		results
+	}
+}
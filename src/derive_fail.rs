@@ -0,0 +1,166 @@
+//! Compile fail tests for `#[derive(Service)]` and `#[service_map]`. They are doc tests, since those
+//! can check that code doesn't compile.
+//!
+//! The baseline, which compiles:
+//!
+//! ```
+//! use thespis_remote::{ derive::{ Service, service_map }, external_deps::thespis::Message };
+//! use serde::{ Serialize, Deserialize };
+//!
+//! #[ derive( Serialize, Deserialize, Service ) ]
+//! #[ service( namespace = "fail" ) ]
+//! //
+//! struct Add( i64 );
+//!
+//! impl Message for Add { type Return = (); }
+//!
+//! #[ service_map( services( Add ) ) ]
+//! //
+//! mod remotes {}
+//!
+//! fn main() {}
+//! ```
+//!
+//! Setting both name and sid:
+//!
+//! ```compile_fail
+//! use thespis_remote::{ derive::Service, external_deps::thespis::Message };
+//! use serde::{ Serialize, Deserialize };
+//!
+//! #[ derive( Serialize, Deserialize, Service ) ]
+//! #[ service( name = "fail::Add", sid = 5 ) ]
+//! //
+//! struct Add( i64 );
+//!
+//! impl Message for Add { type Return = (); }
+//!
+//! fn main() {}
+//! ```
+//!
+//! A fixed sid on a generic type:
+//!
+//! ```compile_fail
+//! use thespis_remote::{ derive::Service, external_deps::thespis::Message };
+//! use serde::{ Serialize, Deserialize };
+//!
+//! #[ derive( Serialize, Deserialize, Service ) ]
+//! #[ service( namespace = "fail", sid = 5 ) ]
+//! //
+//! struct Echo<T>( T );
+//!
+//! impl Message for Echo<u8> { type Return = u8; }
+//!
+//! fn main() {}
+//! ```
+//!
+//! Without namespace:
+//!
+//! ```compile_fail
+//! use thespis_remote::{ derive::Service, external_deps::thespis::Message };
+//! use serde::{ Serialize, Deserialize };
+//!
+//! #[ derive( Serialize, Deserialize, Service ) ]
+//! //
+//! struct Add( i64 );
+//!
+//! impl Message for Add { type Return = (); }
+//!
+//! fn main() {}
+//! ```
+//!
+//! An unknown option:
+//!
+//! ```compile_fail
+//! use thespis_remote::{ derive::Service, external_deps::thespis::Message };
+//! use serde::{ Serialize, Deserialize };
+//!
+//! #[ derive( Serialize, Deserialize, Service ) ]
+//! #[ service( namespace = "fail", nope ) ]
+//! //
+//! struct Add( i64 );
+//!
+//! impl Message for Add { type Return = (); }
+//!
+//! fn main() {}
+//! ```
+//!
+//! A fallible streaming service:
+//!
+//! ```compile_fail
+//! use thespis_remote::{ derive::Service, external_deps::{ thespis::Message, futures::stream::Empty } };
+//! use serde::{ Serialize, Deserialize };
+//!
+//! #[ derive( Serialize, Deserialize, Service ) ]
+//! #[ service( namespace = "fail", stream, fallible ) ]
+//! //
+//! struct Count( u32 );
+//!
+//! impl Message for Count { type Return = Empty<u32>; }
+//!
+//! fn main() {}
+//! ```
+//!
+//! A type that isn't a message:
+//!
+//! ```compile_fail,E0277
+//! use thespis_remote::derive::Service;
+//! use serde::{ Serialize, Deserialize };
+//!
+//! #[ derive( Serialize, Deserialize, Service ) ]
+//! #[ service( namespace = "fail" ) ]
+//! //
+//! struct Add( i64 );
+//!
+//! fn main() {}
+//! ```
+//!
+//! A fallible service that doesn't return a `Result`:
+//!
+//! ```compile_fail,E0277
+//! use thespis_remote::{ derive::Service, external_deps::thespis::Message };
+//! use serde::{ Serialize, Deserialize };
+//!
+//! #[ derive( Serialize, Deserialize, Service ) ]
+//! #[ service( namespace = "fail", fallible ) ]
+//! //
+//! struct Add( i64 );
+//!
+//! impl Message for Add { type Return = (); }
+//!
+//! fn main() {}
+//! ```
+//!
+//! A streaming service that doesn't return a stream:
+//!
+//! ```compile_fail,E0277
+//! use thespis_remote::{ derive::Service, external_deps::thespis::Message };
+//! use serde::{ Serialize, Deserialize };
+//!
+//! #[ derive( Serialize, Deserialize, Service ) ]
+//! #[ service( namespace = "fail", stream ) ]
+//! //
+//! struct Count( u32 );
+//!
+//! impl Message for Count { type Return = u32; }
+//!
+//! fn main() {}
+//! ```
+//!
+//! A service map with a type that isn't a service:
+//!
+//! ```compile_fail,E0277
+//! use thespis_remote::{ derive::service_map, external_deps::thespis::Message };
+//! use serde::{ Serialize, Deserialize };
+//!
+//! #[ derive( Serialize, Deserialize ) ]
+//! //
+//! struct Add( i64 );
+//!
+//! impl Message for Add { type Return = (); }
+//!
+//! #[ service_map( services( Add ) ) ]
+//! //
+//! mod remotes {}
+//!
+//! fn main() {}
+//! ```
@@ -0,0 +1,246 @@
+use crate::{ import::*, * };
+
+
+/// Call or send to any service of a remote by it's [`ServiceID`], without knowing the types at compile time.
+///
+/// This is the untyped counterpart of the `RemoteAddr` generated by [`service_map!`]. It's meant for
+/// gateways, admin tools and tests. Payloads are either raw bytes, which are put on the wire as is, or
+/// a [`serde_cbor::Value`]. The remote must be able to deserialize the payload into the message type of the
+/// service, so when using raw bytes they must be CBOR for remotes built with `service_map!`.
+///
+/// Errors are the same as for `RemoteAddr`, so a service that doesn't exist gives `PeerErr::Remote` with
+/// `ConnectionError::UnknownService` and a call that takes too long gives `PeerErr::Timeout`.
+///
+/// ```ignore
+/// let mut addr = DynRemoteAddr::new( peer );
+/// let sid      = ServiceID::from_seed( b"myapp::Add" );
+///
+/// addr.send( sid, &Value::Integer( 5 ) ).await?;
+///
+/// let sum = addr.call( ServiceID::from_seed( b"myapp::Show" ), &Value::Null ).await?;
+/// ```
+//
+#[ derive( Debug, Clone ) ]
+//
+pub struct DynRemoteAddr<Wf: WireFormat = ThesWF>
+{
+	pub(crate) peer: Addr<Peer<Wf>>,
+
+	// The priority for outgoing calls and sends, if not the default.
+	//
+	pub(crate) priority: Option<Priority>,
+
+	// The trace context sent along with outgoing calls and sends.
+	//
+	pub(crate) trace: Option<TraceContext>,
+}
+
+
+impl<Wf: WireFormat + Send + 'static> DynRemoteAddr<Wf>
+{
+	/// Create a DynRemoteAddr that sends over `peer`.
+	//
+	pub fn new( peer: Addr<Peer<Wf>> ) -> Self
+	{
+		Self { peer, priority: None, trace: None }
+	}
+
+
+	/// Send calls and sends through this address with the given priority. By default both
+	/// have `Priority::Call`. See [`Priority`].
+	//
+	pub fn with_priority( mut self, priority: Priority ) -> Self
+	{
+		self.priority = Some( priority );
+		self
+	}
+
+
+	/// Send the trace context along with calls and sends through this address, so the remote
+	/// continues the trace. See [`TraceContext`].
+	//
+	pub fn with_trace( mut self, trace: impl Into<Option<TraceContext>> ) -> Self
+	{
+		self.trace = trace.into();
+		self
+	}
+
+
+	/// Call the service `sid` with a payload that is already serialized. Returns the payload of the response.
+	//
+	pub async fn call_raw( &mut self, sid: ServiceID, payload: &[u8] ) -> Result< Vec<u8>, PeerErr >
+	{
+		let mut call = Call::new( Self::build_wf( sid, payload )? ).with_trace( self.trace );
+
+		if let Some( priority ) = self.priority
+		{
+			call = call.with_priority( priority );
+		}
+
+		// Can fail if the peer is down already.
+		//
+		let rx = self.peer.call( call ).await
+
+			// The peer panicked.
+			//
+			.map_err( |_|
+			{
+				let ctx = Peer::err_ctx( &self.peer, sid, None, "Call remote service".to_string() );
+
+				PeerErr::PeerGone{ ctx }
+
+			})?
+
+			// The actual sending out over the network can fail, or the call limit of the
+			// peer refuses it.
+			//
+			.map_err( |err| match err
+			{
+				PeerErr::TooManyCalls{..} => err,
+
+				_ =>
+				{
+					let ctx = Peer::err_ctx( &self.peer, sid, None, "Call remote service".to_string() );
+
+					PeerErr::ConnectionClosed{ ctx }
+				}
+
+			})?;
+
+
+		// Channel can be canceled
+		//
+		let re = rx.await
+
+			.map_err( |_|
+			{
+				let ctx = Peer::err_ctx( &self.peer, sid, None, "Peer stopped before receiving response from remote call".to_string() );
+
+				PeerErr::ConnectionClosed{ ctx }
+
+			})?;
+
+
+		match re
+		{
+			Ok( resp ) => Ok( resp.msg().to_vec() ),
+
+			// The remote returned an error. Timeout doesn't come from the remote, see RemoteAddr.
+			//
+			Err( ConnectionError::Timeout{..} ) =>
+			{
+				let ctx = Peer::err_ctx( &self.peer, sid, None, "Time out waiting for response to outgoing call".to_string() );
+
+				Err( PeerErr::Timeout{ ctx } )
+			}
+
+			// The handler of a fallible service returned an error.
+			//
+			Err( ConnectionError::Application{ err, .. } ) =>
+			{
+				let ctx = Peer::err_ctx( &self.peer, sid, None, "Remote handler returned an error".to_string() );
+
+				Err( PeerErr::Application{ err, ctx } )
+			}
+
+			Err( err ) =>
+			{
+				let ctx = Peer::err_ctx( &self.peer, sid, None, "Remote could not process our message".to_string() );
+
+				Err( PeerErr::Remote{ err, ctx } )
+			}
+		}
+	}
+
+
+	/// Call the service `sid` and deserialize the response to a dynamic CBOR value.
+	//
+	pub async fn call( &mut self, sid: ServiceID, msg: &serde_cbor::Value ) -> Result< serde_cbor::Value, PeerErr >
+	{
+		let payload = Self::serialize( sid, msg )?;
+		let resp    = self.call_raw( sid, &payload ).await?;
+
+		serde_cbor::from_slice( &resp ).map_err( |_|
+		{
+			let ctx = Peer::err_ctx( &self.peer, sid, None, "Response to call from remote actor".to_string() );
+
+			PeerErr::Deserialize{ ctx }
+		})
+	}
+
+
+	/// Send a payload that is already serialized to the service `sid`. Returns when the send has gone out,
+	/// so this waits for credit if the remote uses flow control. See [`FlowSend`].
+	//
+	pub async fn send_raw( &mut self, sid: ServiceID, payload: &[u8] ) -> Result< (), PeerErr >
+	{
+		let wf   = Self::build_wf( sid, payload )?;
+		let send = FlowSend::new( wf, self.priority.unwrap_or( Priority::Call ) ).with_trace( self.trace );
+
+		send.send_over( self.peer.clone() ).await
+	}
+
+
+	/// Send a dynamic CBOR value to the service `sid`.
+	//
+	pub async fn send( &mut self, sid: ServiceID, msg: &serde_cbor::Value ) -> Result< (), PeerErr >
+	{
+		let payload = Self::serialize( sid, msg )?;
+
+		self.send_raw( sid, &payload ).await
+	}
+
+
+	fn serialize( sid: ServiceID, msg: &serde_cbor::Value ) -> Result< Vec<u8>, PeerErr >
+	{
+		serde_cbor::to_vec( msg ).map_err( |_|
+		{
+			let ctx = PeerErrCtx::default()
+
+				.context( "Outgoing request".to_string() )
+				.sid    ( sid                            )
+			;
+
+			PeerErr::Serialize{ ctx }
+		})
+	}
+
+
+	pub(crate) fn build_wf( sid: ServiceID, payload: &[u8] ) -> Result< Wf, PeerErr >
+	{
+		let mut wf = Wf::with_capacity( payload.len() );
+		wf.set_sid( sid );
+
+		io::Write::write_all( &mut wf, payload ).map_err( |_|
+		{
+			let ctx = PeerErrCtx::default()
+
+				.context( "Outgoing request".to_string() )
+				.sid    ( sid                            )
+			;
+
+			PeerErr::Serialize{ ctx }
+		})?;
+
+		Ok( wf )
+	}
+}
+
+
+
+impl<Wf: WireFormat> Identify for DynRemoteAddr<Wf>
+{
+	/// Unique id of the peer this sends over
+	//
+	fn id( &self ) -> usize
+	{
+		self.peer.id()
+	}
+
+	/// Name of the peer this sends over
+	//
+	fn name( &self ) -> Option<Arc<str>>
+	{
+		self.peer.name()
+	}
+}
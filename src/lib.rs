@@ -20,24 +20,56 @@
 )]
 
 
+pub mod capture           ;
+    mod dyn_remote_addr   ;
 pub mod peer              ;
+    mod peer_server       ;
     mod relay_map         ;
     mod pub_sub           ;
+    mod service           ;
+    mod service_addr      ;
     mod service_handler   ;
     mod service_map       ;
     mod service_map_macro ;
+    mod service_registry  ;
 pub mod thes_wf           ;
 pub mod wire_format       ;
 
+#[ cfg( feature = "testing" ) ]
+//
+pub mod testing           ;
+
+/// `#[derive(Service)]` and `#[service_map]`. They live in a module of their own so the attribute doesn't
+/// clash with the `service_map!` macro.
+//
+#[ cfg( feature = "derive" ) ]
+//
+pub mod derive
+{
+	pub use thespis_remote_derive::{ Service, service_map };
+}
+
+// Compile fail tests of the derive macros.
+//
+#[ cfg(all( doctest, feature = "derive" )) ]
+//
+mod derive_fail;
+
 pub use
 {
 	thes_wf           :: * ,
+	capture           :: * ,
+	dyn_remote_addr   :: * ,
 	peer              :: * ,
+	peer_server       :: * ,
 	pub_sub           :: * ,
 	relay_map         :: * ,
+	service           :: * ,
+	service_addr      :: * ,
 	service_handler   :: * ,
 	service_map       :: * ,
 	service_map_macro :: * ,
+	service_registry  :: * ,
 	wire_format       :: * ,
 };
 
@@ -56,6 +88,7 @@ pub mod external_deps
 	pub use thespis      ;
 	pub use thespis_impl ;
 	pub use paste        ;
+	pub use tracing      ;
 	pub use parking_lot  ;
 }
 
@@ -77,25 +110,27 @@ mod import
 		parking_lot     :: { Mutex                                               } ,
 		pharos          :: { Pharos, Observe, Observable, ObserveConfig, PharErr } ,
 		rand            :: { Rng                                                 } ,
-		serde           :: { Serialize, Deserialize                              } ,
+		serde           :: { Serialize, Deserialize, de::DeserializeOwned        } ,
 		thespis         :: { *                                                   } ,
 		thespis_impl    :: { Addr, ThesErr                                       } ,
+		tracing         :: { Instrument, Span, field::Empty                      } ,
 		twox_hash       :: { XxHash64                                            } ,
 
 		std ::
 		{
-			collections  :: { HashMap, VecDeque                 } ,
+			collections  :: { HashMap, HashSet, VecDeque        } ,
 			convert      :: { TryFrom, TryInto                  } ,
 			fmt                                                   ,
 			io                                                    ,
 			future       :: { Future                            } ,
 			hash         :: { Hasher                            } ,
 			marker       :: { PhantomData                       } ,
-			num          :: { NonZeroUsize                      } ,
+			num          :: { NonZeroUsize, NonZeroU32, NonZeroU64 } ,
 			ops          :: { DerefMut                          } ,
+			panic        :: { AssertUnwindSafe                  } ,
 			pin          :: { Pin                               } ,
 			sync         :: { Arc                               } ,
-			sync::atomic :: { AtomicI64, AtomicU64, Ordering::* } ,
+			sync::atomic :: { AtomicBool, AtomicI64, AtomicU64, Ordering::* } ,
 			task         :: { Poll, Context, Waker              } ,
 			time         :: { Duration                          } ,
 		},
@@ -104,11 +139,12 @@ mod import
 		futures ::
 		{
 			channel :: { oneshot, mpsc::{ self, UnboundedSender as futUnboundSender } } ,
-			future  :: { FutureExt         } ,
+			future  :: { FutureExt, poll_fn } ,
 			lock    :: { Mutex as FutMutex } ,
 			prelude :: { Stream, Sink      } ,
 			sink    :: { SinkExt           } ,
 			stream  :: { StreamExt, FuturesUnordered         } ,
+			task    :: { Spawn, SpawnExt, LocalSpawn, LocalSpawnExt, FutureObj, SpawnError } ,
 			io      :: { AsyncReadExt      } ,
 			AsyncRead  as FutAsyncRead  ,
 			AsyncWrite as FutAsyncWrite ,
@@ -117,6 +153,25 @@ mod import
 	};
 
 
+	#[ cfg( feature = "tokio" ) ]
+	//
+	pub(crate) use
+	{
+		tokio_crate :: { io::{ AsyncRead as TokioAsyncRead, AsyncWrite as TokioAsyncWrite } } ,
+		tokio_util  :: { compat::TokioAsyncReadCompatExt                                 } ,
+	};
+
+
+	#[ cfg( feature = "websocket" ) ]
+	//
+	pub(crate) use
+	{
+		async_tungstenite :: { WebSocketStream, tungstenite::{ Message as WsMessage, Error as WsErr } } ,
+		async_tungstenite :: { tungstenite::protocol::{ CloseFrame, frame::coding::CloseCode }       } ,
+		futures           :: { stream::SplitSink                                                     } ,
+	};
+
+
 	#[ cfg(test) ]
 	//
 	pub(crate) use
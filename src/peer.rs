@@ -4,29 +4,78 @@
 use crate :: { import::*, * };
 
 
+    mod app_error         ;
     mod backpressure      ;
+    mod builder           ;
     mod call              ;
+    mod call_limit        ;
     mod call_response     ;
+    mod call_stream       ;
+    mod channel           ;
     mod close_connection  ;
     mod connection_error  ;
+    mod flow_control      ;
+    mod idle              ;
     mod incoming          ;
+    mod lifecycle         ;
+    mod local             ;
+    mod outbox            ;
+    mod pair              ;
     mod peer_err          ;
     mod peer_event        ;
+    mod rate_limit        ;
+    mod reflection        ;
 pub mod request_error     ;
     mod response          ;
+    mod stats             ;
+    mod stream_response   ;
     mod timeout           ;
+    mod trace_context     ;
 
-pub use backpressure      :: { BackPressure        } ;
-pub use call              :: { Call                } ;
-pub use call_response     :: { CallResponse        } ;
-pub use close_connection  :: { CloseConnection     } ;
-pub use connection_error  :: { ConnectionError     } ;
-    use incoming          :: { Incoming            } ;
-pub use peer_err          :: { PeerErr, PeerErrCtx } ;
-pub use peer_event        :: { PeerEvent           } ;
-    use request_error     :: { RequestError        } ;
-pub use response          :: { Response            } ;
-    use timeout           :: { Timeout             } ;
+#[ cfg( feature = "tokio" ) ]
+//
+    mod tokio_io          ;
+
+#[ cfg( feature = "websocket" ) ]
+//
+    mod websocket         ;
+
+pub use app_error         :: { AppError, Fallible, Reply                            } ;
+pub use backpressure      :: { BackPressure                                         } ;
+pub use builder           :: { PeerBuilder, PeerConfig                              } ;
+pub use call              :: { Call                                                 } ;
+    use call              :: { OutgoingCall, ReplyTo, StreamReply                   } ;
+pub use call_limit        :: { CallLimit, CallLimitPolicy                           } ;
+    use call_limit        :: { CallSlots                                            } ;
+pub use call_response     :: { CallResponse                                         } ;
+pub use call_stream       :: { CallStream, ResponseStream                           } ;
+    use call_stream       :: { OutgoingStream, StreamCtrl, StreamWindow             } ;
+pub use channel           :: { Channel, ChannelSink, ChannelStream, ChannelListener } ;
+    use channel           :: { ChannelState, ChannelParts                           } ;
+pub use close_connection  :: { CloseConnection                                      } ;
+    use close_connection  :: { CloseHook                                            } ;
+pub use connection_error  :: { ConnectionError                                      } ;
+pub use flow_control      :: { FlowControl, FlowSend, PendingSend                   } ;
+    use flow_control      :: { Credits, Grants                                      } ;
+    use idle              :: { Idle                                                 } ;
+    use incoming          :: { Incoming                                             } ;
+    use lifecycle         :: { Lifecycle                                            } ;
+pub use local             :: { LocalExec                                            } ;
+pub use outbox            :: { Priority, Prioritized                                } ;
+    use outbox            :: { Outbox                                               } ;
+pub use peer_err          :: { PeerErr, PeerErrCtx                                  } ;
+pub use peer_event        :: { PeerEvent                                            } ;
+pub use rate_limit        :: { RateLimit                                            } ;
+    use rate_limit        :: { RateLimiter                                          } ;
+pub use reflection        :: { Reflection, ServiceInfo, TypeInfo                    } ;
+    use request_error     :: { RequestError                                         } ;
+pub use response          :: { Response                                             } ;
+pub use stats             :: { GetStats, PeerStats, ServiceStats, Histogram         } ;
+    use stats             :: { Metrics                                              } ;
+pub use stream_response   :: { StreamResponse                                       } ;
+    use timeout           :: { Timeout                                              } ;
+pub use trace_context     :: { TraceContext                                         } ;
+    use trace_context     :: { RemoteTraces                                         } ;
 
 
 // Reduce trait bound boilerplate, since we have to repeat them all over
@@ -96,6 +145,14 @@ impl<T, Wf> BoundsOut<Wf> for T
 /// one service map may claim to provide a given service. Peer only delivers the message to exactly
 /// one handler.
 ///
+/// ### Creating a peer.
+///
+/// [`PeerBuilder`] creates the peer, registers the service maps, starts the mailbox and returns the
+/// address in one go. It's settings can be loaded from a config file as a [`PeerConfig`]. `Peer::new`
+/// and `Peer::from_async_read` remain available when you need to manage the mailbox yourself.
+///
+/// [`Peer::pair`] connects two peers in memory, which is convenient for testing your services without sockets.
+///
 /// ### Sending messages to remote processes.
 ///
 /// As far as the Peer type is concerned sending actor messages to a remote is relatively simple.
@@ -105,6 +162,42 @@ impl<T, Wf> BoundsOut<Wf> for T
 /// The `service_map!` macro provides a `RemoteAddress` type which acts much the same as a local actor address
 /// and will accept messages of all services that are defined in the service map.
 ///
+/// ### Priorities
+///
+/// Outgoing frames don't go out in the order they are handled by the peer. A task writes them to
+/// the connection from a queue per [`Priority`], so responses and control frames don't wait behind a
+/// burst of sends.
+///
+/// ### Channels
+///
+/// For long lived duplex communication, either side can open a [`Channel`] for a service with
+/// [`Channel::open`]. The other side accepts them from the stream returned by [`Peer::listen_channels`].
+/// A channel is a typed `Sink` + `Stream` pair multiplexed over the connection, with it's own flow
+/// control window.
+///
+/// ### Flow control
+///
+/// With [`Peer::set_flow_control`] a peer gives the remote credit for a number of outstanding requests
+/// and bytes. The remote holds outgoing requests back while it has no credit left. See [`FlowControl`].
+///
+/// ### Metrics
+///
+/// Peer counts frames, bytes, requests, errors and timeouts, overall and per service, and keeps latency
+/// histograms for calls. Send it [`GetStats`] to get a [`PeerStats`] snapshot, which can be rendered
+/// in the Prometheus text format.
+///
+/// ### Tracing
+///
+/// Peer creates `tracing` spans around handling incoming requests and sending out calls, with the peer,
+/// `sid` and `cid` as fields. Relays and [`PubSub`] add their own spans. A [`TraceContext`] can be sent along
+/// with outgoing requests. The remote restores it, so one request can be followed through client,
+/// relays and backend.
+///
+/// ### Reflection
+///
+/// Peer answers calls to a reserved service id itself, with the list of services it exposes. Remotes
+/// can use [`ServiceInfo::list_remote`] to get it. It is disabled by default, enable it with [`Peer::set_reflection`].
+///
 /// ### Closing the connection
 ///
 /// The reasoning behind a peer is that it is tied to a stream/sink, often a framed connection.
@@ -121,7 +214,8 @@ impl<T, Wf> BoundsOut<Wf> for T
 ///
 /// Peer uses the pharos crate to be observable over [`PeerEvent`]. This allows you to detect
 /// when errors happen and to react accordingly. If the connection gets closed, you can make
-/// reconnect and make a new peer.
+/// reconnect and make a new peer. More detailed events about timeouts, incoming calls and backpressure
+/// can be turned on with [`Peer::set_lifecycle_events`].
 ///
 /// ### Errors
 /// A lot of things can go wrong with networking. The main issue is that we interprete the
@@ -143,9 +237,13 @@ impl<T, Wf> BoundsOut<Wf> for T
 //
 pub struct Peer<Wf: 'static + WireFormat = ThesWF>
 {
-	/// The sink
+	/// Outgoing frames wait here for the task that writes them to the connection.
 	//
-	outgoing: Option< Box<dyn BoundsOut<Wf>> >,
+	outbox: Outbox<Wf>,
+
+	/// The task writing to the connection. It owns the sink.
+	//
+	writer: Option<JoinHandle<Result<Response<Wf>, PeerErr>>>,
 
 	/// This is needed so that the loop listening to the incoming stream can send messages to this actor.
 	/// The loop runs in parallel of the rest of the actor, yet processing incoming messages need mutable
@@ -168,10 +266,74 @@ pub struct Peer<Wf: 'static + WireFormat = ThesWF>
 	//
 	services: HashMap< ServiceID, Arc<dyn ServiceMap<Wf>> >,
 
+	/// The sids in services that are aliases of another service, so reflection doesn't list them.
+	//
+	aliases: HashSet< ServiceID >,
+
 	/// We use oneshot channels to give clients a future that will resolve to their response.
 	//
 	responses: HashMap< ConnID, oneshot::Sender<Result<Wf, ConnectionError>> >,
 
+	/// Outgoing streaming calls for which we haven't seen the end of the response stream yet.
+	//
+	streams: HashMap< ConnID, OutgoingStream<Wf> >,
+
+	/// The flow control windows the remote opened for streaming responses we send, by cid of the call.
+	//
+	stream_windows: HashMap< ConnID, StreamWindow >,
+
+	/// Open channels, in both directions.
+	//
+	channels: HashMap< ConnID, ChannelState<Wf> >,
+
+	/// Where to deliver channels the remote opens, per service.
+	//
+	channel_listeners: HashMap< ServiceID, futUnboundSender<ChannelParts<Wf>> >,
+
+	/// The maximum of outstanding outgoing calls and the calls waiting for a slot, if a limit is set.
+	//
+	call_slots: Option<CallSlots<Wf>>,
+
+	/// Credit the remote gave us for outgoing requests, and the requests waiting for it.
+	//
+	credits: Credits<Wf>,
+
+	/// Credit we give the remote for incoming requests, if flow control is set.
+	//
+	grants: Option<Grants>,
+
+	/// Token buckets for incoming requests, if any limits are set.
+	//
+	rate_limiter: Option<RateLimiter>,
+
+	/// How much the reflection service tells remotes.
+	//
+	reflection: Reflection,
+
+	/// Counters for [`GetStats`]. Shared with the tasks processing incoming requests.
+	//
+	metrics: Arc<Mutex< Metrics >>,
+
+	/// The trace contexts the remote sent for requests that haven't come in yet.
+	//
+	remote_traces: RemoteTraces,
+
+	/// Whether the remote told us it understands trace contexts.
+	//
+	trace_supported: bool,
+
+	/// Whether we told the remote that we understand trace contexts.
+	//
+	trace_announced: bool,
+
+	/// The idle timeout, if set.
+	//
+	idle: Option<Idle>,
+
+	/// Whether to send lifecycle events to observers.
+	//
+	lifecycle_events: bool,
+
 	/// The pharos allows us to have observers.
 	//
 	pharos: Pharos<PeerEvent>,
@@ -196,6 +358,11 @@ pub struct Peer<Wf: 'static + WireFormat = ThesWF>
 	//
 	closed: bool,
 
+	// Called by close connection before closing the outgoing sink, so the transport can tell the
+	// remote why we close.
+	//
+	close_hook: Option<CloseHook>,
+
 	// The counter for conn_id. This will wrap. If there are still old connections
 	// open by the time this wraps, we have a problem. It's quite unlikely to happen though.
 	// It would mean this peer has an outstanding call that is still open by the time
@@ -309,6 +476,7 @@ impl<Wf: WireFormat> Peer<Wf>
 			context  : context.as_ref().to_string().into() ,
 			sid      : sid.into()                          ,
 			cid      : cid.into()                          ,
+			trace    : None                                ,
 		}
 	}
 
@@ -339,6 +507,7 @@ impl<Wf: WireFormat> Peer<Wf>
 			context  : context.into()   ,
 			sid      : sid.into()       ,
 			cid      : cid.into()       ,
+			trace    : None             ,
 		}
 	}
 
@@ -401,6 +570,32 @@ impl<Wf: WireFormat> Peer<Wf>
 		;
 
 
+		let outbox = Outbox::new();
+
+		let writer_ctx = PeerErrCtx::default()
+
+			.peer_id  ( addr.id()   )
+			.peer_name( addr.name() )
+		;
+
+		let writer = Self::write_out( outbox.clone(), Box::new(outgoing), addr.clone(), nursery.clone(), writer_ctx );
+
+		let writer_handle = exec.spawn_handle( writer )
+
+			.map_err( |_| -> PeerErr
+			{
+				let ctx = PeerErrCtx::default()
+
+					.peer_id  ( addr.id()                                 )
+					.peer_name( addr.name()                               )
+					.context  ( Some( "Writer task for peer".to_string() ) )
+				;
+
+				PeerErr::Spawn{ ctx }
+			})?
+		;
+
+
 		nursery.nurse( Self::listen_incoming( incoming, addr.clone(), bp.clone() ) )
 
 			.map_err( |_| -> PeerErr
@@ -412,21 +607,41 @@ impl<Wf: WireFormat> Peer<Wf>
 		;
 
 
+		let metrics = Metrics::new( addr.id(), addr.name() );
+
 		Ok( Self
 		{
-			id             : addr.id()                  ,
-			name           : addr.name()                ,
-			outgoing       : Some( Box::new(outgoing) ) ,
-			addr           : Some( addr )               ,
-			responses      : HashMap::new()             ,
-			services       : HashMap::new()             ,
-			pharos         : Pharos::default()          ,
-			timeout        : Duration::from_secs(60)    ,
-			backpressure   : bp                         ,
-			closed         : false                      ,
-			nursery_stream : Some( nursery_handle )     ,
-			nursery                                     ,
-			grace_period                                ,
+			id               : addr.id()                  ,
+			name             : addr.name()                ,
+			writer           : Some( writer_handle )      ,
+			addr             : Some( addr )               ,
+			responses        : HashMap::new()             ,
+			streams          : HashMap::new()             ,
+			stream_windows   : HashMap::new()             ,
+			channels         : HashMap::new()             ,
+			channel_listeners: HashMap::new()             ,
+			services         : HashMap::new()             ,
+			aliases          : HashSet::new()             ,
+			pharos           : Pharos::default()          ,
+			metrics          : Arc::new( Mutex::new( metrics ) ) ,
+			reflection       : Reflection::default()      ,
+			rate_limiter     : None                       ,
+			remote_traces    : RemoteTraces::default()    ,
+			trace_supported  : false                      ,
+			trace_announced  : false                      ,
+			lifecycle_events : false                      ,
+			idle             : None                       ,
+			call_slots       : None                       ,
+			credits          : Credits::default()         ,
+			grants           : None                       ,
+			timeout          : Duration::from_secs(60)    ,
+			backpressure     : bp                         ,
+			closed           : false                      ,
+			close_hook       : None                       ,
+			nursery_stream   : Some( nursery_handle )     ,
+			outbox                                        ,
+			nursery                                       ,
+			grace_period                                  ,
 
 			// must not start at 0. Zero has a special meaning.
 			//
@@ -468,6 +683,7 @@ impl<Wf: WireFormat> Peer<Wf>
 					Response::Nothing         => Ok(())               ,
 					Response::WireFormat  (x) => addr.send( x ).await ,
 					Response::CallResponse(x) => addr.send( x ).await ,
+					Response::Stream      (x) => addr.send( x ).await ,
 				}
 
 				Err(err) => addr.send( RequestError::from( err ) ).await
@@ -498,6 +714,8 @@ impl<Wf: WireFormat> Peer<Wf>
 		-> Result<Response<Wf>, PeerErr>
 
 	{
+		let mut reason = "Connection closed by remote.".to_string();
+
 		// This can fail if:
 		//
 		// the receiver is dropped. The receiver is our mailbox, so it should never be dropped
@@ -505,12 +723,42 @@ impl<Wf: WireFormat> Peer<Wf>
 		//
 		while let Some(msg) = incoming.next().await
 		{
+			// The transport knows why the remote closed the connection.
+			//
+			if let Err( WireErr::Closed{ reason: why } ) = msg
+			{
+				reason = why;
+				break;
+			}
+
 			if let Some( ref bp ) = bp
 			{
 				trace!( "check for backpressure" );
 
+				// Sends wait on their own counter if they have one.
+				//
+				let bp: &BackPressure = match &msg
+				{
+					Ok( frame ) if frame.kind() == WireType::IncomingSend => bp.send_slots().unwrap_or( bp ),
+					_                                                     => bp,
+				};
+
+				// Only the peer knows whether anyone wants to hear about this.
+				//
+				let exhausted = bp.available() <= 0;
+
+				if exhausted
+				{
+					let _ = addr.send( Lifecycle( PeerEvent::BackPressureExhausted ) ).await;
+				}
+
 				bp.wait().await;
 
+				if exhausted
+				{
+					let _ = addr.send( Lifecycle( PeerEvent::BackPressureRestored ) ).await;
+				}
+
 				trace!( "backpressure allows progress now." );
 			}
 
@@ -526,7 +774,7 @@ impl<Wf: WireFormat> Peer<Wf>
 
 		// The connection was closed by remote, tell peer to clean up.
 		//
-		let res = addr.send( CloseConnection{ remote: true, reason: "Connection closed by remote.".to_string() } ).await;
+		let res = addr.send( CloseConnection{ remote: true, reason } ).await;
 
 		// As we hold an address, the only way the mailbox can already be shut
 		// is if the peer panics, or the mailbox get's dropped. Since the mailbox
@@ -547,7 +795,7 @@ impl<Wf: WireFormat> Peer<Wf>
 	//
 	pub fn register_services( &mut self, sm: Arc< dyn ServiceMap<Wf>> )
 	{
-		for sid in sm.services()
+		for sid in sm.services().chain( sm.aliases() )
 		{
 			trace!( "{}: Register Service: {:?}", self.identify(), &sid );
 
@@ -558,39 +806,46 @@ impl<Wf: WireFormat> Peer<Wf>
 			);
 
 			self.services.insert( *sid, sm.clone() );
+			self.metrics.lock().expose( *sid );
 		}
+
+		self.aliases.extend( sm.aliases().copied() );
 	}
 
 
-	// actually send the message accross the wire
+	// Queue a message to be sent out with the default priority for it's type.
 	//
 	async fn send_msg( &mut self, msg: Wf ) -> Result<(), PeerErr>
+	{
+		let priority = Priority::of( msg.kind() );
+
+		self.send_prio( msg, priority ).await
+	}
+
+
+
+	// Queue a message to be sent out accross the wire. It fails if the connection is closed.
+	// Errors writing it to the connection are reported as events.
+	//
+	async fn send_prio( &mut self, msg: Wf, priority: Priority ) -> Result<(), PeerErr>
 	{
 		trace!( "{}: sending OUT WireFormat", self.identify() );
 
-		match &mut self.outgoing
+		let sid = msg.sid();
+		let cid = msg.cid();
+		let len = msg.len();
+
+		self.outbox.push( priority, msg ).await.map_err( |_|
 		{
-			Some( out ) =>
-			{
-				let sid = msg.sid();
-				let cid = msg.cid();
+			let ctx = self.ctx( sid, cid, "Sending out WireFormat" );
 
-				out.send( msg ).await
+			PeerErr::ConnectionClosed{ ctx }
 
-					.map_err( |source|
-					{
-						let ctx = self.ctx( sid, cid, "Sending out WireFormat" );
-						PeerErr::WireFormat{ ctx, source }
-					})
-			}
+		})?;
 
-			None =>
-			{
-				let ctx = PeerErrCtx::default().context( "register_relayed_services".to_string() );
+		self.metrics.lock().frame_out( len );
 
-				Err( PeerErr::ConnectionClosed{ ctx } )
-			}
-		}
+		Ok(())
 	}
 
 
@@ -613,23 +868,24 @@ impl<Wf: WireFormat> Peer<Wf>
 	{
 		trace!( "{}: sending OUT ConnectionError", self.identify() );
 
-		// If self.outgoing is None, we have already closed.
-		//
-		let out = match self.outgoing
-		{
-			Some( ref mut out ) => out,
-			None                => return,
-		};
-
-
 		// sid null is the marker that this is an error message.
 		//
 		let msg = Self::prep_error( cid, &err );
 
+		// If it was a streaming call, it's over.
+		//
+		self.stream_windows.remove( &cid );
+
 
-		// We are already trying to report an error. If we can't send, just give up.
+		// We are already trying to report an error. If we can't send, eg. because we have already
+		// closed, just give up.
 		//
-		let _ = out.send( msg ).await;
+		let len = msg.len();
+
+		if self.outbox.push( Priority::Response, msg ).await.is_ok()
+		{
+			self.metrics.lock().frame_out( len );
+		}
 
 		if close
 		{
@@ -669,7 +925,13 @@ impl<Wf: WireFormat> Handler<Wf> for Peer<Wf>
 	{
 		trace!( "{}: sending OUT WireFormat", self.identify() );
 
-		self.send_msg( msg ).await
+		// Only sends need credit, not responses and frames of channels.
+		//
+		match msg.kind()
+		{
+			WireType::IncomingSend => self.send_request( msg, Priority::Call, None, None ).await,
+			_                      => self.send_msg    ( msg                             ).await,
+		}
 	}
 }
 
@@ -713,5 +975,9 @@ impl<Wf: WireFormat> Drop for Peer<Wf>
 	fn drop( &mut self )
 	{
 		trace!( "Drop {}", self.identify() );
+
+		// Let the writer end.
+		//
+		self.outbox.close();
 	}
 }
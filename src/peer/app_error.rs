@@ -0,0 +1,154 @@
+use crate::{ import::*, * };
+
+
+/// An error returned by the handler of a remote service that is marked as fallible. The caller
+/// receives it as [`PeerErr::Application`], which tells it apart from errors of the connection.
+///
+/// The error is kept serialized as CBOR, since the caller might not know all the error types a
+/// remote can return. Use [`AppError::downcast`] to get it back:
+///
+/// ```ignore
+/// match addr.call( Transfer( 50 ) ).await
+/// {
+///    Err( PeerErr::Application{ err, .. } ) => match err.downcast::<BankErr>()
+///    {
+///       Some( BankErr::InsufficientFunds ) => ...,
+///       ...
+///    }
+///
+///    ...
+/// }
+/// ```
+//
+#[ derive( Debug, Clone, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+pub struct AppError
+{
+	#[ serde( with = "serde_bytes" ) ]
+	//
+	payload: Vec<u8>,
+}
+
+
+impl AppError
+{
+	/// Serialize an error returned by a handler.
+	//
+	pub fn new<E: Serialize>( err: &E ) -> Result< Self, serde_cbor::Error >
+	{
+		Ok( Self { payload: serde_cbor::to_vec( err )? } )
+	}
+
+
+	/// Create an AppError from a payload that is already serialized.
+	//
+	pub fn from_payload( payload: Vec<u8> ) -> Self
+	{
+		Self { payload }
+	}
+
+
+	/// The serialized error.
+	//
+	pub fn payload( &self ) -> &[u8]
+	{
+		&self.payload
+	}
+
+
+	/// Deserialize the error as `E`. Returns `None` if the payload is not a valid `E`.
+	//
+	pub fn downcast<E: DeserializeOwned>( &self ) -> Option<E>
+	{
+		serde_cbor::from_slice( &self.payload ).ok()
+	}
+}
+
+
+impl fmt::Display for AppError
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "Application error ({} bytes)", self.payload.len() )
+	}
+}
+
+
+
+/// The return type of a fallible service. It's implemented for `Result`. When the handler returns
+/// `Err`, the error is sent to the caller as an [`AppError`], rather than as the return value.
+///
+/// Mark a service as fallible with `#[ fallible ]` in [`service_map!`] or with
+/// `#[ service( fallible ) ]` when deriving `Service`.
+//
+pub trait Fallible: Sized
+{
+	/// The value of a successful call.
+	//
+	type Ok;
+
+	/// The application error.
+	//
+	type Err: Serialize;
+
+	/// Split the return value of the handler.
+	//
+	fn into_result( self ) -> Result< Self::Ok, Self::Err >;
+
+	/// Make the return value for a successful call.
+	//
+	fn from_ok( ok: Self::Ok ) -> Self;
+}
+
+
+impl<T, E: Serialize> Fallible for Result<T, E>
+{
+	type Ok  = T;
+	type Err = E;
+
+	fn into_result( self ) -> Result<T, E>
+	{
+		self
+	}
+
+	fn from_ok( ok: T ) -> Self
+	{
+		Ok( ok )
+	}
+}
+
+
+
+/// What to send back to the caller for the return value of a handler. Used by the service maps.
+//
+#[ doc( hidden ) ]
+//
+#[ derive( Debug ) ]
+//
+pub enum Reply<R>
+{
+	/// Send the return value as the response.
+	//
+	Return( R ),
+
+	/// Send a [`ConnectionError::Application`].
+	//
+	AppError( AppError ),
+}
+
+
+impl<R: Fallible> Reply<R>
+{
+	/// The reply for the return value of a fallible service.
+	//
+	#[ doc( hidden ) ]
+	//
+	pub fn fallible( resp: R ) -> Result< Self, serde_cbor::Error >
+	{
+		match resp.into_result()
+		{
+			Ok ( ok  ) => Ok( Reply::Return( R::from_ok( ok ) ) ),
+			Err( err ) => AppError::new( &err ).map( Reply::AppError ),
+		}
+	}
+}
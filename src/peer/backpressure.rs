@@ -8,7 +8,9 @@ use { crate :: { import::* }};
 //
 // TODO: Review and document behavior. It uses a vecdeque, so it stores more than one waker...
 //
-// Currently used only for incoming calls, not incoming sends.
+// By default it's used only for incoming calls, not incoming sends. Use `with_shared_sends` or
+// `with_send_slots` to limit incoming sends as well. A send holds it's slot until the message has
+// been delivered to the mailbox of the handler.
 //
 #[ derive( Debug, Actor ) ]
 //
@@ -19,6 +21,27 @@ pub struct BackPressure
 	available: Arc<AtomicI64>              ,
 	wakers   : Arc<Mutex<VecDeque<Waker>>> ,
 	future   : FutMutex<BPInner>           ,
+	sends    : Sends                       ,
+}
+
+
+// Whether incoming sends take slots.
+//
+#[ derive( Debug ) ]
+//
+enum Sends
+{
+	// Sends don't take slots.
+	//
+	Unlimited,
+
+	// Sends take slots from the same counter as calls.
+	//
+	Shared,
+
+	// Sends have their own counter.
+	//
+	Separate( Box<BackPressure> ),
 }
 
 
@@ -37,6 +60,40 @@ impl BackPressure
 			available: a,
 			wakers   : w,
 			future   : FutMutex::new( BPInner{ available, wakers } ) ,
+			sends    : Sends::Unlimited                               ,
+		}
+	}
+
+
+	/// Incoming sends take slots from the same counter as calls. Use this when sends and calls
+	/// compete for the same resources.
+	//
+	pub fn with_shared_sends( mut self ) -> Self
+	{
+		self.sends = Sends::Shared;
+		self
+	}
+
+
+	/// Incoming sends take slots from a separate counter with `slots` available, so they
+	/// don't compete with calls.
+	//
+	pub fn with_send_slots( mut self, slots: i64 ) -> Self
+	{
+		self.sends = Sends::Separate( Box::new( BackPressure::new( slots ) ) );
+		self
+	}
+
+
+	/// The counter incoming sends take slots from, if any.
+	//
+	pub fn send_slots( &self ) -> Option<&BackPressure>
+	{
+		match &self.sends
+		{
+			Sends::Unlimited      => None          ,
+			Sends::Shared         => Some( self  ) ,
+			Sends::Separate( bp ) => Some( &**bp ) ,
 		}
 	}
 
@@ -82,6 +139,45 @@ impl BackPressure
 
 
 
+// A slot taken by an incoming send. It's given back when this is dropped, so also when the task
+// processing the send gets cancelled.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct SendSlot
+{
+	bp: Arc<BackPressure>,
+}
+
+
+impl SendSlot
+{
+	// Take a slot if sends are limited by `bp`.
+	//
+	pub(crate) fn take( bp: &Arc<BackPressure> ) -> Option<Self>
+	{
+		bp.send_slots()?.remove_slots( NonZeroUsize::new(1).unwrap() );
+
+		Some( Self{ bp: bp.clone() } )
+	}
+}
+
+
+impl Drop for SendSlot
+{
+	fn drop( &mut self )
+	{
+		if let Some( slots ) = self.bp.send_slots()
+		{
+			slots.add_slots( NonZeroUsize::new(1).unwrap() );
+		}
+	}
+}
+
+
+
+
+
 struct BPInner
 {
 	available: Arc<AtomicI64>,
@@ -136,6 +232,7 @@ mod tests
 	//
 	// ✔ basic waking up when adding slots.
 	// ✔ remove slots when polled and returning ready.
+	// ✔ sends use no slots, the same slots or separate slots depending on configuration.
 	//
 	use crate::{ import::{ *, assert_eq }, peer::BackPressure };
 
@@ -195,6 +292,27 @@ mod tests
 			assert_eq!( bp.available(), 2 );
 
 	})}
+
+
+	#[test]
+	//
+	fn send_slots()
+	{
+		let bp = BackPressure::new( 2 );
+
+			assert!( bp.send_slots().is_none() );
+
+		let bp = BackPressure::new( 2 ).with_shared_sends();
+
+			bp.send_slots().unwrap().remove_slots( NonZeroUsize::new(1).unwrap() );
+			assert_eq!( bp.available(), 1 );
+
+		let bp = BackPressure::new( 2 ).with_send_slots( 5 );
+
+			bp.send_slots().unwrap().remove_slots( NonZeroUsize::new(1).unwrap() );
+			assert_eq!( bp.available()                      , 2 );
+			assert_eq!( bp.send_slots().unwrap().available(), 4 );
+	}
 }
 
 
@@ -0,0 +1,445 @@
+use { crate::{ import::*, * }, super::CloseHook };
+
+
+/// The settings of a [`Peer`] that can be expressed as plain data. Use it with [`PeerBuilder::config`].
+///
+/// It implements serde `Deserialize`, so it can be loaded from a configuration file in any format serde
+/// supports. Every field has a default, so the file only needs the settings you want to change.
+/// Durations are given in milliseconds. In toml this looks like:
+///
+/// ```toml
+/// name          = "backend"
+/// timeout       = 5000
+/// max_size      = 65536
+/// backpressure  = 32
+/// idle_timeout  = 300000
+/// call_limit    = { max = 100, policy = "Wait" }
+/// reflection    = "Services"
+/// trace_context = true
+/// ```
+//
+#[ derive( Debug, Clone, PartialEq, Eq, Serialize, Deserialize ) ]
+#[ serde( default ) ]
+//
+pub struct PeerConfig
+{
+	/// The name of the actor. Shows up in logs, errors, spans and stats.
+	//
+	pub name: Option<String>,
+
+	/// How long to wait for the response to an outgoing call. Defaults to 60 seconds.
+	/// See [`Peer::set_timeout`].
+	//
+	#[ serde( with = "millis" ) ]
+	//
+	pub timeout: Duration,
+
+	/// The maximum size of a frame in bytes, in both directions. Only used when the builder frames the
+	/// connection itself, eg. [`PeerBuilder::build_async_read`]. **Set the same max_size in the remote!**
+	/// Defaults to 1 MiB.
+	//
+	pub max_size: usize,
+
+	/// The number of incoming calls that can be processed concurrently. `None` means no limit, which is
+	/// the default. For more options, give a [`BackPressure`] to [`PeerBuilder::backpressure`].
+	//
+	pub backpressure: Option<i64>,
+
+	/// How long to keep processing incoming requests after the connection closes. See [`Peer::new`].
+	/// Defaults to none.
+	//
+	#[ serde( with = "opt_millis" ) ]
+	//
+	pub grace_period: Option<Duration>,
+
+	/// Close the connection when nothing happens on it for this long. See [`Peer::set_idle_timeout`].
+	/// Defaults to none.
+	//
+	#[ serde( with = "opt_millis" ) ]
+	//
+	pub idle_timeout: Option<Duration>,
+
+	/// The maximum number of outstanding outgoing calls. Defaults to none. See [`CallLimit`].
+	//
+	pub call_limit: Option<CallLimit>,
+
+	/// The first connection id used for outgoing calls. Defaults to 1. Connection ids only have to be
+	/// unique per connection, but starting elsewhere can make the logs of different peers easier to
+	/// tell apart.
+	//
+	pub first_cid: NonZeroU64,
+
+	/// How much the reflection service tells remotes. Defaults to [`Reflection::Disabled`].
+	/// See [`Peer::set_reflection`].
+	//
+	pub reflection: Reflection,
+
+	/// Tell the remote that we understand trace contexts, so it sends them along with it's requests.
+	/// Defaults to false. See [`Peer::enable_trace_context`].
+	//
+	pub trace_context: bool,
+}
+
+
+impl Default for PeerConfig
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			name         : None                                         ,
+			timeout      : Duration::from_secs(60)                      ,
+			max_size     : 1024 * 1024                                  ,
+			backpressure : None                                         ,
+			grace_period : None                                         ,
+			idle_timeout : None                                         ,
+			call_limit   : None                                         ,
+			first_cid    : NonZeroU64::new(1).expect( "1 is not zero" ) ,
+			reflection   : Reflection::default()                        ,
+			trace_context: false                                        ,
+		}
+	}
+}
+
+
+
+/// Create a [`Peer`], start it's mailbox and get it's address in one go.
+///
+/// ```ignore
+/// let mut builder = PeerBuilder::new()
+///
+///    .config     ( config          )
+///    .service_map( Arc::new( sm )  )
+/// ;
+///
+/// let evts = builder.observe( ObserveConfig::default() ).await?;
+/// let peer = builder.build_async_read( socket, AsyncStd )?;
+/// ```
+///
+/// The builder is [`Observable`], so observers can be registered before the peer starts and they
+/// won't miss any events.
+//
+pub struct PeerBuilder<Wf: 'static + WireFormat = ThesWF>
+{
+	config      : PeerConfig                     ,
+	backpressure: Option<Arc< BackPressure >>    ,
+	services    : Vec< Arc<dyn ServiceMap<Wf>> > ,
+	pharos      : Pharos<PeerEvent>              ,
+	close_hook  : Option<CloseHook>              ,
+}
+
+
+impl<Wf: WireFormat> PeerBuilder<Wf>
+{
+	/// A builder with the default [`PeerConfig`].
+	//
+	pub fn new() -> Self
+	{
+		Self
+		{
+			config      : PeerConfig::default() ,
+			backpressure: None                  ,
+			services    : Vec::new()            ,
+			pharos      : Pharos::default()     ,
+			close_hook  : None                  ,
+		}
+	}
+
+
+	/// Replace all settings, eg. with a config loaded from a file.
+	//
+	pub fn config( mut self, config: PeerConfig ) -> Self
+	{
+		self.config = config;
+		self
+	}
+
+
+	/// The name of the actor.
+	//
+	pub fn name( mut self, name: impl Into<String> ) -> Self
+	{
+		self.config.name = Some( name.into() );
+		self
+	}
+
+
+	/// How long to wait for the response to an outgoing call.
+	//
+	pub fn timeout( mut self, timeout: Duration ) -> Self
+	{
+		self.config.timeout = timeout;
+		self
+	}
+
+
+	/// The maximum size of a frame in bytes.
+	//
+	pub fn max_size( mut self, max_size: usize ) -> Self
+	{
+		self.config.max_size = max_size;
+		self
+	}
+
+
+	/// Use this backpressure for incoming requests. This takes precedence over [`PeerConfig::backpressure`].
+	//
+	pub fn backpressure( mut self, bp: Arc<BackPressure> ) -> Self
+	{
+		self.backpressure = Some( bp );
+		self
+	}
+
+
+	/// How long to keep processing incoming requests after the connection closes.
+	//
+	pub fn grace_period( mut self, grace_period: Duration ) -> Self
+	{
+		self.config.grace_period = Some( grace_period );
+		self
+	}
+
+
+	/// Close the connection when nothing happens on it for this long.
+	//
+	pub fn idle_timeout( mut self, idle: Duration ) -> Self
+	{
+		self.config.idle_timeout = Some( idle );
+		self
+	}
+
+
+	/// Limit the number of outstanding outgoing calls.
+	//
+	pub fn call_limit( mut self, limit: CallLimit ) -> Self
+	{
+		self.config.call_limit = Some( limit );
+		self
+	}
+
+
+	/// How much the reflection service tells remotes.
+	//
+	pub fn reflection( mut self, reflection: Reflection ) -> Self
+	{
+		self.config.reflection = reflection;
+		self
+	}
+
+
+	/// Tell the remote that we understand trace contexts.
+	//
+	pub fn trace_context( mut self, enabled: bool ) -> Self
+	{
+		self.config.trace_context = enabled;
+		self
+	}
+
+
+	/// Expose the services of this service map. Can be called several times. See [`Peer::register_services`].
+	//
+	pub fn service_map( mut self, sm: Arc<dyn ServiceMap<Wf>> ) -> Self
+	{
+		self.services.push( sm );
+		self
+	}
+
+
+	/// The current settings.
+	//
+	pub fn settings( &self ) -> &PeerConfig
+	{
+		&self.config
+	}
+
+
+	/// Create the peer, start it's mailbox on `exec` and return it's address.
+	//
+	pub fn build
+	(
+		self                                ,
+		incoming: impl BoundsIn<Wf>         ,
+		outgoing: impl BoundsOut<Wf>        ,
+		exec    : impl PeerExec<Wf> + Spawn ,
+	)
+		-> Result< Addr<Peer<Wf>>, PeerErr >
+
+	{
+		self.build_with( exec, |_| (incoming, outgoing) )
+	}
+
+
+	// Set the hook the peer calls before closing the connection. For transports that can tell the remote
+	// why we close.
+	//
+	pub(super) fn close_hook( mut self, hook: CloseHook ) -> Self
+	{
+		self.close_hook = Some( hook );
+		self
+	}
+
+
+	// Like build, but the transport can depend on the address of the peer.
+	//
+	pub(crate) fn build_with<I, O>
+	(
+		self                                                ,
+		exec     : impl PeerExec<Wf> + Spawn                ,
+		transport: impl FnOnce( &Addr<Peer<Wf>> ) -> (I, O) ,
+	)
+		-> Result< Addr<Peer<Wf>>, PeerErr >
+
+		where I: BoundsIn<Wf>  ,
+		      O: BoundsOut<Wf> ,
+
+	{
+		let mut builder = Addr::builder();
+
+		if let Some( name ) = &self.config.name
+		{
+			builder = builder.name( name.clone().into() );
+		}
+
+		let (addr, mb) = builder.build();
+
+		let (incoming, outgoing) = transport( &addr );
+
+		let slots = self.config.backpressure;
+		let bp    = self.backpressure.or_else( || slots.map( |slots| Arc::new( BackPressure::new( slots ) ) ) );
+
+		let mut peer = Peer::new( addr.clone(), incoming, outgoing, exec.clone(), bp, self.config.grace_period )?;
+
+		peer.pharos          = self.pharos;
+		peer.close_hook      = self.close_hook;
+		peer.conn_id_counter = AtomicU64::new( self.config.first_cid.get() );
+
+		peer.set_timeout( self.config.timeout );
+		peer.set_idle_timeout( self.config.idle_timeout )?;
+		peer.set_reflection( self.config.reflection );
+
+		if self.config.trace_context
+		{
+			peer.enable_trace_context();
+		}
+
+		if let Some( limit ) = self.config.call_limit
+		{
+			peer.set_call_limit( limit );
+		}
+
+		for sm in self.services
+		{
+			peer.register_services( sm );
+		}
+
+		exec.spawn( mb.start( peer ).map(|_|()) ).map_err( |_|
+		{
+			let ctx = PeerErrCtx::default()
+
+				.peer_id  ( addr.id()                             )
+				.peer_name( addr.name()                           )
+				.context  ( Some( "Mailbox of peer".to_string() ) )
+			;
+
+			PeerErr::Spawn{ ctx }
+
+		})?;
+
+		Ok( addr )
+	}
+}
+
+
+impl PeerBuilder<ThesWF>
+{
+	/// Frame the connection with the [`thes_wf`](crate::thes_wf) codec, using the `max_size` of
+	/// the config, and build the peer. See [`PeerBuilder::build`].
+	//
+	pub fn build_async_read
+	(
+		self                                                               ,
+		socket: impl FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static ,
+		exec  : impl PeerExec<ThesWF> + Spawn                              ,
+	)
+		-> Result< Addr<Peer>, PeerErr >
+
+	{
+		let (reader, writer) = socket.split();
+
+		let stream = thes_wf::Decoder::new( reader, self.config.max_size );
+		let sink   = thes_wf::Encoder::new( writer, self.config.max_size );
+
+		self.build( stream, sink, exec )
+	}
+}
+
+
+impl<Wf: WireFormat> Default for PeerBuilder<Wf>
+{
+	fn default() -> Self
+	{
+		Self::new()
+	}
+}
+
+
+impl<Wf: WireFormat> fmt::Debug for PeerBuilder<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "PeerBuilder" )
+
+			.field( "config"      , &self.config         )
+			.field( "backpressure", &self.backpressure   )
+			.field( "services"    , &self.services.len() )
+			.finish()
+	}
+}
+
+
+impl<Wf: WireFormat> Observable<PeerEvent> for PeerBuilder<Wf>
+{
+	type Error = PharErr;
+
+	/// Register an observer before the peer starts. See the `Observable` impl of [`Peer`].
+	//
+	fn observe( &mut self, config: ObserveConfig<PeerEvent> ) -> Observe< '_, PeerEvent, PharErr >
+	{
+		self.pharos.observe( config )
+	}
+}
+
+
+
+// Durations in config files are whole milliseconds.
+//
+mod millis
+{
+	use crate::import::*;
+
+	pub(super) fn serialize<S: serde::Serializer>( d: &Duration, s: S ) -> Result<S::Ok, S::Error>
+	{
+		s.serialize_u64( d.as_millis().try_into().unwrap_or( u64::MAX ) )
+	}
+
+	pub(super) fn deserialize<'de, D: serde::Deserializer<'de>>( d: D ) -> Result<Duration, D::Error>
+	{
+		u64::deserialize( d ).map( Duration::from_millis )
+	}
+}
+
+
+mod opt_millis
+{
+	use crate::import::*;
+
+	pub(super) fn serialize<S: serde::Serializer>( d: &Option<Duration>, s: S ) -> Result<S::Ok, S::Error>
+	{
+		d.map( |d| d.as_millis().try_into().unwrap_or( u64::MAX ) ).serialize( s )
+	}
+
+	pub(super) fn deserialize<'de, D: serde::Deserializer<'de>>( d: D ) -> Result<Option<Duration>, D::Error>
+	{
+		Ok( Option::<u64>::deserialize( d )?.map( Duration::from_millis ) )
+	}
+}
@@ -11,8 +11,10 @@ use crate::{ import::*, * };
 //
 pub struct Call<Wf>
 {
-	 wf   : Wf              ,
-	_ghost: PhantomData<Wf> ,
+	 wf      : Wf                   ,
+	 priority: Priority             ,
+	 trace   : Option<TraceContext> ,
+	_ghost   : PhantomData<Wf>      ,
 }
 
 impl<Wf: WireFormat> Message for Call<Wf>
@@ -29,7 +31,23 @@ impl<Wf: WireFormat> Call<Wf>
 	//
 	pub fn new( wf: Wf ) -> Self
 	{
-		Self{ wf, _ghost: PhantomData }
+		Self{ wf, priority: Priority::Call, trace: None, _ghost: PhantomData }
+	}
+
+	/// Send the call out with the given priority instead of `Priority::Call`.
+	//
+	pub fn with_priority( mut self, priority: Priority ) -> Self
+	{
+		self.priority = priority;
+		self
+	}
+
+	/// Send the trace context along with the call. See [`TraceContext`].
+	//
+	pub fn with_trace( mut self, trace: impl Into<Option<TraceContext>> ) -> Self
+	{
+		self.trace = trace.into();
+		self
 	}
 
 	/// Get the service id.
@@ -44,7 +62,8 @@ impl<Wf: WireFormat> Call<Wf>
 
 /// Handler for outgoing Calls
 ///
-/// If the sending to the remote succeeds, you get back a oneshot receiver.
+/// If the sending to the remote succeeds, you get back a oneshot receiver. If the call has to wait
+/// for a slot because of the [`CallLimit`], you get the receiver right away.
 ///
 /// If sending to the remote fails, you get a PeerErr.
 /// If the connection gets dropped before the answer comes, the oneshot::Receiver will err with Canceled.
@@ -54,9 +73,7 @@ impl<Wf: WireFormat + Send + 'static> Handler<Call<Wf>> for Peer<Wf>
 {
 	#[async_fn] fn handle( &mut self, mut call: Call<Wf> ) -> <Call<Wf> as Message>::Return
 	{
-		let identity = self.identify();
-
-		trace!( "{}: polled Handler<Call>", &identity );
+		trace!( "{}: polled Handler<Call>", self.identify() );
 
 		// we no longer have our address, we're shutting down. we can't really do anything
 		// without our address we won't have the sink for the connection either. We can
@@ -70,11 +87,121 @@ impl<Wf: WireFormat + Send + 'static> Handler<Call<Wf>> for Peer<Wf>
 		};
 
 
-		// If self.closed is false, there should always be an address.
+		let cid = self.next_cid();
+
+		call.wf.set_cid( cid );
+
+		let (sender, receiver) = oneshot::channel::< Result<Wf, ConnectionError> >() ;
+
+		let call = OutgoingCall
+		{
+			frame   : call.wf                 ,
+			priority: call.priority           ,
+			trace   : call.trace              ,
+			reply   : ReplyTo::Call( sender ) ,
+		};
+
+		self.send_call( call ).await?;
+
+		Ok( receiver )
+	}
+}
+
+
+
+// An outgoing call that has it's cid, but might still have to wait for a slot. See `CallLimit`.
+//
+pub(super) struct OutgoingCall<Wf>
+{
+	pub(super) frame   : Wf                   ,
+	pub(super) priority: Priority             ,
+	pub(super) trace   : Option<TraceContext> ,
+	pub(super) reply   : ReplyTo<Wf>          ,
+}
+
+
+impl<Wf> OutgoingCall<Wf>
+{
+	// The caller is no longer waiting for the response.
+	//
+	pub(super) fn cancelled( &self ) -> bool
+	{
+		match &self.reply
+		{
+			ReplyTo::Call  ( tx     ) => tx.is_canceled(),
+			ReplyTo::Stream( stream ) => stream.tx.is_closed(),
+		}
+	}
+}
+
+
+
+// Where the response to an outgoing call goes.
+//
+pub(super) enum ReplyTo<Wf>
+{
+	Call  ( oneshot::Sender< Result<Wf, ConnectionError> > ) ,
+	Stream( StreamReply<Wf>                                ) ,
+}
+
+
+
+// The parts of a streaming call the peer needs when it goes out.
+//
+pub(super) struct StreamReply<Wf>
+{
+	pub(super) tx    : mpsc::UnboundedSender< Result<Wf, ConnectionError> > ,
+	pub(super) grants: mpsc::UnboundedReceiver< u32 >                       ,
+	pub(super) window: NonZeroU32                                           ,
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	// Send out a call that got a slot. The response channel and the timeout are only
+	// registered now, so waiting calls don't hold them. On failure the response channel
+	// is dropped.
+	//
+	pub(super) async fn start_call( &mut self, call: OutgoingCall<Wf> ) -> Result<(), PeerErr>
+	{
+		let OutgoingCall{ frame, priority, trace, reply } = call;
+
+		let cid  = frame.cid();
+		let sid  = frame.sid();
+		let span = self.call_span( sid, cid, trace.as_ref() );
+
+		// The response must be expected before the frame goes out, since the frame might wait
+		// for credit and calls without a response are not sent.
 		//
-		let mut self_addr = self.addr.as_ref().unwrap().clone();
-		let mut cid       = ConnID::from( self.conn_id_counter.fetch_add(1, Relaxed ) );
-		let     sid       = call.wf.sid();
+		match reply
+		{
+			ReplyTo::Call  ( tx     ) => { self.responses.insert( cid, tx ); }
+			ReplyTo::Stream( stream ) => self.open_stream( cid, sid, stream, priority ).await?,
+		}
+
+		let mut res = self.send_request( frame, priority, trace, None ).instrument( span ).await;
+
+		if res.is_ok()
+		{
+			res = self.spawn_timeout( cid, sid );
+		}
+
+		if res.is_err()
+		{
+			self.responses.remove( &cid );
+			self.streams  .remove( &cid );
+		}
+
+		res
+	}
+
+
+	// Get a new cid for an outgoing call.
+	//
+	pub(super) fn next_cid( &self ) -> ConnID
+	{
+		let mut cid = ConnID::from( self.conn_id_counter.fetch_add( 1, Relaxed ) );
 
 		// We wrapped round.
 		// It must not be 0 otherwise the remote will consider it a send, and it's reserved.
@@ -84,19 +211,20 @@ impl<Wf: WireFormat + Send + 'static> Handler<Call<Wf>> for Peer<Wf>
 			cid = ConnID::from( self.conn_id_counter.fetch_add( 1, Relaxed ) );
 		}
 
-		call.wf.set_cid( cid );
-
-		self.send_msg( call.wf ).await?;
+		cid
+	}
 
-		// If the above succeeded, store the other end of the channel
-		//
-		let (sender, receiver) = oneshot::channel::< Result<Wf, ConnectionError> >() ;
 
+	// Send a timeout message to ourselves after self.timeout has passed.
+	//
+	pub(super) fn spawn_timeout( &mut self, cid: ConnID, sid: ServiceID ) -> Result<(), PeerErr>
+	{
+		let identity = self.identify();
 
-		// send a timeout message to ourselves.
+		// If self.closed is false, there should always be an address.
 		//
-		let delay = self.timeout;
-
+		let mut self_addr = self.addr.as_ref().unwrap().clone();
+		let     delay     = self.timeout;
 
 		let task = async move
 		{
@@ -116,12 +244,6 @@ impl<Wf: WireFormat + Send + 'static> Handler<Call<Wf>> for Peer<Wf>
 			let ctx = self.ctx( sid, None, "timeout for outgoing Call" );
 
 			PeerErr::Spawn{ ctx }
-
-		})?;
-
-
-		self.responses.insert( cid, sender );
-
-		Ok( receiver )
+		})
 	}
 }
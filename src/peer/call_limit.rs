@@ -0,0 +1,246 @@
+use
+{
+	crate::{ import::*, * } ,
+	super::OutgoingCall     ,
+};
+
+
+/// A maximum for the number of outgoing calls that wait for their response at the same time. Set it
+/// with [`Peer::set_call_limit`], [`PeerBuilder::call_limit`] or in [`PeerConfig::call_limit`].
+///
+/// Every outstanding call costs the peer some memory and a timeout task. When a remote is slow,
+/// they pile up. With a limit, calls beyond the maximum either wait until a slot frees up or are
+/// refused, depending on the [`CallLimitPolicy`]. Streaming calls count as well, until the end of
+/// their stream. A waiting call only takes a place in a queue, bounded by `max_waiting`. It gets it's
+/// response channel and timeout when it goes out.
+///
+/// [`PeerStats`] shows the calls in flight and the ones waiting for a slot.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+pub struct CallLimit
+{
+	/// The maximum number of outstanding outgoing calls.
+	//
+	pub max: NonZeroUsize,
+
+	/// What happens to calls beyond the maximum.
+	//
+	pub policy: CallLimitPolicy,
+
+	/// How many calls can wait for a slot with [`CallLimitPolicy::Wait`]. When the queue is full, calls
+	/// are refused with [`PeerErr::TooManyCalls`]. Defaults to 1024.
+	//
+	#[ serde( default = "default_max_waiting" ) ]
+	//
+	pub max_waiting: usize,
+}
+
+
+impl CallLimit
+{
+	/// Create a new limit. At most 1024 calls wait for a slot, see [`CallLimit::with_max_waiting`].
+	//
+	pub fn new( max: NonZeroUsize, policy: CallLimitPolicy ) -> Self
+	{
+		Self { max, policy, max_waiting: default_max_waiting() }
+	}
+
+
+	/// Set how many calls can wait for a slot with [`CallLimitPolicy::Wait`].
+	//
+	pub fn with_max_waiting( mut self, max_waiting: usize ) -> Self
+	{
+		self.max_waiting = max_waiting;
+		self
+	}
+}
+
+
+fn default_max_waiting() -> usize
+{
+	1024
+}
+
+
+
+/// What to do with an outgoing call when the [`CallLimit`] is reached.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+pub enum CallLimitPolicy
+{
+	/// Queue the call in the peer. It goes out as soon as an outstanding call gets it's response,
+	/// times out or fails. The caller waits for the response as usual, the timeout only starts when
+	/// the call goes out. The handler for the call doesn't block, so the peer keeps processing other
+	/// messages. If the caller gives up, the call doesn't go out.
+	//
+	Wait,
+
+	/// Refuse the call with [`PeerErr::TooManyCalls`].
+	//
+	FailFast,
+}
+
+
+
+// The calls holding a slot and the ones waiting for one.
+//
+pub(super) struct CallSlots<Wf>
+{
+	pub(super) limit  : CallLimit                    ,
+	pub(super) active : HashSet< ConnID >            ,
+	pub(super) waiting: VecDeque< OutgoingCall<Wf> > ,
+}
+
+
+impl<Wf> CallSlots<Wf>
+{
+	fn new( limit: CallLimit ) -> Self
+	{
+		Self
+		{
+			limit                    ,
+			active : HashSet::new()  ,
+			waiting: VecDeque::new() ,
+		}
+	}
+}
+
+
+impl<Wf> fmt::Debug for CallSlots<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "CallSlots" )
+
+			.field( "limit"  , &self.limit         )
+			.field( "active" , &self.active.len()  )
+			.field( "waiting", &self.waiting.len() )
+			.finish()
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Limit the number of outstanding outgoing calls. See [`CallLimit`]. Setting it again replaces
+	/// the previous limit. Calls that went out before the limit was set don't count.
+	//
+	pub fn set_call_limit( &mut self, limit: CallLimit )
+	{
+		match &mut self.call_slots
+		{
+			Some( slots ) => slots.limit = limit,
+			None          => self.call_slots = Some( CallSlots::new( limit ) ),
+		}
+	}
+
+
+	// Send out a call if it gets a slot. Otherwise queue or refuse it depending on the policy.
+	//
+	pub(super) async fn send_call( &mut self, call: OutgoingCall<Wf> ) -> Result<(), PeerErr>
+	{
+		let sid = call.frame.sid();
+		let cid = call.frame.cid();
+
+		let slots = match &mut self.call_slots
+		{
+			Some( slots ) => slots,
+			None          => return self.start_call( call ).await,
+		};
+
+		if slots.active.len() < slots.limit.max.get()
+		{
+			slots.active.insert( cid );
+
+			let res = self.start_call( call ).await;
+
+			if res.is_err()
+			{
+				self.call_slots.as_mut().expect( "call slots" ).active.remove( &cid );
+			}
+
+			return res;
+		}
+
+		let room = slots.waiting.len() < slots.limit.max_waiting;
+
+		match slots.limit.policy
+		{
+			CallLimitPolicy::Wait if room =>
+			{
+				slots.waiting.push_back( call );
+
+				trace!( "{}: call limit reached, queueing call. sid: {}", self.identify(), sid );
+
+				Ok(())
+			}
+
+			_ =>
+			{
+				let ctx = self.ctx( sid, cid, "Maximum of outstanding outgoing calls reached" );
+				let err = PeerErr::TooManyCalls{ ctx };
+
+				self.metrics.lock().error( &err );
+
+				Err( err )
+			}
+		}
+	}
+
+
+	// An outgoing call is done: it got it's response, timed out or failed. Free it's slot and let
+	// waiting calls go out.
+	//
+	pub(super) async fn call_done( &mut self, cid: ConnID )
+	{
+		let slots = match &mut self.call_slots
+		{
+			Some( slots ) => slots,
+			None          => return,
+		};
+
+		if !slots.active.remove( &cid ) { return }
+
+		loop
+		{
+			let slots = self.call_slots.as_mut().expect( "call slots" );
+
+			if slots.active.len() >= slots.limit.max.get() { break }
+
+			let next = match slots.waiting.pop_front()
+			{
+				Some( next ) => next,
+				None         => break,
+			};
+
+			// The caller gave up waiting.
+			//
+			if next.cancelled() { continue }
+
+			let next_cid = next.frame.cid();
+
+			slots.active.insert( next_cid );
+
+			// On failure the response channel is dropped, which wakes up the caller.
+			//
+			if let Err( err ) = self.start_call( next ).await
+			{
+				self.call_slots.as_mut().expect( "call slots" ).active.remove( &next_cid );
+				self.metrics.lock().forget( next_cid );
+
+				self.report( err ).await;
+			}
+		}
+	}
+
+
+	// The number of calls waiting for a slot.
+	//
+	pub(super) fn waiting_calls( &self ) -> usize
+	{
+		self.call_slots.as_ref().map( |s| s.waiting.len() ).unwrap_or_default()
+	}
+}
@@ -29,6 +29,14 @@ impl<Wf> CallResponse<Wf>
 	{
 		Self{ msg }
 	}
+
+
+	/// The frame with the response.
+	//
+	pub fn into_inner( self ) -> Wf
+	{
+		self.msg
+	}
 }
 
 
@@ -42,6 +50,10 @@ impl<Wf: WireFormat + Send + 'static> Handler<CallResponse<Wf>> for Peer<Wf>
 	{
 		trace!( "{}: sending OUT CallResponse", self.identify() );
 
+		// This is also the end of a streaming response.
+		//
+		self.stream_windows.remove( &wrap.msg.cid() );
+
 		let res = self.send_msg( wrap.msg ).await;
 
 		if let Some( ref bp ) = self.backpressure
@@ -0,0 +1,556 @@
+use
+{
+	crate::{ import::*, * }                       ,
+	super::{ OutgoingCall, ReplyTo, StreamReply } ,
+};
+
+
+/// Type representing an outgoing call to a streaming service. The remote will answer with a
+/// number of stream chunks followed by an end of stream marker, all carrying the cid of the
+/// request.
+///
+/// Normally you don't use this directly, but use `RemoteAddr::call_stream` to call
+/// remote streaming services.
+//
+#[ derive( Debug ) ]
+//
+pub struct CallStream<Wf>
+{
+	wf      : Wf                   ,
+	buffer  : usize                ,
+	priority: Priority             ,
+	trace   : Option<TraceContext> ,
+}
+
+impl<Wf: WireFormat> Message for CallStream<Wf>
+{
+	/// We do not await the stream in the async handle method below, since we don't want
+	/// to hang the peer whilst waiting for the response. That's why we return a stream.
+	//
+	type Return = Result< ResponseStream<Wf>, PeerErr >;
+}
+
+impl<Wf: WireFormat> CallStream<Wf>
+{
+	/// Create a new CallStream to send an outgoing message over the peer.
+	///
+	/// *buffer*: The flow control window of the stream. The remote sends at most this many
+	/// stream chunks before you have consumed them. A stream you don't poll only holds up
+	/// the remote task producing it, not the peer.
+	//
+	pub fn new( wf: Wf, buffer: usize ) -> Self
+	{
+		Self{ wf, buffer, priority: Priority::Call, trace: None }
+	}
+
+	/// Send the call out with the given priority instead of `Priority::Call`.
+	//
+	pub fn with_priority( mut self, priority: Priority ) -> Self
+	{
+		self.priority = priority;
+		self
+	}
+
+	/// Send the trace context along with the call. See [`TraceContext`].
+	//
+	pub fn with_trace( mut self, trace: impl Into<Option<TraceContext>> ) -> Self
+	{
+		self.trace = trace.into();
+		self
+	}
+
+	/// Get the service id.
+	//
+	pub fn service( &self ) -> ServiceID
+	{
+		self.wf.sid()
+	}
+}
+
+
+
+/// Handler for outgoing streaming calls.
+///
+/// If the sending to the remote succeeds, you get back a [`ResponseStream`].
+/// If sending to the remote fails, you get a PeerErr.
+//
+impl<Wf: WireFormat + Send + 'static> Handler<CallStream<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, mut call: CallStream<Wf> ) -> <CallStream<Wf> as Message>::Return
+	{
+		trace!( "{}: polled Handler<CallStream>", self.identify() );
+
+		if self.closed
+		{
+			let ctx = self.ctx( None, None, "Handler<CallStream> for Peer" );
+
+			return Err( PeerErr::ConnectionClosed{ ctx } );
+		};
+
+
+		let cid = self.next_cid();
+		let sid = call.wf.sid();
+
+		call.wf.set_cid( cid );
+
+		let window = u32::try_from( call.buffer ).unwrap_or( u32::MAX );
+		let window = NonZeroU32::new( window ).unwrap_or( NonZeroU32::new(1).unwrap() );
+
+		// The ResponseStream returns credit through this channel. When it's dropped, the stream
+		// is cancelled if it's still running.
+		//
+		let (grant_tx, grants) = mpsc::unbounded();
+		let (tx      , rx    ) = mpsc::unbounded();
+
+		let call = OutgoingCall
+		{
+			frame   : call.wf                                              ,
+			priority: call.priority                                        ,
+			trace   : call.trace                                           ,
+			reply   : ReplyTo::Stream( StreamReply{ tx, grants, window } ) ,
+		};
+
+		self.send_call( call ).await?;
+
+		let ctx = self.ctx( sid, cid, "Response stream of outgoing call" );
+
+		Ok( ResponseStream::new( rx, grant_tx, window, ctx ) )
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	// Tell the remote the flow control window of a streaming call that is about to go out and
+	// start forwarding the credit the ResponseStream gives back.
+	//
+	pub(super) async fn open_stream
+	(
+		&mut self                 ,
+		cid     : ConnID          ,
+		sid     : ServiceID       ,
+		stream  : StreamReply<Wf> ,
+		priority: Priority        ,
+	)
+		-> Result<(), PeerErr>
+	{
+		let StreamReply{ tx, mut grants, window } = stream;
+
+		// The window goes out before the call with the same priority, so the remote knows it
+		// by the time the call comes in.
+		//
+		self.send_stream_ctrl( cid, &StreamCtrl::Open( window ), priority ).await?;
+
+		let mut addr = match &self.addr
+		{
+			Some( addr ) => addr.clone(),
+
+			None =>
+			{
+				let ctx = self.ctx( sid, cid, "Handler<CallStream> for Peer" );
+
+				return Err( PeerErr::ConnectionClosed{ ctx } );
+			}
+		};
+
+		let task = async move
+		{
+			while let Some( credits ) = grants.next().await
+			{
+				if addr.send( GrantStream{ cid, ctrl: StreamCtrl::Window( credits ) } ).await.is_err()
+				{
+					return Ok( Response::Nothing );
+				}
+			}
+
+			// If the peer is gone, the stream is gone as well.
+			//
+			let _ = addr.send( GrantStream{ cid, ctrl: StreamCtrl::Cancel } ).await;
+
+			Ok( Response::Nothing )
+		};
+
+		if self.nursery.nurse( task ).is_err()
+		{
+			let ctx = self.ctx( sid, cid, "Spawn task for ResponseStream" );
+
+			return Err( PeerErr::Spawn{ ctx } );
+		}
+
+		self.streams.insert( cid, OutgoingStream{ tx, started: false } );
+
+		Ok(())
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	// Serialize and send a control frame for a streaming response.
+	//
+	pub(super) async fn send_stream_ctrl( &mut self, cid: ConnID, ctrl: &StreamCtrl, priority: Priority ) -> Result<(), PeerErr>
+	{
+		let mut wf = Wf::with_capacity( std::mem::size_of::<StreamCtrl>() * 2 );
+
+		wf.set_sid( ServiceID::stream_ctrl() );
+		wf.set_cid( cid                      );
+
+		serde_cbor::to_writer( &mut wf, ctrl ).expect( "serialize StreamCtrl" );
+
+		self.send_prio( wf, priority ).await
+	}
+
+
+	// Control frame for a streaming response we are sending.
+	//
+	pub(super) async fn stream_ctrl( &mut self, cid: ConnID, frame: Wf )
+	{
+		let ctrl = match serde_cbor::from_slice::<StreamCtrl>( frame.msg() )
+		{
+			Ok ( ctrl ) => ctrl,
+			Err( _    ) =>
+			{
+				let ctx = self.ctx( ServiceID::stream_ctrl(), cid, "Deserialize stream control frame" );
+
+				return self.report( PeerErr::Deserialize{ ctx } ).await;
+			}
+		};
+
+		trace!( "{}: Incoming stream control: {:?}, cid: {}", self.identify(), ctrl, cid );
+
+		match ctrl
+		{
+			StreamCtrl::Open( window ) =>
+			{
+				// The remote doesn't send the call for these. Without a window, the stream is sent
+				// without flow control, which the remote can handle.
+				//
+				if self.stream_windows.len() >= MAX_STREAM_WINDOWS
+				{
+					warn!( "{}: Too many open stream windows, ignoring window for cid: {}.", self.identify(), cid );
+					return;
+				}
+
+				self.stream_windows.insert( cid, StreamWindow::new( window ) );
+			}
+
+			StreamCtrl::Window( credits ) =>
+			{
+				// The stream might have ended already.
+				//
+				if let Some( window ) = self.stream_windows.get( &cid             ) {
+				if let Some( slots  ) = NonZeroUsize::new      ( credits as usize )
+				{
+					window.credits.add_slots( slots );
+				}}
+			}
+
+			StreamCtrl::Cancel =>
+			{
+				if let Some( window ) = self.stream_windows.remove( &cid )
+				{
+					window.cancel();
+				}
+			}
+		}
+	}
+
+
+	// Cancel the streams we are sending, eg. when the connection closes.
+	//
+	pub(super) fn cancel_stream_windows( &mut self )
+	{
+		for (_, window) in self.stream_windows.drain()
+		{
+			window.cancel();
+		}
+	}
+}
+
+
+
+// How many streaming responses can have a flow control window at the same time. This
+// bounds what a remote can make us keep by opening windows without sending the call.
+//
+const MAX_STREAM_WINDOWS: usize = 1024;
+
+
+
+// Control frames for streaming responses. They have the cid of the call.
+//
+#[ derive( Debug, Serialize, Deserialize ) ]
+//
+pub(super) enum StreamCtrl
+{
+	// Sent before a streaming call. How many chunks the remote can send before we consume them.
+	//
+	Open( NonZeroU32 ),
+
+	// We consumed chunks, so the remote can send more.
+	//
+	Window( u32 ),
+
+	// We are no longer interested in the stream.
+	//
+	Cancel,
+}
+
+
+
+// The flow control window of a streaming response we are sending. Shared with the task
+// that sends out the chunks.
+//
+#[ derive( Debug, Clone ) ]
+//
+pub(super) struct StreamWindow
+{
+	pub(super) credits  : Arc<BackPressure> ,
+	pub(super) cancelled: Arc<AtomicBool>   ,
+}
+
+
+impl StreamWindow
+{
+	fn new( window: NonZeroU32 ) -> Self
+	{
+		Self
+		{
+			credits  : Arc::new( BackPressure::new( window.get().into() ) ) ,
+			cancelled: Arc::new( AtomicBool::new( false )                  ) ,
+		}
+	}
+
+
+	// Stop the task sending the stream. Adding a slot wakes it up if it's waiting for credit.
+	//
+	fn cancel( &self )
+	{
+		self.cancelled.store( true, SeqCst );
+		self.credits.add_slots( NonZeroUsize::new(1).unwrap() );
+	}
+
+
+	// Wait for credit to send one chunk. Returns false if the stream was cancelled.
+	//
+	pub(super) async fn take( &self ) -> bool
+	{
+		self.credits.wait().await;
+
+		if self.cancelled.load( SeqCst ) { return false }
+
+		self.credits.remove_slots( NonZeroUsize::new(1).unwrap() );
+
+		true
+	}
+}
+
+
+
+// The consumer of a ResponseStream returns credit or cancels the stream.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct GrantStream
+{
+	cid : ConnID     ,
+	ctrl: StreamCtrl ,
+}
+
+impl Message for GrantStream
+{
+	type Return = ();
+}
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<GrantStream> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: GrantStream )
+	{
+		if self.closed { return }
+
+		let GrantStream{ cid, ctrl } = msg;
+
+		match ctrl
+		{
+			// The stream ended or timed out, no point in granting more.
+			//
+			StreamCtrl::Window(_) if !self.streams.contains_key( &cid ) => return,
+
+			// The consumer dropped the stream before the end.
+			//
+			StreamCtrl::Cancel =>
+			{
+				if self.streams.remove( &cid ).is_none() { return }
+
+				self.metrics.lock().forget( cid );
+				self.call_done( cid ).await;
+			}
+
+			_ => {}
+		}
+
+		if let Err( e ) = self.send_stream_ctrl( cid, &ctrl, Priority::Control ).await
+		{
+			self.report( e ).await;
+		}
+	}
+}
+
+
+
+// The state the peer keeps for a streaming call we made. The timeout only applies
+// until the first frame of the response comes in.
+//
+pub(super) struct OutgoingStream<Wf>
+{
+	pub(super) tx     : mpsc::UnboundedSender< Result<Wf, ConnectionError> > ,
+	pub(super) started: bool                                                 ,
+}
+
+
+
+/// The stream of frames returned by a remote streaming service. This will yield the stream
+/// chunks and end when the remote signals the end of the stream. If the remote reports an error,
+/// the call times out or the connection closes before the end of the stream, it yields one error
+/// and then ends.
+///
+/// Consuming chunks gives the remote credit to send more. Dropping the stream before the end
+/// cancels it on the remote.
+//
+pub struct ResponseStream<Wf>
+{
+	rx      : mpsc::UnboundedReceiver< Result<Wf, ConnectionError> > ,
+	grants  : Option< futUnboundSender<u32> >                        ,
+	batch   : u32                                                    ,
+	consumed: u32                                                    ,
+	ctx     : PeerErrCtx                                             ,
+	done    : bool                                                   ,
+}
+
+
+impl<Wf> ResponseStream<Wf>
+{
+	fn new
+	(
+		rx    : mpsc::UnboundedReceiver< Result<Wf, ConnectionError> > ,
+		grants: futUnboundSender<u32>                                  ,
+		window: NonZeroU32                                             ,
+		ctx   : PeerErrCtx                                             ,
+	)
+		-> Self
+	{
+		// Grant credits in batches so we don't send a control frame for every chunk.
+		//
+		let batch = std::cmp::max( window.get() / 2, 1 );
+
+		Self { rx, grants: Some( grants ), batch, consumed: 0, ctx, done: false }
+	}
+
+
+	// Give the remote credit for a chunk we consumed.
+	//
+	fn consumed( &mut self )
+	{
+		self.consumed += 1;
+
+		if self.consumed < self.batch { return }
+
+		if let Some( grants ) = &self.grants
+		{
+			// If the peer is gone, the next poll of rx will tell.
+			//
+			let _ = grants.unbounded_send( self.consumed );
+		}
+
+		self.consumed = 0;
+	}
+
+
+	// The stream is over. The remote no longer needs credit.
+	//
+	fn end( &mut self )
+	{
+		self.done   = true;
+		self.grants = None;
+	}
+}
+
+
+impl<Wf: WireFormat> Stream for ResponseStream<Wf>
+{
+	type Item = Result<Wf, PeerErr>;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		if self.done
+		{
+			return Poll::Ready( None );
+		}
+
+		let item = match Pin::new( &mut self.rx ).poll_next( cx )
+		{
+			Poll::Pending     => return Poll::Pending,
+			Poll::Ready(item) => item,
+		};
+
+		let ctx = self.ctx.clone();
+
+		let out = match item
+		{
+			Some( Ok(frame) ) if frame.sid().is_stream_end() =>
+			{
+				self.end();
+				return Poll::Ready( None );
+			}
+
+			Some( Ok(frame) ) =>
+			{
+				self.consumed();
+				return Poll::Ready( Some( Ok(frame) ) );
+			}
+
+			Some( Err( ConnectionError::Timeout{..} ) ) =>
+			{
+				let ctx = ctx.context( "Time out waiting for response to outgoing streaming call".to_string() );
+
+				PeerErr::Timeout{ ctx }
+			}
+
+			Some( Err(err) ) =>
+			{
+				let ctx = ctx.context( "Remote could not process our message".to_string() );
+
+				PeerErr::Remote{ err, ctx }
+			}
+
+			// The peer dropped the sender before we saw the end of the stream.
+			//
+			None =>
+			{
+				let ctx = ctx.context( "Peer stopped before the end of the response stream".to_string() );
+
+				PeerErr::ConnectionClosed{ ctx }
+			}
+		};
+
+		self.end();
+
+		Poll::Ready( Some( Err(out) ) )
+	}
+}
+
+
+impl<Wf> fmt::Debug for ResponseStream<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "ResponseStream" )
+
+			.field( "ctx" , &self.ctx  )
+			.field( "done", &self.done )
+
+		.finish()
+	}
+}
@@ -0,0 +1,880 @@
+use
+{
+	crate::{ import::*, * } ,
+	super::RequestError     ,
+};
+
+
+// The window is how many messages we buffer for a channel and the remote chooses it when it opens one,
+// so it can't be bigger than this.
+//
+const MAX_CHANNEL_WINDOW: u32 = 1024;
+
+// How many channels can be open on one connection. The remote can open channels, each of which takes memory.
+//
+const MAX_CHANNELS: usize = 1024;
+
+
+/// A long lived duplex channel to a remote, multiplexed over the connection of a Peer.
+/// Each side of the channel can send messages of type `Out` and receive messages of
+/// type `In`. Messages are serialized with cbor.
+///
+/// Either side can open a channel with [`Channel::open`]. The other side must have
+/// registered a listener for the service id with [`Peer::listen_channels`] before starting
+/// the mailbox of it's peer, otherwise the opener gets a `ConnectionError::UnknownService`
+/// on it's stream.
+///
+/// Every channel has it's own flow control window. The opener chooses it and it applies
+/// in both directions. A side can only send as many messages as the window before the other
+/// side has consumed them from it's stream. This way a slow consumer on one channel never
+/// blocks the other channels or the normal requests on the connection.
+///
+/// The window is at most 1024, a bigger one is reduced to that. A remote can have at most
+/// 1024 channels open on a connection, opening more yields `ConnectionError::RateLimited`.
+///
+/// Use [`Channel::split`] to get a [`ChannelSink`] and a [`ChannelStream`] you can use
+/// from different tasks. Closing the sink tells the remote that we won't send anymore,
+/// which ends the stream on the remote side.
+//
+pub struct Channel<Out, In>
+{
+	id    : ConnID             ,
+	sid   : ServiceID          ,
+	sink  : ChannelSink  <Out> ,
+	stream: ChannelStream<In > ,
+}
+
+
+impl<Out, In> Channel<Out, In>
+
+	where Out: Serialize        + Send + 'static ,
+	      In : DeserializeOwned + Send + 'static ,
+{
+	/// Open a channel to the remote for the service `sid`.
+	///
+	/// *window*: How many messages each side may send before the other side has consumed them.
+	/// At most 1024.
+	//
+	pub async fn open<Wf>( peer: &mut Addr<Peer<Wf>>, sid: ServiceID, window: NonZeroU32 ) -> Result<Self, PeerErr>
+
+		where Wf: WireFormat + Send + 'static
+	{
+		let parts = peer.call( OpenChannel{ sid, window, _ghost: PhantomData } ).await
+
+			.map_err( |_|
+			{
+				let ctx = Peer::err_ctx( peer, sid, None, "Open channel".to_string() );
+
+				PeerErr::PeerGone{ ctx }
+
+			})??
+		;
+
+		Ok( Self::from_parts( parts ) )
+	}
+
+
+	/// The id of this channel. It's the same on both sides of the connection.
+	//
+	pub fn id( &self ) -> ConnID
+	{
+		self.id
+	}
+
+
+	/// The service id this channel was opened for.
+	//
+	pub fn service( &self ) -> ServiceID
+	{
+		self.sid
+	}
+
+
+	/// Split the channel in it's two halves.
+	//
+	pub fn split( self ) -> ( ChannelSink<Out>, ChannelStream<In> )
+	{
+		( self.sink, self.stream )
+	}
+
+
+	fn from_parts<Wf>( parts: ChannelParts<Wf> ) -> Self
+
+		where Wf: WireFormat + Send + 'static
+	{
+		let ChannelParts{ id, sid, window, rx, credits, peer } = parts;
+
+		let ctx = Peer::err_ctx( &peer, sid, id, "Channel".to_string() );
+
+		Self
+		{
+			id                                                                   ,
+			sid                                                                  ,
+			sink  : ChannelSink  ::new( id, peer.clone(), credits, ctx.clone() ) ,
+			stream: ChannelStream::new( id, peer        , rx, window, ctx      ) ,
+		}
+	}
+}
+
+
+
+impl<Out, In> Sink<Out> for Channel<Out, In>
+{
+	type Error = PeerErr;
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Pin::new( &mut self.sink ).poll_ready( cx )
+	}
+
+	fn start_send( mut self: Pin<&mut Self>, item: Out ) -> Result<(), Self::Error>
+	{
+		Pin::new( &mut self.sink ).start_send( item )
+	}
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Pin::new( &mut self.sink ).poll_flush( cx )
+	}
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Pin::new( &mut self.sink ).poll_close( cx )
+	}
+}
+
+
+
+impl<Out, In> Stream for Channel<Out, In>
+{
+	type Item = Result<In, PeerErr>;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		Pin::new( &mut self.stream ).poll_next( cx )
+	}
+}
+
+
+
+impl<Out, In> fmt::Debug for Channel<Out, In>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "Channel" )
+
+			.field( "id" , &self.id  )
+			.field( "sid", &self.sid )
+
+		.finish()
+	}
+}
+
+
+
+// What the sink sends to the peer. Close is in band so it's ordered after the last message.
+//
+enum SinkCmd<Out>
+{
+	Item ( Out ),
+	Close       ,
+}
+
+
+/// The sending half of a [`Channel`]. Every message uses up one credit of the flow control
+/// window. When no credits are left, the sink is not ready until the remote consumes some
+/// messages. Closing the sink ends the stream on the remote side.
+//
+pub struct ChannelSink<Out>
+{
+	id     : ConnID                                                     ,
+	inner  : Pin<Box< dyn Sink< SinkCmd<Out>, Error=PeerErr > + Send >> ,
+	closing: bool                                                       ,
+}
+
+
+impl<Out> ChannelSink<Out>
+
+	where Out: Serialize + Send + 'static
+{
+	fn new<Wf>( id: ConnID, peer: Addr<Peer<Wf>>, credits: Arc<BackPressure>, ctx: PeerErrCtx ) -> Self
+
+		where Wf: WireFormat + Send + 'static
+	{
+		let inner = futures::sink::unfold( (peer, credits), move |(mut peer, credits), cmd: SinkCmd<Out>|
+		{
+			let ctx = ctx.clone();
+
+			async move
+			{
+				let res = match cmd
+				{
+					SinkCmd::Item( item ) =>
+					{
+						let mut wf = Wf::with_capacity( std::mem::size_of::<Out>() );
+
+						wf.set_sid( ServiceID::channel_data() );
+						wf.set_cid( id                        );
+
+						serde_cbor::to_writer( &mut wf, &item ).map_err( |_|
+						{
+							PeerErr::Serialize{ ctx: ctx.clone().context( "Serialize message for channel".to_string() ) }
+						})?;
+
+						credits.wait().await;
+						credits.remove_slots( NonZeroUsize::new(1).unwrap() );
+
+						peer.call( wf ).await
+					}
+
+					SinkCmd::Close => peer.call( CloseChannel{ id } ).await,
+				};
+
+				match res
+				{
+					Ok( Ok(()) ) => Ok( (peer, credits) ),
+					Ok( Err(e) ) => Err( e ),
+
+					Err(_) => Err( PeerErr::PeerGone{ ctx: ctx.context( "Send on channel".to_string() ) } ),
+				}
+			}
+		});
+
+		Self { id, inner: Box::pin( inner ), closing: false }
+	}
+}
+
+
+impl<Out> Sink<Out> for ChannelSink<Out>
+{
+	type Error = PeerErr;
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.inner.as_mut().poll_ready( cx )
+	}
+
+	fn start_send( mut self: Pin<&mut Self>, item: Out ) -> Result<(), Self::Error>
+	{
+		self.inner.as_mut().start_send( SinkCmd::Item(item) )
+	}
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.inner.as_mut().poll_flush( cx )
+	}
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		if !self.closing
+		{
+			futures::ready!( self.inner.as_mut().poll_ready( cx ) )?;
+
+			self.inner.as_mut().start_send( SinkCmd::Close )?;
+			self.closing = true;
+		}
+
+		self.inner.as_mut().poll_close( cx )
+	}
+}
+
+
+impl<Out> fmt::Debug for ChannelSink<Out>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "ChannelSink" )
+
+			.field( "id"     , &self.id      )
+			.field( "closing", &self.closing )
+
+		.finish()
+	}
+}
+
+
+
+/// The receiving half of a [`Channel`]. The stream ends when the remote closes it's sink.
+/// If the remote reports an error for this channel or the connection closes, it yields one
+/// error and then ends. Consuming messages grants new credits to the remote.
+//
+pub struct ChannelStream<In>
+{
+	id   : ConnID                                                     ,
+	inner: Pin<Box< dyn Stream< Item=Result<In, PeerErr> > + Send >> ,
+}
+
+
+impl<In> ChannelStream<In>
+
+	where In: DeserializeOwned + Send + 'static
+{
+	fn new<Wf>
+	(
+		id    : ConnID                                         ,
+		peer  : Addr<Peer<Wf>>                                 ,
+		rx    : mpsc::UnboundedReceiver< Result<Wf, PeerErr> > ,
+		window: NonZeroU32                                     ,
+		ctx   : PeerErrCtx                                     ,
+	)
+		-> Self
+
+		where Wf: WireFormat + Send + 'static
+	{
+		// Grant credits in batches so we don't send a control frame for every message.
+		//
+		let batch = std::cmp::max( window.get() / 2, 1 );
+
+		let inner = futures::stream::unfold( (rx, peer, 0_u32, false), move |(mut rx, mut peer, mut consumed, done)|
+		{
+			let ctx = ctx.clone();
+
+			async move
+			{
+				if done { return None }
+
+				let frame = match rx.next().await?
+				{
+					Ok ( frame ) => frame,
+					Err( err   ) => return Some(( Err(err), (rx, peer, consumed, true) )),
+				};
+
+				consumed += 1;
+
+				if consumed >= batch
+				{
+					// If the peer is gone, the next poll of rx will tell.
+					//
+					let _ = peer.send( GrantChannel{ id, credits: consumed } ).await;
+
+					consumed = 0;
+				}
+
+				let item = serde_cbor::from_slice( frame.msg() ).map_err( |_|
+				{
+					PeerErr::Deserialize{ ctx: ctx.context( "Deserialize message from channel".to_string() ) }
+				});
+
+				Some(( item, (rx, peer, consumed, false) ))
+			}
+		});
+
+		Self { id, inner: Box::pin( inner ) }
+	}
+}
+
+
+impl<In> Stream for ChannelStream<In>
+{
+	type Item = Result<In, PeerErr>;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		self.inner.as_mut().poll_next( cx )
+	}
+}
+
+
+impl<In> fmt::Debug for ChannelStream<In>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "ChannelStream" )
+
+			.field( "id", &self.id )
+
+		.finish()
+	}
+}
+
+
+
+/// The channels the remote opens for a service. Get one from [`Peer::listen_channels`].
+/// The stream ends when the peer closes.
+//
+pub struct ChannelListener<Out, In>
+{
+	sid  : ServiceID                                               ,
+	inner: Pin<Box< dyn Stream< Item=Channel<Out, In> > + Send >> ,
+}
+
+
+impl<Out, In> Stream for ChannelListener<Out, In>
+{
+	type Item = Channel<Out, In>;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		self.inner.as_mut().poll_next( cx )
+	}
+}
+
+
+impl<Out, In> fmt::Debug for ChannelListener<Out, In>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "ChannelListener" )
+
+			.field( "sid", &self.sid )
+
+		.finish()
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	/// Accept channels that the remote opens for the service `sid`. This should be called
+	/// before starting the mailbox of the peer. If the remote tries to open a channel for a
+	/// service nobody listens to, it get's a `ConnectionError::UnknownService`.
+	///
+	/// Registering the same service twice will panic in debug mode.
+	//
+	pub fn listen_channels<Out, In>( &mut self, sid: ServiceID ) -> ChannelListener<Out, In>
+
+		where Out: Serialize        + Send + 'static ,
+		      In : DeserializeOwned + Send + 'static ,
+	{
+		trace!( "{}: Listen for channels: {:?}", self.identify(), &sid );
+
+		debug_assert!
+		(
+			!self.channel_listeners.contains_key( &sid ),
+			"{}: Listen for channels: Can't listen on the same service twice. sid: {}", self.identify(), &sid ,
+		);
+
+		let (tx, rx) = mpsc::unbounded();
+
+		self.channel_listeners.insert( sid, tx );
+
+		ChannelListener { sid, inner: Box::pin( rx.map( Channel::<Out, In>::from_parts ) ) }
+	}
+
+
+	// Serialize and send a control frame for a channel.
+	//
+	async fn send_channel_ctrl( &mut self, id: ConnID, ctrl: &ChannelCtrl ) -> Result<(), PeerErr>
+	{
+		let mut wf = Wf::with_capacity( std::mem::size_of::<ChannelCtrl>() * 2 );
+
+		wf.set_sid( ServiceID::channel_ctrl() );
+		wf.set_cid( id                        );
+
+		serde_cbor::to_writer( &mut wf, ctrl ).expect( "serialize ChannelCtrl" );
+
+		self.send_msg( wf ).await
+	}
+
+
+	// Create the state for a new channel and the parts to hand to the user.
+	//
+	fn new_channel( &mut self, id: ConnID, sid: ServiceID, window: NonZeroU32 ) -> Option< ChannelParts<Wf> >
+	{
+		let peer   = self.addr.clone()?;
+		let window = std::cmp::min( window, NonZeroU32::new( MAX_CHANNEL_WINDOW ).expect( "1024 > 0" ) );
+
+		let (tx, rx) = mpsc::unbounded();
+		let credits  = Arc::new( BackPressure::new( window.get().into() ) );
+
+		self.channels.insert( id, ChannelState
+		{
+			tx           : Some( tx )      ,
+			credits      : credits.clone() ,
+			remote_credit: window.get()    ,
+			local_closed : false           ,
+		});
+
+		Some( ChannelParts{ id, sid, window, rx, credits, peer } )
+	}
+
+
+	// Remove the channel if both sides are done with it.
+	//
+	fn maybe_drop_channel( &mut self, id: ConnID )
+	{
+		if let Some( chan ) = self.channels.get( &id )
+		{
+			if chan.local_closed && chan.tx.is_none()
+			{
+				self.channels.remove( &id );
+			}
+		}
+	}
+
+
+	// Frame with a message on a channel.
+	//
+	pub(super) async fn channel_data( &mut self, id: ConnID, frame: Wf )
+	{
+		let identity = self.identify();
+
+		let chan = match self.channels.get_mut( &id )
+		{
+			Some( chan ) => chan,
+
+			None =>
+			{
+				warn!( "{}: Received message for unknown or closed channel: {}. Dropping message.", identity, id );
+				return;
+			}
+		};
+
+		// The remote doesn't respect the window, we don't buffer more than we promised.
+		//
+		if chan.remote_credit == 0
+		{
+			warn!( "{}: Remote exceeded flow control window of channel: {}. Dropping message.", identity, id );
+			return;
+		}
+
+		chan.remote_credit -= 1;
+
+		trace!( "{}: Incoming channel message, channel: {}", identity, id );
+
+		// The local stream was dropped, nobody is interested anymore.
+		//
+		if let Some( tx ) = &chan.tx
+		{
+			if tx.unbounded_send( Ok(frame) ).is_err()
+			{
+				chan.tx = None;
+				self.maybe_drop_channel( id );
+			}
+		}
+	}
+
+
+	// Control frame for a channel.
+	//
+	pub(super) async fn channel_ctrl( &mut self, id: ConnID, frame: Wf )
+	{
+		let ctrl = match serde_cbor::from_slice::<ChannelCtrl>( frame.msg() )
+		{
+			Ok ( ctrl ) => ctrl,
+			Err( _    ) =>
+			{
+				let ctx = self.ctx( ServiceID::channel_ctrl(), id, "Deserialize channel control frame" );
+
+				return self.handle( RequestError::from( PeerErr::Deserialize{ ctx } ) ).await;
+			}
+		};
+
+		trace!( "{}: Incoming channel control: {:?}, channel: {}", self.identify(), ctrl, id );
+
+		match ctrl
+		{
+			ChannelCtrl::Open{ sid, window } =>
+			{
+				// Don't replace a channel that is open, the remote gets an error on the new one.
+				//
+				if self.channels.contains_key( &id )
+				{
+					let ctx = self.ctx( sid, id, "Remote opens channel with an id that is in use" );
+
+					return self.handle( RequestError::from( PeerErr::ChannelInUse{ ctx } ) ).await;
+				}
+
+				if self.channels.len() >= MAX_CHANNELS
+				{
+					let ctx = self.ctx( sid, id, "Remote opens more channels than allowed" );
+
+					return self.handle( RequestError::from( PeerErr::RateLimited{ ctx } ) ).await;
+				}
+
+				let listener = match self.channel_listeners.get( &sid )
+				{
+					Some( l ) => l.clone(),
+
+					None =>
+					{
+						let ctx = self.ctx( sid, id, "Remote opens channel" );
+
+						return self.handle( RequestError::from( PeerErr::UnknownService{ ctx } ) ).await;
+					}
+				};
+
+				let parts = match self.new_channel( id, sid, window )
+				{
+					Some( parts ) => parts,
+					None          => return,
+				};
+
+				// The listener was dropped.
+				//
+				if listener.unbounded_send( parts ).is_err()
+				{
+					self.channels         .remove( &id  );
+					self.channel_listeners.remove( &sid );
+
+					let ctx = self.ctx( sid, id, "Remote opens channel" );
+
+					self.handle( RequestError::from( PeerErr::UnknownService{ ctx } ) ).await;
+				}
+			}
+
+			ChannelCtrl::Window( credits ) =>
+			{
+				if let Some( chan  ) = self.channels.get( &id       ) {
+				if let Some( slots ) = NonZeroUsize::new( credits as usize )
+				{
+					chan.credits.add_slots( slots );
+				}}
+			}
+
+			ChannelCtrl::Close =>
+			{
+				if let Some( chan ) = self.channels.get_mut( &id )
+				{
+					// Dropping the sender ends the stream.
+					//
+					chan.tx = None;
+				}
+
+				self.maybe_drop_channel( id );
+			}
+		}
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	// The remote reports an error for a channel. It ends the stream.
+	//
+	pub(super) fn channel_err( &mut self, id: ConnID, err: ConnectionError ) -> Result<(), ConnectionError>
+	{
+		let ctx = self.ctx( None, id, "Remote could not process message on channel" );
+
+		match self.channels.remove( &id )
+		{
+			Some( chan ) =>
+			{
+				if let Some( tx ) = chan.tx
+				{
+					let _ = tx.unbounded_send( Err( PeerErr::Remote{ err, ctx } ) );
+				}
+
+				Ok(())
+			}
+
+			None => Err( err ),
+		}
+	}
+
+
+	// End all channel streams with an error when the connection closes.
+	//
+	pub(super) fn close_channels( &mut self )
+	{
+		for (id, chan) in std::mem::take( &mut self.channels )
+		{
+			if let Some( tx ) = chan.tx
+			{
+				let ctx = self.ctx( None, id, "Connection closed before the end of the channel" );
+
+				let _ = tx.unbounded_send( Err( PeerErr::ConnectionClosed{ ctx } ) );
+			}
+		}
+
+		self.channel_listeners.clear();
+	}
+}
+
+
+
+// Control frames for channels.
+//
+#[ derive( Debug, Serialize, Deserialize ) ]
+//
+pub(super) enum ChannelCtrl
+{
+	// Open a channel for a service. The window applies in both directions. The receiver
+	// reduces it to MAX_CHANNEL_WINDOW.
+	//
+	Open { sid: ServiceID, window: NonZeroU32 },
+
+	// The sender of this frame consumed messages, so the receiver can send more.
+	//
+	Window( u32 ),
+
+	// The sender of this frame won't send any more messages on the channel.
+	//
+	Close,
+}
+
+
+
+// The state the peer keeps for an open channel.
+//
+pub(super) struct ChannelState<Wf>
+{
+	// Delivers messages to the ChannelStream. None when the remote closed it's side.
+	//
+	tx: Option< mpsc::UnboundedSender< Result<Wf, PeerErr> > >,
+
+	// How many messages we can still send to the remote. Shared with the ChannelSink.
+	//
+	credits: Arc<BackPressure>,
+
+	// How many messages the remote can still send us.
+	//
+	remote_credit: u32,
+
+	// Whether our ChannelSink is closed.
+	//
+	local_closed: bool,
+}
+
+
+
+// Everything needed to create a Channel for the user.
+//
+pub(super) struct ChannelParts<Wf: WireFormat + 'static>
+{
+	id     : ConnID                                         ,
+	sid    : ServiceID                                      ,
+	window : NonZeroU32                                     ,
+	rx     : mpsc::UnboundedReceiver< Result<Wf, PeerErr> > ,
+	credits: Arc<BackPressure>                              ,
+	peer   : Addr<Peer<Wf>>                                 ,
+}
+
+
+
+// Open a channel to the remote. Used by Channel::open.
+//
+pub(super) struct OpenChannel<Wf>
+{
+	 sid   : ServiceID       ,
+	 window: NonZeroU32      ,
+	_ghost : PhantomData<Wf> ,
+}
+
+impl<Wf: WireFormat + Send + 'static> Message for OpenChannel<Wf>
+{
+	type Return = Result< ChannelParts<Wf>, PeerErr >;
+}
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<OpenChannel<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: OpenChannel<Wf> ) -> <OpenChannel<Wf> as Message>::Return
+	{
+		let OpenChannel{ sid, window, .. } = msg;
+
+		// Ids are random as both sides open channels. Make sure we don't replace one of ours.
+		//
+		let mut id = ConnID::random();
+
+		while self.channels.contains_key( &id )
+		{
+			id = ConnID::random();
+		}
+
+		trace!( "{}: Open channel: {}, sid: {}", self.identify(), id, sid );
+
+		let parts = match self.closed
+		{
+			false => self.new_channel( id, sid, window ),
+			true  => None,
+		};
+
+		let parts = match parts
+		{
+			Some( parts ) => parts,
+
+			None =>
+			{
+				let ctx = self.ctx( sid, id, "Handler<OpenChannel> for Peer" );
+
+				return Err( PeerErr::ConnectionClosed{ ctx } );
+			}
+		};
+
+		if let Err( e ) = self.send_channel_ctrl( id, &ChannelCtrl::Open{ sid, window: parts.window } ).await
+		{
+			self.channels.remove( &id );
+
+			return Err( e );
+		}
+
+		Ok( parts )
+	}
+}
+
+
+
+// Our ChannelStream consumed messages, give the remote more credits.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct GrantChannel
+{
+	id     : ConnID ,
+	credits: u32    ,
+}
+
+impl Message for GrantChannel
+{
+	type Return = ();
+}
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<GrantChannel> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: GrantChannel )
+	{
+		let chan = match self.channels.get_mut( &msg.id )
+		{
+			Some( chan ) => chan,
+			None         => return,
+		};
+
+		// The remote closed, no point in granting more.
+		//
+		if chan.tx.is_none() { return }
+
+		chan.remote_credit = chan.remote_credit.saturating_add( msg.credits );
+
+		if let Err( e ) = self.send_channel_ctrl( msg.id, &ChannelCtrl::Window( msg.credits ) ).await
+		{
+			self.report( e ).await;
+		}
+	}
+}
+
+
+
+// Our ChannelSink was closed, tell the remote.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct CloseChannel
+{
+	id: ConnID,
+}
+
+impl Message for CloseChannel
+{
+	type Return = Result<(), PeerErr>;
+}
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<CloseChannel> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: CloseChannel ) -> Result<(), PeerErr>
+	{
+		match self.channels.get_mut( &msg.id )
+		{
+			Some( chan ) if !chan.local_closed => chan.local_closed = true,
+			_                                  => return Ok(()),
+		}
+
+		self.maybe_drop_channel( msg.id );
+
+		self.send_channel_ctrl( msg.id, &ChannelCtrl::Close ).await
+	}
+}
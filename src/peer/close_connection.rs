@@ -1,4 +1,11 @@
-use { crate :: { peer::* }};
+use { crate :: { peer::* }, futures::future::Either };
+
+
+// How long closing waits for the writer to send out what is queued. A remote that doesn't read
+// can't keep us from closing.
+//
+const WRITER_TIMEOUT: Duration = Duration::from_secs( 5 );
+
 
 /// Control message for [Peer]. The peer needs it's own address for normal operation,
 /// so normally it will never drop, even if you drop all addresses you have of it.
@@ -12,8 +19,12 @@ use { crate :: { peer::* }};
 ///
 /// On an incoming call, an error shall be sent back to the other process.
 ///
-/// The peer will also drop it's outgoing Sink, so the other end of the connection
-/// will be notified that we close it.
+/// The peer will also close it's outgoing Sink, after sending out what was already queued, so the
+/// other end of the connection will be notified that we close it. If the remote doesn't take the
+/// queued frames within 5 seconds, they are dropped. When writing to the connection fails, the peer
+/// closes itself.
+///
+/// Closing a peer that is already closed does nothing.
 ///
 /// If the remote closes the connection, all of this will happen automatically.
 //
@@ -33,12 +44,21 @@ impl Message for CloseConnection { type Return = (); }
 
 
 
+// Called with the CloseConnection before the connection is closed, so transports that can tell the
+// remote why, like WebSocket, can do so.
+//
+pub(crate) type CloseHook = Box< dyn FnOnce( &CloseConnection ) + Send >;
+
+
+
 impl<Wf: WireFormat> Handler<CloseConnection> for Peer<Wf>
 {
 	#[async_fn] fn handle( &mut self, msg: CloseConnection )
 	{
 		trace!( "{}: CloseConnection, by remote: {}, reason: {}", self.identify(), msg.remote, &msg.reason );
 
+		if self.closed { return }
+
 		self.closed = true;
 
 		// Since we don't close it, it shouldn't be closed.
@@ -47,31 +67,59 @@ impl<Wf: WireFormat> Handler<CloseConnection> for Peer<Wf>
 		else          { self.pharos.send( PeerEvent::Closed         ).await.expect( "pharos not closed" ) }
 
 
-		// Try to close the connection properly
+		if let Some( hook ) = self.close_hook.take()
+		{
+			hook( &msg );
+		}
+
+
+		// Try to close the connection properly. The writer sends out what is queued and then
+		// closes the connection.
 		//
-		if let Some(out) = &mut self.outgoing
+		self.outbox.close();
+
+		if let Some( writer ) = self.writer.take()
 		{
-			match out.close().await
-			{
-				Ok(_) => {}
+			let delay = Delay::new( WRITER_TIMEOUT );
 
-				Err(source) =>
-				{
-					let ctx = self.ctx( None, None, "Closing the connection" );
-					let err = PeerErr::WireFormat{ ctx, source };
+			pin_mut!( delay  );
+			pin_mut!( writer );
 
-					// We didn't close it, so the expect should be fine.
-					//
-					self.pharos.send( PeerEvent::Error(err) ).await.expect( "pharos not closed" );
-				}
-			};
+			match futures::future::select( writer, delay ).await
+			{
+				Either::Left ( (Ok (_  ), _) ) => {}
+				Either::Left ( (Err(err), _) ) => self.report( err ).await,
 
-			self.outgoing = None;
-		};
+				// Dropping the handle cancels the writer.
+				//
+				Either::Right( _ ) => warn!
+				(
+					"{}: The remote didn't take the queued frames within {:?}, closing without them.",
+					self.identify(), WRITER_TIMEOUT,
+				),
+			}
+		}
 
 
 		self.nursery.close_nursery();
 
+		// Tasks sending streams might be waiting for credit that will never come.
+		//
+		self.cancel_stream_windows();
+
+		// Stops the clock task of the rate limiter.
+		//
+		self.rate_limiter = None;
+
+		// Stops the task returning credit to the remote.
+		//
+		self.grants = None;
+
+		// Stops the timer of the idle timeout.
+		//
+		self.idle = None;
+		self.metrics.lock().clear();
+
 
 		// try to drop close our mailbox and drop ourselves
 		//
@@ -96,6 +144,11 @@ impl<Wf: WireFormat> Handler<CloseConnection> for Peer<Wf>
 		// alive. This breaks that cycle.
 		//
 		self.services .clear();
+		self.aliases  .clear();
 		self.responses.clear();
+		self.streams  .clear();
+		self.credits  .clear();
+		self.call_slots = None;
+		self.close_channels();
 	}
 }
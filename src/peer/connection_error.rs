@@ -1,4 +1,4 @@
-use crate :: { import::*, ServiceID, ConnID };
+use crate :: { import::*, ServiceID, ConnID, AppError };
 
 /// All errors that can happen when receiving messages over the wire
 /// These will be broadcast to observers, so you can act upon them if necessary.
@@ -10,6 +10,15 @@ use crate :: { import::*, ServiceID, ConnID };
 //
 pub enum ConnectionError
 {
+	/// The handler of a fallible service returned an error. See [`Fallible`](crate::Fallible).
+	//
+	Application{ sid: Option<ServiceID>, cid: Option<ConnID>, err: AppError },
+
+	/// You tried to open a channel with an id that is already in use on the connection.
+	/// The channel that was open is not affected.
+	//
+	ChannelInUse{ sid: Option<ServiceID>, cid: Option<ConnID> },
+
 	/// An error deserializing the incoming actor message. This means the stream might be corrupt,
 	/// so the connection will be closed.
 	//
@@ -38,6 +47,10 @@ pub enum ConnectionError
 	//
 	UnknownService{ sid: Option<ServiceID>, cid: Option<ConnID> },
 
+	/// You sent more requests than the remote allows. Try again later.
+	//
+	RateLimited{ sid: Option<ServiceID>, cid: Option<ConnID> },
+
 	/// We don't provide this service.
 	//
 	PubSubNoCall{ sid: Option<ServiceID>, cid: Option<ConnID> },
@@ -54,6 +67,14 @@ impl fmt::Display for ConnectionError
 	{
 		match &self
 		{
+			ConnectionError::Application{ sid, err, .. } =>
+
+				write!( f, "The handler of the service returned an error: {} (sid: {:?}).", err, sid ),
+
+			ConnectionError::ChannelInUse{ sid, cid } =>
+
+				write!( f, "Remote already has a channel open with this id (sid: {:?}, cid: {:?}).", sid, cid ),
+
 			ConnectionError::Deserialize{ sid, cid } =>
 
 				write!( f, "Remote failed to deserialize your actor message (sid: {:?}, cid: {:?}).", sid, cid ),
@@ -74,6 +95,10 @@ impl fmt::Display for ConnectionError
 
 				write!( f, "Remote does not expose the service you are trying to call (sid: {:?}).", sid ),
 
+			ConnectionError::RateLimited{ sid, .. } =>
+
+				write!( f, "Remote rejected your request because you exceed their rate limit (sid: {:?}).", sid ),
+
 			ConnectionError::PubSubNoCall{ sid, .. } =>
 
 				write!( f, "Remote broadcasts this message type using thespis_remote::PubSub which does not support the `call` operation. Only `send` is supported (sid: {:?}).", sid ),
@@ -0,0 +1,596 @@
+use crate::{ import::*, *, WireType };
+
+
+/// Credit based flow control for requests coming in from the remote. Set it on a [`Peer`] with
+/// [`Peer::set_flow_control`].
+///
+/// The peer advertises to the remote how many requests and how many bytes of requests it may have
+/// outstanding. Every incoming call or send uses one request and the size of it's frame from that credit.
+/// It is given back to the remote when the request has been processed, that is when the handler has
+/// returned for sends and when the response is ready for calls.
+///
+/// When the remote peer runs out of credit, it holds outgoing calls and sends back until new credit comes
+/// in. The peer keeps processing other messages in the meantime. Callers wait longer for their response,
+/// queued calls are still subject to the timeout. Senders using the `RemoteAddr` of `service_map!`,
+/// [`ServiceAddr`] or [`DynRemoteAddr`] wait until their send has gone out, see [`FlowSend`]. If the remote
+/// never advertises credit, outgoing requests are not limited, so this works with remotes that don't use
+/// flow control.
+///
+/// A relay only finishes processing a request when the peer it relays to has answered, so if every
+/// connection along the way uses flow control, backpressure propagates from end to end.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub struct FlowControl
+{
+	/// How many requests the remote can have outstanding.
+	//
+	pub requests: NonZeroU32,
+
+	/// How many bytes of requests the remote can have outstanding. A request bigger than this can
+	/// still be sent when nothing else is outstanding.
+	//
+	pub bytes: NonZeroU64,
+}
+
+
+impl FlowControl
+{
+	/// Create a new window of credit for the remote.
+	//
+	pub fn new( requests: NonZeroU32, bytes: NonZeroU64 ) -> Self
+	{
+		Self { requests, bytes }
+	}
+}
+
+
+
+// The payload of a credit frame. Credit is additive, every frame adds to what was granted before.
+//
+#[ derive( Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+pub(super) struct Credit
+{
+	requests: u32,
+	bytes   : u64,
+}
+
+
+impl Credit
+{
+	fn of( frame: &impl WireFormat ) -> Self
+	{
+		Self { requests: 1, bytes: frame.len() }
+	}
+
+
+	fn add( &mut self, other: Credit )
+	{
+		self.requests = self.requests.saturating_add( other.requests );
+		self.bytes    = self.bytes   .saturating_add( other.bytes    );
+	}
+}
+
+
+
+// The credit the remote has given us for outgoing requests and the requests waiting for it.
+//
+// We count what we send before the remote has advertised anything, so the first grant
+// is correctly reduced by what is already outstanding.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct Credits<Wf>
+{
+	enforced: bool                    ,
+	window  : i64                     ,
+	requests: i64                     ,
+	bytes   : i64                     ,
+	queue   : VecDeque< Waiting<Wf> > ,
+}
+
+
+// An outgoing request waiting for credit. `sent` is told when it goes out.
+//
+#[ derive( Debug ) ]
+//
+struct Waiting<Wf>
+{
+	frame   : Wf                          ,
+	priority: Priority                    ,
+	trace   : Option<TraceContext>        ,
+	sent    : Option<oneshot::Sender<()>> ,
+}
+
+
+impl<Wf> Default for Credits<Wf>
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			enforced: false           ,
+			window  : 0               ,
+			requests: 0               ,
+			bytes   : 0               ,
+			queue   : VecDeque::new() ,
+		}
+	}
+}
+
+
+impl<Wf: WireFormat> Credits<Wf>
+{
+	// Whether we have enough credit to send this frame. A frame bigger than the window
+	// only needs the full window.
+	//
+	fn fits( &self, frame: &Wf ) -> bool
+	{
+		let size = std::cmp::min( Self::size( frame ), self.window );
+
+		!self.enforced || ( self.requests >= 1 && self.bytes >= size )
+	}
+
+
+	fn take( &mut self, frame: &Wf )
+	{
+		self.requests -= 1;
+		self.bytes    -= Self::size( frame );
+	}
+
+
+	fn grant( &mut self, credit: Credit )
+	{
+		if !self.enforced
+		{
+			self.enforced = true;
+			self.window   = i64::try_from( credit.bytes ).unwrap_or( i64::MAX );
+		}
+
+		self.requests = self.requests.saturating_add( i64::from( credit.requests ) );
+		self.bytes    = self.bytes   .saturating_add( i64::try_from( credit.bytes ).unwrap_or( i64::MAX ) );
+	}
+
+
+	fn size( frame: &Wf ) -> i64
+	{
+		i64::try_from( frame.len() ).unwrap_or( i64::MAX )
+	}
+
+
+	// Drop all queued requests. The senders of queued calls are dropped by the peer and the ones
+	// waiting for a send to go out are dropped here, so callers will be woken up.
+	//
+	pub(super) fn clear( &mut self )
+	{
+		self.queue.clear();
+	}
+}
+
+
+
+// The credit we give the remote for incoming requests.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct Grants
+{
+	window     : FlowControl              ,
+	outstanding: u32                      ,
+	pending    : Credit                   ,
+	tx         : futUnboundSender<Credit> ,
+}
+
+
+
+// Held while an incoming request is being processed. Returns the credit to the peer when dropped.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct CreditSlot
+{
+	credit: Credit                   ,
+	tx    : futUnboundSender<Credit> ,
+}
+
+
+impl Drop for CreditSlot
+{
+	fn drop( &mut self )
+	{
+		// If this fails, the peer has closed the connection and the credit no longer matters.
+		//
+		let _ = self.tx.unbounded_send( self.credit );
+	}
+}
+
+
+
+// Tells the peer that an incoming request has been processed, so it can give the credit back.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct ReturnCredit
+{
+	credit: Credit,
+}
+
+impl Message for ReturnCredit
+{
+	type Return = ();
+}
+
+
+
+impl<Wf: WireFormat> Handler<ReturnCredit> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: ReturnCredit )
+	{
+		let grants = match &mut self.grants
+		{
+			Some( grants ) => grants,
+			None           => return,
+		};
+
+		grants.outstanding = grants.outstanding.saturating_sub( msg.credit.requests );
+		grants.pending.add( msg.credit );
+
+		// Don't send a frame for every request, but make sure not to keep credit when
+		// the remote might be waiting for it.
+		//
+		let half_requests = std::cmp::max( grants.window.requests.get() / 2, 1 );
+		let half_bytes    = std::cmp::max( grants.window.bytes   .get() / 2, 1 );
+
+		if     grants.outstanding      == 0
+			|| grants.pending.requests >= half_requests
+			|| grants.pending.bytes    >= half_bytes
+		{
+			let credit = std::mem::take( &mut grants.pending );
+
+			trace!( "{}: granting credit to remote: {:?}", self.identify(), credit );
+
+			let mut wf = Wf::with_capacity( 16 );
+			wf.set_sid( ServiceID::credit() );
+			serde_cbor::to_writer( &mut wf, &credit ).expect( "serialize Credit" );
+
+			if let Err( err ) = self.send_msg( wf ).await
+			{
+				self.report( err ).await;
+			}
+		}
+	}
+}
+
+
+
+/// An outgoing send that is held back while the remote has not given us credit for it. Unlike sending
+/// a `Wf` or [`Prioritized`] to the peer, you get a receiver that resolves when the frame has gone out,
+/// so the sender can wait for it before sending more. It errors with `Canceled` if the connection closes
+/// first.
+///
+/// Normally you don't use this directly, the `RemoteAddr` of `service_map!`, [`ServiceAddr`] and
+/// [`DynRemoteAddr`] use it for sends.
+//
+#[ derive( Debug ) ]
+//
+pub struct FlowSend<Wf>
+{
+	wf      : Wf                   ,
+	priority: Priority             ,
+	trace   : Option<TraceContext> ,
+}
+
+impl<Wf: WireFormat> Message for FlowSend<Wf>
+{
+	type Return = Result< oneshot::Receiver<()>, PeerErr >;
+}
+
+
+impl<Wf: WireFormat + Send + 'static> FlowSend<Wf>
+{
+	/// Send `wf` out with the given priority.
+	//
+	pub fn new( wf: Wf, priority: Priority ) -> Self
+	{
+		Self{ wf, priority, trace: None }
+	}
+
+
+	/// Send the trace context along with the message. See [`TraceContext`].
+	//
+	pub fn with_trace( mut self, trace: impl Into<Option<TraceContext>> ) -> Self
+	{
+		self.trace = trace.into();
+		self
+	}
+
+
+	/// Send this over `peer` and wait until it has gone out.
+	//
+	pub async fn send_over( self, mut peer: Addr<Peer<Wf>> ) -> Result<(), PeerErr>
+	{
+		let ctx = Peer::err_ctx( &peer, self.wf.sid(), None, "Send to remote service".to_string() );
+
+		let sent = peer.call( self ).await
+
+			.map_err( |_| PeerErr::PeerGone{ ctx: ctx.clone() } )??
+		;
+
+		sent.await.map_err( |_| PeerErr::ConnectionClosed{ ctx } )
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<FlowSend<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: FlowSend<Wf> ) -> <FlowSend<Wf> as Message>::Return
+	{
+		trace!( "{}: sending OUT WireFormat held back by flow control", self.identify() );
+
+		if self.closed
+		{
+			let ctx = self.ctx( msg.wf.sid(), None, "Handler<FlowSend> for Peer" );
+
+			return Err( PeerErr::ConnectionClosed{ ctx } );
+		}
+
+		let (tx, rx) = oneshot::channel();
+
+		self.send_request( msg.wf, msg.priority, msg.trace, Some(tx) ).await?;
+
+		Ok( rx )
+	}
+}
+
+
+
+/// The send in progress on an address, so it's `Sink` impl can wait for it to go out. See [`FlowSend`].
+//
+#[ doc( hidden ) ]
+#[ derive( Default ) ]
+//
+pub struct PendingSend
+{
+	fut: Option< Pin<Box< dyn Future< Output = Result<(), PeerErr> > + Send >> >,
+}
+
+
+impl PendingSend
+{
+	/// Start sending `send` over `peer`. The previous send must be done.
+	//
+	pub fn start<Wf: WireFormat + Send + 'static>( &mut self, peer: Addr<Peer<Wf>>, send: FlowSend<Wf> )
+	{
+		debug_assert!( self.fut.is_none(), "start a send before the previous one is done" );
+
+		self.fut = Some( send.send_over( peer ).boxed() );
+	}
+
+
+	/// Ready when there is no send in progress. Returns the result of the send that was in progress.
+	//
+	pub fn poll_done( &mut self, cx: &mut Context<'_> ) -> Poll< Result<(), PeerErr> >
+	{
+		let res = match &mut self.fut
+		{
+			Some( fut ) => match fut.as_mut().poll( cx )
+			{
+				Poll::Ready( res ) => res,
+				Poll::Pending      => return Poll::Pending,
+			},
+
+			None => return Poll::Ready( Ok(()) ),
+		};
+
+		self.fut = None;
+
+		Poll::Ready( res )
+	}
+}
+
+
+// A clone has nothing in progress.
+//
+impl Clone for PendingSend
+{
+	fn clone( &self ) -> Self
+	{
+		Self::default()
+	}
+}
+
+
+impl fmt::Debug for PendingSend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "PendingSend" )
+
+			.field( "in_progress", &self.fut.is_some() )
+			.finish()
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Use credit based flow control for requests coming in from the remote. See [`FlowControl`].
+	/// Set this only once, before starting the mailbox of the peer.
+	///
+	/// This spawns a task that returns credit to the remote. It fails if spawning fails or if
+	/// the connection is already closed.
+	//
+	pub fn set_flow_control( &mut self, window: FlowControl ) -> Result<(), PeerErr>
+	{
+		debug_assert!( self.grants.is_none(), "{}: Flow control can only be set once.", self.identify() );
+
+		let mut addr = match &self.addr
+		{
+			Some( addr ) => addr.clone(),
+
+			None =>
+			{
+				let ctx = self.ctx( None, None, "Set flow control" );
+
+				return Err( PeerErr::ConnectionClosed{ ctx } );
+			}
+		};
+
+		let (tx, mut rx) = mpsc::unbounded();
+
+		// Stops when the peer drops the grants and all credit slots are gone.
+		//
+		let task = async move
+		{
+			while let Some( credit ) = rx.next().await
+			{
+				if addr.send( ReturnCredit{ credit } ).await.is_err() { break }
+			}
+
+			Ok( Response::Nothing )
+		};
+
+		if self.nursery.nurse( task ).is_err()
+		{
+			let ctx = self.ctx( None, None, "Spawn task for flow control" );
+
+			return Err( PeerErr::Spawn{ ctx } );
+		}
+
+		// The initial window is pending. Returning empty credit makes the peer send it out as soon as
+		// the mailbox runs.
+		//
+		let initial = Credit { requests: window.requests.get(), bytes: window.bytes.get() };
+
+		tx.unbounded_send( Credit::default() ).expect( "receiver not dropped" );
+
+		self.grants = Some( Grants
+		{
+			window               ,
+			outstanding: 0       ,
+			pending    : initial ,
+			tx                   ,
+		});
+
+		Ok(())
+	}
+
+
+	// Take credit for an incoming request. The credit is returned when the slot is dropped.
+	//
+	pub(super) fn credit_slot( &mut self, frame: &Wf ) -> Option<CreditSlot>
+	{
+		let grants = self.grants.as_mut()?;
+
+		grants.outstanding = grants.outstanding.saturating_add( 1 );
+
+		Some( CreditSlot{ credit: Credit::of( frame ), tx: grants.tx.clone() } )
+	}
+
+
+	// Send an outgoing call or send, or queue it if the remote hasn't given us enough credit.
+	// `sent` is told when the frame has gone out.
+	//
+	pub(super) async fn send_request
+	(
+		&mut self                             ,
+		frame   : Wf                          ,
+		priority: Priority                    ,
+		trace   : Option<TraceContext>        ,
+		sent    : Option<oneshot::Sender<()>> ,
+	)
+		-> Result<(), PeerErr>
+	{
+		self.metrics.lock().request_out( frame.sid(), frame.cid(), frame.kind() == WireType::IncomingCall );
+
+		if !self.credits.queue.is_empty() || !self.credits.fits( &frame )
+		{
+			trace!( "{}: no credit for outgoing request, queueing it. sid: {}", self.identify(), frame.sid() );
+
+			self.credits.queue.push_back( Waiting{ frame, priority, trace, sent } );
+
+			return Ok(());
+		}
+
+		self.credits.take( &frame );
+
+		self.send_traced( frame, priority, trace ).await?;
+
+		if let Some( sent ) = sent
+		{
+			// The sender might have given up waiting.
+			//
+			let _ = sent.send(());
+		}
+
+		Ok(())
+	}
+
+
+	// The remote gives us credit. Send out what we can from the queue.
+	//
+	pub(super) async fn credit_frame( &mut self, frame: Wf )
+	{
+		let credit: Credit = match serde_cbor::from_slice( frame.msg() )
+		{
+			Ok ( credit ) => credit,
+			Err( _      ) =>
+			{
+				let ctx = self.ctx( None, None, "Deserialize credit frame from remote" );
+
+				self.report( PeerErr::Deserialize{ ctx } ).await;
+
+				return;
+			}
+		};
+
+		trace!( "{}: received credit from remote: {:?}", self.identify(), credit );
+
+		self.credits.grant( credit );
+
+		while let Some( next ) = self.credits.queue.front()
+		{
+			let cid = next.frame.cid();
+
+			// Calls that timed out while waiting don't need to go out anymore.
+			//
+			if next.frame.kind() == WireType::IncomingCall && !self.responses.contains_key( &cid ) && !self.streams.contains_key( &cid )
+			{
+				self.credits.queue.pop_front();
+				continue;
+			}
+
+			if !self.credits.fits( &next.frame ) { break }
+
+			let Waiting{ frame, priority, trace, sent } = self.credits.queue.pop_front().expect( "front exists" );
+
+			self.credits.take( &frame );
+
+			match self.send_traced( frame, priority, trace ).await
+			{
+				Ok(()) =>
+				{
+					if let Some( sent ) = sent
+					{
+						let _ = sent.send(());
+					}
+				}
+
+				Err( err ) =>
+				{
+					// Wake up the caller.
+					//
+					self.responses.remove( &cid );
+					self.streams  .remove( &cid );
+					self.metrics.lock().forget( cid );
+					self.call_done( cid ).await;
+
+					self.report( err ).await;
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,135 @@
+use crate::{ import::*, * };
+
+
+// Sent by the idle timer to ask the peer whether anything happened since the last check.
+// Returns false when the timer should stop.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct IdleCheck;
+
+impl Message for IdleCheck
+{
+	type Return = bool;
+}
+
+
+
+// The state of the idle timeout. The timer task holds a clone of `token` and stops when the
+// peer drops it's copy.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct Idle
+{
+	token : Arc<()> ,
+	frames: u64     ,
+}
+
+
+
+impl<Wf: WireFormat> Handler<IdleCheck> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: IdleCheck ) -> bool
+	{
+		if self.closed { return false }
+
+		let (frames, tasks) = self.metrics.lock().activity();
+
+		let busy = tasks > 0
+			|| !self.responses.is_empty()
+			|| !self.streams  .is_empty()
+			|| !self.channels .is_empty()
+		;
+
+		let idle = match &mut self.idle
+		{
+			Some( idle ) => idle,
+			None         => return false,
+		};
+
+		if busy || frames != idle.frames
+		{
+			idle.frames = frames;
+			return true;
+		}
+
+		debug!( "{}: Connection idle, closing it.", self.identify() );
+
+		let close_conn = CloseConnection{ remote: false, reason: "Connection idle.".to_string() };
+
+		Handler::<CloseConnection>::handle( self, close_conn ).await;
+
+		false
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Close the connection when nothing happens on it for `idle`. Nothing happens means no frames
+	/// are sent or received, no incoming requests are being processed, no outgoing calls wait for
+	/// a response and no channels are open. `None` turns it off, which is the default. Setting it
+	/// again replaces the previous timeout.
+	///
+	/// The connection is checked every `idle`, so it closes somewhere between `idle` and twice `idle`
+	/// after the last activity. Observers get a `PeerEvent::Closed`.
+	///
+	/// This spawns a task that acts as a timer. It fails if spawning fails or if the connection is
+	/// already closed.
+	//
+	pub fn set_idle_timeout( &mut self, idle: Option<Duration> ) -> Result<(), PeerErr>
+	{
+		// Stops the previous timer.
+		//
+		self.idle = None;
+
+		let idle = match idle
+		{
+			Some( idle ) => idle,
+			None         => return Ok(()),
+		};
+
+		let mut addr = match &self.addr
+		{
+			Some( addr ) => addr.clone(),
+
+			None =>
+			{
+				let ctx = self.ctx( None, None, "Set idle timeout" );
+				return Err( PeerErr::ConnectionClosed{ ctx } );
+			}
+		};
+
+		let token = Arc::new(());
+		let timer = token.clone();
+
+		let task = async move
+		{
+			while Arc::strong_count( &timer ) > 1
+			{
+				Delay::new( idle ).await;
+
+				if Arc::strong_count( &timer ) == 1 { break }
+
+				if !addr.send( IdleCheck ).await.unwrap_or( false ) { break }
+			}
+
+			Ok( Response::Nothing )
+		};
+
+		if self.nursery.nurse( task ).is_err()
+		{
+			let ctx = self.ctx( None, None, "Spawn timer for idle timeout" );
+
+			return Err( PeerErr::Spawn{ ctx } );
+		}
+
+		let frames = self.metrics.lock().activity().0;
+
+		self.idle = Some( Idle{ token, frames } );
+
+		Ok(())
+	}
+}
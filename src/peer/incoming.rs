@@ -1,7 +1,11 @@
 use
 {
-	crate::{ import::*, *, WireType },
-	super::RequestError              ,
+	crate::{ import::*, *, WireType } ,
+	super::RequestError               ,
+	super::backpressure::SendSlot     ,
+	super::stats::{ Metrics, now }    ,
+	super::Lifecycle                  ,
+	super::StreamCtrl                 ,
 };
 
 
@@ -53,6 +57,8 @@ impl<Wf: WireFormat + Send + 'static> Handler<Incoming<Wf>> for Peer<Wf>
 		};
 
 
+		self.metrics.lock().frame_in( frame.len() );
+
 		let sid    = frame.sid();
 		let cid    = frame.cid();
 		let kind   = frame.kind();
@@ -65,6 +71,13 @@ impl<Wf: WireFormat + Send + 'static> Handler<Incoming<Wf>> for Peer<Wf>
 			WireType::ConnectionError => self.remote_conn_err( frame, cid        ).await,
 			WireType::IncomingSend    => self.incoming_send  ( sid, frame      ).await,
 			WireType::IncomingCall    => self.incoming_call  ( cid, sid, frame ).await,
+			WireType::StreamChunk     => self.stream_frame   ( cid, frame      ).await,
+			WireType::StreamEnd       => self.stream_frame   ( cid, frame      ).await,
+			WireType::StreamControl   => self.stream_ctrl    ( cid, frame      ).await,
+			WireType::ChannelData     => self.channel_data   ( cid, frame      ).await,
+			WireType::ChannelControl  => self.channel_ctrl   ( cid, frame      ).await,
+			WireType::Credit          => self.credit_frame   ( frame           ).await,
+			WireType::TraceContext    => self.trace_frame    ( frame           )      ,
 
 			WireType::CallResponse =>
 			{
@@ -76,12 +89,16 @@ impl<Wf: WireFormat + Send + 'static> Handler<Incoming<Wf>> for Peer<Wf>
 					//
 					trace!( "{}: Incoming Return", self.identify() );
 
+					self.metrics.lock().response_in( cid );
+
 					// Normally if this fails it means the receiver of the channel was dropped...
 					//
 					if channel.send( Ok(frame) ).is_err()
 					{
 						warn!( "{}: Received response for dead actor, cid: {}.", self.identify(), cid );
 					}
+
+					self.call_done( cid ).await;
 				}
 
 				// There is a CID, so it's a response, but it's not in our self.responses, so it has timed out.
@@ -90,6 +107,8 @@ impl<Wf: WireFormat + Send + 'static> Handler<Incoming<Wf>> for Peer<Wf>
 				else
 				{
 					warn!( "{}: Received response for a timed out outgoing request, cid: {}. Dropping response.", self.identify(), cid );
+
+					self.lifecycle( PeerEvent::LateResponse{ cid } ).await;
 				}
 			}
 		}
@@ -115,16 +134,41 @@ impl<Wf: WireFormat> Peer<Wf>
 			//
 			if let Some( channel ) = self.responses.remove( &cid )
 			{
+				self.metrics.lock().response_in( cid );
+
 				// If this returns an error, it means the receiver was dropped, so if they no longer
 				// care for the result, neither do we, so ignoring the result.
 				//
 				let _ = channel.send( Err( err ) );
 
+				self.call_done( cid ).await;
+
 				// Since this was not our error, just relay the response.
 				//
 				return
 			}
 
+			// Same for a streaming call. The error ends the stream.
+			//
+			if let Some( stream ) = self.streams.remove( &cid )
+			{
+				self.metrics.lock().response_in( cid );
+
+				let _ = stream.tx.unbounded_send( Err( err ) );
+
+				self.call_done( cid ).await;
+
+				return
+			}
+
+			// Same for a channel. The error ends the stream of the channel.
+			//
+			let err = match self.channel_err( cid, err )
+			{
+				Ok (()) => return,
+				Err(e ) => e,
+			};
+
 			// Notify observers
 			//
 			let shine = PeerEvent::RemoteError( err );
@@ -141,11 +185,67 @@ impl<Wf: WireFormat> Peer<Wf>
 		{
 			let ctx = self.ctx( None, cid, "We received an error message from a remote peer, but couldn't deserialize it" );
 			let err = PeerErr::Deserialize{ ctx };
-			let shine = PeerEvent::Error(err);
 
-			// If pharos is closed, we already panicked... so except is fine.
+			self.report( err ).await;
+		}
+	}
+
+
+
+	// A frame of a response to an outgoing streaming call. The end of stream marker is
+	// passed on as well, so the ResponseStream knows the stream ended normally.
+	//
+	async fn stream_frame( &mut self, cid: ConnID, frame: Wf )
+	{
+		let end = frame.sid().is_stream_end();
+
+		let stream = match self.streams.get_mut( &cid )
+		{
+			Some( stream ) => stream,
+
+			// There is a CID, but it's not in our self.streams, so it has timed out or the
+			// consumer went away.
 			//
-			self.pharos.send( shine ).await.expect( "pharos not closed" );
+			None =>
+			{
+				warn!( "{}: Received stream frame for a timed out or dropped outgoing request, cid: {}. Dropping frame.", self.identify(), cid );
+
+				self.lifecycle( PeerEvent::LateResponse{ cid } ).await;
+				return;
+			}
+		};
+
+		stream.started = true;
+
+		trace!( "{}: Incoming stream frame, cid: {}", self.identify(), cid );
+
+		// The remote only sends as many chunks as the consumer gave it credit for, so this
+		// doesn't grow without bound. A remote that doesn't do flow control is not limited.
+		//
+		if stream.tx.unbounded_send( Ok(frame) ).is_err()
+		{
+			// The consumer dropped the stream. It's cancel might still be on it's way, but that does
+			// nothing once the stream is removed, so tell the remote here, otherwise it waits for credit
+			// until the connection closes.
+			//
+			self.streams.remove( &cid );
+			self.metrics.lock().forget( cid );
+			self.call_done( cid ).await;
+
+			if !end
+			{
+				if let Err( e ) = self.send_stream_ctrl( cid, &StreamCtrl::Cancel, Priority::Control ).await
+				{
+					self.report( e ).await;
+				}
+			}
+		}
+
+		else if end
+		{
+			self.streams.remove( &cid );
+			self.metrics.lock().response_in( cid );
+			self.call_done( cid ).await;
 		}
 	}
 
@@ -164,7 +264,21 @@ impl<Wf: WireFormat> Peer<Wf>
 
 		trace!( "{}: Incoming Send, sid: {}", &identity, &sid );
 
-		let ctx = self.ctx( sid, None, "Peer: Handle incoming send" );
+		self.metrics.lock().request_in( sid, false );
+
+		// Continue the trace of the remote, or start a new one.
+		//
+		let remote = self.take_trace( sid, frame.cid() );
+		let trace  = remote.map( |r| r.child() ).unwrap_or_else( TraceContext::root );
+		let span   = self.request_span( WireType::IncomingSend, sid, frame.cid(), &trace, remote.as_ref() );
+
+		// Returns the credit to the remote when dropped, also if we bail out early.
+		//
+		let credit = self.credit_slot( &frame );
+
+		if !self.rate_limit( sid, None ).await { return }
+
+		let ctx = self.ctx( sid, None, "Peer: Handle incoming send" ).trace( trace );
 
 		let sm = match self.services.get( &sid )
 		{
@@ -185,7 +299,9 @@ impl<Wf: WireFormat> Peer<Wf>
 
 		// Send to handling actor,
 		//
-		let fut = match sm.send_service( frame, ctx )
+		let panic_ctx = ctx.clone().context( "Peer: task processing incoming send panicked".to_string() );
+
+		let fut = match span.in_scope( || sm.send_service( frame, ctx ) )
 		{
 			Ok(f) => f,
 
@@ -208,6 +324,20 @@ impl<Wf: WireFormat> Peer<Wf>
 		};
 
 
+		// Hold the slots for backpressure and flow control, if any, until the message is delivered
+		// to the handler. The task guard counts the send as in progress for the stats.
+		//
+		let slots = ( self.backpressure.as_ref().and_then( SendSlot::take ), credit, Metrics::task( &self.metrics, sid, false ) );
+
+		let fut = async move
+		{
+			let res = catch_panic( fut, panic_ctx ).await;
+			drop( slots );
+			res
+
+		}.instrument( span ).boxed();
+
+
 		if self.nursery.nurse( fut ).is_err()
 		{
 			let ctx = self.ctx( sid, None, "sm.send_service" );
@@ -232,14 +362,45 @@ impl<Wf: WireFormat> Peer<Wf>
 	{
 		if self.closed { return }
 
+		self.metrics.lock().request_in( sid, true );
+
+		// Continue the trace of the remote, or start a new one.
+		//
+		let remote = self.take_trace( sid, cid );
+		let trace  = remote.map( |r| r.child() ).unwrap_or_else( TraceContext::root );
+		let span   = self.request_span( WireType::IncomingCall, sid, cid, &trace, remote.as_ref() );
+
+		// Returns the credit to the remote when dropped, also if we bail out early.
+		//
+		let credit = self.credit_slot( &frame );
+
+		if !self.rate_limit( sid, Some(cid) ).await { return }
+
 		if let Some( ref bp ) = self.backpressure
 		{
 			bp.remove_slots( NonZeroUsize::new(1).unwrap() );
 		}
 
+		// The reflection service is answered by the peer itself. It goes out as a CallResponse like
+		// any other response, which frees the backpressure slot. If it's disabled, the remote will
+		// get UnknownService below.
+		//
+		if sid.is_reflection() && self.reflection != Reflection::Disabled
+		{
+			let resp = CallResponse::new( self.reflect( cid ) );
+			drop( credit );
+
+			if let Err( err ) = self.handle( resp ).await
+			{
+				self.report( err ).await;
+			}
+
+			return
+		}
+
 		trace!( "{}: Incoming Call, sid: {}, cid: {}", self.identify(), sid, cid );
 
-		let ctx = self.ctx( sid, cid, "Peer: Handle incoming call" );
+		let ctx = self.ctx( sid, cid, "Peer: Handle incoming call" ).trace( trace );
 
 
 		// Find our handler.
@@ -261,13 +422,48 @@ impl<Wf: WireFormat> Peer<Wf>
 
 		// Get future from service map.
 		//
-		let fut = match sm.call_service( frame, ctx.clone() )
+		let fut = match span.in_scope( || sm.call_service( frame, ctx.clone() ) )
 		{
 			Ok (f) => f,
 			Err(e) => return self.handle( RequestError::from(e) ).await,
 		};
 
 
+		// Hold the credit until the response is ready. The task guard measures how long that takes.
+		//
+		let slots = ( credit, Metrics::task( &self.metrics, sid, true ) );
+
+		// Tell observers when the handler is done, if they want to know.
+		//
+		let done = match &self.addr
+		{
+			Some( addr ) if self.lifecycle_events => Some(( addr.clone(), now() )),
+			_                                     => None,
+		};
+
+		let panic_ctx = ctx.clone().context( "Peer: task processing incoming call panicked".to_string() );
+
+		let fut = async move
+		{
+			let res = catch_panic( fut, panic_ctx ).await;
+			drop( slots );
+
+			if let Some(( mut addr, start )) = done
+			{
+				let duration = start.map( |s| s.elapsed() );
+
+				// If the peer is gone, there is no one to tell.
+				//
+				let _ = addr.send( Lifecycle( PeerEvent::IncomingCallCompleted{ sid, cid, duration } ) ).await;
+			}
+
+			res
+
+		}.instrument( span ).boxed();
+
+		self.lifecycle( PeerEvent::IncomingCallStarted{ sid, cid } ).await;
+
+
 		// Call handling actor,
 		//
 		if self.nursery.nurse( fut ).is_err()
@@ -278,3 +474,37 @@ impl<Wf: WireFormat> Peer<Wf>
 		}
 	}
 }
+
+
+
+/// Run the future processing a request, turning a panic into [`PeerErr::RequestPanic`], so the
+/// remote gets an answer and the peer keeps running. The handling actor runs in its own mailbox,
+/// so a panic there isn't caught here, the future sees [`PeerErr::HandlerDead`].
+//
+async fn catch_panic<Wf>
+(
+	fut: impl Future< Output = Result<Response<Wf>, PeerErr> > ,
+	ctx: PeerErrCtx                                            ,
+)
+	-> Result<Response<Wf>, PeerErr>
+
+{
+	match AssertUnwindSafe( fut ).catch_unwind().await
+	{
+		Ok ( res     ) => res,
+		Err( payload ) =>
+		{
+			let msg = match payload.downcast::<String>()
+			{
+				Ok ( msg     ) => *msg,
+				Err( payload ) => match payload.downcast_ref::<&str>()
+				{
+					Some( msg ) => msg.to_string(),
+					None        => "unknown panic payload".to_string(),
+				}
+			};
+
+			Err( PeerErr::RequestPanic{ ctx, msg } )
+		}
+	}
+}
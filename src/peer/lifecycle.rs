@@ -0,0 +1,52 @@
+use crate::{ import::*, * };
+
+
+// A lifecycle event from a task of the peer, eg. the task processing an incoming call.
+// Dropped if lifecycle events are off.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct Lifecycle( pub(super) PeerEvent );
+
+impl Message for Lifecycle
+{
+	type Return = ();
+}
+
+
+
+impl<Wf: WireFormat> Handler<Lifecycle> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: Lifecycle )
+	{
+		self.lifecycle( msg.0 ).await;
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Send the lifecycle events to observers: timeouts of outgoing calls, late responses, start and
+	/// end of incoming calls and backpressure being exhausted and restored. These are off by default.
+	/// See [`PeerEvent::is_lifecycle`].
+	//
+	pub fn set_lifecycle_events( &mut self, enabled: bool )
+	{
+		self.lifecycle_events = enabled;
+	}
+
+
+	// Notify observers of a lifecycle event, if they are on.
+	//
+	pub(super) async fn lifecycle( &mut self, event: PeerEvent )
+	{
+		if !self.lifecycle_events { return }
+
+		debug_assert!( event.is_lifecycle() );
+
+		// If pharos is closed, we already panicked... so except is fine.
+		//
+		self.pharos.send( event ).await.expect( "pharos not closed" );
+	}
+}
@@ -0,0 +1,151 @@
+use crate::{ import::*, * };
+
+
+// How many frames can wait between a local transport and the peer, in each direction.
+//
+const LOCAL_BUFFER: usize = 32;
+
+
+/// An executor handle for running a [`Peer`] on a single threaded executor, like `futures::executor::LocalPool`,
+/// tokio's `LocalSet` or the browser.
+///
+/// Peer needs an executor that is `Send` and `Sync`. Executors for `!Send` futures usually have a handle that
+/// isn't. `LocalExec` is a handle that can be sent around. Everything spawned on it runs inside one task on the
+/// local executor, so it all stays on the thread of that executor. It works with any executor that implements
+/// `futures::task::LocalSpawn`, which includes all the ones of async_executors that implement `LocalSpawnHandle`.
+///
+/// Normally you don't need this directly. [`PeerBuilder::build_local`] creates it for you.
+//
+#[ derive( Debug, Clone ) ]
+//
+pub struct LocalExec
+{
+	tx: futUnboundSender< FutureObj<'static, ()> >,
+}
+
+
+impl LocalExec
+{
+	/// Spawn the task that runs everything spawned on this handle on `exec`. That task ends when all
+	/// clones of the handle are dropped and all tasks spawned on them are done.
+	//
+	pub fn new( exec: &impl LocalSpawn ) -> Result<Self, SpawnError>
+	{
+		let (tx, rx) = mpsc::unbounded::< FutureObj<'static, ()> >();
+
+		exec.spawn_local( rx.for_each_concurrent( None, |task| task ) )?;
+
+		Ok( Self{ tx } )
+	}
+}
+
+
+impl Spawn for LocalExec
+{
+	fn spawn_obj( &self, future: FutureObj<'static, ()> ) -> Result<(), SpawnError>
+	{
+		self.tx.unbounded_send( future ).map_err( |_| SpawnError::shutdown() )
+	}
+}
+
+
+impl<Out: 'static + Send> SpawnHandle<Out> for LocalExec
+{
+	fn spawn_handle_obj( &self, future: FutureObj<'static, Out> ) -> Result<JoinHandle<Out>, SpawnError>
+	{
+		let (task, handle) = future.remote_handle();
+
+		self.spawn( task )?;
+
+		Ok( JoinHandle::remote_handle( handle ) )
+	}
+}
+
+
+
+impl<Wf: WireFormat> PeerBuilder<Wf>
+{
+	/// Build a peer on a single threaded executor. The transport doesn't need to be `Send`. The handlers
+	/// of the service maps can be `!Send` actors whose mailbox runs on the same executor, since the peer
+	/// only holds their addresses.
+	///
+	/// The peer and all it's tasks run on a [`LocalExec`] on `exec`. The transport is read and written by
+	/// two more local tasks, which pass frames to and from the peer over channels.
+	//
+	pub fn build_local
+	(
+		self                                                                  ,
+		incoming: impl Stream< Item = Result<Wf, WireErr> > + Unpin + 'static ,
+		outgoing: impl Sink<Wf, Error=WireErr > + Unpin + 'static             ,
+		exec    : &impl LocalSpawn                                            ,
+	)
+		-> Result< Addr<Peer<Wf>>, PeerErr >
+
+	{
+		let spawn_err = |context: &str|
+		{
+			let ctx = PeerErrCtx::default()
+
+				.peer_name( self.settings().name.as_deref().map( Into::into ) )
+				.context  ( Some( context.to_string() )                       )
+			;
+
+			PeerErr::Spawn{ ctx }
+		};
+
+		let local = LocalExec::new( exec ).map_err( |_| spawn_err( "Local executor for peer" ) )?;
+
+		let (in_tx , in_rx ) = mpsc::channel( LOCAL_BUFFER );
+		let (out_tx, out_rx) = mpsc::channel::<Wf>( LOCAL_BUFFER );
+
+		// Ends when the transport ends or the peer drops the receiver.
+		//
+		let reader = async move
+		{
+			let _ = incoming.map( Ok ).forward( in_tx ).await;
+		};
+
+		// Ends when the peer closes the channel. Forward then closes the transport.
+		//
+		let writer = async move
+		{
+			if let Err( err ) = out_rx.map( Ok ).forward( outgoing ).await
+			{
+				warn!( "Local peer: failed to write to the connection: {}", err );
+			}
+		};
+
+		exec.spawn_local( reader ).map_err( |_| spawn_err( "Reader of local transport" ) )?;
+		exec.spawn_local( writer ).map_err( |_| spawn_err( "Writer of local transport" ) )?;
+
+		let sink = out_tx.sink_map_err( |_| WireErr::Io{ kind: io::ErrorKind::BrokenPipe } );
+
+		self.build( in_rx, sink, local )
+	}
+}
+
+
+impl PeerBuilder<ThesWF>
+{
+	/// Frame a connection that doesn't need to be `Send` with the [`thes_wf`](crate::thes_wf) codec and
+	/// build the peer on a single threaded executor. See [`PeerBuilder::build_local`].
+	//
+	pub fn build_local_async_read
+	(
+		self                                                        ,
+		socket: impl FutAsyncRead + FutAsyncWrite + Unpin + 'static ,
+		exec  : &impl LocalSpawn                                    ,
+	)
+		-> Result< Addr<Peer>, PeerErr >
+
+	{
+		let (reader, writer) = socket.split();
+
+		let max_size = self.settings().max_size;
+
+		let stream = thes_wf::Decoder::new( reader, max_size );
+		let sink   = thes_wf::Encoder::new( writer, max_size );
+
+		self.build_local( stream, sink, exec )
+	}
+}
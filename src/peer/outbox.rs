@@ -0,0 +1,458 @@
+use crate::{ import::*, *, WireType };
+
+
+// How many frames can wait in each lane before whoever sends on it has to wait.
+//
+const LANE_SIZE: usize = 16;
+
+// A lane that has frames waiting is served after being passed over this many times, even if
+// lanes with a higher priority have frames waiting.
+//
+const STARVE_LIMIT: usize = 8;
+
+const LANES: usize = 4;
+
+
+/// The priority of an outgoing frame. The peer writes frames of higher priority to the connection
+/// first. To make sure lower priorities don't starve, a lane that has been passed over a number of
+/// times gets served before higher ones. Frames with the same priority go out in order.
+///
+/// By default the priority follows from the type of frame. Control frames, like credit for flow
+/// control, go first, then responses (including streamed responses and errors), then outgoing calls
+/// and sends and last frames on channels. Calls and sends share a priority by default, so a call made
+/// after a send doesn't overtake it. You can set the priority of outgoing calls and sends per message
+/// with `RemoteAddr::with_priority`, eg. to mark a burst of sends as `Bulk`.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash ) ]
+//
+pub enum Priority
+{
+	/// Frames the peers use to manage the connection.
+	//
+	Control,
+
+	/// Responses to calls from the remote.
+	//
+	Response,
+
+	/// Outgoing calls and sends.
+	//
+	Call,
+
+	/// Messages on channels and requests that can wait.
+	//
+	Bulk,
+}
+
+
+impl Priority
+{
+	/// The default priority for a type of frame.
+	//
+	pub(crate) fn of( kind: WireType ) -> Self
+	{
+		match kind
+		{
+			WireType::Credit          => Priority::Control  ,
+			WireType::StreamControl   => Priority::Control  ,
+			WireType::CallResponse    => Priority::Response ,
+			WireType::ConnectionError => Priority::Response ,
+			WireType::StreamChunk     => Priority::Response ,
+			WireType::StreamEnd       => Priority::Response ,
+			WireType::IncomingCall    => Priority::Call     ,
+			WireType::IncomingSend    => Priority::Call     ,
+
+			// Trace context is always sent with the priority of the request it belongs to,
+			// so it stays in front of it.
+			//
+			WireType::TraceContext    => Priority::Call     ,
+
+			// Control frames of channels must not overtake the data, otherwise a close could
+			// arrive before the last messages.
+			//
+			WireType::ChannelData     => Priority::Bulk     ,
+			WireType::ChannelControl  => Priority::Bulk     ,
+		}
+	}
+}
+
+
+
+/// An outgoing send with an explicit priority. Normally you don't use this directly, but use
+/// `RemoteAddr::with_priority`. Sending a WireFormat directly to the peer uses the default priority.
+//
+#[ derive( Debug ) ]
+//
+pub struct Prioritized<Wf>
+{
+	wf      : Wf                   ,
+	priority: Priority             ,
+	trace   : Option<TraceContext> ,
+}
+
+impl<Wf: WireFormat> Message for Prioritized<Wf>
+{
+	type Return = Result<(), PeerErr>;
+}
+
+impl<Wf: WireFormat> Prioritized<Wf>
+{
+	/// Send `wf` out with the given priority.
+	//
+	pub fn new( wf: Wf, priority: Priority ) -> Self
+	{
+		Self{ wf, priority, trace: None }
+	}
+
+
+	/// Send the trace context along with the message. See [`TraceContext`].
+	//
+	pub fn with_trace( mut self, trace: impl Into<Option<TraceContext>> ) -> Self
+	{
+		self.trace = trace.into();
+		self
+	}
+}
+
+
+
+/// Handler for outgoing sends with an explicit priority.
+//
+impl<Wf: WireFormat> Handler<Prioritized<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: Prioritized<Wf> ) -> <Prioritized<Wf> as Message>::Return
+	{
+		trace!( "{}: sending OUT WireFormat with priority: {:?}", self.identify(), msg.priority );
+
+		self.send_request( msg.wf, msg.priority, msg.trace, None ).await
+	}
+}
+
+
+
+// The queues between the peer and the task writing to the connection.
+//
+struct Lanes<Wf>
+{
+	queues: [ VecDeque<Wf>; LANES ] ,
+	passed: [ usize       ; LANES ] ,
+	room  : [ Vec<Waker>  ; LANES ] ,
+	writer: Option<Waker>           ,
+	closed: bool                    ,
+}
+
+
+impl<Wf> Lanes<Wf>
+{
+	// Choose the lane to take the next frame from.
+	//
+	fn pick( &mut self ) -> Option<usize>
+	{
+		let busy: Vec<usize> = (0..LANES).filter( |i| !self.queues[*i].is_empty() ).collect();
+
+		let lane = busy.iter().find( |i| self.passed[**i] >= STARVE_LIMIT )
+
+			.or_else( || busy.first() )
+			.copied()?
+		;
+
+		for i in busy
+		{
+			if i == lane { self.passed[i]  = 0 }
+			else         { self.passed[i] += 1 }
+		}
+
+		Some( lane )
+	}
+}
+
+
+
+// The sending side is the peer, the stream side is the task writing to the connection.
+//
+pub(super) struct Outbox<Wf>
+{
+	lanes: Arc<Mutex< Lanes<Wf> >>,
+}
+
+
+impl<Wf> Clone for Outbox<Wf>
+{
+	fn clone( &self ) -> Self
+	{
+		Self { lanes: self.lanes.clone() }
+	}
+}
+
+
+impl<Wf> Outbox<Wf>
+{
+	pub(super) fn new() -> Self
+	{
+		let lanes = Lanes
+		{
+			queues: Default::default() ,
+			passed: [0; LANES]         ,
+			room  : Default::default() ,
+			writer: None               ,
+			closed: false              ,
+		};
+
+		Self { lanes: Arc::new( Mutex::new( lanes ) ) }
+	}
+
+
+	// Queue a frame, waiting for room in it's lane. Gives the frame back if the outbox is closed.
+	//
+	pub(super) async fn push( &self, priority: Priority, frame: Wf ) -> Result<(), Wf>
+	{
+		let lane      = priority as usize;
+		let mut frame = Some( frame );
+
+		poll_fn( |cx|
+		{
+			let mut lanes = self.lanes.lock();
+
+			if lanes.closed
+			{
+				return Poll::Ready( Err( frame.take().expect( "not polled after ready" ) ) );
+			}
+
+			if lanes.queues[lane].len() >= LANE_SIZE
+			{
+				lanes.room[lane].push( cx.waker().clone() );
+
+				return Poll::Pending;
+			}
+
+			lanes.queues[lane].push_back( frame.take().expect( "not polled after ready" ) );
+
+			if let Some( waker ) = lanes.writer.take()
+			{
+				waker.wake();
+			}
+
+			Poll::Ready( Ok(()) )
+
+		}).await
+	}
+
+
+	// Queue a frame without waiting. Gives the frame back if it's lane is full or the outbox is closed.
+	//
+	pub(super) fn try_push( &self, priority: Priority, frame: Wf ) -> Result<(), Wf>
+	{
+		let lane      = priority as usize;
+		let mut lanes = self.lanes.lock();
+
+		if lanes.closed || lanes.queues[lane].len() >= LANE_SIZE
+		{
+			return Err( frame );
+		}
+
+		lanes.queues[lane].push_back( frame );
+
+		if let Some( waker ) = lanes.writer.take()
+		{
+			waker.wake();
+		}
+
+		Ok(())
+	}
+
+
+	// No more frames can be queued. The writer will send out what is queued and then end.
+	//
+	pub(super) fn close( &self )
+	{
+		let mut lanes = self.lanes.lock();
+
+		lanes.closed = true;
+
+		if let Some( waker ) = lanes.writer.take()
+		{
+			waker.wake();
+		}
+
+		for waker in lanes.room.iter_mut().flat_map( |r| r.drain(..) )
+		{
+			waker.wake();
+		}
+	}
+}
+
+
+impl<Wf> Stream for Outbox<Wf>
+{
+	type Item = Wf;
+
+	fn poll_next( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		let mut lanes = self.lanes.lock();
+
+		match lanes.pick()
+		{
+			Some( lane ) =>
+			{
+				let frame = lanes.queues[lane].pop_front().expect( "lane not empty" );
+
+				for waker in lanes.room[lane].drain(..)
+				{
+					waker.wake();
+				}
+
+				Poll::Ready( Some( frame ) )
+			}
+
+			None if lanes.closed => Poll::Ready( None ),
+
+			None =>
+			{
+				lanes.writer = Some( cx.waker().clone() );
+
+				Poll::Pending
+			}
+		}
+	}
+}
+
+
+impl<Wf> fmt::Debug for Outbox<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		let lanes = self.lanes.lock();
+
+		f.debug_struct( "Outbox" )
+
+			.field( "queued", &lanes.queues.iter().map( VecDeque::len ).collect::<Vec<_>>() )
+			.field( "closed", &lanes.closed                                                  )
+			.finish()
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	// The task that writes frames from the outbox to the connection. It ends when the outbox is closed
+	// and empty and then closes the connection.
+	//
+	// The first error writing a frame ends it as well. The outbox is closed so no more frames are
+	// accepted and the peer is told to close, so pending calls fail right away instead of waiting
+	// for their timeout. The error is returned to `CloseConnection`, which reports it.
+	//
+	pub(super) async fn write_out
+	(
+		mut outbox : Outbox<Wf>             ,
+		mut sink   : Box<dyn BoundsOut<Wf>> ,
+		mut addr   : Addr<Peer<Wf>>         ,
+		    nursery: Nursery< Arc< dyn SpawnHandle<Result<Response<Wf>, PeerErr>> + Send + Sync + 'static >, Result<Response<Wf>, PeerErr> >,
+		    ctx    : PeerErrCtx             ,
+	)
+		-> Result<Response<Wf>, PeerErr>
+
+	{
+		while let Some( frame ) = outbox.next().await
+		{
+			let sid = frame.sid();
+
+			if let Err( source ) = sink.send( frame ).await
+			{
+				outbox.close();
+
+				let reason = format!( "Writing to the connection failed: {}", source );
+
+				// The peer might be processing CloseConnection already, waiting for us, so we can't
+				// wait for it's mailbox here. If the nursery is closed, the peer is closing already.
+				//
+				let _ = nursery.nurse( async move
+				{
+					let _ = addr.send( CloseConnection{ remote: false, reason } ).await;
+
+					Ok( Response::Nothing )
+				});
+
+				let ctx = ctx.sid( sid ).context( "Sending out WireFormat".to_string() );
+
+				return Err( PeerErr::WireFormat{ ctx, source } );
+			}
+		}
+
+		sink.close().await.map_err( |source|
+		{
+			let ctx = ctx.context( "Closing the connection".to_string() );
+
+			PeerErr::WireFormat{ ctx, source }
+		})?;
+
+		Ok( Response::Nothing )
+	}
+}
+
+
+
+#[cfg(test)]
+//
+mod tests
+{
+	// What's tested:
+	//
+	// ✔ frames go out by priority, in order within a priority.
+	// ✔ a lane that keeps being passed over gets served.
+	// ✔ after closing, queued frames still go out, new ones are refused.
+	//
+	use crate::{ import::{ *, assert_eq }, peer::Priority };
+	use super::{ Outbox, STARVE_LIMIT };
+
+	#[test]
+	//
+	fn priority_order() { block_on( async
+	{
+		let outbox = Outbox::new();
+
+		outbox.push( Priority::Bulk    , 1 ).await.expect( "push" );
+		outbox.push( Priority::Call    , 2 ).await.expect( "push" );
+		outbox.push( Priority::Bulk    , 3 ).await.expect( "push" );
+		outbox.push( Priority::Response, 4 ).await.expect( "push" );
+		outbox.push( Priority::Control , 5 ).await.expect( "push" );
+
+		outbox.close();
+
+		assert_eq!( vec![ 5, 4, 2, 1, 3 ], outbox.collect::<Vec<u8>>().await );
+	})}
+
+
+	#[test]
+	//
+	fn starvation() { block_on( async
+	{
+		let mut outbox = Outbox::new();
+
+		outbox.push( Priority::Bulk, 0 ).await.expect( "push" );
+
+		for i in 1..=10
+		{
+			outbox.push( Priority::Control, i ).await.expect( "push" );
+		}
+
+		let first: Vec<u8> = (&mut outbox).take( STARVE_LIMIT + 1 ).collect().await;
+
+		assert_eq!( Some( &0 ), first.last() );
+	})}
+
+
+	#[test]
+	//
+	fn close() { block_on( async
+	{
+		let outbox = Outbox::new();
+
+		outbox.push( Priority::Call, 1 ).await.expect( "push" );
+
+		outbox.close();
+
+		assert_eq!( Err(2), outbox.push( Priority::Call, 2 ).await );
+		assert_eq!( vec![ 1 ], outbox.collect::<Vec<u8>>().await );
+	})}
+}
@@ -0,0 +1,47 @@
+use crate::{ import::*, * };
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Create two peers that are connected to each other in memory and return their addresses. Frames
+	/// pass between them over channels, so no sockets and no framing are involved. This is useful for
+	/// testing remote services, or to compose parts of an application that normally run in separate
+	/// processes.
+	///
+	/// The peers are built from `a` and `b`, so register the services each side exposes on their builder.
+	/// `buffer_a` and `buffer_b` are the number of frames that can wait to be read by `a` and `b` respectively
+	/// before the writing side has to wait.
+	///
+	/// When either peer closes the connection, the other one sees `PeerEvent::ClosedByRemote`.
+	///
+	/// ```ignore
+	/// let server = PeerBuilder::new().name( "server" ).service_map( Arc::new( services ) );
+	/// let client = PeerBuilder::new().name( "client" );
+	///
+	/// let (_server, client) = Peer::pair( server, client, 32, 32, exec )?;
+	/// ```
+	//
+	pub fn pair
+	(
+		a       : PeerBuilder<Wf>           ,
+		b       : PeerBuilder<Wf>           ,
+		buffer_a: usize                     ,
+		buffer_b: usize                     ,
+		exec    : impl PeerExec<Wf> + Spawn ,
+	)
+		-> Result< (Addr<Self>, Addr<Self>), PeerErr >
+
+	{
+		let (to_a, from_b) = mpsc::channel::<Wf>( buffer_a );
+		let (to_b, from_a) = mpsc::channel::<Wf>( buffer_b );
+
+		// The other side is gone.
+		//
+		let broken = |_| WireErr::Io{ kind: io::ErrorKind::BrokenPipe };
+
+		let a = a.build( from_b.map( Ok ), to_b.sink_map_err( broken ), exec.clone() )?;
+		let b = b.build( from_a.map( Ok ), to_a.sink_map_err( broken ), exec         )?;
+
+		Ok( (a, b) )
+	}
+}
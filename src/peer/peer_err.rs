@@ -1,4 +1,4 @@
-use crate::{ import::*, ConnID, ServiceID, ConnectionError, WireErr };
+use crate::{ import::*, AppError, ConnID, ServiceID, ConnectionError, WireErr, TraceContext };
 
 
 /// Errors that can happen in thespis_impl.
@@ -9,11 +9,29 @@ use crate::{ import::*, ConnID, ServiceID, ConnectionError, WireErr };
 //
 pub enum PeerErr
 {
+	/// The handler of a remote service returned an application error. See [`Fallible`](crate::Fallible).
+	//
+	Application
+	{
+		ctx: PeerErrCtx ,
+		err: AppError   ,
+	},
+
+	/// The remote tried to open a channel with an id that is already in use. The remote gets
+	/// `ConnectionError::ChannelInUse` and the open channel is not affected.
+	//
+	ChannelInUse
+	{
+		/// The context in which the error happened.
+		//
+		ctx: PeerErrCtx
+	},
+
 	/// Cannot use peer after the connection is closed.
 	//
 	ConnectionClosed
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		ctx: PeerErrCtx
 	},
@@ -23,16 +41,17 @@ pub enum PeerErr
 	//
 	Deserialize
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		ctx: PeerErrCtx
 	},
 
-	/// Cannot deliver because the handling actor is no longer running.
+	/// Cannot deliver because the handling actor is no longer running. This is also what you get when the
+	/// actor panics while handling the message. Its mailbox stops, so the panic message isn't available here.
 	//
 	HandlerDead
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		ctx: PeerErrCtx
 	},
@@ -44,7 +63,7 @@ pub enum PeerErr
 	//
 	NoHandler
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		ctx: PeerErrCtx
 	},
@@ -53,7 +72,7 @@ pub enum PeerErr
 	//
 	PeerGone
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		ctx: PeerErrCtx
 	},
@@ -67,6 +86,15 @@ pub enum PeerErr
 		relay_name: Option<Arc<str>> ,
 	},
 
+	/// A request from the remote was rejected because it exceeded a rate limit set on the peer.
+	//
+	RateLimited
+	{
+		/// The context in which the error happened.
+		//
+		ctx: PeerErrCtx
+	},
+
 	/// An error happened when a remote tried to process your message.
 	//
 	Remote
@@ -75,11 +103,27 @@ pub enum PeerErr
 		err: ConnectionError ,
 	},
 
+	/// The future that a service map returned to process an incoming request panicked, eg. while
+	/// deserializing or in a custom [`ServiceMap`](crate::ServiceMap). A panic in the handling actor
+	/// is reported as [`PeerErr::HandlerDead`] instead. The remote gets `ConnectionError::InternalServerError`
+	/// and the peer keeps running.
+	//
+	RequestPanic
+	{
+		/// The context in which the error happened.
+		//
+		ctx: PeerErrCtx,
+
+		/// The panic message, if it was a string.
+		//
+		msg: String,
+	},
+
 	/// Failed to serialize actor message.
 	//
 	Serialize
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		ctx: PeerErrCtx
 	},
@@ -88,7 +132,7 @@ pub enum PeerErr
 	//
 	Spawn
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		ctx: PeerErrCtx
 	},
@@ -110,7 +154,19 @@ pub enum PeerErr
 	//
 	Timeout
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
+		//
+		ctx: PeerErrCtx
+	},
+
+	/// An outgoing call was refused because the maximum number of outstanding calls set with
+	/// [`Peer::set_call_limit`](crate::Peer::set_call_limit) was reached and the policy is
+	/// [`CallLimitPolicy::FailFast`](crate::CallLimitPolicy::FailFast), or too many calls are
+	/// waiting for a slot already.
+	//
+	TooManyCalls
+	{
+		/// The context in which the error happened.
 		//
 		ctx: PeerErrCtx
 	},
@@ -119,7 +175,7 @@ pub enum PeerErr
 	//
 	UnknownService
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		ctx: PeerErrCtx
 	},
@@ -128,7 +184,7 @@ pub enum PeerErr
 	//
 	WireFormat
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		ctx   : PeerErrCtx ,
 		source: WireErr    ,
@@ -144,7 +200,7 @@ pub enum PeerErr
 	//
 	PubSubNoCall
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		ctx   : PeerErrCtx ,
 	},
@@ -161,6 +217,14 @@ impl fmt::Display for PeerErr
 	{
 		match &self
 		{
+			PeerErr::Application{ err, ctx } =>
+
+				write!( f, "The remote handler returned an error: {}.{}", err, ctx ),
+
+			PeerErr::ChannelInUse{ ctx } =>
+
+				write!( f, "The remote tried to open a channel with an id that is already in use.{}", ctx ),
+
 			PeerErr::ConnectionClosed{ ctx } =>
 
 				write!( f, "Cannot use peer after the connection is closed, operation.{}", ctx ),
@@ -185,10 +249,18 @@ impl fmt::Display for PeerErr
 
 				write!( f, "Failed to relay a request because the connection to the relay has been closed. context.{} relay_id: {}, relay_name: {:?}", ctx, relay_id, relay_name ),
 
+			PeerErr::RateLimited{ ctx } =>
+
+				write!( f, "Request rejected because it exceeds the rate limit.{}", ctx ),
+
 			PeerErr::Remote{ err, ctx } =>
 
 				write!( f, "A remote could not process a message we sent it{:?}.{}", err, ctx ),
 
+			PeerErr::RequestPanic{ ctx, msg } =>
+
+				write!( f, "The future processing a request panicked: {}.{}", msg, ctx ),
+
 			PeerErr::Serialize{ ctx } =>
 
 				write!( f, "Failed to serialize:{}", ctx ),
@@ -205,6 +277,10 @@ impl fmt::Display for PeerErr
 
 				write!( f, "Operation Timed out.{}", ctx ),
 
+			PeerErr::TooManyCalls{ ctx } =>
+
+				write!( f, "The maximum number of outstanding outgoing calls is reached.{}", ctx ),
+
 			PeerErr::UnknownService{ ctx } =>
 
 				write!( f, "Cannot deliver message to unknown service.{}", ctx ),
@@ -246,7 +322,7 @@ impl PeerErr
 
 						format!( "Could not deserialize your message.{}", &ctx ),
 
-					WireErr::Io{..} =>
+					WireErr::Io{..} | WireErr::Closed{..} =>
 
 						format!( "An error happened on the underlying transport.{}", &ctx ),
 				}
@@ -263,21 +339,55 @@ impl PeerErr
 	}
 
 
+	/// The name of the variant, eg. "Timeout". Used to count errors by kind in [`PeerStats`](crate::PeerStats).
+	//
+	pub fn kind( &self ) -> &'static str
+	{
+		match self
+		{
+			PeerErr::Application      {..} => "Application"      ,
+			PeerErr::ChannelInUse     {..} => "ChannelInUse"     ,
+			PeerErr::ConnectionClosed {..} => "ConnectionClosed" ,
+			PeerErr::Deserialize      {..} => "Deserialize"      ,
+			PeerErr::HandlerDead      {..} => "HandlerDead"      ,
+			PeerErr::NoHandler        {..} => "NoHandler"        ,
+			PeerErr::PeerGone         {..} => "PeerGone"         ,
+			PeerErr::RateLimited      {..} => "RateLimited"      ,
+			PeerErr::RelayGone        {..} => "RelayGone"        ,
+			PeerErr::Remote           {..} => "Remote"           ,
+			PeerErr::RequestPanic     {..} => "RequestPanic"     ,
+			PeerErr::Serialize        {..} => "Serialize"        ,
+			PeerErr::Spawn            {..} => "Spawn"            ,
+			PeerErr::ThesErr          {..} => "ThesErr"          ,
+			PeerErr::Timeout          {..} => "Timeout"          ,
+			PeerErr::TooManyCalls     {..} => "TooManyCalls"     ,
+			PeerErr::UnknownService   {..} => "UnknownService"   ,
+			PeerErr::WireFormat       {..} => "WireFormat"       ,
+			PeerErr::PubSubNoCall     {..} => "PubSubNoCall"     ,
+		}
+	}
+
+
 	pub fn ctx( &self ) -> &PeerErrCtx
 	{
 		match self
 		{
+			PeerErr::Application      { ctx, .. } => ctx,
+			PeerErr::ChannelInUse     { ctx, .. } => ctx,
 			PeerErr::ConnectionClosed { ctx, .. } => ctx,
 			PeerErr::Deserialize      { ctx, .. } => ctx,
 			PeerErr::HandlerDead      { ctx, .. } => ctx,
 			PeerErr::NoHandler        { ctx, .. } => ctx,
 			PeerErr::PeerGone         { ctx, .. } => ctx,
+			PeerErr::RateLimited      { ctx, .. } => ctx,
 			PeerErr::RelayGone        { ctx, .. } => ctx,
 			PeerErr::Remote           { ctx, .. } => ctx,
+			PeerErr::RequestPanic     { ctx, .. } => ctx,
 			PeerErr::Serialize        { ctx, .. } => ctx,
 			PeerErr::Spawn            { ctx, .. } => ctx,
 			PeerErr::ThesErr          { ctx, .. } => ctx,
 			PeerErr::Timeout          { ctx, .. } => ctx,
+			PeerErr::TooManyCalls     { ctx, .. } => ctx,
 			PeerErr::UnknownService   { ctx, .. } => ctx,
 			PeerErr::WireFormat       { ctx, .. } => ctx,
 			PeerErr::PubSubNoCall     { ctx, .. } => ctx,
@@ -296,6 +406,11 @@ pub struct PeerErrCtx
 	pub peer_name: Option< Arc<str>  > ,
 	pub sid      : Option< ServiceID > ,
 	pub cid      : Option< ConnID    > ,
+
+	/// The trace context of the request being processed, if any. This is also how service maps
+	/// get the trace context of an incoming request, so relays can pass it on.
+	//
+	pub trace    : Option< TraceContext > ,
 }
 
 
@@ -330,6 +445,12 @@ impl PeerErrCtx
 		self.cid = cid.into();
 		self
 	}
+
+	pub fn trace( mut self, trace: impl Into<Option< TraceContext >> ) -> Self
+	{
+		self.trace = trace.into();
+		self
+	}
 }
 
 
@@ -363,6 +484,11 @@ impl fmt::Display for PeerErrCtx
 			write!( f, " cid: {}.",	x )?;
 		}
 
+		if let Some( x ) = &self.trace
+		{
+			write!( f, " trace_id: {:032x}.",	x.trace_id )?;
+		}
+
 		Ok(())
 	}
 }
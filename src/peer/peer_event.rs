@@ -1,4 +1,4 @@
-use crate::{ PeerErr, ConnectionError };
+use crate::{ PeerErr, ConnectionError, ServiceID, ConnID, import::Duration };
 
 
 /// Events that can happen during the lifecycle of the peer. Use the [`observe`] method to subscribe to events.
@@ -6,6 +6,11 @@ use crate::{ PeerErr, ConnectionError };
 /// When you see either `Closed` or `ClosedByRemote`, the connection is lost and you should drop all
 /// addresses/recipients you hold for this peer, so it can be dropped. You can no longer send messages
 /// over this peer after these events.
+///
+/// The lifecycle events, for which [`PeerEvent::is_lifecycle`] is true, are only sent if you turn them on
+/// with [`Peer::set_lifecycle_events`](crate::Peer::set_lifecycle_events). If you only want some of them,
+/// pass a filter in the `ObserveConfig`, so the others are never queued for you. `is_lifecycle` can be
+/// used as the filter directly.
 //
 #[ derive( Debug, Clone, PartialEq, Eq ) ]
 //
@@ -36,5 +41,69 @@ pub enum PeerEvent
 	/// our messages.
 	//
 	RemoteError( ConnectionError ),
+
+	/// Lifecycle: an outgoing call timed out. The caller gets `ConnectionError::Timeout`.
+	//
+	CallTimeout
+	{
+		sid: ServiceID,
+		cid: ConnID,
+	},
+
+	/// Lifecycle: the response to an outgoing call came in after it timed out or the caller stopped
+	/// waiting for it. It is dropped.
+	//
+	LateResponse
+	{
+		cid: ConnID,
+	},
+
+	/// Lifecycle: processing of an incoming call started.
+	//
+	IncomingCallStarted
+	{
+		sid: ServiceID,
+		cid: ConnID,
+	},
+
+	/// Lifecycle: the handler of an incoming call has returned. The duration is not available on wasm.
+	//
+	IncomingCallCompleted
+	{
+		sid     : ServiceID,
+		cid     : ConnID,
+		duration: Option<Duration>,
+	},
+
+	/// Lifecycle: all slots of the [`BackPressure`](crate::BackPressure) are taken. The peer stops
+	/// reading from the connection.
+	//
+	BackPressureExhausted,
+
+	/// Lifecycle: a slot of the [`BackPressure`](crate::BackPressure) became available again after
+	/// it was exhausted.
+	//
+	BackPressureRestored,
+}
+
+
+impl PeerEvent
+{
+	/// Whether this is one of the opt in lifecycle events.
+	//
+	pub fn is_lifecycle( &self ) -> bool
+	{
+		matches!
+		(
+			self,
+
+			  PeerEvent::CallTimeout          {..}
+			| PeerEvent::LateResponse         {..}
+			| PeerEvent::IncomingCallStarted  {..}
+			| PeerEvent::IncomingCallCompleted{..}
+			| PeerEvent::BackPressureExhausted
+			| PeerEvent::BackPressureRestored
+		)
+	}
 }
 
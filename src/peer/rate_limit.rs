@@ -0,0 +1,288 @@
+use
+{
+	crate::{ import::*, * } ,
+	super::RequestError     ,
+};
+
+
+// How often the ticker refills the token buckets.
+//
+const TICK          : Duration = Duration::from_millis( 100 );
+const TICKS_PER_SEC : u64      = 10;
+
+// Tokens are counted in thousandths, so slow rates still refill every tick.
+//
+const SCALE: u64 = 1000;
+
+
+/// A token bucket limit for incoming requests. Set it on a [`Peer`] for the whole connection with
+/// [`Peer::set_rate_limit`] or for one service with [`Peer::set_service_rate_limit`].
+///
+/// The bucket starts full with `burst` tokens and refills at `rate` tokens per second. Every incoming
+/// request takes one token. When there are none left, incoming calls are answered with
+/// `ConnectionError::RateLimited` and incoming sends are dropped. In both cases observers get a
+/// `PeerEvent::Error( PeerErr::RateLimited )`.
+///
+/// Optionally a hard limit can be set with [`RateLimit::close_after`]. When more requests than
+/// this are rejected within one second, the peer closes the connection.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub struct RateLimit
+{
+	/// How many requests per second are allowed on average.
+	//
+	pub rate: NonZeroU32,
+
+	/// How many requests can come in at once when the bucket is full.
+	//
+	pub burst: NonZeroU32,
+
+	/// Close the connection when more than this number of requests is rejected within one second.
+	//
+	pub close_after: Option<NonZeroU32>,
+}
+
+
+impl RateLimit
+{
+	/// Create a new limit without hard limit.
+	//
+	pub fn new( rate: NonZeroU32, burst: NonZeroU32 ) -> Self
+	{
+		Self { rate, burst, close_after: None }
+	}
+
+
+	/// Close the connection when more than `rejected` requests are rejected within one second.
+	//
+	pub fn close_after( mut self, rejected: NonZeroU32 ) -> Self
+	{
+		self.close_after = Some( rejected );
+		self
+	}
+}
+
+
+
+// What to do with an incoming request.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub(super) enum Verdict
+{
+	Allow  ,
+	Reject ,
+	Close  ,
+}
+
+
+
+#[ derive( Debug ) ]
+//
+struct Bucket
+{
+	limit    : RateLimit ,
+	tokens   : u64       ,
+	last_tick: u64       ,
+	second   : u64       ,
+	rejected : u32       ,
+}
+
+
+impl Bucket
+{
+	fn new( limit: RateLimit, now: u64 ) -> Self
+	{
+		Self
+		{
+			limit                                             ,
+			tokens   : u64::from( limit.burst.get() ) * SCALE ,
+			last_tick: now                                    ,
+			second   : now / TICKS_PER_SEC                    ,
+			rejected : 0                                      ,
+		}
+	}
+
+
+	fn take( &mut self, now: u64 ) -> Verdict
+	{
+		let capacity = u64::from( self.limit.burst.get() ) * SCALE;
+		let refill   = u64::from( self.limit.rate .get() ) * SCALE / TICKS_PER_SEC;
+		let elapsed  = now.saturating_sub( self.last_tick );
+
+		self.tokens    = std::cmp::min( capacity, self.tokens.saturating_add( elapsed.saturating_mul( refill ) ) );
+		self.last_tick = now;
+
+		if now / TICKS_PER_SEC != self.second
+		{
+			self.second   = now / TICKS_PER_SEC;
+			self.rejected = 0;
+		}
+
+		if self.tokens >= SCALE
+		{
+			self.tokens -= SCALE;
+			return Verdict::Allow;
+		}
+
+		self.rejected = self.rejected.saturating_add( 1 );
+
+		match self.limit.close_after
+		{
+			Some( max ) if self.rejected > max.get() => Verdict::Close  ,
+			_                                        => Verdict::Reject ,
+		}
+	}
+}
+
+
+
+// The token buckets of a peer. The clock is a counter incremented by a task, so we don't need
+// `Instant`, which isn't available on wasm.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct RateLimiter
+{
+	ticks     : Arc<AtomicU64>               ,
+	connection: Option<Bucket>               ,
+	services  : HashMap< ServiceID, Bucket > ,
+}
+
+
+impl RateLimiter
+{
+	fn new() -> Self
+	{
+		Self
+		{
+			ticks     : Arc::new( AtomicU64::new(0) ) ,
+			connection: None                          ,
+			services  : HashMap::new()                ,
+		}
+	}
+
+
+	// Take a token from the connection bucket and the bucket of the service if they exist.
+	//
+	pub(super) fn check( &mut self, sid: ServiceID ) -> Verdict
+	{
+		let now = self.ticks.load( SeqCst );
+
+		let conn = match &mut self.connection
+		{
+			Some( bucket ) => bucket.take( now ),
+			None           => Verdict::Allow    ,
+		};
+
+		if conn != Verdict::Allow { return conn }
+
+		match self.services.get_mut( &sid )
+		{
+			Some( bucket ) => bucket.take( now ),
+			None           => Verdict::Allow    ,
+		}
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Limit the rate of incoming requests on this connection. This counts both sends and calls to
+	/// all services. See [`RateLimit`]. Setting it again replaces the previous limit.
+	///
+	/// This spawns a task that acts as a clock for the rate limiter. It fails if spawning fails.
+	//
+	pub fn set_rate_limit( &mut self, limit: RateLimit ) -> Result<(), PeerErr>
+	{
+		let limiter = self.rate_limiter()?;
+		let now     = limiter.ticks.load( SeqCst );
+
+		limiter.connection = Some( Bucket::new( limit, now ) );
+
+		Ok(())
+	}
+
+
+	/// Limit the rate of incoming requests for one service. This applies on top of the limit
+	/// set with [`Peer::set_rate_limit`]. Setting it again replaces the previous limit.
+	///
+	/// This spawns a task that acts as a clock for the rate limiter. It fails if spawning fails.
+	//
+	pub fn set_service_rate_limit( &mut self, sid: ServiceID, limit: RateLimit ) -> Result<(), PeerErr>
+	{
+		let limiter = self.rate_limiter()?;
+		let now     = limiter.ticks.load( SeqCst );
+
+		limiter.services.insert( sid, Bucket::new( limit, now ) );
+
+		Ok(())
+	}
+
+
+	// Get the rate limiter, creating it and spawning the clock if needed.
+	//
+	fn rate_limiter( &mut self ) -> Result< &mut RateLimiter, PeerErr >
+	{
+		if self.rate_limiter.is_none()
+		{
+			let limiter = RateLimiter::new();
+			let ticks   = limiter.ticks.clone();
+
+			// Stops when the peer drops the rate limiter.
+			//
+			let clock = async move
+			{
+				while Arc::strong_count( &ticks ) > 1
+				{
+					Delay::new( TICK ).await;
+
+					ticks.fetch_add( 1, SeqCst );
+				}
+
+				Ok( Response::Nothing )
+			};
+
+			if self.nursery.nurse( clock ).is_err()
+			{
+				let ctx = self.ctx( None, None, "Spawn clock for rate limiter" );
+
+				return Err( PeerErr::Spawn{ ctx } );
+			}
+
+			self.rate_limiter = Some( limiter );
+		}
+
+		Ok( self.rate_limiter.as_mut().unwrap() )
+	}
+
+
+	// Check an incoming request against the rate limits. Returns false if the request must be dropped,
+	// in which case the error has already been reported.
+	//
+	pub(super) async fn rate_limit( &mut self, sid: ServiceID, cid: Option<ConnID> ) -> bool
+	{
+		let verdict = match &mut self.rate_limiter
+		{
+			Some( limiter ) => limiter.check( sid ),
+			None            => return true,
+		};
+
+		if verdict == Verdict::Allow { return true }
+
+		let ctx = self.ctx( sid, cid, "Incoming request exceeds rate limit" );
+
+		self.handle( RequestError::from( PeerErr::RateLimited{ ctx } ) ).await;
+
+		if verdict == Verdict::Close
+		{
+			let close_conn = CloseConnection{ remote: false, reason: "Remote exceeded the hard rate limit.".to_string() };
+
+			Handler::<CloseConnection>::handle( self, close_conn ).await
+		}
+
+		false
+	}
+}
@@ -0,0 +1,199 @@
+use crate::{ import::*, * };
+
+
+/// How much the built in reflection service of a [`Peer`] tells remotes about the services it exposes.
+/// Set it with [`Peer::set_reflection`] or [`PeerConfig::reflection`]. Defaults to `Disabled`, so a peer
+/// only tells remotes what it exposes if you opt in.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+pub enum Reflection
+{
+	/// Remotes calling the reflection service get `ConnectionError::UnknownService`.
+	//
+	Disabled,
+
+	/// Remotes get the service ids and the names registered with [`ServiceID::register_service`].
+	//
+	Services,
+
+	/// Like `Services`, but with the type names of the message and the return type if they are registered
+	/// with [`ServiceID::register_types`].
+	//
+	Full,
+}
+
+
+impl Default for Reflection
+{
+	fn default() -> Self
+	{
+		Reflection::Disabled
+	}
+}
+
+
+
+/// Description of a service exposed by a remote peer, as returned by the reflection service.
+//
+#[ derive( Debug, Clone, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+pub struct ServiceInfo
+{
+	/// The service id.
+	//
+	pub sid: ServiceID,
+
+	/// The name registered for the service on the remote, usually "namespace::Type".
+	//
+	pub name: Option<String>,
+
+	/// The types of the service, only when the remote has reflection set to [`Reflection::Full`].
+	//
+	pub types: Option<TypeInfo>,
+}
+
+
+/// The type names of a service. These are generated by `std::any::type_name`, so they are
+/// meant for humans, not to be parsed.
+//
+#[ derive( Debug, Clone, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+pub struct TypeInfo
+{
+	/// The type of the message.
+	//
+	pub message: String,
+
+	/// The return type of the message. For streaming services, the type of the items.
+	//
+	pub returns: String,
+}
+
+
+
+impl ServiceInfo
+{
+	/// Ask the remote of `peer` which services it exposes. This calls the reflection service of the
+	/// remote, so it fails with `ConnectionError::UnknownService` if the remote has reflection disabled.
+	//
+	pub async fn list_remote<Wf>( peer: &mut Addr<Peer<Wf>> ) -> Result< Vec<ServiceInfo>, PeerErr >
+
+		where Wf: WireFormat + Send + 'static
+	{
+		let sid     = ServiceID::reflection();
+		let mut ctx = Peer::err_ctx( peer, sid, None, "Call reflection service".to_string() );
+
+		let mut wf = Wf::default();
+		wf.set_sid( sid );
+		serde_cbor::to_writer( &mut wf, &() ).expect( "serialize unit" );
+
+		let rx = peer.call( Call::new( wf ) ).await
+
+			.map_err( |_| PeerErr::PeerGone{ ctx: ctx.clone() } )?
+			.map_err( |_| PeerErr::ConnectionClosed{ ctx: ctx.clone() } )?
+		;
+
+		let resp = match rx.await
+		{
+			Ok( Ok(resp) ) => resp,
+
+			Ok( Err( ConnectionError::Timeout{..} ) ) =>
+			{
+				ctx.context = Some( "Time out waiting for response from reflection service".to_string() );
+
+				return Err( PeerErr::Timeout{ ctx } );
+			}
+
+			Ok( Err(err) ) =>
+			{
+				ctx.context = Some( "Remote could not process our reflection request".to_string() );
+
+				return Err( PeerErr::Remote{ err, ctx } );
+			}
+
+			Err(_) =>
+			{
+				ctx.context = Some( "Peer stopped before receiving response from reflection service".to_string() );
+
+				return Err( PeerErr::ConnectionClosed{ ctx } );
+			}
+		};
+
+		serde_cbor::from_slice( resp.msg() ).map_err( |_|
+		{
+			ctx.context = Some( "Response from reflection service".to_string() );
+			ctx.cid     = resp.cid().into();
+
+			PeerErr::Deserialize{ ctx }
+		})
+	}
+
+
+	/// Whether the remote of `peer` exposes the service `sid`. This calls the reflection service
+	/// of the remote every time, so you might want to use [`ServiceInfo::list_remote`] if you need
+	/// to check several services.
+	//
+	pub async fn is_reachable<Wf>( peer: &mut Addr<Peer<Wf>>, sid: ServiceID ) -> Result< bool, PeerErr >
+
+		where Wf: WireFormat + Send + 'static
+	{
+		let services = Self::list_remote( peer ).await?;
+
+		Ok( services.iter().any( |s| s.sid == sid ) )
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Set how much the built in reflection service tells remotes about the services this peer
+	/// exposes. See [`Reflection`].
+	//
+	pub fn set_reflection( &mut self, reflection: Reflection )
+	{
+		self.reflection = reflection;
+	}
+
+
+	// The response to a call to the reflection service.
+	//
+	pub(super) fn reflect( &self, cid: ConnID ) -> Wf
+	{
+		trace!( "{}: Incoming call to reflection service, cid: {}", self.identify(), cid );
+
+		let full = self.reflection == Reflection::Full;
+
+		let sids = self.services.keys().filter( |sid| !self.aliases.contains( sid ) );
+
+		let mut services: Vec<ServiceInfo> = sids.map( |sid|
+		{
+			let types = match full
+			{
+				true  => ServiceID::service_types( *sid ),
+				false => None,
+			};
+
+			ServiceInfo
+			{
+				sid  : *sid                                                                   ,
+				name : ServiceID::service_name( *sid ).map( String::from )                    ,
+				types: types.map( |(m, r)| TypeInfo{ message: m.into(), returns: r.into() } ) ,
+			}
+
+		}).collect();
+
+		// HashMap order is random, give the remote something stable.
+		//
+		services.sort_by_key( |s| -> u64 { s.sid.into() } );
+
+		let mut wf = Wf::with_capacity( services.len() * 32 );
+		wf.set_sid( ServiceID::full() );
+		wf.set_cid( cid               );
+
+		serde_cbor::to_writer( &mut wf, &services ).expect( "serialize ServiceInfo" );
+
+		wf
+	}
+}
@@ -25,7 +25,7 @@ use crate::{ import::*, * };
 ///
 /// - spawn errors can happen when spawning tasks to handle the request
 ///
-/// - handlers might panic
+/// - handlers might panic, the task processing the request catches that
 ///
 /// - remote connection might shut down before we can send a response.
 ///
@@ -59,10 +59,9 @@ impl<Wf: WireFormat> Handler<RequestError> for Peer<Wf>
 {
 	fn handle( &mut self, msg: RequestError ) -> Return<'_, ()> { async move
 	{
-		// All errors get reported through pharos.
-		// expect: pharos shouldn't be closed unless we close it and we don't.
+		// All errors get reported through pharos and counted in the stats.
 		//
-		self.pharos.send( PeerEvent::Error( msg.error.clone() ) ).await.expect( "pharos not closed" );
+		self.report( msg.error.clone() ).await;
 
 		// If it was a send, don't send errors to the remote. Only call buys into feedback.
 		//
@@ -116,13 +115,14 @@ impl<Wf: WireFormat> Handler<RequestError> for Peer<Wf>
 			}
 
 
-			  PeerErr::RelayGone  { ctx, .. }
-			| PeerErr::NoHandler  { ctx     }
-			| PeerErr::HandlerDead{ ctx     } =>
+			  PeerErr::RelayGone   { ctx, .. }
+			| PeerErr::NoHandler   { ctx     }
+			| PeerErr::HandlerDead { ctx     }
+			| PeerErr::RequestPanic{ ctx, .. } =>
 			{
 				// Report to remote, we don't close the connection because we might expose other
 				// services that are still operational, or the actor might be in the process of
-				// being restarted.
+				// being restarted. The panic message stays in our process, it's in the event.
 				//
 				let err = ConnectionError::InternalServerError{ sid: ctx.sid, cid: cid.into() };
 
@@ -143,6 +143,16 @@ impl<Wf: WireFormat> Handler<RequestError> for Peer<Wf>
 			}
 
 
+			PeerErr::ChannelInUse{ ctx } =>
+			{
+				// This is not fatal, NOT closing the connection.
+				//
+				let err = ConnectionError::ChannelInUse{ sid: ctx.sid, cid: cid.into() };
+
+				self.send_err( cid, &err, false ).await;
+			}
+
+
 			PeerErr::UnknownService{ ctx } =>
 			{
 				// This is not fatal, NOT closing the connection.
@@ -153,6 +163,17 @@ impl<Wf: WireFormat> Handler<RequestError> for Peer<Wf>
 			}
 
 
+			PeerErr::RateLimited{ ctx } =>
+			{
+				// This is not fatal, NOT closing the connection. If the remote exceeds a hard limit,
+				// the peer closes the connection itself.
+				//
+				let err = ConnectionError::RateLimited{ sid: ctx.sid, cid: cid.into() };
+
+				self.send_err( cid, &err, false ).await;
+			}
+
+
 			PeerErr::PubSubNoCall{ ctx } =>
 			{
 				// This is not fatal, NOT closing the connection.
@@ -1,4 +1,4 @@
-use crate::{ CallResponse, ThesWF };
+use crate::{ CallResponse, StreamResponse, ThesWF };
 
 /// A type to unify the two types of responses that can be returned by spawned tasks that
 /// process a request.
@@ -15,6 +15,11 @@ pub enum Response<Wf = ThesWF>
 	//
 	CallResponse(CallResponse<Wf>),
 
+	/// Response to a call to a streaming service. The peer will send out all the frames and
+	/// then free the back pressure slot.
+	//
+	Stream(StreamResponse<Wf>),
+
 	/// Nothing, eg. task handles a send.
 	//
 	Nothing,
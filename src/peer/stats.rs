@@ -0,0 +1,653 @@
+use
+{
+	crate :: { import::*, *                                           } ,
+	std   :: { collections::BTreeMap, time::Instant, fmt::Write as _ } ,
+};
+
+
+// Upper bounds of the latency buckets in seconds.
+//
+const BUCKETS: &[f64] = &[ 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0 ];
+
+
+// There is no clock in std on wasm, so latencies are not recorded there.
+//
+pub(super) fn now() -> Option<Instant>
+{
+	#[ cfg(not( target_arch = "wasm32" )) ] { Some( Instant::now() ) }
+	#[ cfg(     target_arch = "wasm32"    ) ] { None                 }
+}
+
+
+
+/// Ask a [`Peer`] for a snapshot of it's statistics. See [`PeerStats`].
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub struct GetStats;
+
+impl Message for GetStats
+{
+	type Return = PeerStats;
+}
+
+
+
+/// A latency histogram. The buckets are fixed, from 1ms up to 10s.
+//
+#[ derive( Debug, Clone, PartialEq ) ]
+//
+pub struct Histogram
+{
+	/// The upper bounds of the buckets in seconds.
+	//
+	pub bounds: &'static [f64],
+
+	/// The number of observations per bucket. These are not cumulative. There is one more than there
+	/// are bounds, which counts observations above the largest bound.
+	//
+	pub counts: Vec<u64>,
+
+	/// The sum of all observations in seconds.
+	//
+	pub sum: f64,
+
+	/// The number of observations.
+	//
+	pub count: u64,
+}
+
+
+impl Default for Histogram
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			bounds: BUCKETS                        ,
+			counts: vec![ 0; BUCKETS.len() + 1 ]   ,
+			sum   : 0.0                            ,
+			count : 0                              ,
+		}
+	}
+}
+
+
+impl Histogram
+{
+	fn observe( &mut self, since: Option<Instant> )
+	{
+		let secs = match since
+		{
+			Some( start ) => start.elapsed().as_secs_f64(),
+			None          => return,
+		};
+
+		let bucket = self.bounds.iter().position( |b| secs <= *b ).unwrap_or( self.bounds.len() );
+
+		self.counts[ bucket ] += 1;
+		self.sum              += secs;
+		self.count            += 1;
+	}
+}
+
+
+
+/// Statistics for one service. Incoming counts requests from the remote, outgoing counts requests
+/// we made to the remote.
+//
+#[ derive( Debug, Clone, Default, PartialEq ) ]
+//
+pub struct ServiceStats
+{
+	/// Incoming calls.
+	//
+	pub calls_in: u64,
+
+	/// Incoming sends.
+	//
+	pub sends_in: u64,
+
+	/// Outgoing calls, including streaming calls.
+	//
+	pub calls_out: u64,
+
+	/// Outgoing sends.
+	//
+	pub sends_out: u64,
+
+	/// Errors reported as [`PeerEvent::Error`] that concern this service.
+	//
+	pub errors: u64,
+
+	/// Outgoing calls that timed out.
+	//
+	pub timeouts: u64,
+
+	/// How long it took for outgoing calls to get a response, or for streaming calls the end of the
+	/// stream.
+	//
+	pub call_latency: Histogram,
+
+	/// How long it took to process incoming calls, until the response was ready to go out.
+	//
+	pub handle_latency: Histogram,
+}
+
+
+
+/// A snapshot of what a [`Peer`] has been doing, returned for [`GetStats`]. Counters count since
+/// the peer was created. Gauges are the values at the time of the snapshot.
+///
+/// Use [`PeerStats::to_prometheus`] to render it in the Prometheus text format, or [`PeerStats::prometheus`]
+/// to render the stats of several peers at once.
+//
+#[ derive( Debug, Clone, Default, PartialEq ) ]
+//
+pub struct PeerStats
+{
+	/// The actor id of the peer.
+	//
+	pub peer_id: usize,
+
+	/// The actor name of the peer.
+	//
+	pub peer_name: Option<Arc<str>>,
+
+	/// Frames received from the connection.
+	//
+	pub frames_in: u64,
+
+	/// Bytes received from the connection.
+	//
+	pub bytes_in: u64,
+
+	/// Frames sent out.
+	//
+	pub frames_out: u64,
+
+	/// Bytes sent out.
+	//
+	pub bytes_out: u64,
+
+	/// Incoming calls.
+	//
+	pub calls_in: u64,
+
+	/// Incoming sends.
+	//
+	pub sends_in: u64,
+
+	/// Outgoing calls, including streaming calls.
+	//
+	pub calls_out: u64,
+
+	/// Outgoing sends.
+	//
+	pub sends_out: u64,
+
+	/// Outgoing calls that timed out.
+	//
+	pub timeouts: u64,
+
+	/// Errors reported as [`PeerEvent::Error`], by [`PeerErr::kind`].
+	//
+	pub errors: BTreeMap<&'static str, u64>,
+
+	/// Errors about a sid that this peer neither exposes nor called, like a remote calling a service
+	/// that doesn't exist. The remote chooses these sids, so they are not counted per service.
+	//
+	pub unknown_sid_errors: u64,
+
+	/// Gauge: outgoing calls waiting for a response.
+	//
+	pub in_flight: usize,
+
+	/// Gauge: outgoing calls waiting for a slot because the [`CallLimit`] is reached.
+	//
+	pub waiting_calls: usize,
+
+	/// The maximum of outstanding outgoing calls, if a [`CallLimit`] is set.
+	//
+	pub max_calls: Option<usize>,
+
+	/// Gauge: incoming requests being processed by tasks in the nursery.
+	//
+	pub tasks: usize,
+
+	/// Gauge: available slots of the [`BackPressure`], if the peer has one.
+	//
+	pub backpressure: Option<i64>,
+
+	/// The statistics per service, for the services this peer exposes and the ones it called.
+	//
+	pub services: HashMap<ServiceID, ServiceStats>,
+}
+
+
+
+// The counters the peer keeps. Shared with the tasks processing requests.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct Metrics
+{
+	stats  : PeerStats                                        ,
+	started: HashMap< ConnID, (ServiceID, Option<Instant>) > ,
+
+	// The sids we keep per service stats for. Only sids we expose or call, so a remote can't make
+	// the stats grow.
+	//
+	known  : HashSet< ServiceID >                             ,
+}
+
+
+impl Metrics
+{
+	pub(super) fn new( peer_id: usize, peer_name: Option<Arc<str>> ) -> Self
+	{
+		Self
+		{
+			stats  : PeerStats{ peer_id, peer_name, ..Default::default() } ,
+			started: HashMap::new()                                         ,
+			known  : HashSet::new()                                         ,
+		}
+	}
+
+
+	// Keep per service stats for a sid this peer exposes.
+	//
+	pub(super) fn expose( &mut self, sid: ServiceID )
+	{
+		self.known.insert( sid );
+	}
+
+
+	fn service( &mut self, sid: ServiceID ) -> Option<&mut ServiceStats>
+	{
+		if !self.known.contains( &sid ) { return None }
+
+		Some( self.stats.services.entry( sid ).or_default() )
+	}
+
+
+	pub(super) fn frame_in( &mut self, len: u64 )
+	{
+		self.stats.frames_in += 1;
+		self.stats.bytes_in  += len;
+	}
+
+
+	pub(super) fn frame_out( &mut self, len: u64 )
+	{
+		self.stats.frames_out += 1;
+		self.stats.bytes_out  += len;
+	}
+
+
+	// Frames in both directions and the number of requests being processed.
+	//
+	pub(super) fn activity( &self ) -> (u64, usize)
+	{
+		(self.stats.frames_in + self.stats.frames_out, self.stats.tasks)
+	}
+
+
+	pub(super) fn request_in( &mut self, sid: ServiceID, call: bool )
+	{
+		match call
+		{
+			true  => self.stats.calls_in += 1,
+			false => self.stats.sends_in += 1,
+		}
+
+		if let Some( svc ) = self.service( sid )
+		{
+			match call
+			{
+				true  => svc.calls_in += 1,
+				false => svc.sends_in += 1,
+			}
+		}
+	}
+
+
+	pub(super) fn request_out( &mut self, sid: ServiceID, cid: ConnID, call: bool )
+	{
+		self.known.insert( sid );
+
+		let svc = self.stats.services.entry( sid ).or_default();
+
+		match call
+		{
+			true  =>
+			{
+				self.stats.calls_out += 1;
+				svc.calls_out += 1;
+				self.started.insert( cid, (sid, now()) );
+			}
+
+			false => { self.stats.sends_out += 1; svc.sends_out += 1; }
+		}
+	}
+
+
+	// An outgoing call got it's response, or an error.
+	//
+	pub(super) fn response_in( &mut self, cid: ConnID )
+	{
+		if let Some( (sid, start) ) = self.started.remove( &cid ) {
+		if let Some( svc        ) = self.service( sid )
+		{
+			svc.call_latency.observe( start );
+		}}
+	}
+
+
+	// An outgoing call for which we no longer wait.
+	//
+	pub(super) fn forget( &mut self, cid: ConnID )
+	{
+		self.started.remove( &cid );
+	}
+
+
+	pub(super) fn timeout( &mut self, sid: ServiceID, cid: ConnID )
+	{
+		self.started.remove( &cid );
+
+		self.stats.timeouts += 1;
+
+		if let Some( svc ) = self.service( sid )
+		{
+			svc.timeouts += 1;
+		}
+	}
+
+
+	pub(super) fn error( &mut self, err: &PeerErr )
+	{
+		*self.stats.errors.entry( err.kind() ).or_default() += 1;
+
+		if let Some( sid ) = err.ctx().sid
+		{
+			match self.service( sid )
+			{
+				Some( svc ) => svc.errors += 1,
+				None        => self.stats.unknown_sid_errors += 1,
+			}
+		}
+	}
+
+
+	// Outgoing calls that were still waiting when the connection closed won't get a response.
+	//
+	pub(super) fn clear( &mut self )
+	{
+		self.started.clear();
+	}
+
+
+	// Track an incoming request while it's being processed.
+	//
+	pub(super) fn task( metrics: &Arc<Mutex<Metrics>>, sid: ServiceID, call: bool ) -> TaskGuard
+	{
+		metrics.lock().stats.tasks += 1;
+
+		TaskGuard{ metrics: metrics.clone(), sid, call, start: now() }
+	}
+}
+
+
+
+// Held by the task processing an incoming request.
+//
+#[ derive( Debug ) ]
+//
+pub(super) struct TaskGuard
+{
+	metrics: Arc<Mutex<Metrics>> ,
+	sid    : ServiceID           ,
+	call   : bool                ,
+	start  : Option<Instant>     ,
+}
+
+
+impl Drop for TaskGuard
+{
+	fn drop( &mut self )
+	{
+		let mut metrics = self.metrics.lock();
+
+		metrics.stats.tasks -= 1;
+
+		if self.call {
+		if let Some( svc ) = metrics.service( self.sid )
+		{
+			svc.handle_latency.observe( self.start );
+		}}
+	}
+}
+
+
+
+impl<Wf: WireFormat> Handler<GetStats> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: GetStats ) -> PeerStats
+	{
+		let mut stats = self.metrics.lock().stats.clone();
+
+		stats.waiting_calls = self.waiting_calls();
+		stats.in_flight     = self.responses.len() + self.streams.len();
+		stats.max_calls     = self.call_slots.as_ref().map( |s| s.limit.max.get() );
+		stats.backpressure  = self.backpressure.as_ref().map( |bp| bp.available() );
+
+		stats
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	// Report an error to observers and count it.
+	//
+	pub(super) async fn report( &mut self, err: PeerErr )
+	{
+		self.metrics.lock().error( &err );
+
+		// If pharos is closed, we already panicked... so except is fine.
+		//
+		self.pharos.send( PeerEvent::Error(err) ).await.expect( "pharos not closed" );
+	}
+}
+
+
+
+type Counter = ( &'static str, &'static str, fn( &PeerStats ) -> u64 );
+type SvcCounter = ( &'static str, &'static str, fn( &ServiceStats ) -> u64 );
+
+
+impl PeerStats
+{
+	/// Render in the Prometheus text exposition format.
+	//
+	pub fn to_prometheus( &self ) -> String
+	{
+		Self::prometheus( std::slice::from_ref( self ) )
+	}
+
+
+	/// Render the stats of several peers in the Prometheus text exposition format. Peers are told apart
+	/// by the `peer_id` and `peer_name` labels, services by the `sid` and `service` labels.
+	//
+	pub fn prometheus( peers: &[PeerStats] ) -> String
+	{
+		let counters: &[Counter] =
+		&[
+			( "thespis_peer_frames_in_total" , "Frames received."             , |s| s.frames_in  ),
+			( "thespis_peer_bytes_in_total"  , "Bytes received."              , |s| s.bytes_in   ),
+			( "thespis_peer_frames_out_total", "Frames sent."                 , |s| s.frames_out ),
+			( "thespis_peer_bytes_out_total" , "Bytes sent."                  , |s| s.bytes_out  ),
+			( "thespis_peer_calls_in_total"  , "Incoming calls."              , |s| s.calls_in   ),
+			( "thespis_peer_sends_in_total"  , "Incoming sends."              , |s| s.sends_in   ),
+			( "thespis_peer_calls_out_total" , "Outgoing calls."              , |s| s.calls_out  ),
+			( "thespis_peer_sends_out_total" , "Outgoing sends."              , |s| s.sends_out  ),
+			( "thespis_peer_timeouts_total"  , "Outgoing calls that timed out.", |s| s.timeouts  ),
+
+			( "thespis_peer_unknown_sid_errors_total", "Errors about services that are neither exposed nor called.", |s| s.unknown_sid_errors ),
+		];
+
+		let gauges: &[Counter] =
+		&[
+			( "thespis_peer_in_flight"    , "Outgoing calls waiting for a response."   , |s| s.in_flight     as u64 ),
+			( "thespis_peer_waiting_calls", "Outgoing calls waiting for a free slot."  , |s| s.waiting_calls as u64 ),
+			( "thespis_peer_tasks"        , "Incoming requests being processed."       , |s| s.tasks         as u64 ),
+		];
+
+		let svc_counters: &[SvcCounter] =
+		&[
+			( "thespis_service_calls_in_total" , "Incoming calls per service."                , |s| s.calls_in  ),
+			( "thespis_service_sends_in_total" , "Incoming sends per service."                , |s| s.sends_in  ),
+			( "thespis_service_calls_out_total", "Outgoing calls per service."                , |s| s.calls_out ),
+			( "thespis_service_sends_out_total", "Outgoing sends per service."                , |s| s.sends_out ),
+			( "thespis_service_errors_total"   , "Errors per service."                        , |s| s.errors    ),
+			( "thespis_service_timeouts_total" , "Outgoing calls that timed out per service." , |s| s.timeouts  ),
+		];
+
+		let mut out = String::new();
+
+		let families = counters.iter().map( |c| (c, "counter") ).chain( gauges.iter().map( |g| (g, "gauge") ) );
+
+		for ((name, help, get), kind) in families
+		{
+			header( &mut out, name, help, kind );
+
+			for peer in peers
+			{
+				sample( &mut out, name, &peer.labels(), get( peer ) as f64 );
+			}
+		}
+
+
+		header( &mut out, "thespis_peer_backpressure_available", "Available slots of the backpressure.", "gauge" );
+
+		for peer in peers
+		{
+			if let Some( available ) = peer.backpressure
+			{
+				sample( &mut out, "thespis_peer_backpressure_available", &peer.labels(), available as f64 );
+			}
+		}
+
+
+		header( &mut out, "thespis_peer_errors_total", "Errors by kind.", "counter" );
+
+		for peer in peers
+		{
+			for (kind, count) in &peer.errors
+			{
+				let labels = format!( "{},kind=\"{}\"", peer.labels(), kind );
+
+				sample( &mut out, "thespis_peer_errors_total", &labels, *count as f64 );
+			}
+		}
+
+
+		for (name, help, get) in svc_counters
+		{
+			header( &mut out, name, help, "counter" );
+
+			for peer in peers {
+			for (sid, svc) in peer.sorted_services()
+			{
+				sample( &mut out, name, &peer.service_labels( sid ), get( svc ) as f64 );
+			}}
+		}
+
+
+		let histograms: &[( &str, &str, fn( &ServiceStats ) -> &Histogram )] =
+		&[
+			( "thespis_service_call_latency_seconds"  , "Time until outgoing calls got a response."   , |s| &s.call_latency   ),
+			( "thespis_service_handle_latency_seconds", "Time to process incoming calls."             , |s| &s.handle_latency ),
+		];
+
+		for (name, help, get) in histograms
+		{
+			header( &mut out, name, help, "histogram" );
+
+			for peer in peers {
+			for (sid, svc) in peer.sorted_services()
+			{
+				let hist       = get( svc );
+				let labels     = peer.service_labels( sid );
+				let mut cumul  = 0;
+
+				for (bound, count) in hist.bounds.iter().zip( &hist.counts )
+				{
+					cumul += count;
+
+					let _ = writeln!( out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, bound, cumul );
+				}
+
+				let _ = writeln!( out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, hist.count );
+				let _ = writeln!( out, "{}_sum{{{}}} {}"               , name, labels, hist.sum   );
+				let _ = writeln!( out, "{}_count{{{}}} {}"             , name, labels, hist.count );
+			}}
+		}
+
+		out
+	}
+
+
+	fn labels( &self ) -> String
+	{
+		match &self.peer_name
+		{
+			Some( name ) => format!( "peer_id=\"{}\",peer_name=\"{}\"", self.peer_id, escape( name ) ),
+			None         => format!( "peer_id=\"{}\""                 , self.peer_id                 ),
+		}
+	}
+
+
+	fn service_labels( &self, sid: ServiceID ) -> String
+	{
+		match ServiceID::service_name( sid )
+		{
+			Some( name ) => format!( "{},sid=\"{:x}\",service=\"{}\"", self.labels(), sid, escape( name ) ),
+			None         => format!( "{},sid=\"{:x}\""               , self.labels(), sid                 ),
+		}
+	}
+
+
+	// HashMap order is random, keep the output stable.
+	//
+	fn sorted_services( &self ) -> Vec<( ServiceID, &ServiceStats )>
+	{
+		let mut services: Vec<_> = self.services.iter().map( |(sid, svc)| (*sid, svc) ).collect();
+
+		services.sort_by_key( |(sid, _)| -> u64 { (*sid).into() } );
+
+		services
+	}
+}
+
+
+
+fn header( out: &mut String, name: &str, help: &str, kind: &str )
+{
+	let _ = writeln!( out, "# HELP {} {}", name, help );
+	let _ = writeln!( out, "# TYPE {} {}", name, kind );
+}
+
+
+fn sample( out: &mut String, name: &str, labels: &str, value: f64 )
+{
+	let _ = writeln!( out, "{}{{{}}} {}", name, labels, value );
+}
+
+
+fn escape( value: &str ) -> String
+{
+	value.replace( '\\', "\\\\" ).replace( '"', "\\\"" ).replace( '\n', "\\n" )
+}
@@ -0,0 +1,143 @@
+use
+{
+	crate::{ import::*, * } ,
+	super::RequestError     ,
+};
+
+
+/// The response of a local streaming service to a remote call. The frames are sent out
+/// one by one, followed by an end of stream marker. The peer only pulls the next frame from
+/// the stream after the previous one has been queued for the connection and when the remote
+/// has given credit for it, so a slow connection or a slow consumer slows down the producer.
+/// If the consumer drops the stream, the remote cancels it and the producer is dropped.
+///
+/// This is produced by service maps. Normally you don't use it directly.
+//
+pub struct StreamResponse<Wf>
+{
+	sid   : ServiceID                                                       ,
+	cid   : ConnID                                                          ,
+	stream: Pin<Box< dyn Stream< Item=Result<Wf, PeerErr> > + Send >> ,
+}
+
+
+impl<Wf: WireFormat> Message for StreamResponse<Wf>
+{
+	type Return = ();
+}
+
+
+impl<Wf> StreamResponse<Wf>
+{
+	/// Create a new StreamResponse. The frames yielded by `stream` should have the sid set to
+	/// [`ServiceID::stream_chunk`] and the cid of the request. If the stream yields an error,
+	/// it is reported to the remote and the stream is abandoned.
+	//
+	pub fn new
+	(
+		sid   : ServiceID                                                     ,
+		cid   : ConnID                                                        ,
+		stream: impl Stream< Item=Result<Wf, PeerErr> > + Send + 'static ,
+	)
+		-> Self
+	{
+		Self { sid, cid, stream: Box::pin( stream ) }
+	}
+}
+
+
+
+/// Spawns a task that forwards the stream to the remote.
+//
+impl<Wf: WireFormat + Send + 'static> Handler<StreamResponse<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, resp: StreamResponse<Wf> )
+	{
+		trace!( "{}: sending OUT StreamResponse, sid: {}, cid: {}", self.identify(), resp.sid, resp.cid );
+
+		// We are closing down, the remote will no longer get the response.
+		//
+		let mut addr = match &self.addr
+		{
+			Some( addr ) => addr.clone(),
+			None         => return,
+		};
+
+		let StreamResponse{ sid, cid, mut stream } = resp;
+
+		// The remote opens the window before the call. If it didn't, it doesn't do flow control.
+		//
+		let window = self.stream_windows.get( &cid ).cloned();
+		let bp     = self.backpressure.clone();
+
+		let task = async move
+		{
+			loop
+			{
+				if let Some( window ) = &window
+				{
+					// The remote cancelled, it doesn't want the end of the stream either.
+					// Free the backpressure slot as CallResponse would.
+					//
+					if !window.take().await
+					{
+						if let Some( bp ) = bp
+						{
+							bp.add_slots( NonZeroUsize::new( 1 ).expect( "1 > 0" ) );
+						}
+
+						return Ok( Response::Nothing );
+					}
+				}
+
+				let item = match stream.next().await
+				{
+					Some( item ) => item,
+					None         => break,
+				};
+
+				// We use call so we only pull the next item once this one is out.
+				//
+				match addr.call( item? ).await
+				{
+					Ok( Ok(()) ) => {}
+
+					// The connection is gone, nobody is listening anymore.
+					//
+					_ => return Ok( Response::Nothing ),
+				}
+			}
+
+			let mut end = Wf::default();
+			end.set_sid( ServiceID::stream_end() );
+			end.set_cid( cid );
+
+			// Goes through CallResponse so the backpressure slot is freed.
+			//
+			Ok( Response::CallResponse( CallResponse::new(end) ) )
+		};
+
+
+		if self.nursery.nurse( task ).is_err()
+		{
+			let ctx = self.ctx( sid, cid, "Spawn task for StreamResponse" );
+
+			self.handle( RequestError::from( PeerErr::Spawn{ ctx } ) ).await;
+		}
+	}
+}
+
+
+
+impl<Wf> fmt::Debug for StreamResponse<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "StreamResponse" )
+
+			.field( "sid", &self.sid )
+			.field( "cid", &self.cid )
+
+		.finish()
+	}
+}
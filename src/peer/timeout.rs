@@ -1,4 +1,8 @@
-use crate::{ import::*, * };
+use
+{
+	crate::{ import::*, * } ,
+	super::StreamCtrl       ,
+};
 
 
 /// Represents a timeout for an outgoing call. Tells the peer to remove the channel waiting
@@ -32,9 +36,40 @@ impl<Wf: WireFormat + Send> Handler<Timeout> for Peer<Wf>
 		{
 			if let Some( tx ) = self.responses.remove( &msg.cid )
 			{
+				self.metrics.lock().timeout( msg.sid, msg.cid );
+
 				// If this fails, the receiver is already gone, so ignore the result.
 				//
 				let _ = tx.send( Err( ConnectionError::Timeout{ sid: msg.sid } ) );
+
+				self.call_done( msg.cid ).await;
+
+				self.lifecycle( PeerEvent::CallTimeout{ sid: msg.sid, cid: msg.cid } ).await;
+			}
+
+			// For streams, the timeout only applies until the first frame comes in.
+			//
+			else if self.streams.get( &msg.cid ).map( |s| !s.started ).unwrap_or_default()
+			{
+				if let Some( stream ) = self.streams.remove( &msg.cid )
+				{
+					self.metrics.lock().timeout( msg.sid, msg.cid );
+
+					// If this fails the receiver is already gone, so ignore the result.
+					//
+					let _ = stream.tx.unbounded_send( Err( ConnectionError::Timeout{ sid: msg.sid } ) );
+
+					// The remote might still start the stream later.
+					//
+					if let Err( e ) = self.send_stream_ctrl( msg.cid, &StreamCtrl::Cancel, Priority::Control ).await
+					{
+						self.report( e ).await;
+					}
+
+					self.call_done( msg.cid ).await;
+
+					self.lifecycle( PeerEvent::CallTimeout{ sid: msg.sid, cid: msg.cid } ).await;
+				}
 			}
 
 		}.boxed()
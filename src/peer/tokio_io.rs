@@ -0,0 +1,52 @@
+use crate::{ import::*, * };
+
+
+impl Peer<ThesWF>
+{
+	/// Create a Peer directly from a tokio connection, like a `tokio::net::TcpStream`. It's the same as
+	/// [`Peer::from_async_read`], but takes the tokio io traits.
+	///
+	/// To run the peer on tokio, use `async_executors::TokioTp` as the executor. The `tokio` feature enables
+	/// it. `async_executors::TokioCt` is enabled as well, but it can only run actors that aren't `Send`. The peer
+	/// needs an executor that implements [`PeerExec`].
+	///
+	/// Requires the `tokio` feature.
+	//
+	pub fn from_tokio_io
+	(
+		addr        : Addr<Self>                                                   ,
+		socket      : impl TokioAsyncRead + TokioAsyncWrite + Unpin + Send + 'static ,
+		max_size    : usize                                                        ,
+		exec        : impl PeerExec<ThesWF>                                        ,
+		bp          : Option<Arc<BackPressure>>                                    ,
+		grace_period: Option<Duration>                                             ,
+	)
+
+		-> Result< Self, PeerErr >
+
+	{
+		Self::from_async_read( addr, socket.compat(), max_size, exec, bp, grace_period )
+	}
+}
+
+
+
+impl PeerBuilder<ThesWF>
+{
+	/// Frame a tokio connection with the [`thes_wf`](crate::thes_wf) codec and build the peer.
+	/// See [`PeerBuilder::build_async_read`] and [`Peer::from_tokio_io`] for the executor.
+	///
+	/// Requires the `tokio` feature.
+	//
+	pub fn build_tokio_io
+	(
+		self                                                                   ,
+		socket: impl TokioAsyncRead + TokioAsyncWrite + Unpin + Send + 'static ,
+		exec  : impl PeerExec<ThesWF> + Spawn                                  ,
+	)
+		-> Result< Addr<Peer>, PeerErr >
+
+	{
+		self.build_async_read( socket.compat(), exec )
+	}
+}
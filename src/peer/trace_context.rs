@@ -0,0 +1,459 @@
+use crate::{ import::*, *, WireType };
+
+
+/// A W3C trace context (the `traceparent` header), so one request can be followed from the client,
+/// through relays, to the backend that handles it.
+///
+/// Give it to outgoing requests with `RemoteAddr::with_trace`, [`Call::with_trace`] or [`Prioritized::with_trace`].
+/// The peer then sends a small frame with the context right in front of the request. The receiving peer
+/// restores it, creates a child context for it's own hop and records both in the `tracing` span around
+/// the handling of the request. Relays pass the child context on to the backend. When no context
+/// comes in with a request, the receiving peer starts a new trace.
+///
+/// Service maps get the context of the request they process in [`PeerErrCtx::trace`], so errors also
+/// carry the trace id.
+///
+/// Receiving trace contexts is opt-in, with [`Peer::enable_trace_context`] or [`PeerConfig::trace_context`].
+/// A peer that opts in tells the remote when the connection starts. Contexts are only sent to remotes that
+/// did, for other remotes they are dropped. Older versions don't know the announcement and report it as an
+/// unknown service on their side, once per connection, so only opt in when the remote is recent enough.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, Hash ) ]
+//
+pub struct TraceContext
+{
+	/// Identifies the whole trace. Never zero.
+	//
+	pub trace_id: u128,
+
+	/// The id of the span that made the request. Never zero.
+	//
+	pub parent_id: u64,
+
+	/// The trace flags. Bit 0 means the caller sampled the trace.
+	//
+	pub flags: u8,
+}
+
+
+impl TraceContext
+{
+	/// Start a new, sampled trace.
+	//
+	pub fn root() -> Self
+	{
+		let mut rng = rand::thread_rng();
+
+		Self
+		{
+			trace_id : rng.gen_range( 1..=u128::MAX ) ,
+			parent_id: rng.gen_range( 1..=u64::MAX  ) ,
+			flags    : 1                              ,
+		}
+	}
+
+
+	/// A context for a span within this trace, to be used as the parent of requests made from it.
+	//
+	pub fn child( &self ) -> Self
+	{
+		Self { parent_id: rand::thread_rng().gen_range( 1..=u64::MAX ), ..*self }
+	}
+
+
+	/// Whether the caller sampled the trace.
+	//
+	pub fn sampled( &self ) -> bool
+	{
+		self.flags & 1 == 1
+	}
+
+
+	/// Format as the value of a `traceparent` header, eg. `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+	//
+	pub fn to_traceparent( &self ) -> String
+	{
+		format!( "00-{:032x}-{:016x}-{:02x}", self.trace_id, self.parent_id, self.flags )
+	}
+
+
+	/// Parse the value of a `traceparent` header. Returns `None` if it's not valid. Versions other than
+	/// `00` are accepted as long as they start with the same fields.
+	//
+	pub fn from_traceparent( header: &str ) -> Option<Self>
+	{
+		let mut parts = header.trim().split( '-' );
+
+		let version = parts.next()?;
+		let trace   = parts.next()?;
+		let parent  = parts.next()?;
+		let flags   = parts.next()?;
+
+		let lower_hex = |s: &str, len| s.len() == len && s.chars().all( |c| matches!( c, '0'..='9' | 'a'..='f' ) );
+
+		if   !lower_hex( version, 2  ) || version == "ff"
+			|| !lower_hex( trace  , 32 )
+			|| !lower_hex( parent , 16 )
+			|| !lower_hex( flags  , 2  )
+			|| ( version == "00" && parts.next().is_some() )
+		{
+			return None;
+		}
+
+		let ctx = Self
+		{
+			trace_id : u128::from_str_radix( trace , 16 ).ok()? ,
+			parent_id: u64 ::from_str_radix( parent, 16 ).ok()? ,
+			flags    : u8  ::from_str_radix( flags , 16 ).ok()? ,
+		};
+
+		if ctx.trace_id == 0 || ctx.parent_id == 0 { return None }
+
+		Some( ctx )
+	}
+}
+
+
+
+// How many trace contexts from the remote can wait for their request. More are dropped.
+//
+const MAX_REMOTE_TRACES: usize = 1024;
+
+
+// The payload of a frame with the trace sid.
+//
+#[ derive( Debug, Clone, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+enum TraceFrame
+{
+	// The peer understands trace contexts. Sent once when the connection starts, with a null cid.
+	//
+	Supported,
+
+	// The trace context of the request for `sid` with the cid of the frame. Sends have a null cid.
+	//
+	Context{ sid: ServiceID, traceparent: String },
+}
+
+
+
+// Trace contexts the remote sent for requests that haven't come in yet, by sid and cid of the request.
+// All sends have the null cid, so several contexts can wait for the same key. They are taken in the
+// order they came in, which is the order of the requests.
+//
+#[ derive( Debug, Default ) ]
+//
+pub(super) struct RemoteTraces
+{
+	waiting: HashMap< (ServiceID, ConnID), VecDeque<TraceContext> >,
+	len    : usize,
+}
+
+
+impl RemoteTraces
+{
+	// Returns false if too many contexts are waiting already.
+	//
+	fn insert( &mut self, sid: ServiceID, cid: ConnID, trace: TraceContext ) -> bool
+	{
+		if self.len >= MAX_REMOTE_TRACES { return false }
+
+		self.waiting.entry( (sid, cid) ).or_default().push_back( trace );
+		self.len += 1;
+
+		true
+	}
+
+
+	fn take( &mut self, sid: ServiceID, cid: ConnID ) -> Option<TraceContext>
+	{
+		let queue = self.waiting.get_mut( &(sid, cid) )?;
+		let trace = queue.pop_front();
+
+		if queue.is_empty()
+		{
+			self.waiting.remove( &(sid, cid) );
+		}
+
+		if trace.is_some() { self.len -= 1; }
+
+		trace
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Tell the remote that this peer understands trace contexts, so it sends them along with it's
+	/// requests. The announcement goes out before anything else. Remotes of older versions report it
+	/// as an unknown service. See [`TraceContext`].
+	//
+	pub fn enable_trace_context( &mut self )
+	{
+		if self.trace_announced { return }
+
+		let support = Self::prep_trace( ConnID::null(), &TraceFrame::Supported );
+		let len     = support.len();
+
+		// The mailbox hasn't started yet, so nothing else was sent and there is room.
+		//
+		if self.outbox.try_push( Priority::Control, support ).is_ok()
+		{
+			self.metrics.lock().frame_out( len );
+			self.trace_announced = true;
+		}
+	}
+
+
+	fn prep_trace( cid: ConnID, payload: &TraceFrame ) -> Wf
+	{
+		let mut wf = Wf::with_capacity( 80 );
+		wf.set_sid( ServiceID::trace() );
+		wf.set_cid( cid                );
+		serde_cbor::to_writer( &mut wf, payload ).expect( "serialize TraceFrame" );
+
+		wf
+	}
+
+
+	// Send out a request, preceded by it's trace context if it has one and the remote understands it.
+	// Both go in the same lane, so they stay together.
+	//
+	pub(super) async fn send_traced( &mut self, frame: Wf, priority: Priority, trace: Option<TraceContext> ) -> Result<(), PeerErr>
+	{
+		if let Some( trace ) = trace
+		{
+			if self.trace_supported
+			{
+				let payload = TraceFrame::Context{ sid: frame.sid(), traceparent: trace.to_traceparent() };
+
+				self.send_prio( Self::prep_trace( frame.cid(), &payload ), priority ).await?;
+			}
+
+			else
+			{
+				trace!( "{}: remote doesn't understand trace contexts, not sending it.", self.identify() );
+			}
+		}
+
+		self.send_prio( frame, priority ).await
+	}
+
+
+	// The remote announces that it understands trace contexts or sends the context for a request that
+	// follows. Invalid contexts are ignored, as the W3C spec demands.
+	//
+	pub(super) fn trace_frame( &mut self, frame: Wf )
+	{
+		let (sid, traceparent) = match serde_cbor::from_slice::<TraceFrame>( frame.msg() )
+		{
+			Ok( TraceFrame::Supported                  ) => { self.trace_supported = true; return }
+			Ok( TraceFrame::Context{ sid, traceparent } ) => (sid, traceparent),
+
+			Err(_) =>
+			{
+				warn!( "{}: Received invalid trace frame from remote, ignoring it.", self.identify() );
+				return
+			}
+		};
+
+		let trace = match TraceContext::from_traceparent( &traceparent )
+		{
+			Some( trace ) => trace,
+
+			None =>
+			{
+				warn!( "{}: Received invalid trace context from remote, ignoring it.", self.identify() );
+				return
+			}
+		};
+
+		if !self.remote_traces.insert( sid, frame.cid(), trace )
+		{
+			warn!( "{}: Too many trace contexts from remote wait for their request, ignoring it.", self.identify() );
+		}
+	}
+
+
+	// Take the trace context the remote sent for this request, if any.
+	//
+	pub(super) fn take_trace( &mut self, sid: ServiceID, cid: ConnID ) -> Option<TraceContext>
+	{
+		self.remote_traces.take( sid, cid )
+	}
+
+
+	// The span around processing an incoming request. `trace` is the context of our hop, `remote` the
+	// one the remote sent us.
+	//
+	pub(super) fn request_span
+	(
+		&self                         ,
+		kind  : WireType              ,
+		sid   : ServiceID             ,
+		cid   : ConnID                ,
+		trace : &TraceContext         ,
+		remote: Option<&TraceContext> ,
+	)
+		-> Span
+	{
+		let span = match kind
+		{
+			WireType::IncomingCall => tracing::info_span!
+			(
+				"incoming_call"                                         ,
+				peer_id   = self.id                                     ,
+				peer_name = ?self.name                                  ,
+				sid       = %sid                                        ,
+				cid       = %cid                                        ,
+				trace_id  = %format_args!( "{:032x}", trace.trace_id  ) ,
+				span_id   = %format_args!( "{:016x}", trace.parent_id ) ,
+				parent_id = Empty                                       ,
+			),
+
+			_ => tracing::info_span!
+			(
+				"incoming_send"                                         ,
+				peer_id   = self.id                                     ,
+				peer_name = ?self.name                                  ,
+				sid       = %sid                                        ,
+				trace_id  = %format_args!( "{:032x}", trace.trace_id  ) ,
+				span_id   = %format_args!( "{:016x}", trace.parent_id ) ,
+				parent_id = Empty                                       ,
+			),
+		};
+
+		if let Some( remote ) = remote
+		{
+			span.record( "parent_id", &format_args!( "{:016x}", remote.parent_id ) );
+		}
+
+		span
+	}
+
+
+	// The span around sending out a call.
+	//
+	pub(super) fn call_span( &self, sid: ServiceID, cid: ConnID, trace: Option<&TraceContext> ) -> Span
+	{
+		let span = tracing::debug_span!
+		(
+			"outgoing_call"          ,
+			peer_id   = self.id      ,
+			peer_name = ?self.name   ,
+			sid       = %sid         ,
+			cid       = %cid         ,
+			trace_id  = Empty        ,
+			parent_id = Empty        ,
+		);
+
+		if let Some( trace ) = trace
+		{
+			span.record( "trace_id" , &format_args!( "{:032x}", trace.trace_id  ) );
+			span.record( "parent_id", &format_args!( "{:016x}", trace.parent_id ) );
+		}
+
+		span
+	}
+}
+
+
+
+#[cfg(test)]
+//
+mod tests
+{
+	// What's tested:
+	//
+	// ✔ a context survives formatting and parsing.
+	// ✔ invalid headers are refused.
+	// ✔ a child keeps the trace id.
+	// ✔ remote contexts are only handed out for the sid and cid they came with, in order.
+	// ✔ the number of waiting remote contexts is bounded.
+	//
+	use crate::{ import::assert_eq, ServiceID, ConnID };
+	use super::{ TraceContext, RemoteTraces, MAX_REMOTE_TRACES };
+
+	#[test]
+	//
+	fn roundtrip()
+	{
+		let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+		let ctx    = TraceContext::from_traceparent( header ).expect( "valid header" );
+
+		assert_eq!( 0x4bf92f3577b34da6a3ce929d0e0e4736, ctx.trace_id  );
+		assert_eq!( 0x00f067aa0ba902b7                , ctx.parent_id );
+		assert!   ( ctx.sampled()                                     );
+		assert_eq!( header, ctx.to_traceparent()                      );
+	}
+
+
+	#[test]
+	//
+	fn invalid()
+	{
+		assert_eq!( None, TraceContext::from_traceparent( "00-00000000000000000000000000000000-00f067aa0ba902b7-01"    ) );
+		assert_eq!( None, TraceContext::from_traceparent( "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01"    ) );
+		assert_eq!( None, TraceContext::from_traceparent( "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01"    ) );
+		assert_eq!( None, TraceContext::from_traceparent( "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"    ) );
+		assert_eq!( None, TraceContext::from_traceparent( "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-xx" ) );
+		assert_eq!( None, TraceContext::from_traceparent( "00-4bf92f3577b34da6a3ce929d0e0e4736"                         ) );
+	}
+
+
+	#[test]
+	//
+	fn child()
+	{
+		let root  = TraceContext::root();
+		let child = root.child();
+
+		assert_eq!( root.trace_id, child.trace_id  );
+		assert_eq!( root.flags   , child.flags     );
+	}
+
+
+	#[test]
+	//
+	fn remote_keyed()
+	{
+		let sid   = ServiceID::from( 5 );
+		let other = ServiceID::from( 6 );
+		let cid   = ConnID   ::from( 7 );
+		let first = TraceContext::root();
+		let last  = TraceContext::root();
+
+		let mut traces = RemoteTraces::default();
+
+		assert!( traces.insert( sid, ConnID::null(), first ) );
+		assert!( traces.insert( sid, ConnID::null(), last  ) );
+
+		assert_eq!( None         , traces.take( sid  , cid            ) );
+		assert_eq!( None         , traces.take( other, ConnID::null() ) );
+		assert_eq!( Some( first ), traces.take( sid  , ConnID::null() ) );
+		assert_eq!( Some( last  ), traces.take( sid  , ConnID::null() ) );
+		assert_eq!( None         , traces.take( sid  , ConnID::null() ) );
+	}
+
+
+	#[test]
+	//
+	fn remote_bounded()
+	{
+		let sid = ServiceID::from( 5 );
+
+		let mut traces = RemoteTraces::default();
+
+		for _ in 0..MAX_REMOTE_TRACES
+		{
+			assert!( traces.insert( sid, ConnID::null(), TraceContext::root() ) );
+		}
+
+		assert!( !traces.insert( sid, ConnID::null(), TraceContext::root() ) );
+
+		traces.take( sid, ConnID::null() );
+
+		assert!( traces.insert( sid, ConnID::null(), TraceContext::root() ) );
+	}
+}
@@ -0,0 +1,252 @@
+use { crate::{ import::*, * }, super::CloseHook };
+
+
+// A control frame carries at most 125 bytes, 2 of which are the close code. A remote refuses a close
+// frame with a longer reason.
+//
+const MAX_CLOSE_REASON: usize = 123;
+
+
+// Every binary WebSocket message carries one frame, so the WebSocket does the framing for us. The hook
+// gives the sink the reason when the peer closes the connection, so it can send it in the close frame.
+//
+fn ws_frames<S>( ws: WebSocketStream<S>, max_size: usize ) -> ( impl BoundsIn<ThesWF>, impl BoundsOut<ThesWF>, CloseHook )
+
+	where S: FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static
+
+{
+	let (sink, stream) = ws.split();
+
+	let incoming = stream.filter_map( move |msg| futures::future::ready( ws_frame( msg, max_size ) ) );
+
+	let reason   = Arc::new( Mutex::new( None ) );
+	let outgoing = WsSink{ sink, reason: reason.clone(), close_sent: false };
+
+	// When the remote closed the connection, tungstenite answers their close frame.
+	//
+	let hook: CloseHook = Box::new( move |msg: &CloseConnection|
+	{
+		if !msg.remote
+		{
+			*reason.lock() = Some( msg.reason.clone() );
+		}
+	});
+
+	(incoming, outgoing, hook)
+}
+
+
+
+// Sends every frame as a binary WebSocket message. When closed by our peer, it sends a close frame with
+// the reason first.
+//
+struct WsSink<S>
+{
+	sink      : SplitSink< WebSocketStream<S>, WsMessage > ,
+	reason    : Arc< Mutex< Option<String> > >             ,
+	close_sent: bool                                       ,
+}
+
+
+impl<S> Sink<ThesWF> for WsSink<S>
+
+	where S: FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static
+
+{
+	type Error = WireErr;
+
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Result<(), Self::Error> >
+	{
+		self.sink.poll_ready_unpin( cx ).map_err( ws_err )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, frame: ThesWF ) -> Result<(), Self::Error>
+	{
+		self.sink.start_send_unpin( WsMessage::Binary( frame.into() ) ).map_err( ws_err )
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Result<(), Self::Error> >
+	{
+		self.sink.poll_flush_unpin( cx ).map_err( ws_err )
+	}
+
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Result<(), Self::Error> >
+	{
+		if !self.close_sent
+		{
+			futures::ready!( self.sink.poll_ready_unpin( cx ) ).map_err( ws_err )?;
+
+			self.close_sent = true;
+
+			let reason = self.reason.lock().take();
+
+			if let Some( mut reason ) = reason
+			{
+				truncate( &mut reason, MAX_CLOSE_REASON );
+
+				let frame = CloseFrame{ code: CloseCode::Normal, reason: reason.into() };
+
+				self.sink.start_send_unpin( WsMessage::Close( Some( frame ) ) ).map_err( ws_err )?;
+			}
+		}
+
+		self.sink.poll_close_unpin( cx ).map_err( ws_err )
+	}
+}
+
+
+
+// Cut a string to at most max bytes, on a char boundary.
+//
+fn truncate( s: &mut String, max: usize )
+{
+	if s.len() <= max { return }
+
+	let end = ( 0..=max ).rev().find( |i| s.is_char_boundary( *i ) ).unwrap_or( 0 );
+
+	s.truncate( end );
+}
+
+
+
+fn ws_err( err: WsErr ) -> WireErr
+{
+	match err
+	{
+		WsErr::Io( err ) => WireErr::from( err ),
+		_                => WireErr::Io{ kind: io::ErrorKind::BrokenPipe },
+	}
+}
+
+
+
+// Turn a WebSocket message into a frame. Ping and pong are answered by tungstenite, so we skip them.
+//
+fn ws_frame( msg: Result<WsMessage, WsErr>, max_size: usize ) -> Option< Result<ThesWF, WireErr> >
+{
+	let data = match msg
+	{
+		Ok( WsMessage::Binary( data ) ) => data,
+
+		Ok( WsMessage::Close( frame ) ) =>
+		{
+			let reason = match frame
+			{
+				Some( frame ) => format!( "WebSocket closed by remote with code {}: {}", u16::from( frame.code ), frame.reason ),
+				None          => "WebSocket closed by remote.".to_string(),
+			};
+
+			return Some( Err( WireErr::Closed{ reason } ) );
+		}
+
+		Ok( WsMessage::Text(_) ) =>
+		{
+			let context = "WebSocket: text messages are not supported, frames must be binary.".to_string();
+
+			return Some( Err( WireErr::Deserialize{ context } ) );
+		}
+
+		Ok(_) => return None,
+
+		// The stream ends right after these.
+		//
+		Err( WsErr::ConnectionClosed ) => return None,
+		Err( WsErr::AlreadyClosed    ) => return None,
+
+		Err( WsErr::Io( err ) ) => return Some( Err( err.into() ) ),
+		Err( _                ) => return Some( Err( WireErr::Io{ kind: io::ErrorKind::InvalidData } ) ),
+	};
+
+	let size = data.len();
+
+	if size > max_size
+	{
+		let context = "WebSocket: incoming message".to_string();
+
+		return Some( Err( WireErr::MessageSizeExceeded{ context, size, max_size } ) );
+	}
+
+	let frame = match ThesWF::try_from( data )
+	{
+		Ok ( frame ) => frame,
+		Err( err   ) => return Some( Err( err ) ),
+	};
+
+	// The header must describe the whole message.
+	//
+	if frame.len() != size as u64
+	{
+		let context = "WebSocket: the length in the header doesn't match the size of the message.".to_string();
+
+		return Some( Err( WireErr::Deserialize{ context } ) );
+	}
+
+	Some( Ok( frame ) )
+}
+
+
+
+impl Peer<ThesWF>
+{
+	/// Create a Peer from a WebSocket, like the ones returned by `async_tungstenite::accept_async` and
+	/// `async_tungstenite::client_async`. Each binary message is a frame. Text messages are refused.
+	///
+	/// When the remote closes the WebSocket, the close code and reason end up in the reason of the
+	/// `CloseConnection` the peer handles. When this peer closes the connection, it sends a close frame
+	/// with the code for a normal closure and the reason of the `CloseConnection`, cut to 123 bytes.
+	///
+	/// Requires the `websocket` feature.
+	//
+	pub fn from_websocket<S>
+	(
+		addr        : Addr<Self>                ,
+		ws          : WebSocketStream<S>        ,
+		max_size    : usize                     ,
+		exec        : impl PeerExec<ThesWF>     ,
+		bp          : Option<Arc<BackPressure>> ,
+		grace_period: Option<Duration>          ,
+	)
+		-> Result< Self, PeerErr >
+
+		where S: FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static
+
+	{
+		let (incoming, outgoing, hook) = ws_frames( ws, max_size );
+
+		let mut peer = Self::new( addr, incoming, outgoing, exec, bp, grace_period )?;
+
+		peer.close_hook = Some( hook );
+
+		Ok( peer )
+	}
+}
+
+
+
+impl PeerBuilder<ThesWF>
+{
+	/// Build the peer on a WebSocket, using the `max_size` of the config as the maximum size of a message.
+	/// See [`Peer::from_websocket`].
+	///
+	/// Requires the `websocket` feature.
+	//
+	pub fn build_websocket<S>
+	(
+		self                                ,
+		ws  : WebSocketStream<S>            ,
+		exec: impl PeerExec<ThesWF> + Spawn ,
+	)
+		-> Result< Addr<Peer>, PeerErr >
+
+		where S: FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static
+
+	{
+		let (incoming, outgoing, hook) = ws_frames( ws, self.settings().max_size );
+
+		self.close_hook( hook ).build( incoming, outgoing, exec )
+	}
+}
@@ -0,0 +1,417 @@
+use crate::{ import::*, * };
+
+
+// Creates the service maps for each new connection.
+//
+type Factory = Box< dyn Fn() -> Vec< Arc<dyn ServiceMap> > + Send + Sync >;
+
+
+/// Accept connections and create a [`Peer`] for each of them. This is what most servers do by hand: take
+/// a connection from a listener, frame it with the [`thes_wf`](crate::thes_wf) codec, build a peer,
+/// register the services and start the mailbox.
+///
+/// The server works with any `Stream` of connections that implement the futures `AsyncRead` and `AsyncWrite`,
+/// like the `incoming()` of an async-std `TcpListener`, or a channel of in memory endpoints. Every peer is built
+/// from the same [`PeerConfig`]. The service maps come from a factory closure which is called once per connection,
+/// so each connection can have it's own handlers or share them by cloning addresses into the maps.
+///
+/// `PeerServer` is an actor. [`PeerServer::start`] spawns the accept loop and returns the address of the server.
+/// Send it [`Shutdown`] to stop accepting connections and close all peers. The shutdown is graceful, each peer
+/// gets it's grace period to finish outstanding work. The server keeps running until it's shut down, even when
+/// you drop it's address.
+///
+/// Register an observer before starting the server to get [`ServerEvent`]s for connects and disconnects.
+///
+/// When a maximum number of connections is set, connections that come in while the server is full are dropped
+/// right away, and a [`ServerEvent::Rejected`] is emitted.
+///
+/// ```ignore
+/// let mut server = PeerServer::new( PeerConfig::default(), || vec![ Arc::new( services() ) as Arc<dyn ServiceMap> ], exec );
+///
+/// server.max_connections( NonZeroUsize::new( 100 ).unwrap() );
+///
+/// let evts       = server.observe( ObserveConfig::default() ).await?;
+/// let mut server = server.start( listener.incoming() )?;
+///
+/// // later
+/// //
+/// server.call( Shutdown ).await?;
+/// ```
+//
+#[ derive( Actor ) ]
+//
+pub struct PeerServer<E: 'static + PeerExec + Spawn>
+{
+	config  : PeerConfig                   ,
+	services: Factory                      ,
+	exec    : E                            ,
+	max     : Option<NonZeroUsize>         ,
+	peers   : HashMap< usize, Addr<Peer> > ,
+	pharos  : Pharos<ServerEvent>          ,
+	addr    : Option< Addr<Self> >         ,
+	accept  : Option< JoinHandle<()> >     ,
+	closed  : bool                         ,
+}
+
+
+/// Events emitted by a [`PeerServer`].
+//
+#[ derive( Debug, Clone ) ]
+//
+pub enum ServerEvent
+{
+	/// A connection was accepted and it's peer is running.
+	//
+	Connected
+	{
+		/// The address of the new peer.
+		//
+		peer: Addr<Peer>,
+	},
+
+	/// The peer of a connection closed. `remote` is true when the remote closed the connection.
+	//
+	Disconnected
+	{
+		/// The id of the address of the peer.
+		//
+		peer_id: usize,
+
+		/// Whether the remote closed the connection.
+		//
+		remote: bool,
+	},
+
+	/// A connection was dropped because the server has the maximum number of connections.
+	//
+	Rejected,
+
+	/// Creating a peer for a connection failed. The connection is dropped.
+	//
+	Error( PeerErr ),
+
+	/// The listener returned an error. The server keeps accepting.
+	//
+	AcceptError( io::ErrorKind ),
+
+	/// The listener stream ended. Existing peers keep running.
+	//
+	ListenerEnded,
+
+	/// The server was shut down and all peers are closed.
+	//
+	Shutdown,
+}
+
+
+/// Stop accepting connections and close all peers of a [`PeerServer`]. Returns when all peers are closed.
+//
+#[ derive( Debug, Clone, Copy ) ]
+//
+pub struct Shutdown;
+
+impl Message for Shutdown { type Return = (); }
+
+
+// Sent by the accept loop. None when the listener ended.
+//
+struct Accept<T>( Option< io::Result<T> > );
+
+impl<T: Send + 'static> Message for Accept<T> { type Return = (); }
+
+
+// Sent by the task watching the events of a peer when it closes.
+//
+#[ derive( Debug ) ]
+//
+struct PeerClosed
+{
+	peer_id: usize ,
+	remote : bool  ,
+}
+
+impl Message for PeerClosed { type Return = (); }
+
+
+
+impl<E> PeerServer<E> where E: PeerExec + Spawn
+{
+	/// Create a server. Every peer is built with `config` and the service maps returned by `services`,
+	/// and runs on `exec`.
+	//
+	pub fn new
+	(
+		config  : PeerConfig                                                      ,
+		services: impl Fn() -> Vec< Arc<dyn ServiceMap> > + Send + Sync + 'static ,
+		exec    : E                                                               ,
+	)
+		-> Self
+
+	{
+		Self
+		{
+			config                         ,
+			services: Box::new( services ) ,
+			exec                           ,
+			max     : None                 ,
+			peers   : HashMap::new()       ,
+			pharos  : Pharos::default()    ,
+			addr    : None                 ,
+			accept  : None                 ,
+			closed  : false                ,
+		}
+	}
+
+
+	/// The maximum number of connections. Defaults to no limit.
+	//
+	pub fn max_connections( &mut self, max: NonZeroUsize ) -> &mut Self
+	{
+		self.max = Some( max );
+		self
+	}
+
+
+	/// Spawn the accept loop and the mailbox of the server on the executor and return the address of the server.
+	/// The accept loop stops when the listener ends or on [`Shutdown`].
+	//
+	pub fn start<T>( mut self, listener: impl Stream< Item = io::Result<T> > + Unpin + Send + 'static )
+
+		-> Result< Addr<Self>, PeerErr >
+
+		where T: FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static
+
+	{
+		let (addr, mb) = Addr::builder().name( "peer_server".into() ).build();
+
+		let spawn_err = |context: &str|
+		{
+			let ctx = PeerErrCtx::default()
+
+				.peer_name( addr.name()                  )
+				.context  ( Some( context.to_string() ) )
+			;
+
+			PeerErr::Spawn{ ctx }
+		};
+
+		let mut accept_addr = addr.clone();
+		let mut listener    = listener;
+
+		let accept = async move
+		{
+			while let Some( conn ) = listener.next().await
+			{
+				if accept_addr.send( Accept( Some(conn) ) ).await.is_err() { return }
+			}
+
+			let _ = accept_addr.send( Accept::<T>( None ) ).await;
+		};
+
+		self.accept = Some( self.exec.spawn_handle( accept ).map_err( |_| spawn_err( "Accept loop of server" ) )? );
+		self.addr   = Some( addr.clone() );
+
+		let exec = self.exec.clone();
+
+		exec.spawn( mb.start( self ).map(|_|()) ).map_err( |_| spawn_err( "Mailbox of server" ) )?;
+
+		Ok( addr )
+	}
+
+
+	// Build a peer for a new connection.
+	//
+	async fn connect<T>( &mut self, conn: T ) -> Result< Addr<Peer>, PeerErr >
+
+		where T: FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static
+
+	{
+		let mut builder = PeerBuilder::new().config( self.config.clone() );
+
+		for sm in (self.services)()
+		{
+			builder = builder.service_map( sm );
+		}
+
+		let evts = builder.observe( ObserveConfig::default().filter( is_close ) ).await
+
+			.expect( "pharos not closed" )
+		;
+
+		let peer    = builder.build_async_read( conn, self.exec.clone() )?;
+		let peer_id = peer.id();
+
+		// Ends when the peer closes, or when the server is gone.
+		//
+		let mut server = self.addr.clone().expect( "server is started" );
+
+		let watch = async move
+		{
+			let mut evts = evts;
+
+			if let Some( evt ) = evts.next().await
+			{
+				let remote = evt == PeerEvent::ClosedByRemote;
+
+				let _ = server.send( PeerClosed{ peer_id, remote } ).await;
+			}
+		};
+
+		self.exec.spawn( watch ).map_err( |_|
+		{
+			let ctx = PeerErrCtx::default()
+
+				.peer_id( peer_id                                       )
+				.context( Some( "Close watcher of server".to_string() ) )
+			;
+
+			PeerErr::Spawn{ ctx }
+
+		})?;
+
+		Ok( peer )
+	}
+
+
+	async fn emit( &mut self, evt: ServerEvent )
+	{
+		self.pharos.send( evt ).await.expect( "pharos not closed" );
+	}
+}
+
+
+fn is_close( evt: &PeerEvent ) -> bool
+{
+	matches!( evt, PeerEvent::Closed | PeerEvent::ClosedByRemote )
+}
+
+
+
+impl<E, T> Handler< Accept<T> > for PeerServer<E>
+
+	where E: PeerExec + Spawn                                      ,
+	      T: FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static ,
+
+{
+	#[async_fn] fn handle( &mut self, msg: Accept<T> )
+	{
+		let conn = match msg.0
+		{
+			Some( Ok(conn) ) => conn,
+
+			Some( Err(err) ) =>
+			{
+				warn!( "PeerServer: failed to accept a connection: {}", err );
+				return self.emit( ServerEvent::AcceptError( err.kind() ) ).await;
+			}
+
+			None => return self.emit( ServerEvent::ListenerEnded ).await,
+		};
+
+		// A connection that came in while shutting down is dropped.
+		//
+		if self.closed { return }
+
+		if let Some( max ) = self.max
+		{
+			if self.peers.len() >= max.get()
+			{
+				debug!( "PeerServer: maximum of {} connections reached, dropping connection.", max );
+				return self.emit( ServerEvent::Rejected ).await;
+			}
+		}
+
+		match self.connect( conn ).await
+		{
+			Ok( peer ) =>
+			{
+				self.peers.insert( peer.id(), peer.clone() );
+				self.emit( ServerEvent::Connected{ peer } ).await;
+			}
+
+			Err( err ) =>
+			{
+				error!( "PeerServer: failed to create a peer: {}", err );
+				self.emit( ServerEvent::Error( err ) ).await;
+			}
+		}
+	}
+}
+
+
+
+impl<E> Handler<PeerClosed> for PeerServer<E> where E: PeerExec + Spawn
+{
+	#[async_fn] fn handle( &mut self, msg: PeerClosed )
+	{
+		// On shutdown the peers are removed before they close.
+		//
+		if self.peers.remove( &msg.peer_id ).is_some()
+		{
+			self.emit( ServerEvent::Disconnected{ peer_id: msg.peer_id, remote: msg.remote } ).await;
+		}
+	}
+}
+
+
+
+impl<E> Handler<Shutdown> for PeerServer<E> where E: PeerExec + Spawn
+{
+	#[async_fn] fn handle( &mut self, _msg: Shutdown )
+	{
+		if self.closed { return }
+
+		self.closed = true;
+
+		// Dropping the handle cancels the accept loop.
+		//
+		self.accept = None;
+
+		let peers: Vec<_> = self.peers.drain().collect();
+
+		for (peer_id, mut peer) in peers
+		{
+			// The peer might have closed in the mean time, in which case it's mailbox is gone.
+			//
+			let _ = peer.call( CloseConnection{ remote: false, reason: "Server shutdown.".to_string() } ).await;
+
+			self.emit( ServerEvent::Disconnected{ peer_id, remote: false } ).await;
+		}
+
+		self.emit( ServerEvent::Shutdown ).await;
+
+		// Let our mailbox end when the user drops their addresses.
+		//
+		self.addr = None;
+	}
+}
+
+
+
+impl<E> Observable<ServerEvent> for PeerServer<E> where E: PeerExec + Spawn
+{
+	type Error = PharErr;
+
+	/// Register an observer before starting the server.
+	//
+	fn observe( &mut self, config: ObserveConfig<ServerEvent> ) -> Observe< '_, ServerEvent, PharErr >
+	{
+		self.pharos.observe( config )
+	}
+}
+
+
+
+impl<E> fmt::Debug for PeerServer<E> where E: PeerExec + Spawn
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "PeerServer" )
+
+			.field( "config", &self.config      )
+			.field( "max"   , &self.max         )
+			.field( "peers" , &self.peers.len() )
+			.field( "closed", &self.closed      )
+			.finish()
+	}
+}
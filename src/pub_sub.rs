@@ -149,6 +149,7 @@ impl<Wf: WireFormat> ServiceMap<Wf> for PubSub<Wf>
 		trace!( "PubSub: Incoming Send for relayed subscribers." );
 
 		let peer_id = ctx.peer_id;
+		let sid     = msg.sid();
 
 		let mut unordered: FuturesUnordered<_> =
 		{
@@ -178,12 +179,20 @@ impl<Wf: WireFormat> ServiceMap<Wf> for PubSub<Wf>
 		};
 
 
+		let span = tracing::info_span!
+		(
+			"pub_sub"                       ,
+			peer_id     = ?peer_id          ,
+			sid         = %sid              ,
+			subscribers = unordered.len()   ,
+		);
+
 		Ok( async move
 		{
 			while unordered.next().await.is_some() {}
 			Ok( Response::Nothing )
 
-		}.boxed())
+		}.instrument( span ).boxed())
 	}
 
 
@@ -50,7 +50,8 @@ impl<Wf: WireFormat> ServiceMap<Wf> for RelayMap<Wf>
 		{
 			ServiceHandler::Address( a ) =>
 			{
-				let mut a = a.clone_box();
+				let mut a    = a.clone_box();
+				let     span = relay_span( sid, a.id(), a.name() );
 
 				let task = async move
 				{
@@ -61,13 +62,14 @@ impl<Wf: WireFormat> ServiceMap<Wf> for RelayMap<Wf>
 					}
 				};
 
-				Ok( task.boxed() )
+				Ok( task.instrument( span ).boxed() )
 			}
 
 
 			ServiceHandler::Closure( c ) =>
 			{
-				let mut a = c(&sid);
+				let mut a    = c(&sid);
+				let     span = relay_span( sid, a.id(), a.name() );
 
 				let task = async move
 				{
@@ -78,7 +80,7 @@ impl<Wf: WireFormat> ServiceMap<Wf> for RelayMap<Wf>
 					}
 				};
 
-				Ok( task.boxed() )
+				Ok( task.instrument( span ).boxed() )
 			}
 		}
 	}
@@ -98,8 +100,21 @@ impl<Wf: WireFormat> ServiceMap<Wf> for RelayMap<Wf>
 
 		match &*self.handler.lock()
 		{
-			ServiceHandler::Address( a ) => Ok( make_call( a.clone_box(), frame, ctx ).boxed() ),
-			ServiceHandler::Closure( c ) => Ok( make_call( c(&sid)      , frame, ctx ).boxed() ),
+			ServiceHandler::Address( a ) =>
+			{
+				let relay = a.clone_box();
+				let span  = relay_span( sid, relay.id(), relay.name() );
+
+				Ok( make_call( relay, frame, ctx ).instrument( span ).boxed() )
+			}
+
+			ServiceHandler::Closure( c ) =>
+			{
+				let relay = c(&sid);
+				let span  = relay_span( sid, relay.id(), relay.name() );
+
+				Ok( make_call( relay, frame, ctx ).instrument( span ).boxed() )
+			}
 		}
 	}
 
@@ -111,6 +126,14 @@ impl<Wf: WireFormat> ServiceMap<Wf> for RelayMap<Wf>
 }
 
 
+// The span around relaying a request.
+//
+fn relay_span( sid: ServiceID, relay_id: usize, relay_name: Option<Arc<str>> ) -> Span
+{
+	tracing::info_span!( "relay", sid = %sid, relay_id, relay_name = ?relay_name )
+}
+
+
 #[ allow(clippy::needless_return) ]
 //
 async fn make_call<T, Wf: WireFormat + Send + 'static>( mut relay: Box<T>, frame: Wf, ctx: PeerErrCtx )
@@ -125,8 +148,11 @@ async fn make_call<T, Wf: WireFormat + Send + 'static>( mut relay: Box<T>, frame
 	let peer_id    = ctx.peer_id;
 	let relay_id   = relay.id();
 	let relay_name = relay.name();
+
+	// Pass the trace on, with the span of our peer as the parent.
+	//
+	let new_call   = Call::new( frame ).with_trace( ctx.trace );
 	let relay_gone = PeerErr::RelayGone{ ctx, relay_id, relay_name };
-	let new_call   = Call::new( frame );
 
 	// Peer for relay still online.
 	// FIXME: use map_err when rustc supports it... currently relay_gone would have to be cloned.
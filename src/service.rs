@@ -0,0 +1,77 @@
+use crate::{ import::*, * };
+
+
+/// A [`Message`] that can be received from remote code. It has a unique [`ServiceID`] that lets
+/// a remote tell which type to deserialize a frame into.
+///
+/// This is the crate level counterpart of the `Service` trait that `service_map!` generates in each
+/// namespace. It's used by [`ServiceRegistry`] and [`ServiceAddr`] and implemented with
+/// `#[derive(Service)]` when the `derive` feature is enabled:
+///
+/// ```ignore
+/// #[ derive( Serialize, Deserialize, Service ) ]
+/// #[ service( namespace = "myns" ) ]
+/// //
+/// struct Add( i64 );
+/// ```
+///
+/// The sid is a hash of the namespace and the name of the type, "myns::Add", so it's the same as
+/// for `Add` in a `service_map!` with namespace `myns`. It can be set with `#[ service( name = "..." ) ]`
+/// or `#[ service( sid = 0x1234 ) ]` instead.
+///
+/// For a generic type, the [`std::any::type_name`] of the type arguments is hashed as well, so
+/// `Echo<String>` and `Echo<u8>` have different sids and can be in the same service map. `type_name`
+/// is only guaranteed to be the same for one version of rustc, so both sides should be compiled with
+/// the same compiler. A generic type can't have a fixed `sid`.
+//
+pub trait Service
+
+	where  Self                    : Message + Serialize + DeserializeOwned,
+	      <Self as Message>::Return:           Serialize + DeserializeOwned,
+{
+	/// The name of the service, "namespace::Type" unless it's set explicitly. Used for log output and reflection.
+	//
+	const NAME: &'static str;
+
+	/// The unique service id. For a given name, this must always be the same, even across processes
+	/// compiled with different versions of rustc.
+	//
+	fn sid() -> ServiceID where Self: Sized;
+
+	/// Separate an application error from the return value of the handler. This is implemented for
+	/// services marked `#[ service( fallible ) ]`, don't implement it yourself.
+	//
+	#[ doc( hidden ) ]
+	//
+	fn reply( resp: <Self as Message>::Return ) -> Result< Reply<<Self as Message>::Return>, serde_cbor::Error >
+
+		where Self: Sized
+	{
+		Ok( Reply::Return( resp ) )
+	}
+}
+
+
+
+/// A [`Message`] that can be received from remote code and for which the handler returns a stream
+/// of responses. The `Return` type of the message must be a [`ServiceStream`].
+///
+/// Implemented with `#[derive(Service)]` and `#[ service( namespace = "myns", stream ) ]`.
+/// See [`Service`] for the service id.
+//
+pub trait StreamService
+
+	where  Self: Message + Serialize + DeserializeOwned,
+{
+	/// The type of the items in the stream returned by the handler.
+	//
+	type Item: Serialize + DeserializeOwned + Send + 'static;
+
+	/// The name of the service. See [`Service::NAME`].
+	//
+	const NAME: &'static str;
+
+	/// The unique service id. See [`Service::sid`].
+	//
+	fn sid() -> ServiceID where Self: Sized;
+}
@@ -0,0 +1,301 @@
+use crate::{ import::*, * };
+
+
+/// Call or send to the services of a remote with types that implement the crate level [`Service`]
+/// and [`StreamService`] traits. This is the counterpart of [`ServiceRegistry`] on the calling side and
+/// does the same as the `RemoteAddr` generated by `service_map!`, but for any service, including
+/// generic ones.
+///
+/// ```ignore
+/// let mut addr = ServiceAddr::new( peer );
+///
+/// addr.send( Add(5) ).await?;
+///
+/// let sum = addr.call( Show ).await?;
+/// ```
+//
+#[ derive( Debug, Clone ) ]
+//
+pub struct ServiceAddr<Wf: WireFormat = ThesWF>
+{
+	inner: DynRemoteAddr<Wf>,
+
+	// The send that hasn't gone out yet.
+	//
+	sending: PendingSend,
+}
+
+
+impl<Wf: WireFormat + Send + 'static> ServiceAddr<Wf>
+{
+	/// Create a ServiceAddr that sends over `peer`.
+	//
+	pub fn new( peer: Addr<Peer<Wf>> ) -> Self
+	{
+		Self { inner: DynRemoteAddr::new( peer ), sending: PendingSend::default() }
+	}
+
+
+	/// Send calls and sends through this address with the given priority. By default both
+	/// have `Priority::Call`. See [`Priority`].
+	//
+	pub fn with_priority( mut self, priority: Priority ) -> Self
+	{
+		self.inner = self.inner.with_priority( priority );
+		self
+	}
+
+
+	/// Send the trace context along with calls and sends through this address, so the remote
+	/// continues the trace. See [`TraceContext`].
+	//
+	pub fn with_trace( mut self, trace: impl Into<Option<TraceContext>> ) -> Self
+	{
+		self.inner = self.inner.with_trace( trace );
+		self
+	}
+
+
+	/// Ask the remote whether it exposes the service `S`. See [`ServiceInfo::is_reachable`].
+	//
+	pub async fn is_reachable<S>( &mut self ) -> Result< bool, PeerErr >
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		ServiceInfo::is_reachable( &mut self.inner.peer, <S as Service>::sid() ).await
+	}
+
+
+	/// Call a remote streaming service. The outer result reports failure to send out the request.
+	/// The items of the stream are the deserialized responses. If an error happens while
+	/// receiving the stream, it is yielded and the stream ends.
+	///
+	/// The flow control window of the stream is 16 items. Once 16 items have arrived that you haven't
+	/// consumed, the remote stops sending until you do. The peer keeps reading from the connection.
+	//
+	pub async fn call_stream<S>( &mut self, msg: S )
+
+		-> Result< impl Stream< Item = Result<<S as StreamService>::Item, PeerErr> >, PeerErr >
+
+		where  S: StreamService + Send,
+	{
+		let sid = <S as StreamService>::sid();
+
+		let mut wf = Wf::with_capacity( std::mem::size_of::<S>() * 2 );
+		wf.set_sid( sid );
+
+		serde_cbor::to_writer( &mut wf, &msg ).map_err( |_|
+		{
+			let ctx = PeerErrCtx::default()
+
+				.context( "Outgoing streaming request".to_string() )
+				.sid    ( sid                                      )
+			;
+
+			PeerErr::Serialize{ ctx }
+
+		})?;
+
+		let mut call = CallStream::new( wf, 16 ).with_trace( self.inner.trace );
+
+		if let Some( priority ) = self.inner.priority
+		{
+			call = call.with_priority( priority );
+		}
+
+		call_remote_stream( &mut self.inner.peer, call, sid ).await
+	}
+
+
+	fn serialize<S>( &self, msg: &S ) -> Result< Vec<u8>, PeerErr >
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		serde_cbor::to_vec( msg ).map_err( |_|
+		{
+			let ctx = PeerErrCtx::default()
+
+				.context( "Outgoing request".to_string() )
+				.sid    ( <S as Service>::sid()          )
+			;
+
+			PeerErr::Serialize{ ctx }
+		})
+	}
+}
+
+
+
+impl<S, Wf> Address<S> for ServiceAddr<Wf>
+
+	where  S                    : Service + Send,
+	      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+	       Wf                   : WireFormat + Send + 'static,
+
+{
+	/// Call a remote actor. Errors are the same as for [`DynRemoteAddr::call_raw`], and
+	/// `PeerErr::Serialize` or `PeerErr::Deserialize` when the message or the response
+	/// can't be (de)serialized.
+	//
+	fn call( &mut self, msg: S ) -> Return<Result< <S as Message>::Return, PeerErr >> { async move
+	{
+		let sid     = <S as Service>::sid();
+		let payload = self.serialize( &msg )?;
+		let resp    = self.inner.call_raw( sid, &payload ).await?;
+
+		serde_cbor::from_slice( &resp ).map_err( |_|
+		{
+			let ctx = Peer::err_ctx( &self.inner.peer, sid, None, "Response to call from remote actor".to_string() );
+
+			PeerErr::Deserialize{ ctx }
+		})
+
+	}.boxed() }
+
+
+	/// Obtain a clone of this recipient as a trait object.
+	//
+	fn clone_box( &self ) -> BoxAddress<S, PeerErr>
+	{
+		Box::new( self.clone() )
+	}
+}
+
+
+
+impl<S, Wf> Sink<S> for ServiceAddr<Wf>
+
+	where  S                    : Service + Send,
+	      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+	       Wf                   : WireFormat + Send + 'static,
+
+{
+	type Error = PeerErr;
+
+
+	/// Ready when the previous send has gone out. If the remote uses flow control, that is when it
+	/// gave us credit for it. See [`FlowSend`].
+	//
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context ) -> Poll<Result<(), Self::Error>>
+	{
+		self.sending.poll_done( cx )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, msg: S ) -> Result<(), Self::Error>
+	{
+		let payload = self.serialize( &msg )?;
+		let wf      = DynRemoteAddr::<Wf>::build_wf( <S as Service>::sid(), &payload )?;
+		let send    = FlowSend::new( wf, self.inner.priority.unwrap_or( Priority::Call ) ).with_trace( self.inner.trace );
+		let peer    = self.inner.peer.clone();
+
+		self.sending.start( peer, send );
+
+		Ok(())
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context ) -> Poll<Result<(), Self::Error>>
+	{
+		self.sending.poll_done( cx )
+	}
+
+
+	/// Waits for the last send to go out. The connection only closes when the peer is dropped.
+	//
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context ) -> Poll<Result<(), Self::Error>>
+	{
+		self.sending.poll_done( cx )
+	}
+}
+
+
+
+impl<Wf: WireFormat> Identify for ServiceAddr<Wf>
+{
+	/// Unique id of the peer this sends over
+	//
+	fn id( &self ) -> usize
+	{
+		self.inner.id()
+	}
+
+	/// Name of the peer this sends over
+	//
+	fn name( &self ) -> Option<Arc<str>>
+	{
+		self.inner.name()
+	}
+}
+
+
+
+/// Send out a streaming call and deserialize the items of the response. Shared with the `RemoteAddr`
+/// generated by `service_map!`.
+//
+#[ doc( hidden ) ]
+//
+pub async fn call_remote_stream<Wf, I>( peer: &mut Addr<Peer<Wf>>, call: CallStream<Wf>, sid: ServiceID )
+
+	-> Result< impl Stream< Item = Result<I, PeerErr> >, PeerErr >
+
+	where Wf: WireFormat + Send + 'static ,
+	      I : DeserializeOwned             ,
+{
+	// Can fail if the peer is down already.
+	//
+	let stream = peer.call( call ).await
+
+		// The peer panicked.
+		//
+		.map_err( |_|
+		{
+			let ctx = Peer::err_ctx( peer, sid, None, "Call remote streaming service".to_string() );
+
+			PeerErr::PeerGone{ ctx }
+
+		})?
+
+		// The actual sending out over the network can fail, or the call limit of the
+		// peer refuses it.
+		//
+		.map_err( |err| match err
+		{
+			PeerErr::TooManyCalls{..} => err,
+
+			_ =>
+			{
+				let ctx = Peer::err_ctx( peer, sid, None, "Call remote streaming service".to_string() );
+
+				PeerErr::ConnectionClosed{ ctx }
+			}
+
+		})?;
+
+
+	// Deserialize the payload of each item.
+	//
+	let peer_id   = peer.id();
+	let peer_name = peer.name();
+
+	Ok( stream.map( move |item|
+	{
+		let frame = item?;
+
+		serde_cbor::from_slice( frame.msg() ).map_err( |_|
+		{
+			let ctx = PeerErrCtx::default()
+
+				.context  ( "Item of response stream from remote actor".to_string() )
+				.peer_id  ( peer_id                                                 )
+				.peer_name( peer_name.clone()                                       )
+				.sid      ( sid                                                     )
+				.cid      ( frame.cid()                                             )
+			;
+
+			PeerErr::Deserialize{ ctx }
+		})
+	}))
+}
@@ -1,6 +1,13 @@
 use crate::{ *, import::*, peer::Response } ;
 
 
+/// The return type for handlers of streaming services. Declare the service under `streams`
+/// in `service_map!` and implement `Message` for it with `type Return = ServiceStream<Item>`.
+/// Every item will be sent to the caller as it is yielded by the stream.
+//
+pub type ServiceStream<T> = Pin<Box< dyn Stream<Item=T> + Send >>;
+
+
 /// This interface is what the Peer type uses to deliver messages. An implementation is provided
 /// for you in the `service_map` macro. You can however roll your own.
 ///
@@ -34,4 +41,14 @@ pub trait ServiceMap<Wf = ThesWF>: fmt::Debug + Send + Sync
 	// TODO: Find a way to avoid the heap allocation.
 	//
 	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >;
+
+
+	/// Get a list of the other sids that this service map accepts in place of the sid of one of its services.
+	/// The peer routes them to this service map, but they are not reported as services by reflection.
+	/// The default implementation has no aliases.
+	//
+	fn aliases( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		Box::new( std::iter::empty() )
+	}
 }
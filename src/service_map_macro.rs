@@ -13,11 +13,121 @@
 /// it all in action. There are many integration tests as well testing each feature of the remote actors
 /// in the `tests/remote` folder..
 ///
+/// Streaming services can be listed under the optional `streams` parameter. The handlers for these
+/// return a [`ServiceStream`](crate::ServiceStream) and the items are sent back to the caller one by
+/// one. On the calling side, use `RemoteAddr::call_stream` to get a stream of responses.
+///
 /// A unique service id is crated for each service based on the "<namespace>::<service>". It uses
 /// the exact strings you provide to the macro. Server and client need to provide the exact same
 /// parameters to the macro in order to be able to communicate, eg. if you refer to the service types
 /// as some path (eg. `module::Type`), both server and client need to do so.
 ///
+/// ### Stable service ids
+///
+/// Renaming a type or changing the namespace changes the service id, which breaks compatibility
+/// with peers that are already deployed. To avoid that, each service can be preceded by attributes:
+///
+/// - `#[ name = "myns::OldName" ]`: the name that is hashed for the service id instead of "<namespace>::<service>".
+///   Use the old "<namespace>::<service>" to keep the sid of a renamed type. It's also the name given to
+///   remotes by the reflection service.
+/// - `#[ sid = 0x1234 ]`: use this number as the service id. Set either `name` or `sid`, not both. Setting
+///   both is a compile error.
+/// - `#[ alias = "myns::Older" ]`: also accept the service id of this name for incoming messages. Can be
+///   repeated. Useful during a migration, until all remotes use the new sid. Outgoing messages always use
+///   the sid of the service.
+/// - `#[ alias_sid = 0x1234 ]`: like `alias`, but with the number of the service id.
+///
+/// `Services::new` panics if two services or aliases in the same service map have the same sid, or if
+/// one of them is a value reserved by thespis_remote. Aliases are not listed by `ServiceMap::services`,
+/// so reflection only lists the service itself. They are listed by `ServiceMap::aliases`.
+///
+/// ```ignore
+/// service_map!
+/// (
+///    namespace  : myns   ;
+///    wire_format: ThesWF ;
+///
+///    services:
+///
+///       #[ name  = "myns::Add"   ] Addition,
+///       #[ sid   = 0x10          ] Show,
+///       #[ alias = "myns::Minus" ] Sub;
+/// );
+/// ```
+///
+/// Setting both `name` and `sid` on a service doesn't compile:
+///
+/// ```compile_fail
+/// use thespis_remote::{ service_map, external_deps::thespis::Message };
+/// use serde::{ Serialize, Deserialize };
+///
+/// #[ derive( Serialize, Deserialize ) ] struct Add( i64 );
+///
+/// impl Message for Add { type Return = (); }
+///
+/// service_map!
+/// (
+///    namespace  : myns                   ;
+///    wire_format: thespis_remote::ThesWF ;
+///
+///    services:
+///
+///       #[ name = "myns::Add" ] #[ sid = 0x10 ] Add;
+/// );
+///
+/// fn main() {}
+/// ```
+///
+/// An unknown attribute, eg. a typo, doesn't compile either:
+///
+/// ```compile_fail
+/// use thespis_remote::{ service_map, external_deps::thespis::Message };
+/// use serde::{ Serialize, Deserialize };
+///
+/// #[ derive( Serialize, Deserialize ) ] struct Add( i64 );
+///
+/// impl Message for Add { type Return = (); }
+///
+/// service_map!
+/// (
+///    namespace  : myns                   ;
+///    wire_format: thespis_remote::ThesWF ;
+///
+///    services:
+///
+///       #[ alais = "myns::Plus" ] Add;
+/// );
+///
+/// fn main() {}
+/// ```
+///
+/// ### Application errors
+///
+/// When a handler returns `Result<T, E>`, the whole result is the return value of the call. Mark the
+/// service `#[ fallible ]` to send `E` to the caller as an error instead. The caller gets
+/// `PeerErr::Application` with an [`AppError`](crate::AppError) that can be downcast to `E`, so it
+/// can tell the handler refusing a request from a failing connection. On success the caller still gets
+/// `Ok( Ok(t) )`. The `Return` type must implement [`Fallible`](crate::Fallible), which is the case for `Result`.
+///
+/// ```ignore
+/// service_map!
+/// (
+///    namespace  : bank   ;
+///    wire_format: ThesWF ;
+///    services   : Balance, #[ fallible ] Transfer;
+/// );
+///
+/// match addr.call( Transfer( 50 ) ).await
+/// {
+///    Ok ( Ok(receipt) )                      => ...,
+///    Err( PeerErr::Application{ err, .. } ) => println!( "{:?}", err.downcast::<BankErr>() ),
+///    Err( other )                            => ...,
+///    ...
+/// }
+/// ```
+///
+/// `fallible` is not supported for streaming services.
+///
 /// Types created by this macro, for the following invocation:
 ///
 /// ```ignore
@@ -29,7 +139,11 @@
 ///    services:
 ///
 ///       ServiceA,
-///       ServiceB,
+///       ServiceB;
+///
+///    streams:
+///
+///       ServiceC,
 /// );
 ///
 /// mod myns
@@ -39,6 +153,10 @@
 ///    impl Service for ServiceA {...} // self being myns
 ///    impl Service for ServiceB {...}
 ///
+///    // ServiceC returns a ServiceStream
+///    //
+///    impl StreamService for ServiceC {...}
+///
 ///    pub struct Services {}
 ///
 ///    impl Namespace for Services { const NAMESPACE: &'static str = "myns"; }
@@ -84,9 +202,14 @@ macro_rules! service_map
 	//
 	wire_format: $wf: path;
 
-	/// Comma separated list of Services you want to include. They must be in scope.
+	/// Comma separated list of Services you want to include. They must be in scope. Each service can
+	/// be preceded by attributes, see the documentation of the macro.
+	//
+	services: $( $( #[ $key: ident $( = $val: literal )? ] )* $services: path ),+ $(,)? $(;)?
+
+	/// Optional comma separated list of streaming services. Their handlers return a `ServiceStream`.
 	//
-	services: $($services: path),+ $(,)? $(;)?
+	$( streams: $( $( #[ $skey: ident = $sval: literal ] )* $streams: path ),+ $(,)? $(;)? )?
 ) =>
 
 {
@@ -102,7 +225,7 @@ use
 	// we should not have a leading comma before the next item, but if the comma is after the closing
 	// parenthesis, it will not output a trailing comma, which will be needed to separate from the next item.
 	//
-	super :: { $( $services, )+                                                                               } ,
+	super :: { $( $services, )+ $($( $streams, )+)?                                                           } ,
 	$crate:: { *, peer::request_error::RequestError                                                           } ,
 	std   :: { pin::Pin, collections::HashMap, fmt, any::Any, sync::{ Arc, Once }, ops::Deref, future::Future } ,
 
@@ -110,6 +233,7 @@ use
 	{
 		once_cell       :: { sync::Lazy                                          } ,
 		futures         :: { future::FutureExt, task::{ Context, Poll }, SinkExt } ,
+		futures         :: { stream::Stream                                      } ,
 		thespis         :: { *                                                   } ,
 		thespis_impl    :: { Addr, ThesErr, ThesRes                              } ,
 		serde_cbor      :: { self, from_slice as des                             } ,
@@ -147,6 +271,36 @@ pub trait Service
 	/// programs written in other languages can also communicate with your services.
 	//
 	fn sid() -> ServiceID where Self: Sized;
+
+	/// Separate an application error from the return value of the handler. This is implemented for
+	/// services marked `#[ fallible ]`, don't implement it yourself.
+	//
+	#[ doc( hidden ) ]
+	//
+	fn reply( resp: <Self as Message>::Return ) -> Result< Reply<<Self as Message>::Return>, serde_cbor::Error >
+
+		where Self: Sized
+	{
+		Ok( Reply::Return( resp ) )
+	}
+}
+
+
+
+/// A [Message] that can be received from remote code and for which the handler returns a stream of
+/// responses. The `Return` type of the message must be a [`ServiceStream`].
+//
+pub trait StreamService
+
+	where  Self: Message + Serialize + DeserializeOwned,
+{
+	/// The type of the items in the stream returned by the handler.
+	//
+	type Item: Serialize + DeserializeOwned + Send + 'static;
+
+	/// The unique service id. See [`Service::sid`].
+	//
+	fn sid() -> ServiceID where Self: Sized;
 }
 
 
@@ -157,22 +311,48 @@ $(
 
 	impl Service for $services
 	{
-		/// A service ID that is unique for this type, based on a hash of the namespace and type name.
+		/// A service ID that is unique for this type, based on a hash of the namespace and type name,
+		/// unless a name or sid is set for it in the service map.
 		//
 		fn sid() -> ServiceID
 		{
 			static INSTANCE : Lazy< ServiceID > = Lazy::new( ||
 
-				ServiceID::from_seed( stringify!( $ns::$services ).as_bytes() )
+				$crate::__service_sid!( stringify!( $ns::$services ) ; $( $key $( = $val )? ; )* )
 			);
 
 			*INSTANCE
 		}
+
+		$crate::__service_reply!( $( $key $( = $val )? ; )* );
 	}
 
 )+
 
 
+$($(
+
+	impl StreamService for $streams
+	{
+		type Item = <<$streams as Message>::Return as Stream>::Item;
+
+		/// A service ID that is unique for this type, based on a hash of the namespace and type name,
+		/// unless a name or sid is set for it in the service map.
+		//
+		fn sid() -> ServiceID
+		{
+			static INSTANCE : Lazy< ServiceID > = Lazy::new( ||
+
+				$crate::__service_sid!( stringify!( $ns::$streams ) ; $( $skey = $sval ; )* )
+			);
+
+			*INSTANCE
+		}
+	}
+
+)+)?
+
+
 /// The actual service map.
 /// Use it to get a recipient to a remote service.
 //
@@ -181,6 +361,10 @@ pub struct Services
 	// The addresses to the actors that handle incoming messages.
 	//
 	handlers: HashMap< ServiceID, Mutex<Box<dyn Any + Send>> >,
+
+	// The sids that are accepted in place of the sid of a service, to the sid of the service.
+	//
+	aliases: HashMap< ServiceID, ServiceID >,
 }
 
 
@@ -205,6 +389,10 @@ impl fmt::Debug for Services
 			width = std::cmp::max( width, stringify!( $services ).len() );
 		)+
 
+		$($(
+			width = std::cmp::max( width, stringify!( $streams ).len() );
+		)+)?
+
 		write!( f, "{}::Services\n{{\n", stringify!( $ns ) )?;
 
 
@@ -246,6 +434,44 @@ impl fmt::Debug for Services
 			write!( f, "\n" )?;
 		)+
 
+		$($(
+			let sid = <$streams as StreamService>::sid();
+
+			write!
+			(
+				f,
+
+				"\t{:width$} - sid: 0x{:02x} - handler: ",
+
+				stringify!( $streams ),
+				sid,
+
+				width = width
+			)?;
+
+			if let Some(h) = self.handlers.get( &sid )
+			{
+				let h = h.lock();
+
+				// This expect shouldn't ever fail. We manually make the receiver in this file.
+				//
+				let handler: &BoxAddress<$streams, ThesErr> = h.downcast_ref().expect( "downcast receiver in Debug for Services" );
+
+				match handler.name()
+				{
+					Some(n) => write!( f, "id({}), name({})", &handler.id(), &n )?,
+					None    => write!( f, "id({})", &handler.id() )?,
+				};
+			}
+
+			else
+			{
+				write!( f, "none" )?;
+			}
+
+			write!( f, "\n" )?;
+		)+)?
+
 		write!( f, "}}" )
 	}
 }
@@ -278,6 +504,18 @@ impl Clone for Services
 					},
 				)+
 
+				$($(
+					_ if *k == <$streams as StreamService>::sid() =>
+					{
+						// This should never fail, we make this type in this file.
+						//
+						let v = v.lock();
+						let h: &BoxAddress<$streams, ThesErr> = v.downcast_ref().expect( "downcast receiver in Clone" );
+
+						handlers.insert( *k, Mutex::new( Box::new(h.clone_box()) ) );
+					},
+				)+)?
+
 
 				// every sid in our handlers map should also be a valid service in this service map,
 				// so this should never happen
@@ -287,7 +525,7 @@ impl Clone for Services
 
 		}
 
-		Self { handlers }
+		Self { handlers, aliases: self.aliases.clone() }
 	}
 }
 
@@ -306,12 +544,115 @@ impl Services
 
 				[< __ONCE__ $services >].call_once( ||
 				{
-					ServiceID::register_service( $services::sid(), concat!( stringify!($ns) , "::", stringify!($services) ) );
+					let name = $crate::__service_name!( concat!( stringify!($ns) , "::", stringify!($services) ) ; $( $key $( = $val )? ; )* );
+
+					ServiceID::register_service( $services::sid(), name );
+
+					for (alias, alias_name) in $crate::__service_aliases!( [] ; $( $key $( = $val )? ; )* )
+					{
+						ServiceID::register_service( alias, alias_name.unwrap_or( name ) );
+					}
+
+					ServiceID::register_types
+					(
+						$services::sid()                                            ,
+						::std::any::type_name::<$services>()                        ,
+						::std::any::type_name::< <$services as Message>::Return >() ,
+					);
 				});
 			}
 		)+
 
-		Self{ handlers: HashMap::new() }
+		$($(
+			paste::expr!
+			{
+				static [< __ONCE__ $streams >]: Once = Once::new();
+
+				[< __ONCE__ $streams >].call_once( ||
+				{
+					let name = $crate::__service_name!( concat!( stringify!($ns) , "::", stringify!($streams) ) ; $( $skey = $sval ; )* );
+
+					ServiceID::register_service( <$streams as StreamService>::sid(), name );
+
+					for (alias, alias_name) in $crate::__service_aliases!( [] ; $( $skey = $sval ; )* )
+					{
+						ServiceID::register_service( alias, alias_name.unwrap_or( name ) );
+					}
+
+					ServiceID::register_types
+					(
+						<$streams as StreamService>::sid()                             ,
+						::std::any::type_name::<$streams>()                            ,
+						::std::any::type_name::< <$streams as StreamService>::Item >() ,
+					);
+				});
+			}
+		)+)?
+
+		static ALIASES: Lazy< HashMap<ServiceID, ServiceID> > = Lazy::new( Services::collect_aliases );
+
+		Self{ handlers: HashMap::new(), aliases: ALIASES.clone() }
+	}
+
+
+	// Collect the aliases of all services and verify that no two services or aliases have the
+	// same sid and that none of them is reserved.
+	//
+	fn collect_aliases() -> HashMap<ServiceID, ServiceID>
+	{
+		let mut sids   : HashMap< ServiceID, &'static str > = HashMap::new();
+		let mut aliases: HashMap< ServiceID, ServiceID    > = HashMap::new();
+
+		let mut check = |sid: ServiceID, name: &'static str|
+		{
+			let raw: u64 = sid.into();
+
+			if sid.is_reserved()
+			{
+				panic!( "service_map!( {} ): the sid of {} ({:#018x}) is reserved by thespis_remote.", stringify!( $ns ), name, raw );
+			}
+
+			if let Some( other ) = sids.insert( sid, name )
+			{
+				panic!( "service_map!( {} ): {} and {} have the same sid ({:#018x}).", stringify!( $ns ), other, name, raw );
+			}
+		};
+
+		$(
+			let sid  = <$services as Service>::sid();
+			let name = $crate::__service_name!( concat!( stringify!($ns) , "::", stringify!($services) ) ; $( $key $( = $val )? ; )* );
+
+			check( sid, name );
+
+			for (alias, alias_name) in $crate::__service_aliases!( [] ; $( $key $( = $val )? ; )* )
+			{
+				check( alias, alias_name.unwrap_or( name ) );
+				aliases.insert( alias, sid );
+			}
+		)+
+
+		$($(
+			let sid  = <$streams as StreamService>::sid();
+			let name = $crate::__service_name!( concat!( stringify!($ns) , "::", stringify!($streams) ) ; $( $skey = $sval ; )* );
+
+			check( sid, name );
+
+			for (alias, alias_name) in $crate::__service_aliases!( [] ; $( $skey = $sval ; )* )
+			{
+				check( alias, alias_name.unwrap_or( name ) );
+				aliases.insert( alias, sid );
+			}
+		)+)?
+
+		aliases
+	}
+
+
+	// The sid of the service for a sid that came in over the network, which might be an alias.
+	//
+	fn resolve( &self, sid: ServiceID ) -> ServiceID
+	{
+		self.aliases.get( &sid ).copied().unwrap_or( sid )
 	}
 
 
@@ -327,84 +668,65 @@ impl Services
 	}
 
 
+	/// Register a handler for a given streaming service type
+	/// Calling this method twice for the same type will override the first handler.
+	//
+	pub fn register_stream_handler<S>( &mut self, handler: BoxAddress<S, ThesErr> )
+
+		where  S                    : StreamService,
+		      <S as Message>::Return: Stream< Item = <S as StreamService>::Item >,
+	{
+		self.handlers.insert( <S as StreamService>::sid(), Mutex::new(Box::new( handler )) );
+	}
+
+
 	// Helper function for call_service below.
 	// The receiver passed in here keeps a mutex locked. This method should never be async, nor await anything.
 	//
 	fn call_service_gen<S>
 	(
-		    msg      :  $wf                   ,
-		    receiver : &Box< dyn Any + Send > ,
-		mut ctx      :  PeerErrCtx            ,
+		msg      :  $wf                   ,
+		receiver : &Box< dyn Any + Send > ,
+		ctx      :  PeerErrCtx            ,
 
 	) -> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
 
-		where  S                    : Service + Send,
+		where  S                    : Service + Send + 'static,
 		      <S as Message>::Return: Serialize + DeserializeOwned + Send + ,
 
 	{
-		let sid = <S as Service>::sid();
-
-		// Deserialize the message.
-		//
-		let message: S = match des( &msg.msg() )
-		{
-			Ok (x) => x,
-			Err(_) => return Err( PeerErr::Deserialize{ ctx } )
-		};
-
-
 		// Downcast the receiver, should never fail as we make it in this file.
 		//
-		let backup: &BoxAddress<S, ThesErr> = receiver.downcast_ref()
+		let rec: &BoxAddress<S, ThesErr> = receiver.downcast_ref()
 
 			.expect( "downcast receiver in call_service_gen" );
 
-
-		let mut rec = backup.clone_box() ;
-		let     cid = msg.cid()      ;
-
-		Ok( async move
-		{
-			// Call the service and wait for the response
-			//
-			let response = match rec.call( message ).await
-			{
-				Ok(x) => x,
-
-				Err(_) =>
-				{
-					// unwrap: we pass it in from below in this file, it has a context guaranteed.
-					//
-					ctx.context.as_mut().map( |c| c.push_str( " - Process call for local Actor" ) );
-
-					return Err( PeerErr::HandlerDead{ ctx } );
-				}
-			};
+		process_call( &msg, rec.clone_box(), <S as Service>::sid(), <S as Service>::reply, ctx )
+	}
 
 
-			// Create a $wf response.
-			// The sid must be full to differentiate a response from a request. If the request
-			// has timed out, the remote peer will no longer have the cid in their list of open requests,
-			// so they would not know this was a response otherwise.
-			//
-			let mut wf = <$wf>::with_capacity( ::std::mem::size_of::<S>() * 2 );
-			wf.set_sid( ServiceID::full() );
-			wf.set_cid( cid               );
-
-			// serialize the response
-			//
-			serde_cbor::to_writer( &mut wf, &response ).map_err( |_|
-			{
-				ctx.context.as_mut().map( |c| c.push_str( " - Response to remote call" ) );
+	// Helper function for call_service below, for streaming services.
+	// The receiver passed in here keeps a mutex locked. This method should never be async, nor await anything.
+	//
+	fn call_stream_gen<S>
+	(
+		msg      :  $wf                   ,
+		receiver : &Box< dyn Any + Send > ,
+		ctx      :  PeerErrCtx            ,
 
-				PeerErr::Serialize{ ctx }
+	) -> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
 
-			})?;
+		where  S                    : StreamService + Send + 'static,
+		      <S as Message>::Return: Stream< Item = <S as StreamService>::Item > + Send + 'static,
 
+	{
+		// Downcast the receiver, should never fail as we make it in this file.
+		//
+		let rec: &BoxAddress<S, ThesErr> = receiver.downcast_ref()
 
-			Ok( Response::CallResponse( CallResponse::new(wf) ))
+			.expect( "downcast receiver in call_stream_gen" );
 
-		}.boxed() )
+		process_stream( &msg, rec.clone_box(), ctx )
 	}
 }
 
@@ -419,6 +741,20 @@ impl ServiceMap<$wf> for Services
 	}
 
 
+	// Only the aliases of services that have a handler, so the peer doesn't route others to us.
+	//
+	fn aliases( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		let aliases = self.aliases.iter()
+
+			.filter( move |(_, sid)| self.handlers.contains_key( *sid ) )
+			.map   ( |(alias, _)| alias                                 )
+		;
+
+		Box::new( aliases )
+	}
+
+
 
 	/// Will match the type of the service id to deserialize the message and send it to the handling actor.
 	///
@@ -431,7 +767,7 @@ impl ServiceMap<$wf> for Services
 		-> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
 
 	{
-		let sid = msg.sid();
+		let sid = self.resolve( msg.sid() );
 		let ctx = ctx.context( "Services::send_service".to_string() );
 
 		// This sid should be in our map.
@@ -456,29 +792,7 @@ impl ServiceMap<$wf> for Services
 
 						.expect( "downcast receiver in send_service" );
 
-
-					// Deserialize.
-					//
-					let message: $services = match des( &msg.msg() )
-					{
-						Ok (x) => x,
-						Err(_) => return Err( PeerErr::Deserialize{ ctx } ),
-					};
-
-
-					// We need to clone the receiver so it can be inside the future as &mut self.
-					//
-					let mut rec = rec.clone_box();
-
-					Ok( async move
-					{
-						match rec.send( message ).await
-						{
-							Ok (_) => Ok ( Response::Nothing                 ),
-							Err(_) => Err( PeerErr::HandlerDead{ ctx } ),
-						}
-
-					}.boxed() )
+					process_send( &msg, rec.clone_box(), ctx )
 				},
 			)+
 
@@ -507,7 +821,7 @@ impl ServiceMap<$wf> for Services
 
 	) -> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
 	{
-		let sid = msg.sid();
+		let sid = self.resolve( msg.sid() );
 		let ctx = ctx.context( "Services::call_service".to_string() );
 
 		let receiver = match self.handlers.get( &sid )
@@ -532,6 +846,13 @@ impl ServiceMap<$wf> for Services
 				}
 			)+
 
+			$($(
+				_ if sid == <$streams as StreamService>::sid() =>
+				{
+					Self::call_stream_gen::<$streams>( msg, &*receiver, ctx )
+				}
+			)+)?
+
 
 			_ => return Err( PeerErr::UnknownService{ ctx } )
 		}
@@ -560,7 +881,19 @@ pub struct RemoteAddr
 	//       type of this message. It would have to be an enum as well, and every caller would have to
 	//       match on it. For now we will keep our dependency on Peer and Addr.
 	//
-	peer: Addr<Peer<$wf>>
+	peer: Addr<Peer<$wf>>,
+
+	// The priority for outgoing calls and sends, if not the default.
+	//
+	priority: Option<Priority>,
+
+	// The trace context sent along with outgoing calls and sends.
+	//
+	trace: Option<TraceContext>,
+
+	// The send that hasn't gone out yet.
+	//
+	sending: PendingSend,
 }
 
 
@@ -570,7 +903,40 @@ impl RemoteAddr
 	//
 	pub fn new( peer: Addr<Peer<$wf>> ) -> Self
 	{
-		Self { peer }
+		Self { peer, priority: None, trace: None, sending: PendingSend::default() }
+	}
+
+
+	/// Send calls and sends through this address with the given priority. By default both
+	/// have `Priority::Call`. See [`Priority`].
+	//
+	pub fn with_priority( mut self, priority: Priority ) -> Self
+	{
+		self.priority = Some( priority );
+		self
+	}
+
+
+	/// Send the trace context along with calls and sends through this address, so the remote
+	/// continues the trace. See [`TraceContext`].
+	//
+	pub fn with_trace( mut self, trace: impl Into<Option<TraceContext>> ) -> Self
+	{
+		self.trace = trace.into();
+		self
+	}
+
+
+	/// Ask the remote whether it exposes the service `S`. This uses the reflection service of the
+	/// remote, so it fails if the remote has reflection disabled. Useful to verify a connection
+	/// before the first call.
+	//
+	pub async fn is_reachable<S>( &mut self ) -> Result< bool, PeerErr >
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+	{
+		ServiceInfo::is_reachable( &mut self.peer, <S as Service>::sid() ).await
 	}
 
 
@@ -634,6 +1000,59 @@ impl RemoteAddr
 
 		Ok( Call::new( wf ) )
 	}
+
+
+	/// Take the raw message and turn it into a CallStream
+	//
+	fn build_call_stream<S>( msg: S ) -> Result< CallStream<$wf>, PeerErr >
+
+		where  S: StreamService + Send,
+
+	{
+		let sid = <S as StreamService>::sid();
+
+		let mut wf = <$wf>::with_capacity( ::std::mem::size_of::<S>() * 2 );
+		wf.set_sid( sid );
+
+		// serialize the request
+		//
+		serde_cbor::to_writer( &mut wf, &msg ).map_err( |_|
+		{
+			let mut ctx = PeerErrCtx::default();
+			ctx.context = "Outgoing streaming request".to_string().into();
+			ctx.sid     = sid.into();
+
+			PeerErr::Serialize{ ctx }
+
+		})?;
+
+		Ok( CallStream::new( wf, 16 ) )
+	}
+
+
+	/// Call a remote streaming service. The outer result reports failure to send out the request.
+	/// The items of the stream are the deserialized responses. If an error happens while
+	/// receiving the stream, it is yielded and the stream ends.
+	///
+	/// The flow control window of the stream is 16 items. Once 16 items have arrived that you haven't
+	/// consumed, the remote stops sending until you do. The peer keeps reading from the connection.
+	//
+	pub async fn call_stream<S>( &mut self, msg: S )
+
+		-> Result< impl Stream< Item = Result<<S as StreamService>::Item, PeerErr> >, PeerErr >
+
+		where  S: StreamService + Send,
+	{
+		let     sid  = <S as StreamService>::sid();
+		let mut call = Self::build_call_stream( msg )?.with_trace( self.trace );
+
+		if let Some( priority ) = self.priority
+		{
+			call = call.with_priority( priority );
+		}
+
+		call_remote_stream( &mut self.peer, call, sid ).await
+	}
 }
 
 
@@ -655,7 +1074,12 @@ impl<S> Address<S> for RemoteAddr
 	{
 		// Serialization can fail
 		//
-		let call = Self::build_call( msg )?;
+		let mut call = Self::build_call( msg )?.with_trace( self.trace );
+
+		if let Some( priority ) = self.priority
+		{
+			call = call.with_priority( priority );
+		}
 
 		// Can fail if the peer is down already.
 		//
@@ -671,13 +1095,19 @@ impl<S> Address<S> for RemoteAddr
 
 			})?
 
-			// The actual sending out over the network can fail.
+			// The actual sending out over the network can fail, or the call limit of the
+			// peer refuses it.
 			//
-			.map_err( |_|
+			.map_err( |err| match err
 			{
-				let ctx = Peer::err_ctx( &self.peer, <S as Service>::sid(), None, "Call remote service".to_string() );
+				PeerErr::TooManyCalls{..} => err,
 
-				PeerErr::ConnectionClosed{ ctx }
+				_ =>
+				{
+					let ctx = Peer::err_ctx( &self.peer, <S as Service>::sid(), None, "Call remote service".to_string() );
+
+					PeerErr::ConnectionClosed{ ctx }
+				}
 
 			})?;
 
@@ -695,6 +1125,7 @@ impl<S> Address<S> for RemoteAddr
 					peer_name: self.peer.name()                                                              ,
 					sid      : <S as Service>::sid().into()                                                  ,
 					cid      : None                                                                          ,
+					trace    : None                                                                          ,
 				};
 
 				PeerErr::ConnectionClosed{ ctx }
@@ -721,6 +1152,7 @@ impl<S> Address<S> for RemoteAddr
 							peer_name: self.peer.name()                                         ,
 							sid      : <S as Service>::sid().into()                             ,
 							cid      : resp.cid().into()                                        ,
+							trace    : None                                                     ,
 						};
 
 						PeerErr::Deserialize{ ctx }
@@ -740,6 +1172,7 @@ impl<S> Address<S> for RemoteAddr
 					peer_name: self.peer.name()                                           ,
 					sid      : <S as Service>::sid().into()                               ,
 					cid      : None                                                       ,
+					trace    : None                                                       ,
 				};
 
 				match err
@@ -761,6 +1194,15 @@ impl<S> Address<S> for RemoteAddr
 						Err( PeerErr::Timeout{ ctx } )
 					}
 
+					// The handler of a fallible service returned an error.
+					//
+					ConnectionError::Application{ err, .. } =>
+					{
+						ctx.context = Some( "Remote handler returned an error".to_string() );
+
+						Err( PeerErr::Application{ err, ctx } )
+					}
+
 					_ =>
 					{
 						Err( PeerErr::Remote{ err, ctx } )
@@ -776,7 +1218,7 @@ impl<S> Address<S> for RemoteAddr
 	//
 	fn clone_box( &self ) -> BoxAddress<S, PeerErr>
 	{
-		Box::new( Self { peer: self.peer.clone() } )
+		Box::new( self.clone() )
 	}
 }
 
@@ -792,50 +1234,38 @@ impl<S> Sink<S> for RemoteAddr
 	type Error = PeerErr;
 
 
+	/// Ready when the previous send has gone out. If the remote uses flow control, that is when it
+	/// gave us credit for it. See [`FlowSend`].
+	//
 	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context ) -> Poll<Result<(), Self::Error>>
 	{
-		Sink::<$wf>::poll_ready( Pin::new( &mut self.peer ), cx )
-
-			.map_err( |source|
-			{
-				let ctx = Peer::err_ctx( &self.peer, <S as Service>::sid(), None, "Send on RemoteAddr".to_string() );
-
-				PeerErr::ThesErr{ ctx, source: Arc::new(source) }
-			})
+		self.sending.poll_done( cx )
 	}
 
 
 	fn start_send( mut self: Pin<&mut Self>, msg: S ) -> Result<(), Self::Error>
 	{
-		Sink::<$wf>::start_send( Pin::new( &mut self.peer ), Self::build_wf( msg, ConnID::null() )? )
+		let wf   = Self::build_wf( msg, ConnID::null() )?;
+		let send = FlowSend::new( wf, self.priority.unwrap_or( Priority::Call ) ).with_trace( self.trace );
+		let peer = self.peer.clone();
 
-			.map_err( |source|
-			{
-				let ctx = Peer::err_ctx( &self.peer, <S as Service>::sid(), None, "Send on RemoteAddr".to_string() );
+		self.sending.start( peer, send );
 
-				PeerErr::ThesErr{ ctx, source: Arc::new(source) }
-			})
+		Ok(())
 	}
 
 
 	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context ) -> Poll<Result<(), Self::Error>>
 	{
-		Sink::<$wf>::poll_flush( Pin::new( &mut self.peer ), cx )
-
-			.map_err( |source|
-			{
-				let ctx = Peer::err_ctx( &self.peer, <S as Service>::sid(), None, "Send on RemoteAddr".to_string() );
-
-				PeerErr::ThesErr{ ctx, source: Arc::new(source) }
-			})
+		self.sending.poll_done( cx )
 	}
 
 
-	/// Will only close when dropped, this method can never return ready
+	/// Waits for the last send to go out. The connection only closes when the peer is dropped.
 	//
 	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context ) -> Poll<Result<(), Self::Error>>
 	{
-		Poll::Ready(Ok(()))
+		self.sending.poll_done( cx )
 	}
 }
 
@@ -858,3 +1288,253 @@ impl Identify for RemoteAddr
 }
 
 }}} // End of macro
+
+
+
+// Compute the sid of a service from the attributes given to it in `service_map!`. `$default` is the
+// name that is hashed when there is no name or sid attribute.
+//
+#[ doc( hidden ) ]
+#[ macro_export ]
+//
+macro_rules! __service_sid
+{
+	// Once the name or the sid is found, check that the rest doesn't set either of them again,
+	// otherwise the name given to reflection might not be the one the sid is derived from.
+	//
+	( @name $name: literal ; ) =>
+	{
+		$crate::ServiceID::from_seed( $name.as_bytes() )
+	};
+
+	( @sid $sid: literal ; ) =>
+	{
+		{ let sid: u64 = $sid; $crate::ServiceID::from( sid ) }
+	};
+
+	( @$found: ident $val: literal ; name = $name: literal ; $($rest: tt)* ) =>
+	{
+		compile_error!( concat!( "service_map!: set either `name` or `sid` on a service, not both, or several times. Found: name = ", stringify!( $name ) ) )
+	};
+
+	( @$found: ident $val: literal ; sid = $sid: literal ; $($rest: tt)* ) =>
+	{
+		compile_error!( concat!( "service_map!: set either `name` or `sid` on a service, not both, or several times. Found: sid = ", stringify!( $sid ) ) )
+	};
+
+	( @$found: ident $val: literal ; alias = $alias: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_sid!( @$found $val ; $($rest)* )
+	};
+
+	( @$found: ident $val: literal ; alias_sid = $alias: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_sid!( @$found $val ; $($rest)* )
+	};
+
+	( @$found: ident $val: literal ; fallible ; $($rest: tt)* ) =>
+	{
+		$crate::__service_sid!( @$found $val ; $($rest)* )
+	};
+
+	( @$found: ident $val: literal ; $key: ident $( = $other: literal )? ; $($rest: tt)* ) =>
+	{
+		$crate::__service_attr_err!( $key $( = $other )? )
+	};
+
+	( $default: expr ; ) =>
+	{
+		$crate::ServiceID::from_seed( $default.as_bytes() )
+	};
+
+	( $default: expr ; name = $name: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_sid!( @name $name ; $($rest)* )
+	};
+
+	( $default: expr ; sid = $sid: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_sid!( @sid $sid ; $($rest)* )
+	};
+
+	( $default: expr ; alias = $alias: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_sid!( $default ; $($rest)* )
+	};
+
+	( $default: expr ; alias_sid = $alias: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_sid!( $default ; $($rest)* )
+	};
+
+	( $default: expr ; fallible ; $($rest: tt)* ) =>
+	{
+		$crate::__service_sid!( $default ; $($rest)* )
+	};
+
+	( $default: expr ; $key: ident $( = $val: literal )? ; $($rest: tt)* ) =>
+	{
+		$crate::__service_attr_err!( $key $( = $val )? )
+	};
+}
+
+
+
+// The name of a service from the attributes given to it in `service_map!`.
+//
+#[ doc( hidden ) ]
+#[ macro_export ]
+//
+macro_rules! __service_name
+{
+	( $default: expr ; ) =>
+	{
+		$default
+	};
+
+	( $default: expr ; name = $name: literal ; $($rest: tt)* ) =>
+	{
+		$name
+	};
+
+	( $default: expr ; sid = $sid: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_name!( $default ; $($rest)* )
+	};
+
+	( $default: expr ; alias = $alias: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_name!( $default ; $($rest)* )
+	};
+
+	( $default: expr ; alias_sid = $alias: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_name!( $default ; $($rest)* )
+	};
+
+	( $default: expr ; fallible ; $($rest: tt)* ) =>
+	{
+		$crate::__service_name!( $default ; $($rest)* )
+	};
+
+	( $default: expr ; $key: ident $( = $val: literal )? ; $($rest: tt)* ) =>
+	{
+		$crate::__service_attr_err!( $key $( = $val )? )
+	};
+}
+
+
+
+// The aliases of a service from the attributes given to it in `service_map!`, as a Vec of the sid
+// and the name, if the alias was given by name. The aliases found so far are collected between the brackets.
+//
+#[ doc( hidden ) ]
+#[ macro_export ]
+//
+macro_rules! __service_aliases
+{
+	( [ $($out: expr,)* ] ; ) =>
+	{
+		{
+			let aliases: Vec<( $crate::ServiceID, Option<&'static str> )> = vec![ $($out,)* ];
+			aliases
+		}
+	};
+
+	( [ $($out: expr,)* ] ; alias = $name: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_aliases!( [ $($out,)* ( $crate::ServiceID::from_seed( $name.as_bytes() ), Some( $name ) ), ] ; $($rest)* )
+	};
+
+	( [ $($out: expr,)* ] ; alias_sid = $sid: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_aliases!( [ $($out,)* ( { let sid: u64 = $sid; $crate::ServiceID::from( sid ) }, None ), ] ; $($rest)* )
+	};
+
+	( [ $($out: expr,)* ] ; name = $name: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_aliases!( [ $($out,)* ] ; $($rest)* )
+	};
+
+	( [ $($out: expr,)* ] ; sid = $sid: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_aliases!( [ $($out,)* ] ; $($rest)* )
+	};
+
+	( [ $($out: expr,)* ] ; fallible ; $($rest: tt)* ) =>
+	{
+		$crate::__service_aliases!( [ $($out,)* ] ; $($rest)* )
+	};
+
+	( [ $($out: expr,)* ] ; $key: ident $( = $val: literal )? ; $($rest: tt)* ) =>
+	{
+		$crate::__service_attr_err!( $key $( = $val )? )
+	};
+}
+
+
+
+// Override `Service::reply` for services marked `#[ fallible ]` in `service_map!`.
+//
+#[ doc( hidden ) ]
+#[ macro_export ]
+//
+macro_rules! __service_reply
+{
+	() => {};
+
+	( fallible ; $($rest: tt)* ) =>
+	{
+		fn reply( resp: <Self as $crate::external_deps::thespis::Message>::Return )
+
+			-> Result< $crate::Reply<<Self as $crate::external_deps::thespis::Message>::Return>, $crate::external_deps::serde_cbor::Error >
+		{
+			$crate::Reply::fallible( resp )
+		}
+	};
+
+	( name = $name: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_reply!{ $($rest)* }
+	};
+
+	( sid = $sid: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_reply!{ $($rest)* }
+	};
+
+	( alias = $alias: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_reply!{ $($rest)* }
+	};
+
+	( alias_sid = $alias: literal ; $($rest: tt)* ) =>
+	{
+		$crate::__service_reply!{ $($rest)* }
+	};
+
+	( $key: ident $( = $val: literal )? ; $($rest: tt)* ) =>
+	{
+		$crate::__service_attr_err!{ $key $( = $val )? }
+	};
+}
+
+
+
+// Reject an attribute of a service in `service_map!` that isn't known, or is given in the wrong form,
+// rather than silently ignoring a typo.
+//
+#[ doc( hidden ) ]
+#[ macro_export ]
+//
+macro_rules! __service_attr_err
+{
+	( $key: ident $( = $val: literal )? ) =>
+	{
+		compile_error!( concat!
+		(
+			"service_map!: unknown service attribute `", stringify!( $key $( = $val )? ), "`, expected one of: ",
+			"name = \"...\", sid = 0x1234, alias = \"...\", alias_sid = 0x1234, fallible",
+		))
+	};
+}
@@ -0,0 +1,527 @@
+use crate::{ import::*, * };
+
+
+type ServiceFuture<Wf> = Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>;
+
+
+/// A [`ServiceMap`] for types that implement the crate level [`Service`] and [`StreamService`] traits,
+/// usually with `#[derive(Service)]`. It does the same as the `Services` type generated by `service_map!`,
+/// but it's a normal generic type, so it works with generic message types and doesn't need a macro.
+///
+/// The `#[service_map]` attribute of the `derive` feature generates a wrapper around it that only
+/// accepts the services listed for it.
+///
+/// ```ignore
+/// let mut sm = ServiceRegistry::new();
+///
+/// sm.register_handler::<Add>( addr.clone_box() );
+/// sm.register_handler::<Show>( addr.clone_box() );
+///
+/// let peer = PeerBuilder::new().service_map( Arc::new( sm ) );
+/// ```
+///
+/// ### Panics
+///
+/// Declaring or registering a handler for a service panics if another type in the registry has the same
+/// sid or if the sid is reserved by thespis_remote.
+//
+pub struct ServiceRegistry<Wf = ThesWF>
+{
+	// The name and type name of each declared service, to detect two types with the same sid.
+	//
+	services: HashMap< ServiceID, (&'static str, &'static str) >,
+
+	// The handlers of the services, with the type of the message erased.
+	//
+	handlers: HashMap< ServiceID, Box<dyn Handle<Wf>> >,
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> ServiceRegistry<Wf>
+{
+	/// Create an empty registry.
+	//
+	pub fn new() -> Self
+	{
+		Self { services: HashMap::new(), handlers: HashMap::new() }
+	}
+
+
+	/// Declare a service without registering a handler for it. This checks that it's sid doesn't
+	/// conflict with other services and registers it's name for log output and reflection.
+	//
+	pub fn declare<S>( &mut self )
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		self.declare_sid
+		(
+			<S as Service>::sid()                                ,
+			<S as Service>::NAME                                 ,
+			std::any::type_name::<S>()                           ,
+			std::any::type_name::< <S as Message>::Return >()    ,
+		);
+	}
+
+
+	/// Declare a streaming service without registering a handler for it. See [`ServiceRegistry::declare`].
+	//
+	pub fn declare_stream<S>( &mut self )
+
+		where S: StreamService,
+	{
+		self.declare_sid
+		(
+			<S as StreamService>::sid()                                ,
+			<S as StreamService>::NAME                                 ,
+			std::any::type_name::<S>()                                 ,
+			std::any::type_name::< <S as StreamService>::Item >()      ,
+		);
+	}
+
+
+	fn declare_sid( &mut self, sid: ServiceID, name: &'static str, ty: &'static str, returns: &'static str )
+	{
+		let raw: u64 = sid.into();
+
+		if sid.is_reserved()
+		{
+			panic!( "ServiceRegistry: the sid of {} ({:#018x}) is reserved by thespis_remote.", name, raw );
+		}
+
+		if let Some( (other, other_ty) ) = self.services.get( &sid )
+		{
+			if *other_ty != ty
+			{
+				panic!( "ServiceRegistry: {} and {} have the same sid ({:#018x}).", other, name, raw );
+			}
+		}
+
+		self.services.insert( sid, (name, ty) );
+
+		ServiceID::register_service( sid, name          );
+		ServiceID::register_types  ( sid, ty, returns );
+	}
+
+
+	/// Register a handler for a service. Calling this method twice for the same type will override
+	/// the first handler.
+	//
+	pub fn register_handler<S>( &mut self, handler: BoxAddress<S, ThesErr> )
+
+		where  S                    : Service + Send + 'static,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+	{
+		self.declare::<S>();
+
+		self.handlers.insert( <S as Service>::sid(), Box::new( ServiceHandle{ addr: Mutex::new( handler ) } ) );
+	}
+
+
+	/// Register a handler for a streaming service. Calling this method twice for the same type will
+	/// override the first handler.
+	//
+	pub fn register_stream_handler<S>( &mut self, handler: BoxAddress<S, ThesErr> )
+
+		where  S                    : StreamService + Send + 'static,
+		      <S as Message>::Return: Stream< Item = <S as StreamService>::Item > + Send + 'static,
+	{
+		self.declare_stream::<S>();
+
+		self.handlers.insert( <S as StreamService>::sid(), Box::new( StreamHandle{ addr: Mutex::new( handler ) } ) );
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Default for ServiceRegistry<Wf>
+{
+	fn default() -> Self
+	{
+		Self::new()
+	}
+}
+
+
+
+impl<Wf> Clone for ServiceRegistry<Wf>
+{
+	fn clone( &self ) -> Self
+	{
+		let handlers = self.handlers.iter().map( |(sid, h)| (*sid, h.boxed_clone()) ).collect();
+
+		Self { services: self.services.clone(), handlers }
+	}
+}
+
+
+
+/// Will print something like:
+///
+/// ```ignore
+/// ServiceRegistry
+/// {
+///    myns::Add  - sid: 0x6440cfd17c374646 - handler: id(0), name(actor_name)
+///    myns::Show - sid: 0xcdd3781867767588 - handler: none
+/// }
+/// ```
+//
+impl<Wf> fmt::Debug for ServiceRegistry<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		// HashMap order is random, sort by name.
+		//
+		let mut services: Vec<_> = self.services.iter().collect();
+		services.sort_by_key( |(_, (name, _))| *name );
+
+		let width = services.iter().map( |(_, (name, _))| name.len() ).max().unwrap_or( 0 );
+
+		writeln!( f, "ServiceRegistry\n{{" )?;
+
+		for (sid, (name, _)) in services
+		{
+			let raw: u64 = (*sid).into();
+
+			write!( f, "\t{:width$} - sid: {:#018x} - handler: ", name, raw, width = width )?;
+
+			match self.handlers.get( sid ).map( |h| h.actor() )
+			{
+				Some( (id, Some(n)) ) => writeln!( f, "id({}), name({})", id, n )?,
+				Some( (id, None   ) ) => writeln!( f, "id({})", id            )?,
+				None                  => writeln!( f, "none"                   )?,
+			}
+		}
+
+		write!( f, "}}" )
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> ServiceMap<Wf> for ServiceRegistry<Wf>
+{
+	/// Deserialize the message and send it to the handling actor.
+	///
+	/// This can return the following errors:
+	/// - PeerErr::NoHandler
+	/// - PeerErr::Deserialize
+	//
+	fn send_service( &self, msg: Wf, ctx: PeerErrCtx ) -> Result< ServiceFuture<Wf>, PeerErr >
+	{
+		let ctx = ctx.context( "ServiceRegistry::send_service".to_string() );
+
+		match self.handlers.get( &msg.sid() )
+		{
+			Some( handler ) => handler.send( msg, ctx ),
+			None            => Err( PeerErr::NoHandler{ ctx } ),
+		}
+	}
+
+
+	/// Deserialize the message and call the handling actor. It returns a future that actually calls
+	/// the handling actor. This futures is spawned by Peer.
+	///
+	/// This can return the following errors:
+	/// - PeerErr::NoHandler
+	/// - PeerErr::Deserialize
+	//
+	fn call_service( &self, msg: Wf, ctx: PeerErrCtx ) -> Result< ServiceFuture<Wf>, PeerErr >
+	{
+		let ctx = ctx.context( "ServiceRegistry::call_service".to_string() );
+
+		match self.handlers.get( &msg.sid() )
+		{
+			Some( handler ) => handler.call( msg, ctx ),
+			None            => Err( PeerErr::NoHandler{ ctx } ),
+		}
+	}
+
+
+	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		Box::new( self.handlers.keys() )
+	}
+}
+
+
+
+// The handler of one service, with the type of the message erased.
+//
+trait Handle<Wf>: Send + Sync
+{
+	fn send( &self, msg: Wf, ctx: PeerErrCtx ) -> Result< ServiceFuture<Wf>, PeerErr >;
+	fn call( &self, msg: Wf, ctx: PeerErrCtx ) -> Result< ServiceFuture<Wf>, PeerErr >;
+
+	// The id and name of the handling actor.
+	//
+	fn actor( &self ) -> (usize, Option<Arc<str>>);
+
+	fn boxed_clone( &self ) -> Box< dyn Handle<Wf> >;
+}
+
+
+
+// We need the Mutex because ServiceMap has to be Sync and the addresses generally aren't.
+//
+struct ServiceHandle<S>
+{
+	addr: Mutex< BoxAddress<S, ThesErr> >,
+}
+
+
+impl<Wf, S> Handle<Wf> for ServiceHandle<S>
+
+	where  Wf                   : WireFormat + Send + 'static,
+	       S                    : Service + Send + 'static,
+	      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+{
+	fn send( &self, msg: Wf, ctx: PeerErrCtx ) -> Result< ServiceFuture<Wf>, PeerErr >
+	{
+		process_send( &msg, self.addr.lock().clone_box(), ctx )
+	}
+
+
+	fn call( &self, msg: Wf, ctx: PeerErrCtx ) -> Result< ServiceFuture<Wf>, PeerErr >
+	{
+		process_call( &msg, self.addr.lock().clone_box(), <S as Service>::sid(), <S as Service>::reply, ctx )
+	}
+
+
+	fn actor( &self ) -> (usize, Option<Arc<str>>)
+	{
+		let addr = self.addr.lock();
+
+		(addr.id(), addr.name())
+	}
+
+
+	fn boxed_clone( &self ) -> Box< dyn Handle<Wf> >
+	{
+		Box::new( Self{ addr: Mutex::new( self.addr.lock().clone_box() ) } )
+	}
+}
+
+
+
+struct StreamHandle<S>
+{
+	addr: Mutex< BoxAddress<S, ThesErr> >,
+}
+
+
+impl<Wf, S> Handle<Wf> for StreamHandle<S>
+
+	where  Wf                   : WireFormat + Send + 'static,
+	       S                    : StreamService + Send + 'static,
+	      <S as Message>::Return: Stream< Item = <S as StreamService>::Item > + Send + 'static,
+{
+	// Streaming services can only be called.
+	//
+	fn send( &self, _msg: Wf, ctx: PeerErrCtx ) -> Result< ServiceFuture<Wf>, PeerErr >
+	{
+		Err( PeerErr::NoHandler{ ctx } )
+	}
+
+
+	fn call( &self, msg: Wf, ctx: PeerErrCtx ) -> Result< ServiceFuture<Wf>, PeerErr >
+	{
+		process_stream( &msg, self.addr.lock().clone_box(), ctx )
+	}
+
+
+	fn actor( &self ) -> (usize, Option<Arc<str>>)
+	{
+		let addr = self.addr.lock();
+
+		(addr.id(), addr.name())
+	}
+
+
+	fn boxed_clone( &self ) -> Box< dyn Handle<Wf> >
+	{
+		Box::new( Self{ addr: Mutex::new( self.addr.lock().clone_box() ) } )
+	}
+}
+
+
+
+// Processing of incoming requests, shared with the service maps generated by `service_map!`. They
+// deserialize the request and return a future that delivers it to the handler and turns the response
+// into frames. They must not block, since service maps call them with a lock held.
+//
+// They don't use the `Service` traits, since each `service_map!` has it's own.
+
+
+/// Deliver a send to `handler`.
+//
+#[ doc( hidden ) ]
+//
+pub fn process_send<Wf, M>( msg: &Wf, mut handler: BoxAddress<M, ThesErr>, ctx: PeerErrCtx )
+
+	-> Result< ServiceFuture<Wf>, PeerErr >
+
+	where Wf: WireFormat + Send + 'static             ,
+	      M : Message + DeserializeOwned + Send + 'static,
+{
+	let message: M = match serde_cbor::from_slice( msg.msg() )
+	{
+		Ok (x) => x,
+		Err(_) => return Err( PeerErr::Deserialize{ ctx } ),
+	};
+
+	Ok( async move
+	{
+		match handler.send( message ).await
+		{
+			Ok (_) => Ok ( Response::Nothing           ),
+			Err(_) => Err( PeerErr::HandlerDead{ ctx } ),
+		}
+
+	}.boxed() )
+}
+
+
+
+/// Call `handler` and serialize the response. `sid` is the sid of the service, also when the request
+/// came in with an alias. `reply` separates application errors of fallible services from the response.
+//
+#[ doc( hidden ) ]
+//
+pub fn process_call<Wf, M>
+(
+	    msg    : &Wf                                                                                  ,
+	mut handler: BoxAddress<M, ThesErr>                                                               ,
+	    sid    : ServiceID                                                                            ,
+	    reply  : fn( <M as Message>::Return ) -> Result< Reply<<M as Message>::Return>, serde_cbor::Error > ,
+	mut ctx    : PeerErrCtx                                                                           ,
+)
+	-> Result< ServiceFuture<Wf>, PeerErr >
+
+	where  Wf                   : WireFormat + Send + 'static             ,
+	       M                    : Message + DeserializeOwned + Send + 'static,
+	      <M as Message>::Return: Serialize + Send                        ,
+{
+	let message: M = match serde_cbor::from_slice( msg.msg() )
+	{
+		Ok (x) => x,
+		Err(_) => return Err( PeerErr::Deserialize{ ctx } ),
+	};
+
+	let cid = msg.cid();
+
+	Ok( async move
+	{
+		let response = match handler.call( message ).await
+		{
+			Ok(x) => x,
+
+			Err(_) =>
+			{
+				ctx.context.as_mut().map( |c| c.push_str( " - Process call for local Actor" ) );
+
+				return Err( PeerErr::HandlerDead{ ctx } );
+			}
+		};
+
+		// An application error of a fallible service goes back as a ConnectionError.
+		//
+		let response = match reply( response )
+		{
+			Ok( Reply::Return( r ) ) => r,
+
+			Ok( Reply::AppError( err ) ) =>
+			{
+				let err = ConnectionError::Application{ sid: sid.into(), cid: cid.into(), err };
+
+				return Ok( Response::CallResponse( CallResponse::new( Peer::<Wf>::prep_error( cid, &err ) ) ));
+			}
+
+			Err(_) =>
+			{
+				ctx.context.as_mut().map( |c| c.push_str( " - Application error of remote call" ) );
+
+				return Err( PeerErr::Serialize{ ctx } );
+			}
+		};
+
+		// The sid must be full to differentiate a response from a request. If the request
+		// has timed out, the remote peer will no longer have the cid in their list of open requests,
+		// so they would not know this was a response otherwise.
+		//
+		let mut wf = Wf::with_capacity( std::mem::size_of::<M>() * 2 );
+		wf.set_sid( ServiceID::full() );
+		wf.set_cid( cid               );
+
+		serde_cbor::to_writer( &mut wf, &response ).map_err( |_|
+		{
+			ctx.context.as_mut().map( |c| c.push_str( " - Response to remote call" ) );
+
+			PeerErr::Serialize{ ctx }
+
+		})?;
+
+		Ok( Response::CallResponse( CallResponse::new(wf) ))
+
+	}.boxed() )
+}
+
+
+
+/// Call `handler` of a streaming service and turn the items of the stream it returns into frames.
+//
+#[ doc( hidden ) ]
+//
+pub fn process_stream<Wf, M>( msg: &Wf, mut handler: BoxAddress<M, ThesErr>, mut ctx: PeerErrCtx )
+
+	-> Result< ServiceFuture<Wf>, PeerErr >
+
+	where  Wf                                     : WireFormat + Send + 'static             ,
+	       M                                      : Message + DeserializeOwned + Send + 'static,
+	      <M as Message>::Return                  : Stream + Send + 'static                 ,
+	      <<M as Message>::Return as Stream>::Item: Serialize                               ,
+{
+	let message: M = match serde_cbor::from_slice( msg.msg() )
+	{
+		Ok (x) => x,
+		Err(_) => return Err( PeerErr::Deserialize{ ctx } ),
+	};
+
+	let sid = msg.sid();
+	let cid = msg.cid();
+
+	Ok( async move
+	{
+		let stream = match handler.call( message ).await
+		{
+			Ok(x) => x,
+
+			Err(_) =>
+			{
+				ctx.context.as_mut().map( |c| c.push_str( " - Process streaming call for local Actor" ) );
+
+				return Err( PeerErr::HandlerDead{ ctx } );
+			}
+		};
+
+		ctx.context.as_mut().map( |c| c.push_str( " - Item of response stream to remote call" ) );
+
+		// Every item becomes a stream chunk with the cid of the request.
+		//
+		let frames = stream.map( move |item|
+		{
+			let mut wf = Wf::with_capacity( std::mem::size_of_val( &item ) * 2 );
+			wf.set_sid( ServiceID::stream_chunk() );
+			wf.set_cid( cid                       );
+
+			serde_cbor::to_writer( &mut wf, &item ).map_err( |_| PeerErr::Serialize{ ctx: ctx.clone() } )?;
+
+			Ok( wf )
+		});
+
+		Ok( Response::Stream( StreamResponse::new( sid, cid, frames ) ))
+
+	}.boxed() )
+}
@@ -0,0 +1,16 @@
+//! Tools for testing code that uses thespis_remote. Requires the `testing` feature.
+//!
+//! - [`ScriptedRemote`] plays the remote end of a connection from a script. It checks the frames your peer
+//!   sends and answers with canned responses or [`ConnectionError`](crate::ConnectionError)s. Use it to test
+//!   how clients deal with whatever a server might throw at them.
+//! - [`MockServiceMap`] is a service map that records every request and answers with canned responses.
+//! - [`MockHandler`] is an actor that handles a message by responding, responding late, panicking or never
+//!   responding at all. Register it in a `service_map!` to test the error paths of your services.
+//
+    mod handler     ;
+    mod remote      ;
+    mod service_map ;
+
+pub use handler     :: { MockHandler, Behaviour                      } ;
+pub use remote      :: { ScriptedRemote, ScriptErr                   } ;
+pub use service_map :: { MockServiceMap, RecordedRequest, RequestLog } ;
@@ -0,0 +1,92 @@
+use crate::{ import::* };
+
+
+/// What a [`MockHandler`] does with a message.
+//
+#[ derive( Debug, Clone ) ]
+//
+pub enum Behaviour<R>
+{
+	/// Respond right away.
+	//
+	Respond( R ),
+
+	/// Respond after a delay. Use it to trigger timeouts in the caller.
+	//
+	Delay( Duration, R ),
+
+	/// Panic in the handler. The mailbox of the handler stops, so the caller gets an error.
+	//
+	Panic,
+
+	/// Drop the message and never respond. Note that this blocks the mailbox of the handler.
+	//
+	Drop,
+}
+
+
+/// An actor that handles `M` according to a [`Behaviour`] and keeps the messages it receives. Register it
+/// in a `service_map!` to test how your services and their callers deal with slow, broken or silent handlers.
+//
+#[ derive( Actor ) ]
+//
+pub struct MockHandler<M: 'static + Message>
+{
+	behaviour: Behaviour<M::Return>   ,
+	received : Arc< Mutex< Vec<M> > > ,
+}
+
+
+impl<M: Message> MockHandler<M>
+{
+	/// Create a handler that treats every message according to `behaviour`.
+	//
+	pub fn new( behaviour: Behaviour<M::Return> ) -> Self
+	{
+		Self
+		{
+			behaviour                    ,
+			received: Default::default() ,
+		}
+	}
+
+
+	/// The messages received so far. Take it before starting the mailbox.
+	//
+	pub fn received( &self ) -> Arc< Mutex< Vec<M> > >
+	{
+		self.received.clone()
+	}
+}
+
+
+impl<M> Handler<M> for MockHandler<M>
+
+	where M: Message, M::Return: Clone
+
+{
+	#[async_fn] fn handle( &mut self, msg: M ) -> M::Return
+	{
+		self.received.lock().push( msg );
+
+		match self.behaviour.clone()
+		{
+			Behaviour::Respond( resp       ) => resp,
+			Behaviour::Delay  ( wait, resp ) => { Delay::new( wait ).await; resp }
+			Behaviour::Panic                 => panic!( "MockHandler: told to panic." ),
+			Behaviour::Drop                  => futures::future::pending().await,
+		}
+	}
+}
+
+
+impl<M: Message> fmt::Debug for MockHandler<M>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "MockHandler" )
+
+			.field( "received", &self.received.lock().len() )
+			.finish()
+	}
+}
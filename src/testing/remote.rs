@@ -0,0 +1,281 @@
+use crate::{ import::*, * };
+
+
+// How many frames can wait in each direction between the peer and the script.
+//
+const SCRIPT_BUFFER: usize = 32;
+
+
+// Checks the payload of a frame.
+//
+type Check = Box< dyn Fn( &[u8] ) -> Result<(), String> + Send >;
+
+
+enum Step
+{
+	Expect{ sid: ServiceID, call: bool, check: Check } ,
+	Reply ( Vec<u8>         )                          ,
+	Error ( ConnectionError )                          ,
+	Frame ( ThesWF          )                          ,
+	Close                                              ,
+}
+
+
+/// The remote end of a connection that follows a script. Build a peer connected to it with
+/// [`ScriptedRemote::connect`], write the script and then [`run`](ScriptedRemote::run) it while your
+/// code uses the peer.
+///
+/// The script is strict. Every frame the peer sends must be expected by the next step. The first frame that
+/// doesn't match makes `run` return a [`ScriptErr`] that says which step failed and why. When the script is
+/// done, the connection is closed.
+///
+/// Replies go to the last call that was expected, so a call is normally followed by a reply.
+///
+/// ```ignore
+/// let (peer, mut remote) = ScriptedRemote::connect( PeerBuilder::new(), exec )?;
+///
+/// remote
+///
+/// 	.expect_call( <Add as remotes::Service>::sid(), Add(5) )
+/// 	.reply( &() )
+/// 	.expect_call( <Show as remotes::Service>::sid(), Show )
+/// 	.reply_error( ConnectionError::InternalServerError{ sid: None, cid: None } )
+/// ;
+///
+/// let mut addr = remotes::RemoteAddr::new( peer );
+///
+/// let client = async move
+/// {
+/// 	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+/// 	assert!( addr.call( Show ).await.is_err() );
+/// };
+///
+/// let (script, _) = join( remote.run(), client ).await;
+///
+/// script?;
+/// ```
+//
+pub struct ScriptedRemote
+{
+	incoming: mpsc::Receiver<ThesWF> ,
+	outgoing: mpsc::Sender<ThesWF>   ,
+	steps   : Vec<Step>              ,
+	cid     : ConnID                 ,
+}
+
+
+/// A [`ScriptedRemote`] got something else than the script expected.
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct ScriptErr
+{
+	/// The index of the step that failed, starting at 0.
+	//
+	pub step: usize,
+
+	/// What went wrong.
+	//
+	pub problem: String,
+}
+
+
+impl fmt::Display for ScriptErr
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "Scripted remote failed at step {}: {}", self.step, self.problem )
+	}
+}
+
+impl std::error::Error for ScriptErr {}
+
+
+
+impl ScriptedRemote
+{
+	/// Build a peer from `builder` and connect it to a scripted remote in memory.
+	//
+	pub fn connect( builder: PeerBuilder, exec: impl PeerExec + Spawn ) -> Result< (Addr<Peer>, Self), PeerErr >
+	{
+		let (outgoing, peer_in ) = mpsc::channel::<ThesWF>( SCRIPT_BUFFER );
+		let (peer_out, incoming) = mpsc::channel::<ThesWF>( SCRIPT_BUFFER );
+
+		let broken = |_| WireErr::Io{ kind: io::ErrorKind::BrokenPipe };
+
+		let peer = builder.build( peer_in.map( Ok ), peer_out.sink_map_err( broken ), exec )?;
+
+		let remote = Self
+		{
+			incoming                 ,
+			outgoing                 ,
+			steps   : Vec::new()     ,
+			cid     : ConnID::null() ,
+		};
+
+		Ok( (peer, remote) )
+	}
+
+
+	/// Expect a send for `sid` with a payload that deserializes to `msg`.
+	//
+	pub fn expect_send<M>( &mut self, sid: ServiceID, msg: M ) -> &mut Self
+
+		where M: DeserializeOwned + PartialEq + fmt::Debug + Send + 'static
+
+	{
+		self.steps.push( Step::Expect{ sid, call: false, check: check( msg ) } );
+		self
+	}
+
+
+	/// Expect a call for `sid` with a payload that deserializes to `msg`. The replies that follow go to this call.
+	//
+	pub fn expect_call<M>( &mut self, sid: ServiceID, msg: M ) -> &mut Self
+
+		where M: DeserializeOwned + PartialEq + fmt::Debug + Send + 'static
+
+	{
+		self.steps.push( Step::Expect{ sid, call: true, check: check( msg ) } );
+		self
+	}
+
+
+	/// Respond to the last expected call with `resp`.
+	///
+	/// # Panics
+	///
+	/// When `resp` can't be serialized.
+	//
+	pub fn reply<R: Serialize>( &mut self, resp: &R ) -> &mut Self
+	{
+		let payload = serde_cbor::to_vec( resp ).expect( "serialize reply" );
+
+		self.steps.push( Step::Reply( payload ) );
+		self
+	}
+
+
+	/// Respond to the last expected call with an error.
+	//
+	pub fn reply_error( &mut self, err: ConnectionError ) -> &mut Self
+	{
+		self.steps.push( Step::Error( err ) );
+		self
+	}
+
+
+	/// Send a frame as is. Use it to send frames the peer doesn't expect, or that are broken.
+	//
+	pub fn send_frame( &mut self, frame: ThesWF ) -> &mut Self
+	{
+		self.steps.push( Step::Frame( frame ) );
+		self
+	}
+
+
+	/// Close the connection. The peer sees `PeerEvent::ClosedByRemote`. Steps after this are ignored.
+	//
+	pub fn close( &mut self ) -> &mut Self
+	{
+		self.steps.push( Step::Close );
+		self
+	}
+
+
+	/// Play the script. Returns when all steps are done or on the first step that fails.
+	//
+	pub async fn run( &mut self ) -> Result<(), ScriptErr>
+	{
+		let steps = std::mem::take( &mut self.steps );
+
+		for (step, action) in steps.into_iter().enumerate()
+		{
+			let fail = |problem: String| ScriptErr{ step, problem };
+
+			match action
+			{
+				Step::Expect{ sid, call, check } =>
+				{
+					let frame = match self.incoming.next().await
+					{
+						Some( frame ) => frame,
+						None          => return Err( fail( "The connection was closed.".to_string() ) ),
+					};
+
+					if frame.sid() != sid
+					{
+						return Err( fail( format!( "Expected a frame for sid {}, got sid {}.", sid, frame.sid() ) ) );
+					}
+
+					if frame.cid().is_null() == call
+					{
+						let expected = if call { "call" } else { "send" };
+
+						return Err( fail( format!( "Expected a {}, got cid {}.", expected, frame.cid() ) ) );
+					}
+
+					check( frame.msg() ).map_err( fail )?;
+
+					if call { self.cid = frame.cid(); }
+				}
+
+				Step::Reply( payload ) =>
+				{
+					let mut frame = ThesWF::with_capacity( payload.len() );
+
+					frame.set_sid( ServiceID::full() );
+					frame.set_cid( self.cid          );
+
+					io::Write::write_all( &mut frame, &payload ).expect( "write to ThesWF" );
+
+					self.send( frame ).await.map_err( fail )?;
+				}
+
+				Step::Error( err   ) => self.send( Peer::<ThesWF>::prep_error( self.cid, &err ) ).await.map_err( fail )?,
+				Step::Frame( frame ) => self.send( frame                                       ).await.map_err( fail )?,
+				Step::Close          => break,
+			}
+		}
+
+		self.outgoing.close_channel();
+
+		Ok(())
+	}
+
+
+	async fn send( &mut self, frame: ThesWF ) -> Result<(), String>
+	{
+		self.outgoing.send( frame ).await.map_err( |_| "The connection was closed.".to_string() )
+	}
+}
+
+
+impl fmt::Debug for ScriptedRemote
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "ScriptedRemote" )
+
+			.field( "steps", &self.steps.len() )
+			.field( "cid"  , &self.cid         )
+			.finish()
+	}
+}
+
+
+
+// Compare the payload to msg.
+//
+fn check<M>( msg: M ) -> Check
+
+	where M: DeserializeOwned + PartialEq + fmt::Debug + Send + 'static
+
+{
+	Box::new( move |payload: &[u8]| match serde_cbor::from_slice::<M>( payload )
+	{
+		Ok ( got ) if got == msg => Ok(()),
+		Ok ( got )               => Err( format!( "Expected {:?}, got {:?}.", msg, got ) ),
+		Err( _   )               => Err( format!( "Could not deserialize the payload as {}.", std::any::type_name::<M>() ) ),
+	})
+}
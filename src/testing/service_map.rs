@@ -0,0 +1,175 @@
+use crate::{ import::*, *, peer::Response };
+
+
+/// The requests a [`MockServiceMap`] received, in order.
+//
+pub type RequestLog = Arc< Mutex< Vec<RecordedRequest> > >;
+
+
+/// A request received by a [`MockServiceMap`].
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct RecordedRequest
+{
+	/// The service that was requested.
+	//
+	pub sid: ServiceID,
+
+	/// The connection id of the request. Null for sends.
+	//
+	pub cid: ConnID,
+
+	/// The serialized message.
+	//
+	pub payload: Vec<u8>,
+}
+
+
+impl RecordedRequest
+{
+	/// Deserialize the message. None when it's not an `M`.
+	//
+	pub fn decode<M: DeserializeOwned>( &self ) -> Option<M>
+	{
+		serde_cbor::from_slice( &self.payload ).ok()
+	}
+}
+
+
+
+#[ derive( Debug, Clone ) ]
+//
+enum Reply
+{
+	Response( Vec<u8>         ),
+	Error   ( ConnectionError ),
+}
+
+
+/// A [`ServiceMap`] that records all requests it receives and answers calls with canned responses.
+/// Calls for a service without a reply get `ConnectionError::InternalServerError`.
+///
+/// Take the [`log`](MockServiceMap::log) before registering the service map with a peer.
+//
+#[ derive( Debug ) ]
+//
+pub struct MockServiceMap
+{
+	services: Vec<ServiceID>            ,
+	replies : HashMap<ServiceID, Reply> ,
+	log     : RequestLog                ,
+}
+
+
+impl MockServiceMap
+{
+	/// Create a service map that accepts requests for `services`.
+	//
+	pub fn new( services: Vec<ServiceID> ) -> Self
+	{
+		Self
+		{
+			services                    ,
+			replies: HashMap::new()     ,
+			log    : Default::default() ,
+		}
+	}
+
+
+	/// Answer all calls for `sid` with `resp`.
+	///
+	/// # Panics
+	///
+	/// When `resp` can't be serialized.
+	//
+	pub fn reply<R: Serialize>( &mut self, sid: ServiceID, resp: &R ) -> &mut Self
+	{
+		let payload = serde_cbor::to_vec( resp ).expect( "serialize reply" );
+
+		self.replies.insert( sid, Reply::Response( payload ) );
+		self
+	}
+
+
+	/// Answer all calls for `sid` with an error.
+	//
+	pub fn reply_error( &mut self, sid: ServiceID, err: ConnectionError ) -> &mut Self
+	{
+		self.replies.insert( sid, Reply::Error( err ) );
+		self
+	}
+
+
+	/// The requests received so far.
+	//
+	pub fn log( &self ) -> RequestLog
+	{
+		self.log.clone()
+	}
+
+
+	fn record( &self, msg: &ThesWF )
+	{
+		self.log.lock().push( RecordedRequest{ sid: msg.sid(), cid: msg.cid(), payload: msg.msg().to_vec() } );
+	}
+}
+
+
+
+impl ServiceMap for MockServiceMap
+{
+	fn send_service( &self, msg: ThesWF, _ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<ThesWF>, PeerErr> > + Send >>, PeerErr >
+
+	{
+		self.record( &msg );
+
+		Ok( async { Ok( Response::Nothing ) }.boxed() )
+	}
+
+
+	fn call_service( &self, msg: ThesWF, _ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<ThesWF>, PeerErr> > + Send >>, PeerErr >
+
+	{
+		self.record( &msg );
+
+		let sid = msg.sid();
+		let cid = msg.cid();
+
+		let frame = match self.replies.get( &sid )
+		{
+			Some( Reply::Response( payload ) ) =>
+			{
+				let mut frame = ThesWF::with_capacity( payload.len() );
+
+				frame.set_sid( ServiceID::full() );
+				frame.set_cid( cid               );
+
+				io::Write::write_all( &mut frame, payload ).expect( "write to ThesWF" );
+
+				frame
+			}
+
+			Some( Reply::Error( err ) ) => Peer::<ThesWF>::prep_error( cid, err ),
+
+			None =>
+			{
+				let err = ConnectionError::InternalServerError{ sid: Some( sid ), cid: Some( cid ) };
+
+				Peer::<ThesWF>::prep_error( cid, &err )
+			}
+		};
+
+		Ok( async move { Ok( Response::CallResponse( CallResponse::new( frame ) ) ) }.boxed() )
+	}
+
+
+	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		Box::new( self.services.iter() )
+	}
+}
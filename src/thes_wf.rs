@@ -100,7 +100,7 @@ impl ThesWF
 {
 	/// Get direct access to the buffer.
 	//
-	fn as_buf( &self ) -> &[u8]
+	pub(crate) fn as_buf( &self ) -> &[u8]
 	{
 		self.data.get_ref()
 	}
@@ -252,6 +252,18 @@ impl TryFrom< Vec<u8> > for ThesWF
 
 
 
+impl From<ThesWF> for Vec<u8>
+{
+	/// The bytes of the frame, header included, as they go out on the wire.
+	//
+	fn from( wf: ThesWF ) -> Vec<u8>
+	{
+		wf.data.into_inner()
+	}
+}
+
+
+
 #[ cfg(test) ]
 //
 mod tests
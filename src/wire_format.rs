@@ -14,10 +14,9 @@ pub use
 	service_id :: * ,
 	conn_id    :: * ,
 	wire_err   :: * ,
+	wire_type  :: * ,
 };
 
-pub(crate) use wire_type::WireType;
-
 /// Trait holding the required functionality to function as a WireFormat for thespis_remote.
 //
 #[ allow(clippy::len_without_is_empty) ]
@@ -65,8 +64,15 @@ pub trait WireFormat : Message< Return = Result<(), PeerErr> > + Default + Clone
 	{
 		match self.sid()
 		{
-			x if x.is_null() => WireType::ConnectionError ,
-			x if x.is_full() => WireType::CallResponse    ,
+			x if x.is_null()         => WireType::ConnectionError ,
+			x if x.is_full()         => WireType::CallResponse    ,
+			x if x.is_stream_chunk() => WireType::StreamChunk     ,
+			x if x.is_stream_end()   => WireType::StreamEnd       ,
+			x if x.is_stream_ctrl()  => WireType::StreamControl   ,
+			x if x.is_channel_data() => WireType::ChannelData     ,
+			x if x.is_channel_ctrl() => WireType::ChannelControl  ,
+			x if x.is_credit()       => WireType::Credit          ,
+			x if x.is_trace()        => WireType::TraceContext    ,
 
 			_ =>
 			{
@@ -5,11 +5,30 @@ use
 };
 
 
+// Reserved values, counting down from full.
+//
+const SID_STREAM_CHUNK: u64 = u64::MAX - 1;
+const SID_STREAM_END  : u64 = u64::MAX - 2;
+const SID_CHANNEL_DATA: u64 = u64::MAX - 3;
+const SID_CHANNEL_CTRL: u64 = u64::MAX - 4;
+const SID_REFLECTION  : u64 = u64::MAX - 5;
+const SID_CREDIT      : u64 = u64::MAX - 6;
+const SID_TRACE       : u64 = u64::MAX - 7;
+const SID_STREAM_CTRL : u64 = u64::MAX - 8;
+
+
 static SERVICES: SyncLazy<Mutex< HashMap<ServiceID, &'static str> >> = SyncLazy::new( ||
 
 	Mutex::new( HashMap::new() )
 );
 
+// The message type and return type of each service, for reflection.
+//
+static TYPES: SyncLazy<Mutex< HashMap<ServiceID, (&'static str, &'static str)> >> = SyncLazy::new( ||
+
+	Mutex::new( HashMap::new() )
+);
+
 /// A unique identifier for a service that is exposed to other processes. This will allow
 /// identifying the type to which the payload needs to be deserialized and the actor to which
 /// this message is to be delivered.
@@ -18,8 +37,9 @@ static SERVICES: SyncLazy<Mutex< HashMap<ServiceID, &'static str> >> = SyncLazy:
 /// of collision, but we use xxhash which for the moment only supports 64 bit, so we hash the
 /// namespace and typename separately both to 64 bits.
 ///
-/// 2 values are reserved, all zero's and all one's are used as special values by Peer to
-/// detect error conditions. If ever your namespace + typename would hash to one of these,
+/// Some values are reserved. All zero's and all one's are used as special values by Peer to
+/// detect error conditions and responses. The values just below all one's are used to mark
+/// frames of streaming responses, channels and flow control and for the built in reflection service. If ever your namespace + typename would hash to one of these,
 /// please change them.
 //
 #[ derive( Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize ) ]
@@ -39,9 +59,11 @@ impl ServiceID
 	{
 		let inner = UniqueID::from_seed( data );
 
-		debug_assert!( !inner.is_null(), "Hashing your namespace + typename generated a hash that is all zero's, which is a reserved value. Please slightly change either one." );
+		let sid = Self{ inner };
+
+		debug_assert!( !sid.is_reserved(), "Hashing your namespace + typename generated a hash that is a reserved value. Please slightly change either one." );
 
-		Self{ inner }
+		sid
 	}
 
 
@@ -78,6 +100,155 @@ impl ServiceID
 	}
 
 
+	/// Marks a frame as one item of a streaming response. Value reserved by thespis.
+	//
+	pub fn stream_chunk() -> Self
+	{
+		Self{ inner: UniqueID::from( SID_STREAM_CHUNK ) }
+	}
+
+
+	/// Predicate for the stream chunk marker.
+	//
+	pub fn is_stream_chunk( &self ) -> bool
+	{
+		*self == Self::stream_chunk()
+	}
+
+
+	/// Marks a frame as the end of a streaming response. Value reserved by thespis.
+	//
+	pub fn stream_end() -> Self
+	{
+		Self{ inner: UniqueID::from( SID_STREAM_END ) }
+	}
+
+
+	/// Predicate for the end of stream marker.
+	//
+	pub fn is_stream_end( &self ) -> bool
+	{
+		*self == Self::stream_end()
+	}
+
+
+	/// Marks a frame as a control message for a streaming response (flow control window, cancel).
+	/// Value reserved by thespis.
+	//
+	pub fn stream_ctrl() -> Self
+	{
+		Self{ inner: UniqueID::from( SID_STREAM_CTRL ) }
+	}
+
+
+	/// Predicate for the stream control marker.
+	//
+	pub fn is_stream_ctrl( &self ) -> bool
+	{
+		*self == Self::stream_ctrl()
+	}
+
+
+	/// Marks a frame as a message on a channel. Value reserved by thespis.
+	//
+	pub fn channel_data() -> Self
+	{
+		Self{ inner: UniqueID::from( SID_CHANNEL_DATA ) }
+	}
+
+
+	/// Predicate for the channel data marker.
+	//
+	pub fn is_channel_data( &self ) -> bool
+	{
+		*self == Self::channel_data()
+	}
+
+
+	/// Marks a frame as a control message for a channel (open, close, flow control).
+	/// Value reserved by thespis.
+	//
+	pub fn channel_ctrl() -> Self
+	{
+		Self{ inner: UniqueID::from( SID_CHANNEL_CTRL ) }
+	}
+
+
+	/// Predicate for the channel control marker.
+	//
+	pub fn is_channel_ctrl( &self ) -> bool
+	{
+		*self == Self::channel_ctrl()
+	}
+
+
+	/// The built in reflection service of Peer. Calling it returns the services a peer exposes.
+	/// Value reserved by thespis.
+	//
+	pub fn reflection() -> Self
+	{
+		Self{ inner: UniqueID::from( SID_REFLECTION ) }
+	}
+
+
+	/// Predicate for the reflection service.
+	//
+	pub fn is_reflection( &self ) -> bool
+	{
+		*self == Self::reflection()
+	}
+
+
+	/// Marks a frame that gives the remote credit for flow control. Value reserved by thespis.
+	//
+	pub fn credit() -> Self
+	{
+		Self{ inner: UniqueID::from( SID_CREDIT ) }
+	}
+
+
+	/// Predicate for the credit marker.
+	//
+	pub fn is_credit( &self ) -> bool
+	{
+		*self == Self::credit()
+	}
+
+
+	/// Marks a frame that carries the trace context for the request that follows it. Value reserved by thespis.
+	//
+	pub fn trace() -> Self
+	{
+		Self{ inner: UniqueID::from( SID_TRACE ) }
+	}
+
+
+	/// Predicate for the trace context marker.
+	//
+	pub fn is_trace( &self ) -> bool
+	{
+		*self == Self::trace()
+	}
+
+
+	/// Whether this is one of the values reserved by thespis. These cannot be used
+	/// to identify user services.
+	//
+	pub fn is_reserved( &self ) -> bool
+	{
+		   self.is_null()
+		|| self.is_full()
+		|| self.is_stream_chunk()
+		|| self.is_stream_end()
+		|| self.is_stream_ctrl()
+		|| self.is_channel_data()
+		|| self.is_channel_ctrl()
+		|| self.is_reflection()
+		|| self.is_credit()
+		|| self.is_trace()
+	}
+
+
 	/// Register the typename a ServiceID refers to so it can be used later for log output.
 	/// the `service_map!` macro does this automatically for you.
 	//
@@ -97,6 +268,28 @@ impl ServiceID
 
 		s.get( &sid ).copied()
 	}
+
+
+	/// Register the type names of the message and the return type of a service. These are
+	/// given to remotes by the reflection service if it's set to [`Reflection::Full`].
+	/// The `service_map!` macro does this automatically for you.
+	//
+	pub fn register_types( sid: ServiceID, message: &'static str, returns: &'static str )
+	{
+		let mut s = TYPES.lock();
+
+		s.entry( sid ).or_insert( (message, returns) );
+	}
+
+
+	/// Look up the type names of the message and the return type for a ServiceID.
+	//
+	pub fn service_types( sid: ServiceID ) -> Option<(&'static str, &'static str)>
+	{
+		let s = TYPES.lock();
+
+		s.get( &sid ).copied()
+	}
 }
 
 
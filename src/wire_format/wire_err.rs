@@ -31,7 +31,7 @@ pub enum WireErr
 	//
 	Deserialize
 	{
-		/// The contex in which the error happened.
+		/// The context in which the error happened.
 		//
 		context: String,
 	},
@@ -48,6 +48,17 @@ pub enum WireErr
 		//
 		kind: std::io::ErrorKind
 	},
+
+
+	/// The remote closed the connection and the transport told us why, like the close frame of a WebSocket.
+	/// A transport can return this from it's stream of frames. The peer closes the connection with this reason.
+	//
+	Closed
+	{
+		/// Why the remote closed the connection.
+		//
+		reason: String,
+	},
 }
 
 
@@ -72,6 +83,10 @@ impl fmt::Display for WireErr
 			WireErr::Io{ kind } =>
 
 				write!( f, "Io: {:?}", kind ),
+
+			WireErr::Closed{ reason } =>
+
+				write!( f, "Connection closed by remote: {}", reason ),
 		}
 	}
 }
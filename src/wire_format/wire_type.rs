@@ -1,6 +1,6 @@
 /// Type of message.
 //
-#[ derive(Debug) ]
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
 //
 pub enum WireType
 {
@@ -8,4 +8,32 @@ pub enum WireType
 	IncomingSend,
 	IncomingCall,
 	CallResponse,
+
+	/// One item of a streaming response.
+	//
+	StreamChunk,
+
+	/// Marks the end of a streaming response.
+	//
+	StreamEnd,
+
+	/// Flow control for a streaming response, or cancelling it.
+	//
+	StreamControl,
+
+	/// A message on a channel.
+	//
+	ChannelData,
+
+	/// Opening, closing or flow control for a channel.
+	//
+	ChannelControl,
+
+	/// Credit for flow control.
+	//
+	Credit,
+
+	/// The trace context of the request that follows.
+	//
+	TraceContext,
 }
@@ -0,0 +1,180 @@
+// Tests:
+//
+// ✔ a fallible service returns Ok( Ok(t) ) on success.
+// ✔ an error of a fallible service gives PeerErr::Application, which can be downcast to the error type.
+// ✔ a service that isn't fallible returns the error as part of the return value.
+// ✔ DynRemoteAddr gets PeerErr::Application as well.
+//
+mod common;
+
+use
+{
+	common                        :: { *, import::{ *, assert_eq } } ,
+	serde                         :: { Serialize, Deserialize      } ,
+	thespis_remote::external_deps :: { serde_cbor                  } ,
+};
+
+
+#[ derive( Serialize, Deserialize, Debug, PartialEq ) ]
+//
+pub enum BankErr
+{
+	InsufficientFunds{ balance: i64 },
+}
+
+
+#[ derive( Serialize, Deserialize, Debug ) ] pub struct Withdraw   ( pub i64 );
+#[ derive( Serialize, Deserialize, Debug ) ] pub struct TryWithdraw( pub i64 );
+
+impl Message for Withdraw    { type Return = Result<i64, BankErr>; }
+impl Message for TryWithdraw { type Return = Result<i64, BankErr>; }
+
+
+#[ derive( Actor ) ] struct Bank( i64 );
+
+
+impl Bank
+{
+	fn withdraw( &mut self, amount: i64 ) -> Result<i64, BankErr>
+	{
+		if amount > self.0
+		{
+			return Err( BankErr::InsufficientFunds{ balance: self.0 } );
+		}
+
+		self.0 -= amount;
+
+		Ok( self.0 )
+	}
+}
+
+
+impl Handler<Withdraw> for Bank
+{
+	#[async_fn] fn handle( &mut self, msg: Withdraw ) -> Result<i64, BankErr>
+	{
+		self.withdraw( msg.0 )
+	}
+}
+
+
+impl Handler<TryWithdraw> for Bank
+{
+	#[async_fn] fn handle( &mut self, msg: TryWithdraw ) -> Result<i64, BankErr>
+	{
+		self.withdraw( msg.0 )
+	}
+}
+
+
+service_map!
+(
+	namespace  : bank                                ;
+	wire_format: ThesWF                              ;
+	services   : #[ fallible ] Withdraw, TryWithdraw ;
+);
+
+
+fn connect() -> (Addr<Peer>, bank::RemoteAddr)
+{
+	let bank = Addr::builder().start( Bank(100), &exec() ).expect( "spawn actor mailbox" );
+
+	let mut sm = bank::Services::new();
+
+	sm.register_handler::<Withdraw   >( bank.clone_box() );
+	sm.register_handler::<TryWithdraw>( bank.clone_box() );
+
+	let server: PeerBuilder = PeerBuilder::new().name( "server" ).service_map( Arc::new( sm ) );
+	let client: PeerBuilder = PeerBuilder::new().name( "client" );
+
+	let (_server, client) = Peer::pair( server, client, 8, 8, exec() ).expect( "build pair" );
+
+	(client.clone(), bank::RemoteAddr::new( client ))
+}
+
+
+async fn close( mut peer: Addr<Peer> )
+{
+	peer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// A fallible service returns Ok( Ok(t) ) on success.
+//
+#[async_std::test]
+//
+async fn app_error_ok()
+{
+	let (peer, mut addr) = connect();
+
+	assert_eq!( Ok( Ok(60) ), addr.call( Withdraw(40) ).await );
+
+	close( peer ).await;
+}
+
+
+
+// An error of a fallible service gives PeerErr::Application, which can be downcast to the error type.
+//
+#[async_std::test]
+//
+async fn app_error_err()
+{
+	let (peer, mut addr) = connect();
+
+	let err = match addr.call( Withdraw(150) ).await
+	{
+		Err( PeerErr::Application{ err, .. } ) => err,
+		other                                  => panic!( "expected PeerErr::Application, got: {:?}", other ),
+	};
+
+	assert_eq!( Some( BankErr::InsufficientFunds{ balance: 100 } ), err.downcast::<BankErr>() );
+	assert_eq!( None, err.downcast::<String>() );
+
+	// The connection is still usable.
+	//
+	assert_eq!( Ok( Ok(0) ), addr.call( Withdraw(100) ).await );
+
+	close( peer ).await;
+}
+
+
+
+// A service that isn't fallible returns the error as part of the return value.
+//
+#[async_std::test]
+//
+async fn app_error_not_fallible()
+{
+	let (peer, mut addr) = connect();
+
+	assert_eq!( Ok( Err( BankErr::InsufficientFunds{ balance: 100 } ) ), addr.call( TryWithdraw(150) ).await );
+
+	close( peer ).await;
+}
+
+
+
+// DynRemoteAddr gets PeerErr::Application as well.
+//
+#[async_std::test]
+//
+async fn app_error_dyn()
+{
+	let (peer, _) = connect();
+
+	let mut addr = DynRemoteAddr::new( peer.clone() );
+	let sid      = <Withdraw as bank::Service>::sid();
+	let msg      = serde_cbor::value::to_value( Withdraw(150) ).expect( "serialize" );
+
+	let err = match addr.call( sid, &msg ).await
+	{
+		Err( PeerErr::Application{ err, .. } ) => err,
+		other                                  => panic!( "expected PeerErr::Application, got: {:?}", other ),
+	};
+
+	assert_eq!( Some( BankErr::InsufficientFunds{ balance: 100 } ), err.downcast() );
+
+	close( peer ).await;
+}
@@ -6,12 +6,13 @@
 // ✔ back pressure does not apply to sends
 // ✔ back pressure does not apply to relays
 // ✔ do not deadlock
+// ✔ sends with their own slots release them, so all sends are processed
 //
 mod common;
 
 use
 {
-	common        :: { *, import::{ * }                                        } ,
+	common        :: { *, import::{ *, assert_eq }                             } ,
 	std           :: { time::Duration, sync::atomic::{ AtomicUsize, Ordering } } ,
 	futures_timer :: { Delay                                                   } ,
 	serde         :: { Serialize, Deserialize                                  } ,
@@ -96,9 +97,9 @@ async fn backpressure_basic()
 	{
 		// Create mailbox for our handler
 		//
-		let slow  = Addr::builder().start( Slow , &AsyncStd ).expect( "spawn actor mailbox" );
-		let slow2 = Addr::builder().start( Slow , &AsyncStd ).expect( "spawn actor mailbox" );
-		let after = Addr::builder().start( After, &AsyncStd ).expect( "spawn actor mailbox" );
+		let slow  = Addr::builder().start( Slow , &exec() ).expect( "spawn actor mailbox" );
+		let slow2 = Addr::builder().start( Slow , &exec() ).expect( "spawn actor mailbox" );
+		let after = Addr::builder().start( After, &exec() ).expect( "spawn actor mailbox" );
 
 		// Create a service map
 		//
@@ -121,7 +122,7 @@ async fn backpressure_basic()
 			peer_addr,
 			server,
 			1024,
-			AsyncStd,
+			exec(),
 			Some(Arc::new( BackPressure::new(2) )),
 			None
 
@@ -134,7 +135,7 @@ async fn backpressure_basic()
 
 		let (fut, handle) = peer_mb.start(peer).remote_handle();
 
-		AsyncStd.spawn( fut ).expect( "start mailbox of Peer" );
+		exec().spawn( fut ).expect( "start mailbox of Peer" );
 		handle.await;
 
 		trace!( "end of peera" );
@@ -143,7 +144,7 @@ async fn backpressure_basic()
 
 	let peerb = async move
 	{
-		let (mut peera, _)  = peer_connect( client, AsyncStd, "peer_b_to_peera" ).await;
+		let (mut peera, _)  = peer_connect( client, exec(), "peer_b_to_peera" ).await;
 
 		// Call the service and receive the response
 		//
@@ -159,11 +160,11 @@ async fn backpressure_basic()
 		let (add2_fut, add2_handle) = add2.remote_handle();
 		let (show_fut, show_handle) = show.remote_handle();
 
-		AsyncStd.spawn( add1_fut ).expect( "spawn add1"  );
-		AsyncStd.spawn( add2_fut ).expect( "spawn add2"  );
+		exec().spawn( add1_fut ).expect( "spawn add1"  );
+		exec().spawn( add2_fut ).expect( "spawn add2"  );
 
 		Delay::new( Duration::from_millis(10) ).await;
-		AsyncStd.spawn( show_fut ).expect( "spawn check" );
+		exec().spawn( show_fut ).expect( "spawn check" );
 
 		add1_handle.await;
 		add2_handle.await;
@@ -182,3 +183,61 @@ async fn backpressure_basic()
 	//
 	join( peera, peerb ).await;
 }
+
+
+
+// With a single send slot, every send must release it once delivered to the handler, otherwise
+// the second send would never be processed. The call uses it's own slots.
+//
+#[async_std::test]
+//
+async fn backpressure_sends()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let peera = async move
+	{
+		let(peer_addr, peer_mb) = Addr::builder().name( "server".into() ).build();
+
+		let bp = BackPressure::new( 1 ).with_send_slots( 1 );
+
+		let mut peer = Peer::from_async_read
+		(
+			peer_addr,
+			server,
+			1024,
+			exec(),
+			Some(Arc::new( bp )),
+			None
+
+		).expect( "spawn peer" );
+
+		peer.register_services( Arc::new( add_show_sum() ) );
+
+		let (fut, handle) = peer_mb.start(peer).remote_handle();
+
+		exec().spawn( fut ).expect( "start mailbox of Peer" );
+		handle.await;
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, _)  = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+		let mut addr = remotes::RemoteAddr::new( peera.clone() );
+
+		for _ in 0..10
+		{
+			addr.send( Add(1) ).await.expect( "send Add" );
+		}
+
+		// Sends are delivered in order to the same mailbox, so Show sees all of them.
+		//
+		assert_eq!( Ok(10), addr.call( Show ).await );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
@@ -25,7 +25,7 @@ async fn basic_remote()
 	{
 		// get a framed connection
 		//
-		let (_, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "peera" ).await;
+		let (_, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "peera" ).await;
 
 		handle.await;
 
@@ -35,7 +35,7 @@ async fn basic_remote()
 
 	let peerb = async move
 	{
-		let (mut peera, _)  = peer_connect( client, AsyncStd, "peer_b_to_peera" ).await;
+		let (mut peera, _)  = peer_connect( client, exec(), "peer_b_to_peera" ).await;
 
 		// Call the service and receive the response
 		//
@@ -108,7 +108,7 @@ async fn parallel()
 
 		// create peer with stream/sink
 		//
-		let mut peer = Peer::from_async_read( peer_addr.clone(), server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+		let mut peer = Peer::from_async_read( peer_addr.clone(), server, 1024, exec(), None, None ).expect( "spawn peer" );
 
 		// Create recipients
 		//
@@ -116,7 +116,7 @@ async fn parallel()
 
 		// Create mailbox for our handler
 		//
-		let addr_handler = Addr::builder().start( Parallel{ sum: Box::new( addr ) }, &AsyncStd ).expect( "spawn actor mailbox" );
+		let addr_handler = Addr::builder().start( Parallel{ sum: Box::new( addr ) }, &exec() ).expect( "spawn actor mailbox" );
 
 		// register Sum with peer as handler for Add and Show
 		//
@@ -125,7 +125,7 @@ async fn parallel()
 
 		peer.register_services( Arc::new( sm ) );
 
-		AsyncStd.spawn( peer_mb.start(peer).map(|_|()) ).expect( "Failed to start mailbox of Peer" );
+		exec().spawn( peer_mb.start(peer).map(|_|()) ).expect( "Failed to start mailbox of Peer" );
 	};
 
 
@@ -137,11 +137,11 @@ async fn parallel()
 
 		// create peer with stream/sink
 		//
-		let mut peer = Peer::from_async_read( peer_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+		let mut peer = Peer::from_async_read( peer_addr.clone(), client, 1024, exec(), None, None ).expect( "spawn peer" );
 
 		// Create mailbox for our handler
 		//
-		let addr_handler = Addr::builder().start( Sum(19), &AsyncStd ).expect( "spawn actor mailbox" );
+		let addr_handler = Addr::builder().start( Sum(19), &exec() ).expect( "spawn actor mailbox" );
 
 
 		// register Sum with peer as handler for Add and Show
@@ -152,7 +152,7 @@ async fn parallel()
 		peer.register_services( Arc::new( sm ) );
 
 
-		AsyncStd.spawn( peer_mb.start(peer).map(|_|()) ).expect( "Failed to start mailbox of Peer" );
+		exec().spawn( peer_mb.start(peer).map(|_|()) ).expect( "Failed to start mailbox of Peer" );
 
 		// Create recipients
 		//
@@ -191,7 +191,7 @@ async fn call_after_close_connection()
 
 	let nodeb = async move
 	{
-		let (peera, mut peera_evts) = peer_connect( client, AsyncStd, "nodeb_to_node_a" ).await;
+		let (peera, mut peera_evts) = peer_connect( client, exec(), "nodeb_to_node_a" ).await;
 
 		// Call the service and receive the response
 		//
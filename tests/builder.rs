@@ -0,0 +1,111 @@
+// Tests:
+//
+// ✔ peers created by the builder are started and process calls.
+// ✔ a partial config deserializes with defaults for the missing settings.
+// ✔ the idle timeout closes a connection on which nothing happens.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+
+// Peers created by the builder are started and process calls.
+//
+#[async_std::test]
+//
+async fn builder_calls()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let _server = PeerBuilder::new()
+
+		.name       ( "server"                   )
+		.max_size   ( 1024                       )
+		.service_map( Arc::new( add_show_sum() ) )
+		.build_async_read( server, exec() )
+		.expect( "build server peer" )
+	;
+
+	let mut client = PeerBuilder::new()
+
+		.name    ( "client"                  )
+		.max_size( 1024                      )
+		.timeout ( Duration::from_secs( 10 ) )
+		.build_async_read( client, exec() )
+		.expect( "build client peer" )
+	;
+
+	assert_eq!( Some( "client".into() ), client.name() );
+
+	let mut addr = remotes::RemoteAddr::new( client.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// A partial config deserializes with defaults for the missing settings.
+//
+#[test]
+//
+fn builder_config()
+{
+	let mut file = std::collections::BTreeMap::new();
+
+	file.insert( "timeout"     , 500 );
+	file.insert( "backpressure", 4   );
+
+	let bytes  = external_deps::serde_cbor::to_vec( &file ).expect( "serialize" );
+	let config = external_deps::serde_cbor::from_slice::<PeerConfig>( &bytes ).expect( "deserialize" );
+
+	assert_eq!( Duration::from_millis( 500 ), config.timeout      );
+	assert_eq!( Some( 4 )                   , config.backpressure );
+	assert_eq!( None                        , config.name         );
+	assert_eq!( None                        , config.idle_timeout );
+
+	assert_eq!( PeerConfig::default().max_size, config.max_size );
+
+	// It round trips.
+	//
+	let bytes = external_deps::serde_cbor::to_vec( &config ).expect( "serialize" );
+
+	assert_eq!( config, external_deps::serde_cbor::from_slice( &bytes ).expect( "deserialize" ) );
+}
+
+
+
+// The idle timeout closes a connection on which nothing happens.
+//
+#[async_std::test]
+//
+async fn builder_idle()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let mut builder: PeerBuilder = PeerBuilder::new()
+
+		.name        ( "server"                    )
+		.max_size    ( 1024                        )
+		.idle_timeout( Duration::from_millis( 50 ) )
+		.service_map ( Arc::new( add_show_sum() )  )
+	;
+
+	let mut server_evts = builder.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+
+	let _server = builder.build_async_read( server, exec() ).expect( "build server peer" );
+
+	let (client, mut client_evts) = peer_connect( client, exec(), "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client.clone() );
+
+	// Activity keeps it open.
+	//
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+
+	assert_eq!( Some( PeerEvent::Closed         ), server_evts.next().await );
+	assert_eq!( Some( PeerEvent::ClosedByRemote ), client_evts.next().await );
+}
@@ -0,0 +1,207 @@
+// Tests:
+//
+// ✔ calls beyond the limit are refused with the FailFast policy.
+// ✔ calls beyond the limit wait for a slot with the Wait policy, and show up in the stats.
+// ✔ calls are refused when too many are waiting for a slot.
+// ✔ the timeout of a waiting call only starts when it goes out.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures_timer :: { Delay                       } ,
+	std           :: { num::NonZeroUsize           } ,
+};
+
+
+#[ derive(Actor) ] struct Slow;
+
+impl Handler<Add> for Slow
+{
+	fn handle( &mut self, _msg: Add ) -> Return<'_, ()> { async move
+	{
+		Delay::new( Duration::from_millis(100) ).await;
+
+	}.boxed() }
+}
+
+
+
+service_map!
+(
+	namespace  : clsm   ;
+	wire_format: ThesWF ;
+	services   : Add    ;
+);
+
+
+
+// Allow one outstanding call.
+//
+fn one_slot( policy: CallLimitPolicy ) -> CallLimit
+{
+	CallLimit::new( NonZeroUsize::new(1).unwrap(), policy )
+}
+
+
+// Connect a client with the given call limit and timeout to a server with a slow Add handler.
+//
+fn connect( limit: CallLimit, timeout: Duration ) -> (Addr<Peer>, Addr<Peer>)
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let slow   = Addr::builder().start( Slow, &exec() ).expect( "spawn actor mailbox" );
+	let mut sm = clsm::Services::new();
+
+	sm.register_handler::<Add>( slow.clone_box() );
+
+	let server = PeerBuilder::new()
+
+		.name       ( "server"       )
+		.service_map( Arc::new( sm ) )
+		.build_async_read( server, exec() )
+		.expect( "build server peer" )
+	;
+
+	let client = PeerBuilder::new()
+
+		.name      ( "client" )
+		.call_limit( limit    )
+		.timeout   ( timeout  )
+		.build_async_read( client, exec() )
+		.expect( "build client peer" )
+	;
+
+	(server, client)
+}
+
+
+
+// Calls beyond the limit are refused with the FailFast policy.
+//
+#[async_std::test]
+//
+async fn call_limit_fail_fast()
+{
+	let (_server, mut client) = connect( one_slot( CallLimitPolicy::FailFast ), Duration::from_secs(10) );
+
+	let mut addr  = clsm::RemoteAddr::new( client.clone() );
+	let mut addr2 = clsm::RemoteAddr::new( client.clone() );
+
+	let (first, second) = join( addr.call( Add(1) ), addr2.call( Add(2) ) ).await;
+
+	assert_eq!( Ok(()), first );
+	assert_matches!( second, Err( PeerErr::TooManyCalls{..} ) );
+
+	// The slot is free again.
+	//
+	assert_eq!( Ok(()), addr.call( Add(3) ).await );
+
+	let stats = client.call( GetStats ).await.expect( "get stats" );
+
+	assert_eq!( Some( &1 ), stats.errors.get( "TooManyCalls" ) );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Calls beyond the limit wait for a slot with the Wait policy, and show up in the stats.
+//
+#[async_std::test]
+//
+async fn call_limit_wait()
+{
+	let (_server, mut client) = connect( one_slot( CallLimitPolicy::Wait ), Duration::from_secs(10) );
+
+	let mut addr  = clsm::RemoteAddr::new( client.clone() );
+	let mut addr2 = clsm::RemoteAddr::new( client.clone() );
+	let mut addr3 = clsm::RemoteAddr::new( client.clone() );
+	let mut peer  = client.clone();
+
+	let stats = async move
+	{
+		Delay::new( Duration::from_millis(50) ).await;
+
+		peer.call( GetStats ).await.expect( "get stats" )
+	};
+
+	let (first, second, (third, stats)) = join3
+	(
+		addr .call( Add(1) ),
+		addr2.call( Add(2) ),
+		join( addr3.call( Add(3) ), stats ),
+	)
+	.await;
+
+	assert_eq!( Ok(()), first  );
+	assert_eq!( Ok(()), second );
+	assert_eq!( Ok(()), third  );
+
+	assert_eq!( 1        , stats.in_flight     );
+	assert_eq!( 2        , stats.waiting_calls );
+	assert_eq!( Some( 1 ), stats.max_calls     );
+
+	let stats = client.call( GetStats ).await.expect( "get stats" );
+
+	assert_eq!( 0, stats.in_flight     );
+	assert_eq!( 0, stats.waiting_calls );
+	assert_eq!( 3, stats.calls_out     );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Calls are refused when too many are waiting for a slot.
+//
+#[async_std::test]
+//
+async fn call_limit_max_waiting()
+{
+	let limit = one_slot( CallLimitPolicy::Wait ).with_max_waiting( 1 );
+
+	let (_server, mut client) = connect( limit, Duration::from_secs(10) );
+
+	let mut addr  = clsm::RemoteAddr::new( client.clone() );
+	let mut addr2 = clsm::RemoteAddr::new( client.clone() );
+	let mut addr3 = clsm::RemoteAddr::new( client.clone() );
+
+	let (first, second, third) = join3
+	(
+		addr .call( Add(1) ),
+		addr2.call( Add(2) ),
+		addr3.call( Add(3) ),
+	)
+	.await;
+
+	assert_eq!( Ok(()), first  );
+	assert_eq!( Ok(()), second );
+	assert_matches!( third, Err( PeerErr::TooManyCalls{..} ) );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// The timeout of a waiting call only starts when it goes out.
+//
+#[async_std::test]
+//
+async fn call_limit_timeout()
+{
+	// Each Add takes 100ms, so the second call only gets it's response after 200ms.
+	//
+	let (_server, mut client) = connect( one_slot( CallLimitPolicy::Wait ), Duration::from_millis(150) );
+
+	let mut addr  = clsm::RemoteAddr::new( client.clone() );
+	let mut addr2 = clsm::RemoteAddr::new( client.clone() );
+
+	let (first, second) = join( addr.call( Add(1) ), addr2.call( Add(2) ) ).await;
+
+	assert_eq!( Ok(()), first  );
+	assert_eq!( Ok(()), second );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,167 @@
+// Tests:
+//
+// ✔ a recorder writes every frame with it's direction and peer id, and the reader gives them back in order.
+// ✔ replaying a capture into a service map gives the same responses.
+// ✔ replaying a capture into a peer makes it send the same frames.
+//
+mod common;
+
+use
+{
+	common                        :: { *, import::{ *, assert_eq } } ,
+	futures                       :: { AsyncReadExt                } ,
+	std                           :: { io, sync::Mutex             } ,
+	thespis_remote::external_deps :: { serde_cbor                  } ,
+};
+
+
+// A capture in memory.
+//
+#[ derive( Clone, Default ) ]
+//
+struct Buffer( Arc<Mutex< Vec<u8> >> );
+
+impl io::Write for Buffer
+{
+	fn write( &mut self, buf: &[u8] ) -> io::Result<usize>
+	{
+		self.0.lock().unwrap().write( buf )
+	}
+
+	fn flush( &mut self ) -> io::Result<()>
+	{
+		Ok(())
+	}
+}
+
+
+impl Buffer
+{
+	fn bytes( &self ) -> Vec<u8>
+	{
+		self.0.lock().unwrap().clone()
+	}
+}
+
+
+
+// Record the server side of a client calling Add(5) and Show. Returns the capture and the ids of
+// the server and the client.
+//
+async fn record() -> (Vec<u8>, usize, usize)
+{
+	let buffer   = Buffer::default();
+	let recorder = Recorder::new( buffer.clone() ).expect( "write header" );
+
+	let (server, client) = Endpoint::pair( 64, 64 );
+	let (reader, writer) = server.split();
+
+	let server = PeerBuilder::new()
+
+		.service_map( Arc::new( add_show_sum() ) )
+
+		.build_recorded
+		(
+			thes_wf::Decoder::new( reader, 1024 ) ,
+			thes_wf::Encoder::new( writer, 1024 ) ,
+			&recorder                             ,
+			exec()                                ,
+		)
+
+		.expect( "build server peer" )
+	;
+
+	let mut client = PeerBuilder::new().build_async_read( client, exec() ).expect( "build client peer" );
+	let mut addr   = remotes::RemoteAddr::new( client.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	(buffer.bytes(), server.id(), client.id())
+}
+
+
+
+// A recorder writes every frame with it's direction and peer id, and the reader gives them back in order.
+//
+#[async_std::test]
+//
+async fn capture_record_read()
+{
+	let (capture, server, _) = record().await;
+
+	let frames: Vec<_> = CaptureReader::new( capture.as_slice(), 1024 ).expect( "read header" )
+
+		.collect::< io::Result<_> >()
+		.expect( "read capture" )
+	;
+
+	let directions: Vec<_> = frames.iter().map( |f| f.direction ).collect();
+
+	assert_eq!
+	(
+		vec![ Direction::Incoming, Direction::Outgoing, Direction::Incoming, Direction::Outgoing ],
+		directions
+	);
+
+	assert!( frames.iter().all( |f| f.peer_id == server ) );
+	assert!( frames.windows(2).all( |w| w[0].time <= w[1].time ) );
+
+	// Each response goes to the call before it.
+	//
+	assert_eq!( frames[0].frame.cid(), frames[1].frame.cid() );
+	assert_eq!( frames[2].frame.cid(), frames[3].frame.cid() );
+}
+
+
+
+// Replaying a capture into a service map gives the same responses.
+//
+#[async_std::test]
+//
+async fn capture_replay_service_map()
+{
+	let (capture, server, client) = record().await;
+
+	let replay = Replay::read( capture.as_slice(), 1024 ).expect( "read capture" );
+
+	assert_eq!( 0, replay.clone().peer( client ).frames().len() );
+
+	let results = replay.peer( server ).into_service_map( &add_show_sum() ).await;
+
+	assert_eq!( 2, results.len() );
+
+	let show = match results.into_iter().nth(1)
+	{
+		Some( Ok( Response::CallResponse( resp ) ) ) => resp.into_inner(),
+		other                                        => panic!( "unexpected result: {:?}", other ),
+	};
+
+	assert_eq!( 5, serde_cbor::from_slice::<i64>( show.msg() ).expect( "deserialize" ) );
+}
+
+
+
+// Replaying a capture into a peer makes it send the same frames.
+//
+#[async_std::test]
+//
+async fn capture_replay_peer()
+{
+	let (capture, _, _) = record().await;
+
+	let replay   = Replay::read( capture.as_slice(), 1024 ).expect( "read capture" );
+	let expected = replay.outgoing().cloned().collect::<Vec<_>>();
+
+	let builder: PeerBuilder = PeerBuilder::new().service_map( Arc::new( add_show_sum() ) );
+
+	let (mut peer, sent) = replay.into_peer( builder, exec() ).expect( "build peer" );
+
+	let sent: Vec<_> = sent.take( expected.len() ).collect().await;
+
+	assert_eq!( expected, sent );
+
+	peer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
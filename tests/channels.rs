@@ -0,0 +1,333 @@
+// Tests:
+//
+// ✔ open a channel, send messages both ways and close it from both sides.
+// ✔ opening a channel for a service the remote doesn't listen on yields UnknownService.
+// ✔ the sender can't exceed the flow control window before the remote consumes messages.
+// ✔ opening a channel with an id that is in use yields ChannelInUse and leaves the open channel alone.
+// ✔ opening more channels than allowed yields RateLimited.
+//
+mod common;
+
+use
+{
+	common                        :: { *, import::{ *, assert_eq }         } ,
+	futures_timer                 :: { Delay                               } ,
+	futures                       :: { SinkExt, future::{ select, Either } } ,
+	serde                         :: { Serialize                           } ,
+	std                           :: { num::NonZeroU32                     } ,
+	thespis_remote::external_deps :: { serde_cbor                          } ,
+};
+
+
+fn echo_sid() -> ServiceID
+{
+	ServiceID::from_seed( b"channels::Echo" )
+}
+
+
+// Start a peer that listens for channels on echo_sid. Returns the listener.
+//
+fn listening_peer( socket: Endpoint, name: &str ) -> (Addr<Peer>, ChannelListener<String, String>)
+{
+	let (peer_addr, peer_mb) = Addr::builder().name( name.into() ).build();
+
+	let mut peer = Peer::from_async_read( peer_addr.clone(), socket, 1024, exec(), None, None ).expect( "spawn peer" );
+
+	let listener = peer.listen_channels::<String, String>( echo_sid() );
+
+	exec().spawn( peer_mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
+
+	(peer_addr, listener)
+}
+
+
+
+// Open a channel, send messages both ways and close it from both sides.
+//
+#[async_std::test]
+//
+async fn channel_echo()
+{
+	// flexi_logger::Logger::with_str( "trace" ).start().unwrap();
+
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let peera = async move
+	{
+		let (_peer, mut listener) = listening_peer( server, "peera" );
+
+		let chan = listener.next().await.expect( "incoming channel" );
+
+		assert_eq!( echo_sid(), chan.service() );
+
+		let (mut sink, mut stream) = chan.split();
+
+		while let Some( msg ) = stream.next().await
+		{
+			sink.send( format!( "echo: {}", msg.expect( "channel message" ) ) ).await.expect( "echo" );
+		}
+
+		sink.close().await.expect( "close sink" );
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+		let window = NonZeroU32::new( 4 ).unwrap();
+		let chan   = Channel::<String, String>::open( &mut peera, echo_sid(), window ).await.expect( "open channel" );
+
+		let (mut sink, stream) = chan.split();
+
+		// Both ends echo concurrently, so we send more than the window allows.
+		//
+		let send = async move
+		{
+			for i in 0..10
+			{
+				sink.send( format!( "{}", i ) ).await.expect( "send on channel" );
+			}
+
+			sink.close().await.expect( "close channel" );
+		};
+
+		let recv = stream.map( |item| item.expect( "channel message" ) ).collect::<Vec<String>>();
+
+		let ((), items) = join( send, recv ).await;
+
+		let expect: Vec<String> = (0..10).map( |i| format!( "echo: {}", i ) ).collect();
+
+		assert_eq!( expect, items );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
+
+
+
+// Opening a channel for a service the remote doesn't listen on yields UnknownService.
+//
+#[async_std::test]
+//
+async fn channel_unknown_service()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let peera = async move
+	{
+		let (_, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "peera" ).await;
+
+		handle.await;
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+		let window   = NonZeroU32::new( 4 ).unwrap();
+		let mut chan = Channel::<String, String>::open( &mut peera, echo_sid(), window ).await.expect( "open channel" );
+
+		assert_matches!
+		(
+			chan.next().await,
+			Some( Err( PeerErr::Remote{ err: ConnectionError::UnknownService{..}, .. } ) )
+		);
+
+		assert!( chan.next().await.is_none() );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
+
+
+
+// The sender can't exceed the flow control window before the remote consumes messages.
+//
+#[async_std::test]
+//
+async fn channel_window()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (consume_tx, consume_rx) = futures::channel::oneshot::channel::<()>();
+
+	let peera = async move
+	{
+		let (_peer, mut listener) = listening_peer( server, "peera" );
+
+		let chan = listener.next().await.expect( "incoming channel" );
+
+		// Don't consume anything until the client saw the window fill up.
+		//
+		consume_rx.await.expect( "consume signal" );
+
+		let items: Vec<String> = chan.take( 3 ).map( |item| item.expect( "channel message" ) ).collect().await;
+
+		assert_eq!( vec![ "0".to_string(), "1".to_string(), "2".to_string() ], items );
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+		let window = NonZeroU32::new( 2 ).unwrap();
+		let chan   = Channel::<String, String>::open( &mut peera, echo_sid(), window ).await.expect( "open channel" );
+
+		let (mut sink, _stream) = chan.split();
+
+		sink.send( "0".to_string() ).await.expect( "send on channel" );
+		sink.send( "1".to_string() ).await.expect( "send on channel" );
+		sink.feed( "2".to_string() ).await.expect( "feed channel"    );
+
+		// The window is full, so flushing can't complete.
+		//
+		match select( sink.flush(), Delay::new( Duration::from_millis(50) ) ).await
+		{
+			Either::Left (_) => panic!( "sent more messages than the window allows" ),
+			Either::Right(_) => {}
+		}
+
+		consume_tx.send(()).expect( "signal consume" );
+
+		sink.flush().await.expect( "flush channel" );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
+
+
+
+// Same layout as the control frames of the peer, so we can send a raw one.
+//
+#[ derive( Serialize ) ]
+//
+enum ChannelCtrl
+{
+	Open{ sid: ServiceID, window: NonZeroU32 },
+}
+
+
+
+// Opening a channel with an id that is in use yields ChannelInUse and leaves the open channel alone.
+//
+#[async_std::test]
+//
+async fn channel_in_use()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let peera = async move
+	{
+		let (_peer, mut listener) = listening_peer( server, "peera" );
+
+		let chan = listener.next().await.expect( "incoming channel" );
+
+		let (_sink, mut stream) = chan.split();
+
+		// The original channel still delivers.
+		//
+		assert_eq!( Some( Ok( "still open".to_string() ) ), stream.next().await );
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+		let window = NonZeroU32::new( 4 ).unwrap();
+		let chan   = Channel::<String, String>::open( &mut peera, echo_sid(), window ).await.expect( "open channel" );
+		let id     = chan.id();
+
+		let (mut sink, mut stream) = chan.split();
+
+		// Open a second time with the same id.
+		//
+		let mut wf = ThesWF::with_capacity( 64 );
+
+		wf.set_sid( ServiceID::channel_ctrl() );
+		wf.set_cid( id                        );
+
+		serde_cbor::to_writer( &mut wf, &ChannelCtrl::Open{ sid: echo_sid(), window } ).expect( "serialize" );
+
+		peera.send( wf ).await.expect( "send open" );
+
+		assert_matches!
+		(
+			stream.next().await,
+			Some( Err( PeerErr::Remote{ err: ConnectionError::ChannelInUse{ cid, .. }, .. } ) ) if cid == Some( id )
+		);
+
+		sink.send( "still open".to_string() ).await.expect( "send on channel" );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
+
+
+
+// Opening more channels than allowed yields RateLimited.
+//
+#[async_std::test]
+//
+async fn channel_too_many()
+{
+	let (server, client)   = Endpoint::pair( 64, 64 );
+	let (done_tx, done_rx) = futures::channel::oneshot::channel::<()>();
+
+	let peera = async move
+	{
+		// Keep the listener alive so the channels stay open.
+		//
+		let (_peer, _listener) = listening_peer( server, "peera" );
+
+		let _ = done_rx.await;
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, mut evts) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+		let window = NonZeroU32::new( 4 ).unwrap();
+		let mut id = ConnID::random();
+
+		// The limit is 1024 channels, open one more.
+		//
+		for _ in 0..1025
+		{
+			id = ConnID::random();
+
+			let mut wf = ThesWF::with_capacity( 64 );
+
+			wf.set_sid( ServiceID::channel_ctrl() );
+			wf.set_cid( id                        );
+
+			serde_cbor::to_writer( &mut wf, &ChannelCtrl::Open{ sid: echo_sid(), window } ).expect( "serialize" );
+
+			peera.send( wf ).await.expect( "send open" );
+		}
+
+		assert_eq!
+		(
+			PeerEvent::RemoteError( ConnectionError::RateLimited{ sid: Some( echo_sid() ), cid: Some( id ) } ),
+			evts.next().await.expect( "event" )
+		);
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+
+		done_tx.send(()).expect( "signal end" );
+	};
+
+	join( peera, peerb ).await;
+}
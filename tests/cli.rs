@@ -0,0 +1,237 @@
+#![ cfg( feature = "cli" ) ]
+
+// Tests:
+//
+// ✔ the cli lists the services of a peer.
+// ✔ the cli calls a service with a JSON and a CBOR diagnostic payload and prints the response.
+// ✔ the cli sends to a service.
+// ✔ the cli reports a ConnectionError for an unknown service.
+// ✔ the cli decodes a capture from stdin with service names resolved.
+//
+mod common;
+
+use
+{
+	common    :: { *, import::{ *, assert_eq }                      } ,
+	async_std :: { net::TcpListener                                 } ,
+	futures   :: { AsyncReadExt                                     } ,
+	std       :: { process::{ Command, Output, Stdio }, sync::Mutex } ,
+};
+
+
+// A capture in memory.
+//
+#[ derive( Clone, Default ) ]
+//
+struct Buffer( Arc<Mutex< Vec<u8> >> );
+
+impl std::io::Write for Buffer
+{
+	fn write( &mut self, buf: &[u8] ) -> std::io::Result<usize>
+	{
+		self.0.lock().unwrap().write( buf )
+	}
+
+	fn flush( &mut self ) -> std::io::Result<()>
+	{
+		Ok(())
+	}
+}
+
+
+impl Buffer
+{
+	fn bytes( &self ) -> Vec<u8>
+	{
+		self.0.lock().unwrap().clone()
+	}
+}
+
+
+
+// Listen on a TCP port and serve Add, Show and Sub on every connection. All connections share
+// the same Sum actor. Everything the server peers see is recorded in the returned buffer.
+//
+async fn server() -> (String, Buffer)
+{
+	let listener = TcpListener::bind( "127.0.0.1:0" ).await.expect( "bind listener" );
+	let addr     = listener.local_addr().expect( "local addr" ).to_string();
+	let buffer   = Buffer::default();
+	let recorder = Recorder::new( buffer.clone() ).expect( "write header" );
+	let sm       = Arc::new( add_show_sum() );
+
+	exec().spawn( async move
+	{
+		let mut incoming = listener.incoming();
+
+		while let Some( Ok(socket) ) = incoming.next().await
+		{
+			let (reader, writer) = socket.split();
+
+			// The peer keeps itself alive until the connection closes.
+			//
+			PeerBuilder::new()
+
+				.service_map( sm.clone()          )
+				.reflection ( Reflection::Services )
+
+				.build_recorded
+				(
+					thes_wf::Decoder::new( reader, 1024 ) ,
+					thes_wf::Encoder::new( writer, 1024 ) ,
+					&recorder                             ,
+					exec()                                ,
+				)
+
+				.expect( "build server peer" )
+			;
+		}
+
+	}).expect( "spawn accept loop" );
+
+	(addr, buffer)
+}
+
+
+
+// Run the cli. This blocks the test task, the server runs on the executor.
+//
+fn thespis( args: &[&str], stdin: Option<Vec<u8>> ) -> Output
+{
+	let mut child = Command::new( env!( "CARGO_BIN_EXE_thespis" ) )
+
+		.args  ( args            )
+		.stdin ( Stdio::piped()  )
+		.stdout( Stdio::piped()  )
+		.stderr( Stdio::piped()  )
+		.spawn ()
+		.expect( "run thespis cli" )
+	;
+
+	let mut input = child.stdin.take().expect( "stdin" );
+
+	if let Some( bytes ) = stdin
+	{
+		input.write_all( &bytes ).expect( "write stdin" );
+	}
+
+	drop( input );
+
+	child.wait_with_output().expect( "wait for thespis cli" )
+}
+
+
+fn stdout( out: &Output ) -> String
+{
+	String::from_utf8_lossy( &out.stdout ).trim().to_string()
+}
+
+
+
+// The cli lists the services of a peer.
+//
+#[async_std::test]
+//
+async fn cli_services()
+{
+	let (addr, _) = server().await;
+
+	let out = thespis( &[ "services", &addr ], None );
+
+	assert!( out.status.success() );
+
+	let services = stdout( &out );
+
+	assert!( services.contains( "remotes::Add"  ) );
+	assert!( services.contains( "remotes::Show" ) );
+	assert!( services.contains( "remotes::Sub"  ) );
+}
+
+
+
+// The cli calls a service with a JSON and a CBOR diagnostic payload and prints the response.
+//
+#[async_std::test]
+//
+async fn cli_call()
+{
+	let (addr, _) = server().await;
+
+	let out = thespis( &[ "call", &addr, "remotes::Add", "5" ], None );
+
+	assert!( out.status.success() );
+	assert_eq!( "null", stdout( &out ) );
+
+	let out = thespis( &[ "call", &addr, "remotes::Add", "2", "--diag" ], None );
+
+	assert!( out.status.success() );
+
+	let out = thespis( &[ "call", &addr, "remotes::Show", "null" ], None );
+
+	assert!( out.status.success() );
+	assert_eq!( "7", stdout( &out ) );
+}
+
+
+
+// The cli sends to a service.
+//
+#[async_std::test]
+//
+async fn cli_send()
+{
+	let (addr, _) = server().await;
+
+	let out = thespis( &[ "send", &addr, "remotes::Add", "3" ], None );
+
+	assert!( out.status.success() );
+	assert_eq!( "", stdout( &out ) );
+
+	let out = thespis( &[ "call", &addr, "remotes::Show", "null" ], None );
+
+	assert_eq!( "3", stdout( &out ) );
+}
+
+
+
+// The cli reports a ConnectionError for an unknown service.
+//
+#[async_std::test]
+//
+async fn cli_unknown_service()
+{
+	let (addr, _) = server().await;
+
+	let out = thespis( &[ "call", &addr, "remotes::Nope", "null" ], None );
+
+	assert!( !out.status.success() );
+	assert!( String::from_utf8_lossy( &out.stderr ).contains( "UnknownService" ) );
+}
+
+
+
+// The cli decodes a capture from stdin with service names resolved.
+//
+#[async_std::test]
+//
+async fn cli_decode()
+{
+	let (addr, capture) = server().await;
+
+	let out = thespis( &[ "call", &addr, "remotes::Show", "null" ], None );
+
+	assert_eq!( "0", stdout( &out ) );
+
+	let out = thespis( &[ "decode", "--service", "remotes::Show" ], Some( capture.bytes() ) );
+
+	assert!( out.status.success() );
+
+	let decoded = stdout( &out );
+	let lines: Vec<_> = decoded.lines().collect();
+
+	assert_eq!( 4, lines.len() );
+
+	assert!( lines[0].contains( "<-" ) && lines[0].contains( "IncomingCall" ) && lines[0].contains( "remotes::Show" ) );
+	assert!( lines[2].contains( "->" ) && lines[2].contains( "CallResponse" ) );
+	assert_eq!( "0", lines[3].trim() );
+}
@@ -49,11 +49,47 @@ pub use actors::*;
 
 
 
+/// The executor the tests run their actors and peers on. With the `tokio` feature, actors and peers run
+/// on a tokio runtime instead of async-std. The tests in `tokio_io.rs` run entirely on tokio.
+//
+#[ cfg( not( feature = "tokio" ) ) ]
+//
+pub type Exec = AsyncStd;
+
+#[ cfg( feature = "tokio" ) ]
+//
+pub type Exec = TokioTp;
+
+
+#[ cfg( not( feature = "tokio" ) ) ]
+//
+pub fn exec() -> Exec
+{
+	AsyncStd
+}
+
+
+#[ cfg( feature = "tokio" ) ]
+//
+pub fn exec() -> Exec
+{
+	use once_cell::sync::Lazy;
+
+	static RUNTIME: Lazy< TokioTp > = Lazy::new( ||
+	{
+		TokioTp::try_from( tokio::runtime::Builder::new_multi_thread().enable_all() ).expect( "create tokio runtime" )
+	});
+
+	RUNTIME.clone()
+}
+
+
+
 pub fn add_show_sum() -> remotes::Services
 {
 	// Create mailbox for our handler
 	//
-	let addr_handler = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+	let addr_handler = Addr::builder().start( Sum(0), &exec() ).expect( "spawn actor mailbox" );
 
 	// Create a service map
 	//
@@ -0,0 +1,337 @@
+#![ cfg( feature = "derive" ) ]
+
+// Tests:
+//
+// ✔ the sid of a derived service is the same as in a service_map! with the same namespace.
+// ✔ the name and sid options set the sid.
+// ✔ every instance of a generic service has it's own sid.
+// ✔ call and send through a #[service_map] and it's RemoteAddr.
+// ✔ call two instances of a generic service in the same map.
+// ✔ call a streaming service.
+// ✔ a service of the map without a handler gives ConnectionError::UnknownService.
+// ✔ an error of a fallible service gives PeerErr::Application.
+//
+mod common;
+
+use
+{
+	common                 :: { *, import::{ *, assert_eq }                  } ,
+	serde                  :: { Serialize, Deserialize, de::DeserializeOwned } ,
+	thespis_remote::derive :: { Service, service_map                         } ,
+};
+
+
+// Same name and namespace as the Add in common, which is in the service_map! remotes.
+//
+mod same
+{
+	use super::*;
+
+	#[ derive( Serialize, Deserialize, Service ) ]
+	#[ service( namespace = "remotes" ) ]
+	//
+	pub struct Add( pub i64 );
+
+	impl Message for Add { type Return = (); }
+}
+
+
+#[ derive( Serialize, Deserialize, Debug, Service ) ]
+#[ service( namespace = "derive" ) ]
+//
+pub struct Mul( pub i64 );
+
+impl Message for Mul { type Return = (); }
+
+
+#[ derive( Serialize, Deserialize, Debug, Service ) ]
+#[ service( name = "calc::Total" ) ]
+//
+pub struct Total;
+
+impl Message for Total { type Return = i64; }
+
+
+#[ derive( Serialize, Deserialize, Debug, Service ) ]
+#[ service( namespace = "derive", sid = 0x1234 ) ]
+//
+pub struct Reset;
+
+impl Message for Reset { type Return = (); }
+
+
+#[ derive( Serialize, Deserialize, Debug, Service ) ]
+#[ service( namespace = "derive" ) ]
+//
+pub struct Echo<T>( pub T );
+
+impl<T: Serialize + DeserializeOwned + Send + 'static> Message for Echo<T> { type Return = T; }
+
+
+#[ derive( Serialize, Deserialize, Debug, Service ) ]
+#[ service( namespace = "derive", fallible ) ]
+//
+pub struct Div( pub i64 );
+
+impl Message for Div { type Return = Result<(), String>; }
+
+
+#[ derive( Serialize, Deserialize, Debug, Service ) ]
+#[ service( namespace = "derive", stream ) ]
+//
+pub struct Count( pub u64 );
+
+impl Message for Count { type Return = ServiceStream<u64>; }
+
+
+
+#[ derive( Actor ) ] struct Calc( i64 );
+
+
+impl Handler<Mul> for Calc
+{
+	#[async_fn] fn handle( &mut self, msg: Mul ) -> ()
+	{
+		self.0 *= msg.0;
+	}
+}
+
+
+impl Handler<Total> for Calc
+{
+	#[async_fn] fn handle( &mut self, _msg: Total ) -> i64
+	{
+		self.0
+	}
+}
+
+
+impl Handler<Reset> for Calc
+{
+	#[async_fn] fn handle( &mut self, _msg: Reset ) -> ()
+	{
+		self.0 = 1;
+	}
+}
+
+
+impl Handler< Echo<String> > for Calc
+{
+	#[async_fn] fn handle( &mut self, msg: Echo<String> ) -> String
+	{
+		msg.0
+	}
+}
+
+
+impl Handler< Echo<u8> > for Calc
+{
+	#[async_fn] fn handle( &mut self, msg: Echo<u8> ) -> u8
+	{
+		msg.0 + 1
+	}
+}
+
+
+impl Handler<Div> for Calc
+{
+	#[async_fn] fn handle( &mut self, msg: Div ) -> Result<(), String>
+	{
+		if msg.0 == 0 { return Err( "division by zero".to_string() ) }
+
+		self.0 /= msg.0;
+
+		Ok(())
+	}
+}
+
+
+impl Handler<Count> for Calc
+{
+	#[async_fn] fn handle( &mut self, msg: Count ) -> ServiceStream<u64>
+	{
+		futures::stream::iter( 0..msg.0 ).boxed()
+	}
+}
+
+
+
+#[ service_map( wire_format = ThesWF, services( Mul, Div, Total, Reset, Echo<String>, Echo<u8> ), streams( Count ) ) ]
+//
+mod calc {}
+
+
+// Reset is in the map, but doesn't get a handler.
+//
+fn calc_sm() -> calc::Services
+{
+	let addr = Addr::builder().start( Calc(1), &exec() ).expect( "spawn actor mailbox" );
+
+	let mut sm = calc::Services::new();
+
+	sm.register_handler::<Mul          >( addr.clone_box() );
+	sm.register_handler::<Div          >( addr.clone_box() );
+	sm.register_handler::<Total        >( addr.clone_box() );
+	sm.register_handler::<Echo<String> >( addr.clone_box() );
+	sm.register_handler::<Echo<u8>     >( addr.clone_box() );
+
+	sm.register_stream_handler::<Count>( addr.clone_box() );
+
+	sm
+}
+
+
+fn connect() -> (Addr<Peer>, calc::RemoteAddr)
+{
+	let server: PeerBuilder = PeerBuilder::new().name( "server" ).service_map( Arc::new( calc_sm() ) );
+	let client: PeerBuilder = PeerBuilder::new().name( "client" );
+
+	let (_server, client) = Peer::pair( server, client, 8, 8, exec() ).expect( "build pair" );
+
+	(client.clone(), calc::RemoteAddr::new( client ))
+}
+
+
+async fn close( mut peer: Addr<Peer> )
+{
+	peer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// The sid of a derived service is the same as in a service_map! with the same namespace.
+//
+#[test]
+//
+fn derive_sid_namespace()
+{
+	assert_eq!( <Add as remotes::Service>::sid(), <same::Add as Service>::sid() );
+	assert_eq!( "remotes::Add"                  , <same::Add as Service>::NAME  );
+}
+
+
+
+// The name and sid options set the sid.
+//
+#[test]
+//
+fn derive_sid_explicit()
+{
+	assert_eq!( ServiceID::from_seed( b"calc::Total" ), <Total as Service>::sid() );
+	assert_eq!( "calc::Total"                         , <Total as Service>::NAME  );
+
+	assert_eq!( ServiceID::from( 0x1234 ), <Reset as Service>::sid() );
+	assert_eq!( "derive::Reset"          , <Reset as Service>::NAME  );
+}
+
+
+
+// Every instance of a generic service has it's own sid.
+//
+#[test]
+//
+fn derive_sid_generic()
+{
+	let seed = format!( "derive::Echo<{}>", std::any::type_name::<String>() );
+
+	assert_eq!( ServiceID::from_seed( seed.as_bytes() ), <Echo<String> as Service>::sid() );
+	assert_ne!( <Echo<String> as Service>::sid()       , <Echo<u8>     as Service>::sid() );
+	assert_eq!( "derive::Echo"                         , <Echo<u8>     as Service>::NAME  );
+}
+
+
+
+// Call and send through a #[service_map] and it's RemoteAddr.
+//
+#[async_std::test]
+//
+async fn derive_call()
+{
+	let (peer, mut addr) = connect();
+
+	addr.send( Mul(3) ).await.expect( "send Mul" );
+
+	assert_eq!( 3, addr.call( Total ).await.expect( "call Total" ) );
+
+	addr.call( Mul(2) ).await.expect( "call Mul" );
+
+	assert_eq!( 6, addr.call( Total ).await.expect( "call Total" ) );
+
+	close( peer ).await;
+}
+
+
+
+// Call two instances of a generic service in the same map.
+//
+#[async_std::test]
+//
+async fn derive_generic()
+{
+	let (peer, mut addr) = connect();
+
+	let echo = addr.call( Echo( "hello".to_string() ) ).await.expect( "call Echo<String>" );
+
+	assert_eq!( "hello", echo );
+	assert_eq!( 6, addr.call( Echo( 5u8 ) ).await.expect( "call Echo<u8>" ) );
+
+	close( peer ).await;
+}
+
+
+
+// Call a streaming service.
+//
+#[async_std::test]
+//
+async fn derive_stream()
+{
+	let (peer, mut addr) = connect();
+
+	let stream = addr.call_stream( Count(3) ).await.expect( "call_stream" );
+	let items: Vec<u64> = stream.map( |item| item.expect( "stream item" ) ).collect().await;
+
+	assert_eq!( vec![ 0, 1, 2 ], items );
+
+	close( peer ).await;
+}
+
+
+
+// A service of the map without a handler gives ConnectionError::UnknownService.
+//
+#[async_std::test]
+//
+async fn derive_no_handler()
+{
+	let (peer, mut addr) = connect();
+
+	let res = addr.call( Reset ).await;
+
+	assert_matches!( res, Err( PeerErr::Remote{ err: ConnectionError::UnknownService{..}, .. } ) );
+
+	close( peer ).await;
+}
+
+
+
+// An error of a fallible service gives PeerErr::Application.
+//
+#[async_std::test]
+//
+async fn derive_fallible()
+{
+	let (peer, mut addr) = connect();
+
+	assert_eq!( Ok( Ok(()) ), addr.call( Div(1) ).await );
+
+	let err = match addr.call( Div(0) ).await
+	{
+		Err( PeerErr::Application{ err, .. } ) => err,
+		other                                  => panic!( "expected PeerErr::Application, got: {:?}", other ),
+	};
+
+	assert_eq!( Some( "division by zero".to_string() ), err.downcast() );
+
+	close( peer ).await;
+}
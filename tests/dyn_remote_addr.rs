@@ -0,0 +1,121 @@
+// Tests:
+//
+// ✔ call and send with serde_cbor::Value.
+// ✔ call and send with raw payloads.
+// ✔ an unknown service gives PeerErr::Remote with ConnectionError::UnknownService.
+// ✔ a payload the remote can't deserialize gives PeerErr::Remote with ConnectionError::Deserialize.
+//
+mod common;
+
+use
+{
+	common                        :: { *, import::{ *, assert_eq } } ,
+	thespis_remote::external_deps :: { serde_cbor::{ self, Value } } ,
+};
+
+
+// A client connected to a peer that serves Add, Show and Sub.
+//
+fn connect() -> (Addr<Peer>, DynRemoteAddr)
+{
+	let server: PeerBuilder = PeerBuilder::new().name( "server" ).service_map( Arc::new( add_show_sum() ) );
+	let client: PeerBuilder = PeerBuilder::new().name( "client" );
+
+	let (_server, client) = Peer::pair( server, client, 8, 8, exec() ).expect( "build pair" );
+
+	(client.clone(), DynRemoteAddr::new( client ))
+}
+
+
+async fn close( mut peer: Addr<Peer> )
+{
+	peer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+fn add() -> ServiceID
+{
+	<Add as remotes::Service>::sid()
+}
+
+
+fn show() -> ServiceID
+{
+	<Show as remotes::Service>::sid()
+}
+
+
+
+// Call and send with serde_cbor::Value.
+//
+#[async_std::test]
+//
+async fn dyn_value()
+{
+	let (peer, mut addr) = connect();
+
+	assert_eq!( Ok( Value::Null ), addr.call( add(), &Value::Integer(5) ).await );
+
+	addr.send( add(), &Value::Integer(2) ).await.expect( "send Add" );
+
+	assert_eq!( Ok( Value::Integer(7) ), addr.call( show(), &Value::Null ).await );
+
+	close( peer ).await;
+}
+
+
+
+// Call and send with raw payloads.
+//
+#[async_std::test]
+//
+async fn dyn_raw()
+{
+	let (peer, mut addr) = connect();
+
+	let five     = serde_cbor::to_vec( &Add(5) ).expect( "serialize" );
+	let show_msg = serde_cbor::to_vec( &Show   ).expect( "serialize" );
+
+	addr.send_raw( add(), &five ).await.expect( "send Add" );
+
+	let resp = addr.call_raw( show(), &show_msg ).await.expect( "call Show" );
+
+	assert_eq!( 5, serde_cbor::from_slice::<i64>( &resp ).expect( "deserialize" ) );
+
+	close( peer ).await;
+}
+
+
+
+// An unknown service gives PeerErr::Remote with ConnectionError::UnknownService.
+//
+#[async_std::test]
+//
+async fn dyn_unknown_service()
+{
+	let (peer, mut addr) = connect();
+
+	let sid = ServiceID::from_seed( b"remotes::Nope" );
+	let res = addr.call( sid, &Value::Null ).await;
+
+	assert_matches!( res, Err( PeerErr::Remote{ err: ConnectionError::UnknownService{..}, .. } ) );
+
+	close( peer ).await;
+}
+
+
+
+// A payload the remote can't deserialize gives PeerErr::Remote with ConnectionError::Deserialize.
+//
+#[async_std::test]
+//
+async fn dyn_deserialize()
+{
+	let (peer, mut addr) = connect();
+
+	let res = addr.call( add(), &Value::Text( "five".to_string() ) ).await;
+
+	assert_matches!( res, Err( PeerErr::Remote{ err: ConnectionError::Deserialize{..}, .. } ) );
+
+	close( peer ).await;
+}
@@ -33,7 +33,7 @@ async fn close_connection()
 	{
 		// get a framed connection
 		//
-		let (_, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "nodea" ).await;
+		let (_, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "nodea" ).await;
 
 		handle.await;
 	};
@@ -41,7 +41,7 @@ async fn close_connection()
 
 	let nodeb = async move
 	{
-		let (mut peera, mut peera_evts)  = peer_connect( client, AsyncStd, "nodeb_to_nodea" ).await;
+		let (mut peera, mut peera_evts)  = peer_connect( client, exec(), "nodeb_to_nodea" ).await;
 
 		// Close the connection and check the event
 		//
@@ -54,8 +54,8 @@ async fn close_connection()
 	// As far as I can tell, execution order is not defined, so hmm, there is no
 	// guarantee that a is listening before b tries to connect, but it seems to work for now.
 	//
-	let a_handle = AsyncStd.spawn_handle( nodea ).expect( "Spawn peera"  );
-	let b_handle = AsyncStd.spawn_handle( nodeb ).expect( "Spawn peerb"  );
+	let a_handle = exec().spawn_handle( nodea ).expect( "Spawn peera"  );
+	let b_handle = exec().spawn_handle( nodeb ).expect( "Spawn peerb"  );
 
 	join( a_handle, b_handle ).await;
 }
@@ -74,7 +74,7 @@ async fn close_connection_call()
 	{
 		// get a framed connection
 		//
-		let (_, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "nodea" ).await;
+		let (_, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "nodea" ).await;
 
 		handle.await;
 	};
@@ -82,7 +82,7 @@ async fn close_connection_call()
 
 	let nodeb = async move
 	{
-		let (mut peera, mut peera_evts)  = peer_connect( client, AsyncStd, "nodeb_to_nodea" ).await;
+		let (mut peera, mut peera_evts)  = peer_connect( client, exec(), "nodeb_to_nodea" ).await;
 
 		// Close the connection and check the event
 		//
@@ -95,8 +95,8 @@ async fn close_connection_call()
 	// As far as I can tell, execution order is not defined, so hmm, there is no
 	// guarantee that a is listening before b tries to connect, but it seems to work for now.
 	//
-	let a_handle = AsyncStd.spawn_handle( nodea ).expect( "Spawn peera"  );
-	let b_handle = AsyncStd.spawn_handle( nodeb ).expect( "Spawn peerb"  );
+	let a_handle = exec().spawn_handle( nodea ).expect( "Spawn peera"  );
+	let b_handle = exec().spawn_handle( nodeb ).expect( "Spawn peerb"  );
 
 	join( a_handle, b_handle ).await;
 }
@@ -118,7 +118,7 @@ async fn header_unknown_service_error()
 	{
 		// get a framed connection
 		//
-		let (_, mut evts, handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "nodea" ).await;
+		let (_, mut evts, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "nodea" ).await;
 
 		let sid = Some( ServiceID::from(1) );
 
@@ -138,7 +138,7 @@ async fn header_unknown_service_error()
 
 	let nodeb = async move
 	{
-		let (mut peera, mut peera_evts)  = peer_connect( client, AsyncStd, "nodeb_to_nodea" ).await;
+		let (mut peera, mut peera_evts)  = peer_connect( client, exec(), "nodeb_to_nodea" ).await;
 
 		// Create some random data that shouldn't deserialize
 		//
@@ -165,8 +165,8 @@ async fn header_unknown_service_error()
 	// As far as I can tell, execution order is not defined, so hmm, there is no
 	// guarantee that a is listening before b tries to connect, but it seems to work for now.
 	//
-	let a_handle = AsyncStd.spawn_handle( nodea ).expect( "Spawn peera"  );
-	let b_handle = AsyncStd.spawn_handle( nodeb ).expect( "Spawn peerb"  );
+	let a_handle = exec().spawn_handle( nodea ).expect( "Spawn peera"  );
+	let b_handle = exec().spawn_handle( nodeb ).expect( "Spawn peerb"  );
 
 	join( a_handle, b_handle ).await;
 }
@@ -188,7 +188,7 @@ async fn call_deserialize()
 	{
 		// get a framed connection
 		//
-		let (_, mut evts, handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "nodea" ).await;
+		let (_, mut evts, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "nodea" ).await;
 
 
 		match evts.next().await.unwrap()
@@ -207,7 +207,7 @@ async fn call_deserialize()
 
 	let nodeb = async move
 	{
-		let (mut peera, mut peera_evts)  = peer_connect( client, AsyncStd, "nodeb_to_nodea" ).await;
+		let (mut peera, mut peera_evts)  = peer_connect( client, exec(), "nodeb_to_nodea" ).await;
 
 		// Create some random data that shouldn't deserialize
 		//
@@ -245,8 +245,8 @@ async fn call_deserialize()
 	// As far as I can tell, execution order is not defined, so hmm, there is no
 	// guarantee that a is listening before b tries to connect, but it seems to work for now.
 	//
-	let a_handle = AsyncStd.spawn_handle( nodea ).expect( "Spawn peera"  );
-	let b_handle = AsyncStd.spawn_handle( nodeb ).expect( "Spawn peerb"  );
+	let a_handle = exec().spawn_handle( nodea ).expect( "Spawn peera"  );
+	let b_handle = exec().spawn_handle( nodeb ).expect( "Spawn peerb"  );
 
 	join( a_handle, b_handle ).await;
 }
@@ -267,7 +267,7 @@ async fn sm_deserialize_error()
 	{
 		// get a framed connection
 		//
-		let (_, mut evts, handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "nodea" ).await;
+		let (_, mut evts, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "nodea" ).await;
 
 		match evts.next().await.unwrap()
 		{
@@ -285,7 +285,7 @@ async fn sm_deserialize_error()
 
 	let nodeb = async move
 	{
-		let (mut peera, mut peera_evts) = peer_connect( client, AsyncStd, "nodeb_to_nodea" ).await;
+		let (mut peera, mut peera_evts) = peer_connect( client, exec(), "nodeb_to_nodea" ).await;
 
 		// Create some random data that shouldn't deserialize
 		//
@@ -318,8 +318,8 @@ async fn sm_deserialize_error()
 	// As far as I can tell, execution order is not defined, so hmm, there is no
 	// guarantee that a is listening before b tries to connect, but it seems to work for now.
 	//
-	let a_handle = AsyncStd.spawn_handle( nodea ).expect( "Spawn peera"  );
-	let b_handle = AsyncStd.spawn_handle( nodeb ).expect( "Spawn peerb"  );
+	let a_handle = exec().spawn_handle( nodea ).expect( "Spawn peera"  );
+	let b_handle = exec().spawn_handle( nodeb ).expect( "Spawn peerb"  );
 
 	join( a_handle, b_handle ).await;
 }
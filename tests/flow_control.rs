@@ -0,0 +1,210 @@
+// Tests:
+//
+// ✔ a call waits for request credit before it goes out.
+// ✔ a call waits for byte credit before it goes out, a request bigger than the window still goes out.
+// ✔ a send is held back while there is no credit.
+// ✔ without flow control on the remote, requests are not limited.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq }                                                } ,
+	futures_timer :: { Delay                                                                      } ,
+	std           :: { num::{ NonZeroU32, NonZeroU64 }, sync::atomic::AtomicUsize, time::Instant } ,
+};
+
+
+#[ derive(Actor) ] struct Slow( Arc<AtomicUsize> );
+
+impl Handler<Add> for Slow
+{
+	fn handle( &mut self, _msg: Add ) -> Return<'_, ()> { async move
+	{
+		Delay::new( Duration::from_millis(100) ).await;
+
+		self.0.fetch_add( 1, Relaxed );
+
+	}.boxed() }
+}
+
+
+
+#[ derive(Actor) ] struct After( Arc<AtomicUsize> );
+
+impl Handler<Show> for After
+{
+	fn handle( &mut self, _msg: Show ) -> Return<'_, i64> { async move
+	{
+		self.0.load( Relaxed ) as i64
+
+	}.boxed() }
+}
+
+
+
+service_map!
+(
+	namespace  : fcsm       ;
+	wire_format: ThesWF     ;
+	services   : Add, Show  ;
+);
+
+
+
+fn window( requests: u32, bytes: u64 ) -> FlowControl
+{
+	FlowControl::new( NonZeroU32::new( requests ).unwrap(), NonZeroU64::new( bytes ).unwrap() )
+}
+
+
+// Start a peer exposing a slow Add and a fast Show which tells how many Adds have finished.
+//
+fn server( socket: Endpoint, flow: Option<FlowControl> ) -> Addr<Peer>
+{
+	let counter = Arc::new( AtomicUsize::new(0) );
+
+	let slow  = Addr::builder().start( Slow ( counter.clone() ), &exec() ).expect( "spawn actor mailbox" );
+	let after = Addr::builder().start( After( counter         ), &exec() ).expect( "spawn actor mailbox" );
+
+	let mut sm = fcsm::Services::new();
+
+	sm.register_handler::<Add >( slow .clone_box() );
+	sm.register_handler::<Show>( after.clone_box() );
+
+	let (peer_addr, peer_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( peer_addr.clone(), socket, 1024, exec(), None, None ).expect( "spawn peer" );
+
+	peer.register_services( Arc::new( sm ) );
+
+	if let Some( flow ) = flow
+	{
+		peer.set_flow_control( flow ).expect( "set flow control" );
+	}
+
+	exec().spawn( peer_mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
+
+	peer_addr
+}
+
+
+// Call Add and Show at the same time and return what Show saw.
+//
+async fn add_and_show( peer: &Addr<Peer> ) -> i64
+{
+	let mut add  = fcsm::RemoteAddr::new( peer.clone() );
+	let mut show = fcsm::RemoteAddr::new( peer.clone() );
+
+	// Make sure the initial credit from the server came in.
+	//
+	assert_eq!( Ok(0), show.call( Show ).await );
+
+	let (added, shown) = join( add.call( Add(1) ), show.call( Show ) ).await;
+
+	assert_eq!( Ok(()), added );
+
+	shown.expect( "call Show" )
+}
+
+
+
+// A call waits for request credit before it goes out.
+//
+#[async_std::test]
+//
+async fn flow_control_requests()
+{
+	let (server_end, client_end) = Endpoint::pair( 64, 64 );
+
+	let _server = server( server_end, Some( window( 1, 1024 ) ) );
+
+	let (mut client, _) = peer_connect( client_end, exec(), "client" ).await;
+
+	// Show only went out when Add was done.
+	//
+	assert_eq!( 1, add_and_show( &client ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// A call waits for byte credit before it goes out, a request bigger than the window still goes out.
+//
+#[async_std::test]
+//
+async fn flow_control_bytes()
+{
+	let (server_end, client_end) = Endpoint::pair( 64, 64 );
+
+	let _server = server( server_end, Some( window( 100, 1 ) ) );
+
+	let (mut client, _) = peer_connect( client_end, exec(), "client" ).await;
+
+	assert_eq!( 1, add_and_show( &client ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// A send is held back while there is no credit.
+//
+#[async_std::test]
+//
+async fn flow_control_send()
+{
+	let (server_end, client_end) = Endpoint::pair( 64, 64 );
+
+	let _server = server( server_end, Some( window( 1, 1024 ) ) );
+
+	let (mut client, _) = peer_connect( client_end, exec(), "client" ).await;
+
+	let mut add  = fcsm::RemoteAddr::new( client.clone() );
+	let mut send = fcsm::RemoteAddr::new( client.clone() );
+
+	// Make sure the initial credit from the server came in.
+	//
+	assert_eq!( Ok(0), add.call( Show ).await );
+
+	let start = Instant::now();
+
+	// The call takes the only credit until Slow is done with it, 100ms.
+	//
+	let sent = async
+	{
+		let res = send.send( Add(1) ).await;
+
+		( res, start.elapsed() )
+	};
+
+	let (added, (sent, elapsed)) = join( add.call( Add(1) ), sent ).await;
+
+	assert_eq!( Ok(()), added );
+	assert_eq!( Ok(()), sent  );
+	assert!( elapsed >= Duration::from_millis(50) );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Without flow control on the remote, requests are not limited.
+//
+#[async_std::test]
+//
+async fn flow_control_none()
+{
+	let (server_end, client_end) = Endpoint::pair( 64, 64 );
+
+	let _server = server( server_end, None );
+
+	let (mut client, _) = peer_connect( client_end, exec(), "client" ).await;
+
+	// Show was processed while Add was still running.
+	//
+	assert_eq!( 0, add_and_show( &client ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,226 @@
+// Tests:
+//
+// ✔ a call whose request future panics gets InternalServerError and the server emits RequestPanic with the message.
+// ✔ a send whose request future panics emits RequestPanic and the peer keeps processing requests.
+// ✔ a call whose handling actor panics gets InternalServerError and the server emits HandlerDead.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq }, remotes::Service } ,
+};
+
+
+// Delegates to the services of common, but the futures processing `Show` and `Sub` panic.
+//
+#[ derive( Debug ) ]
+//
+struct Panicky( remotes::Services );
+
+
+impl Panicky
+{
+	fn new() -> Self
+	{
+		Self( add_show_sum() )
+	}
+
+
+	fn panics( msg: &ThesWF ) -> bool
+	{
+		msg.sid() == Show::sid() || msg.sid() == Sub::sid()
+	}
+}
+
+
+impl ServiceMap for Panicky
+{
+	fn send_service( &self, msg: ThesWF, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<ThesWF>, PeerErr> > + Send >>, PeerErr >
+	{
+		if Self::panics( &msg )
+		{
+			return Ok( async { panic!( "sub is broken" ) }.boxed() );
+		}
+
+		self.0.send_service( msg, ctx )
+	}
+
+
+	fn call_service( &self, msg: ThesWF, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<ThesWF>, PeerErr> > + Send >>, PeerErr >
+	{
+		if Self::panics( &msg )
+		{
+			return Ok( async { panic!( "show is broken" ) }.boxed() );
+		}
+
+		self.0.call_service( msg, ctx )
+	}
+
+
+	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		self.0.services()
+	}
+}
+
+
+// An actor that panics when asked to show its value.
+//
+#[ derive( Actor ) ]
+//
+struct Broken;
+
+
+impl Handler< Show > for Broken
+{
+	#[async_fn] fn handle( &mut self, _msg: Show ) -> i64
+	{
+		panic!( "actor is broken" );
+	}
+}
+
+
+fn connect() -> (Addr<Peer>, Addr<Peer>)
+{
+	let server: PeerBuilder = PeerBuilder::new().name( "server" ).service_map( Arc::new( Panicky::new() ) );
+	let client: PeerBuilder = PeerBuilder::new().name( "client" );
+
+	Peer::pair( server, client, 8, 8, exec() ).expect( "build pair" )
+}
+
+
+// Wait for the next error event.
+//
+async fn next_err( evts: &mut Events<PeerEvent> ) -> PeerErr
+{
+	while let Some( evt ) = evts.next().await
+	{
+		if let PeerEvent::Error( err ) = evt { return err }
+	}
+
+	panic!( "no error event before the peer closed" );
+}
+
+
+
+// A call whose request future panics gets InternalServerError and the server emits RequestPanic with the message.
+//
+#[async_std::test]
+//
+async fn handler_panic_call()
+{
+	let (mut server, mut client) = connect();
+
+	let mut evts = server.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+	let mut addr = remotes::RemoteAddr::new( client.clone() );
+
+	assert_matches!
+	(
+		addr.call( Show ).await,
+
+		Err( PeerErr::Remote{ err: ConnectionError::InternalServerError{ sid, .. }, .. } )
+
+			if sid == Some( Show::sid() )
+	);
+
+	assert_matches!
+	(
+		next_err( &mut evts ).await,
+
+		PeerErr::RequestPanic{ ctx, msg }
+
+			if msg == "show is broken" && ctx.sid == Some( Show::sid() )
+	);
+
+	// The server still answers.
+	//
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// A send whose request future panics emits RequestPanic and the peer keeps processing requests.
+//
+#[async_std::test]
+//
+async fn handler_panic_send()
+{
+	let (mut server, mut client) = connect();
+
+	let mut evts = server.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+	let mut addr = remotes::RemoteAddr::new( client.clone() );
+
+	addr.send( Sub(5) ).await.expect( "send Sub" );
+
+	assert_matches!
+	(
+		next_err( &mut evts ).await,
+
+		PeerErr::RequestPanic{ ctx, msg }
+
+			if msg == "sub is broken" && ctx.sid == Some( Sub::sid() ) && ctx.cid.is_none()
+	);
+
+	addr.send( Add(5) ).await.expect( "send Add" );
+
+	assert_eq!( Ok(()), addr.call( Add(0) ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// A call whose handling actor panics gets InternalServerError and the server emits HandlerDead.
+// The actor runs in its own mailbox, so the panic message isn't available to the peer.
+//
+#[async_std::test]
+//
+async fn handler_panic_actor()
+{
+	let sum    = Addr::builder().start( Sum(0), &exec() ).expect( "spawn actor mailbox" );
+	let broken = Addr::builder().start( Broken, &exec() ).expect( "spawn actor mailbox" );
+
+	let mut sm = remotes::Services::new();
+
+	sm.register_handler::<Add >( sum   .clone_box() );
+	sm.register_handler::<Show>( broken.clone_box() );
+
+	let server: PeerBuilder = PeerBuilder::new().name( "server" ).service_map( Arc::new( sm ) );
+	let client: PeerBuilder = PeerBuilder::new().name( "client" );
+
+	let (mut server, mut client) = Peer::pair( server, client, 8, 8, exec() ).expect( "build pair" );
+
+	let mut evts = server.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+	let mut addr = remotes::RemoteAddr::new( client.clone() );
+
+	assert_matches!
+	(
+		addr.call( Show ).await,
+
+		Err( PeerErr::Remote{ err: ConnectionError::InternalServerError{ sid, .. }, .. } )
+
+			if sid == Some( Show::sid() )
+	);
+
+	assert_matches!
+	(
+		next_err( &mut evts ).await,
+
+		PeerErr::HandlerDead{ ctx }
+
+			if ctx.sid == Some( Show::sid() )
+	);
+
+	// The other services still work.
+	//
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
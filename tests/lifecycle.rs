@@ -0,0 +1,141 @@
+// Tests:
+//
+// ✔ a timed out call and it's late response are reported.
+// ✔ start and completion of incoming calls are reported.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures_timer :: { Delay                       } ,
+};
+
+
+#[ derive(Actor) ] struct Slow;
+
+impl Handler<Add> for Slow
+{
+	fn handle( &mut self, _msg: Add ) -> Return<'_, ()> { async move
+	{
+		Delay::new( Duration::from_millis(100) ).await;
+
+	}.boxed() }
+}
+
+
+
+service_map!
+(
+	namespace  : lcsm   ;
+	wire_format: ThesWF ;
+	services   : Add    ;
+);
+
+
+
+// Start a peer with lifecycle events on and observe only those.
+//
+async fn lifecycle_peer( socket: Endpoint, name: &str, sm: Option<lcsm::Services>, timeout: Duration ) -> (Addr<Peer>, Events<PeerEvent>)
+{
+	let (peer_addr, peer_mb) = Addr::builder().name( name.into() ).build();
+
+	let mut peer = Peer::from_async_read( peer_addr.clone(), socket, 1024, exec(), None, None ).expect( "spawn peer" );
+
+	peer.set_timeout( timeout );
+	peer.set_lifecycle_events( true );
+
+	let evts = peer.observe( ObserveConfig::default().filter( PeerEvent::is_lifecycle ) ).await.expect( "pharos not closed" );
+
+	if let Some( sm ) = sm
+	{
+		peer.register_services( Arc::new( sm ) );
+	}
+
+	exec().spawn( peer_mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
+
+	(peer_addr, evts)
+}
+
+
+fn slow_services() -> lcsm::Services
+{
+	let slow   = Addr::builder().start( Slow, &exec() ).expect( "spawn actor mailbox" );
+	let mut sm = lcsm::Services::new();
+
+	sm.register_handler::<Add>( slow.clone_box() );
+
+	sm
+}
+
+
+
+// A timed out call and it's late response are reported.
+//
+#[async_std::test]
+//
+async fn lifecycle_timeout()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server, _) = lifecycle_peer( server, "server", Some( slow_services() ), Duration::from_secs(60) ).await;
+
+	let (mut client, mut evts) = lifecycle_peer( client, "client", None, Duration::from_millis(10) ).await;
+
+	let mut addr = lcsm::RemoteAddr::new( client.clone() );
+
+	assert_matches!( addr.call( Add(1) ).await, Err( PeerErr::Timeout{..} ) );
+
+	let sid = <Add as lcsm::Service>::sid();
+
+	let cid = match evts.next().await
+	{
+		Some( PeerEvent::CallTimeout{ sid: s, cid } ) if s == sid => cid,
+		other => panic!( "unexpected event: {:?}", other ),
+	};
+
+	assert_eq!( Some( PeerEvent::LateResponse{ cid } ), evts.next().await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Start and completion of incoming calls are reported.
+//
+#[async_std::test]
+//
+async fn lifecycle_incoming()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server, mut evts) = lifecycle_peer( server, "server", Some( slow_services() ), Duration::from_secs(60) ).await;
+
+	let (mut client, _) = peer_connect( client, exec(), "client" ).await;
+
+	let mut addr = lcsm::RemoteAddr::new( client.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(1) ).await );
+
+	let sid = <Add as lcsm::Service>::sid();
+
+	let cid = match evts.next().await
+	{
+		Some( PeerEvent::IncomingCallStarted{ sid: s, cid } ) if s == sid => cid,
+		other => panic!( "unexpected event: {:?}", other ),
+	};
+
+	match evts.next().await
+	{
+		Some( PeerEvent::IncomingCallCompleted{ sid: s, cid: c, duration } ) =>
+		{
+			assert_eq!( sid, s );
+			assert_eq!( cid, c );
+			assert!( duration.expect( "duration" ) >= Duration::from_millis(100) );
+		}
+
+		other => panic!( "unexpected event: {:?}", other ),
+	}
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,132 @@
+// Tests:
+//
+// ✔ peers run on a LocalPool with a !Send transport and a !Send handler.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq }                                       } ,
+	futures :: { AsyncRead, AsyncWrite                                             } ,
+	std     :: { rc::Rc, cell::Cell, marker::PhantomData, task::{ Context, Poll }, io } ,
+};
+
+
+// A handler that can't leave it's thread.
+//
+#[ derive( Actor, Default ) ] struct LocalSum( Rc<Cell<i64>> );
+
+
+impl Handler<Add> for LocalSum
+{
+	fn handle_local( &mut self, msg: Add ) -> ReturnNoSend<()> { Box::pin( async move
+	{
+		self.0.set( self.0.get() + msg.0 );
+	})}
+
+	fn handle( &mut self, _msg: Add ) -> Return<()>
+	{
+		unreachable!( "Cannot be spawned on a threadpool" );
+	}
+}
+
+
+impl Handler<Show> for LocalSum
+{
+	fn handle_local( &mut self, _msg: Show ) -> ReturnNoSend<i64> { Box::pin( async move
+	{
+		self.0.get()
+	})}
+
+	fn handle( &mut self, _msg: Show ) -> Return<i64>
+	{
+		unreachable!( "Cannot be spawned on a threadpool" );
+	}
+}
+
+
+
+// A transport that can't leave it's thread.
+//
+struct NotSend<T>
+{
+	inner: T                   ,
+	_rc  : PhantomData<Rc<()>> ,
+}
+
+
+impl<T: AsyncRead + Unpin> AsyncRead for NotSend<T>
+{
+	fn poll_read( mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8] ) -> Poll<io::Result<usize>>
+	{
+		Pin::new( &mut self.inner ).poll_read( cx, buf )
+	}
+}
+
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for NotSend<T>
+{
+	fn poll_write( mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll<io::Result<usize>>
+	{
+		Pin::new( &mut self.inner ).poll_write( cx, buf )
+	}
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<io::Result<()>>
+	{
+		Pin::new( &mut self.inner ).poll_flush( cx )
+	}
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<io::Result<()>>
+	{
+		Pin::new( &mut self.inner ).poll_close( cx )
+	}
+}
+
+
+
+// Peers run on a LocalPool with a !Send transport and a !Send handler.
+//
+#[test]
+//
+fn local_peer()
+{
+	let mut pool = LocalPool::new();
+	let     exec = pool.spawner();
+
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let server = NotSend{ inner: server, _rc: PhantomData };
+	let client = NotSend{ inner: client, _rc: PhantomData };
+
+	let sum = Addr::builder().start_local( LocalSum::default(), &exec ).expect( "spawn actor mailbox" );
+
+	let mut sm = remotes::Services::new();
+
+	sm.register_handler::<Add >( sum.clone_box() );
+	sm.register_handler::<Show>( sum.clone_box() );
+
+	let _server = PeerBuilder::new()
+
+		.name       ( "server"       )
+		.service_map( Arc::new( sm ) )
+		.build_local_async_read( server, &exec )
+		.expect( "build server peer" )
+	;
+
+	let mut client = PeerBuilder::new()
+
+		.name( "client" )
+		.build_local_async_read( client, &exec )
+		.expect( "build client peer" )
+	;
+
+	pool.run_until( async move
+	{
+		let mut addr = remotes::RemoteAddr::new( client.clone() );
+
+		assert_eq!( Ok(()), addr.call( Add(5) ).await );
+		assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+		client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	});
+}
@@ -0,0 +1,53 @@
+// Tests:
+//
+// ✔ both peers of a pair can call the services of the other side.
+// ✔ closing one peer of a pair is seen by the other.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+
+// Both peers of a pair can call the services of the other side.
+//
+#[async_std::test]
+//
+async fn pair_calls()
+{
+	let a: PeerBuilder = PeerBuilder::new().name( "a" ).service_map( Arc::new( add_show_sum() ) );
+	let b: PeerBuilder = PeerBuilder::new().name( "b" ).service_map( Arc::new( add_show_sum() ) );
+
+	let (mut a, b) = Peer::pair( a, b, 8, 8, exec() ).expect( "build pair" );
+
+	let mut to_b = remotes::RemoteAddr::new( a.clone() );
+	let mut to_a = remotes::RemoteAddr::new( b.clone() );
+
+	assert_eq!( Ok(()), to_b.call( Add(5) ).await );
+	assert_eq!( Ok(()), to_a.call( Add(2) ).await );
+
+	assert_eq!( Ok(5), to_b.call( Show ).await );
+	assert_eq!( Ok(2), to_a.call( Show ).await );
+
+	a.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Closing one peer of a pair is seen by the other.
+//
+#[async_std::test]
+//
+async fn pair_close()
+{
+	let     a: PeerBuilder = PeerBuilder::new().name( "a" );
+	let mut b: PeerBuilder = PeerBuilder::new().name( "b" );
+
+	let mut evts = b.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+
+	let (mut a, _b) = Peer::pair( a, b, 0, 0, exec() ).expect( "build pair" );
+
+	a.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	assert_eq!( Some( PeerEvent::ClosedByRemote ), evts.next().await );
+}
@@ -0,0 +1,136 @@
+// Tests:
+//
+// ✔ every connection gets a peer with it's own services, and connects/disconnects are reported.
+// ✔ connections beyond the maximum are dropped and reported.
+// ✔ shutdown closes all peers.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq, assert_ne }        } ,
+	futures :: { channel::mpsc::{ unbounded, UnboundedSender } } ,
+	std     :: { io, num::NonZeroUsize                         } ,
+};
+
+
+type Listener = UnboundedSender< io::Result<Endpoint> >;
+
+
+// Start a server on a channel of in memory connections. Every connection gets a new Sum.
+//
+async fn server( max: Option<usize> ) -> (Addr< PeerServer<Exec> >, Events<ServerEvent>, Listener)
+{
+	let (tx, rx) = unbounded();
+
+	let services = || vec![ Arc::new( add_show_sum() ) as Arc<dyn ServiceMap> ];
+
+	let mut server = PeerServer::new( PeerConfig::default(), services, exec() );
+
+	if let Some( max ) = max
+	{
+		server.max_connections( NonZeroUsize::new( max ).unwrap() );
+	}
+
+	let evts = server.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+	let addr = server.start( rx ).expect( "start server" );
+
+	(addr, evts, tx)
+}
+
+
+// Hand a connection to the server and connect a client peer to it.
+//
+async fn connect( listener: &Listener, name: &str ) -> (Addr<Peer>, Events<PeerEvent>)
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	listener.unbounded_send( Ok( server ) ).expect( "send connection" );
+
+	peer_connect( client, exec(), name ).await
+}
+
+
+
+// Every connection gets a peer with it's own services, and connects/disconnects are reported.
+//
+#[async_std::test]
+//
+async fn peer_server_connects()
+{
+	let (_server, mut evts, listener) = server( None ).await;
+
+	let (mut peer_a, mut evts_a) = connect( &listener, "client_a" ).await;
+	let (    peer_b, _evts_b   ) = connect( &listener, "client_b" ).await;
+
+	let mut addr_a = remotes::RemoteAddr::new( peer_a.clone() );
+	let mut addr_b = remotes::RemoteAddr::new( peer_b.clone() );
+
+	assert_eq!( Ok(()), addr_a.call( Add(5) ).await );
+	assert_eq!( Ok(()), addr_b.call( Add(3) ).await );
+
+	assert_eq!( Ok(5), addr_a.call( Show ).await );
+	assert_eq!( Ok(3), addr_b.call( Show ).await );
+
+	let first  = match evts.next().await.unwrap() { ServerEvent::Connected{ peer } => peer.id(), evt => panic!( "{:?}", evt ) };
+	let second = match evts.next().await.unwrap() { ServerEvent::Connected{ peer } => peer.id(), evt => panic!( "{:?}", evt ) };
+
+	assert_ne!( first, second );
+
+	peer_a.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	assert_eq!( Some( PeerEvent::Closed ), evts_a.next().await );
+
+	assert_matches!( evts.next().await, Some( ServerEvent::Disconnected{ remote: true, .. } ) );
+
+	drop( listener );
+
+	assert_matches!( evts.next().await, Some( ServerEvent::ListenerEnded ) );
+}
+
+
+
+// Connections beyond the maximum are dropped and reported.
+//
+#[async_std::test]
+//
+async fn peer_server_max_connections()
+{
+	let (_server, mut evts, listener) = server( Some(1) ).await;
+
+	let (_peer_a, _evts_a   ) = connect( &listener, "client_a" ).await;
+	let (_peer_b, mut evts_b) = connect( &listener, "client_b" ).await;
+
+	assert_matches!( evts.next().await, Some( ServerEvent::Connected{..} ) );
+	assert_matches!( evts.next().await, Some( ServerEvent::Rejected      ) );
+
+	assert_eq!( Some( PeerEvent::ClosedByRemote ), evts_b.next().await );
+}
+
+
+
+// Shutdown closes all peers.
+//
+#[async_std::test]
+//
+async fn peer_server_shutdown()
+{
+	let (mut server, mut evts, listener) = server( None ).await;
+
+	let (_peer_a, mut evts_a) = connect( &listener, "client_a" ).await;
+	let (_peer_b, mut evts_b) = connect( &listener, "client_b" ).await;
+
+	assert_matches!( evts.next().await, Some( ServerEvent::Connected{..} ) );
+	assert_matches!( evts.next().await, Some( ServerEvent::Connected{..} ) );
+
+	server.call( Shutdown ).await.expect( "shutdown server" );
+
+	assert_matches!( evts.next().await, Some( ServerEvent::Disconnected{ remote: false, .. } ) );
+	assert_matches!( evts.next().await, Some( ServerEvent::Disconnected{ remote: false, .. } ) );
+	assert_matches!( evts.next().await, Some( ServerEvent::Shutdown                          ) );
+
+	assert_eq!( Some( PeerEvent::ClosedByRemote ), evts_a.next().await );
+	assert_eq!( Some( PeerEvent::ClosedByRemote ), evts_b.next().await );
+
+	drop( listener );
+}
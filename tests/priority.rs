@@ -0,0 +1,116 @@
+// Tests:
+//
+// ✔ calls with an explicit priority get their response.
+// ✔ sends with an explicit priority are delivered in order.
+// ✔ a call on a connection that can't be written to fails right away.
+// ✔ closing doesn't wait forever for a remote that doesn't read.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { SinkExt, channel::mpsc      } ,
+	std     :: { io::ErrorKind, time::Instant } ,
+};
+
+
+
+// Calls with an explicit priority get their response.
+//
+#[async_std::test]
+//
+async fn priority_call()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "peera" ).await;
+
+	let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( peera.clone() ).with_priority( Priority::Control );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+	peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+
+	handle.await;
+}
+
+
+
+// Sends with an explicit priority are delivered in order.
+//
+#[async_std::test]
+//
+async fn priority_send()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "peera" ).await;
+
+	let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( peera.clone() ).with_priority( Priority::Bulk );
+
+	for i in 1..=10
+	{
+		addr.send( Add(i) ).await.expect( "send Add" );
+	}
+
+	assert_eq!( Ok(55), addr.call( Show ).await );
+
+	peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+
+	handle.await;
+}
+
+
+
+// A call on a connection that can't be written to fails right away.
+//
+#[async_std::test]
+//
+async fn priority_broken_sink()
+{
+	let (tx, rx) = mpsc::channel::<ThesWF>( 8 );
+
+	drop( rx );
+
+	let broken  = |_| WireErr::Io{ kind: ErrorKind::BrokenPipe };
+	let builder: PeerBuilder = PeerBuilder::new();
+
+	let peer = builder.build( futures::stream::pending(), tx.sink_map_err( broken ), exec() ).expect( "build peer" );
+
+	let mut addr = remotes::RemoteAddr::new( peer );
+
+	assert_matches!( addr.call( Add(5) ).await, Err( PeerErr::ConnectionClosed{..} ) );
+}
+
+
+
+// Closing doesn't wait forever for a remote that doesn't read.
+//
+#[async_std::test]
+//
+async fn priority_close_stuck()
+{
+	// Has room for one frame, the announcement of trace context support.
+	//
+	let (tx, _rx) = mpsc::channel::<ThesWF>( 0 );
+
+	let broken  = |_| WireErr::Io{ kind: ErrorKind::BrokenPipe };
+	let builder: PeerBuilder = PeerBuilder::new();
+
+	let mut peer = builder.build( futures::stream::pending(), tx.sink_map_err( broken ), exec() ).expect( "build peer" );
+	let mut addr = remotes::RemoteAddr::new( peer.clone() );
+
+	addr.send( Add(5) ).await.expect( "send Add" );
+
+	let start = Instant::now();
+
+	peer.call( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	assert!( start.elapsed() < Duration::from_secs( 10 ) );
+}
@@ -41,7 +41,7 @@ async fn pubsub()
 
 	let provider = async move
 	{
-		let exec = AsyncStd.instrument( span_provider );
+		let exec = exec().instrument( span_provider );
 
 		debug!( "start mailbox for provider_to_relay" );
 
@@ -67,7 +67,7 @@ async fn pubsub()
 
 	let relays = async move
 	{
-		let exec = AsyncStd.instrument( span_relay );
+		let exec = exec().instrument( span_relay );
 
 
 		let services = vec![<Add as remotes::Service>::sid()];
@@ -98,10 +98,10 @@ async fn pubsub()
 
 		peer_a.register_services( Arc::new( pubsub ) );
 
-		let  peer_a_handle = AsyncStd.spawn_handle( peer_mb_a.start(peer_a) ).expect( "Start mb" );
-		let _peer_c_handle = AsyncStd.spawn_handle( peer_mb_c.start(peer_c) ).expect( "Start mb" );
-		let _peer_d_handle = AsyncStd.spawn_handle( peer_mb_d.start(peer_d) ).expect( "Start mb" );
-		let _peer_e_handle = AsyncStd.spawn_handle( peer_mb_e.start(peer_e) ).expect( "Start mb" );
+		let  peer_a_handle = exec().spawn_handle( peer_mb_a.start(peer_a) ).expect( "Start mb" );
+		let _peer_c_handle = exec().spawn_handle( peer_mb_c.start(peer_c) ).expect( "Start mb" );
+		let _peer_d_handle = exec().spawn_handle( peer_mb_d.start(peer_d) ).expect( "Start mb" );
+		let _peer_e_handle = exec().spawn_handle( peer_mb_e.start(peer_e) ).expect( "Start mb" );
 
 		peer_a_handle.await;
 
@@ -115,9 +115,9 @@ async fn pubsub()
 	}.instrument( span_relay2 );
 
 
-	let consumer_c = consumer( "task_c", cb, 15, AsyncStd.instrument( span_c.clone() ) ).instrument( span_c );
-	let consumer_d = consumer( "task_d", db, 15, AsyncStd.instrument( span_d.clone() ) ).instrument( span_d );
-	let consumer_e = consumer( "task_e", eb, 15, AsyncStd.instrument( span_e.clone() ) ).instrument( span_e );
+	let consumer_c = consumer( "task_c", cb, 15, exec().instrument( span_c.clone() ) ).instrument( span_c );
+	let consumer_d = consumer( "task_d", db, 15, exec().instrument( span_d.clone() ) ).instrument( span_d );
+	let consumer_e = consumer( "task_e", eb, 15, exec().instrument( span_e.clone() ) ).instrument( span_e );
 
 	let relays     = relays  ;
 	let provider   = provider.instrument( tracing::info_span!("task_provider") );
@@ -125,17 +125,17 @@ async fn pubsub()
 
 	let mut futs = futures::stream::FuturesUnordered::new();
 
-	futs.push( AsyncStd.spawn_handle( consumer_c ).unwrap() );
-	futs.push( AsyncStd.spawn_handle( consumer_d ).unwrap() );
-	futs.push( AsyncStd.spawn_handle( consumer_e ).unwrap() );
-	futs.push( AsyncStd.spawn_handle( relays     ).unwrap() );
-	futs.push( AsyncStd.spawn_handle( provider   ).unwrap() );
+	futs.push( exec().spawn_handle( consumer_c ).unwrap() );
+	futs.push( exec().spawn_handle( consumer_d ).unwrap() );
+	futs.push( exec().spawn_handle( consumer_e ).unwrap() );
+	futs.push( exec().spawn_handle( relays     ).unwrap() );
+	futs.push( exec().spawn_handle( provider   ).unwrap() );
 
 	while let Some(_) = futs.next().await {}
 }
 
 
-async fn consumer( name: &str, endpoint: Endpoint, expect: i64, exec: tracing_futures::Instrumented<AsyncStd> )
+async fn consumer( name: &str, endpoint: Endpoint, expect: i64, exec: tracing_futures::Instrumented<Exec> )
 {
 	// Create mailbox for our handler
 	//
@@ -226,7 +226,7 @@ async fn pubsub_rt()
 
 	let provider = async move
 	{
-		let exec = AsyncStd.instrument( span_provider );
+		let exec = exec().instrument( span_provider );
 
 		debug!( "start mailbox for provider_to_relay" );
 
@@ -263,7 +263,7 @@ async fn pubsub_rt()
 
 	let relays = async move
 	{
-		let exec = AsyncStd.instrument( span_relay );
+		let exec = exec().instrument( span_relay );
 
 
 		let services = vec![<Add as remotes::Service>::sid()];
@@ -295,10 +295,10 @@ async fn pubsub_rt()
 
 
 
-		let  peer_a_handle = AsyncStd.spawn_handle( peer_mb_a.start(peer_a) ).expect( "Start mb" );
-		let _peer_c_handle = AsyncStd.spawn_handle( peer_mb_c.start(peer_c) ).expect( "Start mb" );
-		let _peer_d_handle = AsyncStd.spawn_handle( peer_mb_d.start(peer_d) ).expect( "Start mb" );
-		let _peer_e_handle = AsyncStd.spawn_handle( peer_mb_e.start(peer_e) ).expect( "Start mb" );
+		let  peer_a_handle = exec().spawn_handle( peer_mb_a.start(peer_a) ).expect( "Start mb" );
+		let _peer_c_handle = exec().spawn_handle( peer_mb_c.start(peer_c) ).expect( "Start mb" );
+		let _peer_d_handle = exec().spawn_handle( peer_mb_d.start(peer_d) ).expect( "Start mb" );
+		let _peer_e_handle = exec().spawn_handle( peer_mb_e.start(peer_e) ).expect( "Start mb" );
 
 			let peer_addr_c2 = peer_addr_c.clone_box();
 			subscriber.send( peer_addr_c2 ).await.expect( "send on channel" );
@@ -334,9 +334,9 @@ async fn pubsub_rt()
 	}.instrument( span_relay2 );
 
 
-	let consumer_c = consumer( "task_c", cb, 15, AsyncStd.instrument( span_c.clone() ) ).instrument( span_c );
-	let consumer_d = consumer( "task_d", db, 10, AsyncStd.instrument( span_d.clone() ) ).instrument( span_d );
-	let consumer_e = consumer( "task_e", eb, 0, AsyncStd.instrument( span_e.clone() ) ).instrument( span_e );
+	let consumer_c = consumer( "task_c", cb, 15, exec().instrument( span_c.clone() ) ).instrument( span_c );
+	let consumer_d = consumer( "task_d", db, 10, exec().instrument( span_d.clone() ) ).instrument( span_d );
+	let consumer_e = consumer( "task_e", eb, 0, exec().instrument( span_e.clone() ) ).instrument( span_e );
 
 	let relays     = relays  ;
 	let provider   = provider.instrument( tracing::info_span!("task_provider") );
@@ -344,11 +344,11 @@ async fn pubsub_rt()
 
 	let mut futs = futures::stream::FuturesUnordered::new();
 
-	futs.push( AsyncStd.spawn_handle( consumer_c ).unwrap() );
-	futs.push( AsyncStd.spawn_handle( consumer_d ).unwrap() );
-	futs.push( AsyncStd.spawn_handle( consumer_e ).unwrap() );
-	futs.push( AsyncStd.spawn_handle( relays     ).unwrap() );
-	futs.push( AsyncStd.spawn_handle( provider   ).unwrap() );
+	futs.push( exec().spawn_handle( consumer_c ).unwrap() );
+	futs.push( exec().spawn_handle( consumer_d ).unwrap() );
+	futs.push( exec().spawn_handle( consumer_e ).unwrap() );
+	futs.push( exec().spawn_handle( relays     ).unwrap() );
+	futs.push( exec().spawn_handle( provider   ).unwrap() );
 
 	while let Some(_) = futs.next().await {}
 }
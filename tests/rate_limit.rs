@@ -0,0 +1,138 @@
+// Tests:
+//
+// ✔ calls over the connection limit are answered with RateLimited.
+// ✔ sends over the service limit are dropped with an event, other services are not affected.
+// ✔ exceeding the hard limit closes the connection.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq } } ,
+	std    :: { num::NonZeroU32             } ,
+};
+
+
+fn limit( rate: u32, burst: u32 ) -> RateLimit
+{
+	RateLimit::new( NonZeroU32::new( rate ).unwrap(), NonZeroU32::new( burst ).unwrap() )
+}
+
+
+// Start a peer exposing Add and Show, let the caller set rate limits before the mailbox starts.
+//
+async fn limited_peer( socket: Endpoint, setup: impl FnOnce( &mut Peer ) ) -> (Addr<Peer>, Events<PeerEvent>)
+{
+	let (peer_addr, peer_mb) = Addr::builder().name( "peera".into() ).build();
+
+	let mut peer = Peer::from_async_read( peer_addr.clone(), socket, 1024, exec(), None, None ).expect( "spawn peer" );
+	let evts     = peer.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+
+	peer.register_services( Arc::new( add_show_sum() ) );
+
+	setup( &mut peer );
+
+	exec().spawn( peer_mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
+
+	(peer_addr, evts)
+}
+
+
+
+// Calls over the connection limit are answered with RateLimited.
+//
+#[async_std::test]
+//
+async fn rate_limit_call()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_peera, _) = limited_peer( server, |peer|
+	{
+		peer.set_rate_limit( limit( 1, 2 ) ).expect( "set rate limit" );
+
+	}).await;
+
+	let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( peera.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+	assert_matches!
+	(
+		addr.call( Show ).await,
+		Err( PeerErr::Remote{ err: ConnectionError::RateLimited{..}, .. } )
+	);
+
+	peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+}
+
+
+
+// Sends over the service limit are dropped with an event, other services are not affected.
+//
+#[async_std::test]
+//
+async fn rate_limit_send()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_peera, mut evts) = limited_peer( server, |peer|
+	{
+		let sid = <Add as remotes::Service>::sid();
+
+		peer.set_service_rate_limit( sid, limit( 1, 2 ) ).expect( "set rate limit" );
+
+	}).await;
+
+	let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( peera.clone() );
+
+	for _ in 0..3
+	{
+		addr.send( Add(1) ).await.expect( "send Add" );
+	}
+
+	assert_matches!( evts.next().await, Some( PeerEvent::Error( PeerErr::RateLimited{..} ) ) );
+
+	assert_eq!( Ok(2), addr.call( Show ).await );
+
+	peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+}
+
+
+
+// Exceeding the hard limit closes the connection.
+//
+#[async_std::test]
+//
+async fn rate_limit_hard()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_peera, mut evts) = limited_peer( server, |peer|
+	{
+		let hard = limit( 1, 1 ).close_after( NonZeroU32::new( 1 ).unwrap() );
+
+		peer.set_rate_limit( hard ).expect( "set rate limit" );
+
+	}).await;
+
+	let (peera, mut peera_evts) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( peera.clone() );
+
+	for _ in 0..3
+	{
+		addr.send( Add(1) ).await.expect( "send Add" );
+	}
+
+	assert_matches!( evts.next().await, Some( PeerEvent::Error( PeerErr::RateLimited{..} ) ) );
+	assert_matches!( evts.next().await, Some( PeerEvent::Error( PeerErr::RateLimited{..} ) ) );
+	assert_eq!     ( Some( PeerEvent::Closed ), evts.next().await );
+
+	assert_eq!( Some( PeerEvent::ClosedByRemote ), peera_evts.next().await );
+}
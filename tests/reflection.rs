@@ -0,0 +1,130 @@
+// Tests:
+//
+// ✔ list the services of a remote with their names.
+// ✔ Reflection::Full adds type names.
+// ✔ reflection is disabled by default, the remote answers UnknownService.
+// ✔ RemoteAddr::is_reachable tells which services the remote exposes.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+// Start a peer exposing Add and Show with the given reflection setting.
+//
+fn reflecting_peer( socket: Endpoint, reflection: Reflection ) -> Addr<Peer>
+{
+	let (peer_addr, peer_mb) = Addr::builder().name( "peera".into() ).build();
+
+	let mut peer = Peer::from_async_read( peer_addr.clone(), socket, 1024, exec(), None, None ).expect( "spawn peer" );
+
+	peer.register_services( Arc::new( add_show_sum() ) );
+	peer.set_reflection( reflection );
+
+	exec().spawn( peer_mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
+
+	peer_addr
+}
+
+
+
+// List the services of a remote with their names.
+//
+#[async_std::test]
+//
+async fn reflection_list()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let _peera = reflecting_peer( server, Reflection::Services );
+
+	let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let services = ServiceInfo::list_remote( &mut peera ).await.expect( "list services" );
+
+	let mut names: Vec<&str> = services.iter().map( |s| s.name.as_deref().expect( "name" ) ).collect();
+	names.sort_unstable();
+
+	assert_eq!( vec![ "remotes::Add", "remotes::Show" ], names );
+	assert!( services.iter().all( |s| s.types.is_none() ) );
+
+	assert!( services.iter().any( |s| s.sid == <Add  as remotes::Service>::sid() ) );
+	assert!( services.iter().any( |s| s.sid == <Show as remotes::Service>::sid() ) );
+
+	peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+}
+
+
+
+// Reflection::Full adds type names.
+//
+#[async_std::test]
+//
+async fn reflection_full()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let _peera = reflecting_peer( server, Reflection::Full );
+
+	let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let services = ServiceInfo::list_remote( &mut peera ).await.expect( "list services" );
+
+	let show = services.iter()
+
+		.find( |s| s.sid == <Show as remotes::Service>::sid() )
+		.expect( "Show service" )
+		.types.clone()
+		.expect( "type info" )
+	;
+
+	assert!  ( show.message.ends_with( "Show" ) );
+	assert_eq!( "i64", show.returns );
+
+	peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+}
+
+
+
+// Reflection is disabled by default, the remote answers UnknownService.
+//
+#[async_std::test]
+//
+async fn reflection_disabled()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let _peera = reflecting_peer( server, Reflection::default() );
+
+	let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	assert_matches!
+	(
+		ServiceInfo::list_remote( &mut peera ).await,
+		Err( PeerErr::Remote{ err: ConnectionError::UnknownService{..}, .. } )
+	);
+
+	peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+}
+
+
+
+// RemoteAddr::is_reachable tells which services the remote exposes.
+//
+#[async_std::test]
+//
+async fn reflection_reachable()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let _peera = reflecting_peer( server, Reflection::Services );
+
+	let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( peera.clone() );
+
+	assert_eq!( Ok(true ), addr.is_reachable::<Add>().await );
+	assert_eq!( Ok(false), addr.is_reachable::<Sub>().await );
+
+	peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+}
@@ -32,7 +32,7 @@ async fn relay_once()
 		// Create mailbox for our handler
 		//
 		debug!( "start mailbox for handler" );
-		let addr_handler = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+		let addr_handler = Addr::builder().start( Sum(0), &exec() ).expect( "spawn actor mailbox" );
 
 
 		// register Sum with peer as handler for Add and Show
@@ -46,7 +46,7 @@ async fn relay_once()
 		// get a framed connection
 		//
 		debug!( "start mailbox for provider" );
-		let (peer_addr, _peer_evts, handle) = peer_listen( ab, Arc::new( sm ), AsyncStd, "provider" ).await;
+		let (peer_addr, _peer_evts, handle) = peer_listen( ab, Arc::new( sm ), exec(), "provider" ).await;
 
 		drop( peer_addr );
 
@@ -62,7 +62,7 @@ async fn relay_once()
 	{
 		debug!( "start mailbox for consumer_to_relay" );
 
-		let (mut to_relay, _)  = peer_connect( cb, AsyncStd, "consumer_to_relay" ).await;
+		let (mut to_relay, _)  = peer_connect( cb, exec(), "consumer_to_relay" ).await;
 
 		// Call the service and receive the response
 		//
@@ -84,7 +84,7 @@ async fn relay_once()
 
 	let relays = async move
 	{
-		relay( ba, bc, Box::pin( consumer ), true, AsyncStd ).await;
+		relay( ba, bc, Box::pin( consumer ), true, exec() ).await;
 
 		warn!( "relays end" );
 	};
@@ -112,7 +112,7 @@ async fn relay_multi()
 	{
 		// get a framed connection
 		//
-		let (_, _, handle) = peer_listen( ab, Arc::new( add_show_sum() ), AsyncStd, "provider" ).await;
+		let (_, _, handle) = peer_listen( ab, Arc::new( add_show_sum() ), exec(), "provider" ).await;
 
 		handle.await;
 
@@ -124,7 +124,7 @@ async fn relay_multi()
 
 	let consumer = async move
 	{
-		let (mut relay, _)  = peer_connect( fe, AsyncStd, "consumer_to_relay" ).await;
+		let (mut relay, _)  = peer_connect( fe, exec(), "consumer_to_relay" ).await;
 
 		// Call the service and receive the response
 		//
@@ -142,10 +142,10 @@ async fn relay_multi()
 
 	let relays = async move
 	{
-		let  relay4 = relay( ed, ef, Box::pin( consumer ), true, AsyncStd )       ;
-		let  relay3 = relay( dc, de, Box::pin( relay4   ), true, AsyncStd )       ;
-		let  relay2 = relay( cb, cd, Box::pin( relay3   ), true, AsyncStd )       ;
-		              relay( ba, bc, Box::pin( relay2   ), true, AsyncStd ).await ;
+		let  relay4 = relay( ed, ef, Box::pin( consumer ), true, exec() )       ;
+		let  relay3 = relay( dc, de, Box::pin( relay4   ), true, exec() )       ;
+		let  relay2 = relay( cb, cd, Box::pin( relay3   ), true, exec() )       ;
+		              relay( ba, bc, Box::pin( relay2   ), true, exec() ).await ;
 	};
 
 	join( provider, relays ).await;
@@ -168,7 +168,7 @@ async fn relay_unknown_service()
 	{
 		// get a framed connection
 		//
-		let (_, _, handle) = peer_listen( ab, Arc::new( add_show_sum() ), AsyncStd, "provider" ).await;
+		let (_, _, handle) = peer_listen( ab, Arc::new( add_show_sum() ), exec(), "provider" ).await;
 
 		handle.await;
 
@@ -180,7 +180,7 @@ async fn relay_unknown_service()
 
 	let consumer = async move
 	{
-		let (mut relay, _relay_evts) = peer_connect( cb, AsyncStd, "consumer_to_relay" ).await;
+		let (mut relay, _relay_evts) = peer_connect( cb, exec(), "consumer_to_relay" ).await;
 
 		// Create some random data that shouldn't deserialize
 		//
@@ -209,10 +209,10 @@ async fn relay_unknown_service()
 	};
 
 
-	let relay = relay( ba, bc, Box::pin( consumer ), true, AsyncStd );
+	let relay = relay( ba, bc, Box::pin( consumer ), true, exec() );
 
-	let provi_handle = AsyncStd.spawn_handle( provider ).expect( "Spawn provider"  );
-	let relay_handle = AsyncStd.spawn_handle( relay    ).expect( "Spawn relays"  );
+	let provi_handle = exec().spawn_handle( provider ).expect( "Spawn provider"  );
+	let relay_handle = exec().spawn_handle( relay    ).expect( "Spawn relays"  );
 
 	join( provi_handle, relay_handle ).await;
 }
@@ -21,8 +21,8 @@ async fn relay_once_load_balance()
 
 	let (bc, cb) = Endpoint::pair( 64, 64 );
 
-	let (provider_cx , provider_handle ) = provider( Some( "provider1".into() ), AsyncStd ).await;
-	let (provider_cx2, provider_handle2) = provider( Some( "provider1".into() ), AsyncStd ).await;
+	let (provider_cx , provider_handle ) = provider( Some( "provider1".into() ), exec() ).await;
+	let (provider_cx2, provider_handle2) = provider( Some( "provider1".into() ), exec() ).await;
 
 
 	// --------------------------------------
@@ -31,7 +31,7 @@ async fn relay_once_load_balance()
 	{
 		debug!( "start mailbox for consumer_to_relay" );
 
-		let (mut to_relay, _)  = peer_connect( cb, AsyncStd, "consumer_to_relay" ).await;
+		let (mut to_relay, _)  = peer_connect( cb, exec(), "consumer_to_relay" ).await;
 
 		// Call the service and receive the response
 		//
@@ -69,7 +69,7 @@ async fn relay_once_load_balance()
 
 	let relays = async move
 	{
-		relay_closure( vec![ provider_cx, provider_cx2 ], bc, Box::pin( consumer ), true, AsyncStd ).await;
+		relay_closure( vec![ provider_cx, provider_cx2 ], bc, Box::pin( consumer ), true, exec() ).await;
 
 		warn!( "relays end" );
 	};
@@ -96,9 +96,9 @@ async fn debug()
 	let (peer_addr, mb) = Addr::builder().name( "relay_to_consumer".into() ).build();
 	let id              = peer_addr.id()                                            ;
 
-	let peer = Peer::from_async_read( peer_addr.clone(), cx, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+	let peer = Peer::from_async_read( peer_addr.clone(), cx, 1024, exec(), None, None ).expect( "spawn peer" );
 
-	AsyncStd.spawn( mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
+	exec().spawn( mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
 
 
 	let add  = <Add  as remotes::Service>::sid();
@@ -5,6 +5,11 @@
 // - ✔ Verify that the same      service, in the same      namespace but in different servicemap has identical sid
 // - ✔ Test clone.
 // - ✔ Test Debug.
+// - ✔ An explicit name gives the sid of that name, so a service can keep it's sid when renamed.
+// - ✔ An explicit sid is used as is.
+// - ✔ Incoming messages for an alias are delivered to the service.
+// - ✔ Aliases are not listed as services, neither by the service map nor by reflection.
+// - ✔ Two services with the same sid make Services::new panic.
 // - Test ServiceID::Debug
 // - Test adding services at runtime.
 //
@@ -47,6 +52,46 @@ mod b
 	);
 }
 
+mod c
+{
+	use crate::*;
+
+	service_map!
+	(
+		namespace  : renamed ;
+		wire_format: ThesWF  ;
+
+		services:
+
+			#[ name = "remotes::Add" ] Add,
+			#[ sid  = 0x1234         ] Sub;
+	);
+
+
+	service_map!
+	(
+		namespace  : migrated ;
+		wire_format: ThesWF   ;
+
+		services:
+
+			#[ alias = "remotes::Add"  ] Add,
+			#[ alias = "remotes::Show" ] Show;
+	);
+
+
+	service_map!
+	(
+		namespace  : dup    ;
+		wire_format: ThesWF ;
+
+		services:
+
+			#[ name = "dup::Same" ] Add,
+			#[ name = "dup::Same" ] Show;
+	);
+}
+
 
 // Verify that the same service, in a different namespace has different service id.
 //
@@ -151,3 +196,114 @@ async fn debug_service_id()
 }
 
 
+
+
+
+// An explicit name gives the sid of that name, so a service can keep it's sid when renamed.
+//
+#[test]
+//
+fn sid_explicit_name()
+{
+	assert_eq!( <Add as a::remotes::Service>::sid(), <Add as c::renamed::Service>::sid() );
+}
+
+
+
+// An explicit sid is used as is.
+//
+#[test]
+//
+fn sid_explicit()
+{
+	assert_eq!( ServiceID::from( 0x1234 ), <Sub as c::renamed::Service>::sid() );
+}
+
+
+
+// Incoming messages for an alias are delivered to the service.
+//
+#[async_std::test]
+//
+async fn sid_alias()
+{
+	assert_ne!( <Add as a::remotes::Service>::sid(), <Add as c::migrated::Service>::sid() );
+
+	let handler = Addr::builder().start( Sum(0), &exec() ).expect( "spawn actor mailbox" );
+
+	let mut sm = c::migrated::Services::new();
+
+	sm.register_handler::<Add >( handler.clone_box() );
+	sm.register_handler::<Show>( handler.clone_box() );
+
+	let server: PeerBuilder = PeerBuilder::new().name( "server" ).service_map( Arc::new( sm ) );
+	let client: PeerBuilder = PeerBuilder::new().name( "client" );
+
+	let (_server, mut client) = Peer::pair( server, client, 8, 8, exec() ).expect( "build pair" );
+
+	// The client still uses the old sids.
+	//
+	let mut addr = remotes::RemoteAddr::new( client.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Aliases are not listed as services, neither by the service map nor by reflection.
+//
+#[async_std::test]
+//
+async fn sid_alias_reflection()
+{
+	let handler = Addr::builder().start( Sum(0), &exec() ).expect( "spawn actor mailbox" );
+
+	let mut sm = c::migrated::Services::new();
+
+	sm.register_handler::<Add >( handler.clone_box() );
+	sm.register_handler::<Show>( handler.clone_box() );
+
+	let mut services: Vec<ServiceID> = sm.services().copied().collect();
+	let mut aliases : Vec<ServiceID> = sm.aliases ().copied().collect();
+
+	services.sort_by_key( |sid| -> u64 { (*sid).into() } );
+	aliases .sort_by_key( |sid| -> u64 { (*sid).into() } );
+
+	let mut expect_services = vec![ <Add as c::migrated::Service>::sid(), <Show as c::migrated::Service>::sid() ];
+	let mut expect_aliases  = vec![ <Add as a::remotes ::Service>::sid(), <Show as a::remotes ::Service>::sid() ];
+
+	expect_services.sort_by_key( |sid| -> u64 { (*sid).into() } );
+	expect_aliases .sort_by_key( |sid| -> u64 { (*sid).into() } );
+
+	assert_eq!( expect_services, services );
+	assert_eq!( expect_aliases , aliases  );
+
+	let server: PeerBuilder = PeerBuilder::new().name( "server" ).service_map( Arc::new( sm ) ).reflection( Reflection::Services );
+	let client: PeerBuilder = PeerBuilder::new().name( "client" );
+
+	let (_server, mut client) = Peer::pair( server, client, 8, 8, exec() ).expect( "build pair" );
+
+	let listed: Vec<ServiceID> = ServiceInfo::list_remote( &mut client ).await.expect( "list services" )
+
+		.iter().map( |s| s.sid ).collect()
+	;
+
+	assert_eq!( expect_services, listed );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Two services with the same sid make Services::new panic.
+//
+#[test]
+#[ should_panic( expected = "have the same sid" ) ]
+//
+fn sid_duplicate()
+{
+	let _sm = c::dup::Services::new();
+}
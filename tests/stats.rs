@@ -0,0 +1,165 @@
+// Tests:
+//
+// ✔ frames, bytes, calls and sends are counted on both sides, overall and per service.
+// ✔ errors are counted by kind and per service, and show up in the Prometheus output.
+// ✔ errors about services the peer neither exposes nor calls are counted once for the peer, not per service.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+
+// Frames, bytes, calls and sends are counted on both sides, overall and per service.
+//
+#[async_std::test]
+//
+async fn stats_counters()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut peera, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "peera" ).await;
+
+	let (mut peerb, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( peerb.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	addr.send( Add(1) ).await.expect( "send Add" );
+	assert_eq!( Ok(6) , addr.call( Show   ).await );
+
+	let add  = <Add  as remotes::Service>::sid();
+	let show = <Show as remotes::Service>::sid();
+
+	let client = peerb.call( GetStats ).await.expect( "get stats" );
+	let server = peera.call( GetStats ).await.expect( "get stats" );
+
+	assert_eq!( 3, client.frames_out );
+	assert_eq!( 2, client.frames_in  );
+	assert_eq!( 2, client.calls_out  );
+	assert_eq!( 1, client.sends_out  );
+	assert_eq!( 0, client.in_flight  );
+
+	assert_eq!( 1, client.services[ &add  ].calls_out           );
+	assert_eq!( 1, client.services[ &add  ].sends_out           );
+	assert_eq!( 1, client.services[ &show ].call_latency.count  );
+
+	assert_eq!( 3                , server.frames_in );
+	assert_eq!( client.bytes_out , server.bytes_in  );
+	assert_eq!( client.bytes_in  , server.bytes_out );
+	assert_eq!( 2                , server.calls_in  );
+	assert_eq!( 1                , server.sends_in  );
+
+	assert_eq!( 1, server.services[ &add  ].sends_in              );
+	assert_eq!( 1, server.services[ &show ].handle_latency.count  );
+
+	peerb.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+
+	// Let the mailbox of peera end.
+	//
+	drop( peera );
+	handle.await;
+}
+
+
+
+// An actor that panics when asked to show its value.
+//
+#[ derive( Actor ) ]
+//
+struct Broken;
+
+
+impl Handler< Show > for Broken
+{
+	#[async_fn] fn handle( &mut self, _msg: Show ) -> i64
+	{
+		panic!( "actor is broken" );
+	}
+}
+
+
+
+// Errors are counted by kind and per service, and show up in the Prometheus output.
+//
+#[async_std::test]
+//
+async fn stats_errors()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	// Show panics in the handler, so the error concerns a service peera exposes.
+	//
+	let broken = Addr::builder().start( Broken, &exec() ).expect( "spawn actor mailbox" );
+	let mut sm = remotes::Services::new();
+
+	sm.register_handler::<Show>( broken.clone_box() );
+
+	let (mut peera, _, handle) = peer_listen( server, Arc::new( sm ), exec(), "peera" ).await;
+
+	let (mut peerb, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( peerb.clone() );
+
+	assert!( addr.call( Show ).await.is_err() );
+
+	let stats = peera.call( GetStats ).await.expect( "get stats" );
+	let show  = <Show as remotes::Service>::sid();
+
+	assert_eq!( Some( &1 ), stats.errors.get( "HandlerDead" ) );
+	assert_eq!( 1         , stats.services[ &show ].errors    );
+	assert_eq!( 0         , stats.unknown_sid_errors          );
+
+	let text = stats.to_prometheus();
+
+	assert!( text.contains( "# TYPE thespis_peer_errors_total counter" ) );
+	assert!( text.contains( "kind=\"HandlerDead\"} 1"                  ) );
+	assert!( text.contains( "peer_name=\"peera\""                      ) );
+	assert!( text.contains( "service=\"remotes::Show\""                ) );
+
+	peerb.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+
+	// Let the mailbox of peera end.
+	//
+	drop( peera );
+	handle.await;
+}
+
+
+
+// Errors about services the peer neither exposes nor calls are counted once for the peer, not per service.
+//
+#[async_std::test]
+//
+async fn stats_unknown_sid()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut peera, _, handle) = peer_listen( server, Arc::new( add_show_sum() ), exec(), "peera" ).await;
+
+	let (mut peerb, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( peerb.clone() );
+
+	// Sub is not exposed by peera.
+	//
+	assert!( addr.call( Sub(1) ).await.is_err() );
+	assert!( addr.call( Sub(2) ).await.is_err() );
+
+	let stats = peera.call( GetStats ).await.expect( "get stats" );
+	let sub   = <Sub as remotes::Service>::sid();
+
+	assert_eq!( Some( &2 ), stats.errors.get( "UnknownService" ) );
+	assert_eq!( 2         , stats.unknown_sid_errors             );
+	assert!   ( !stats.services.contains_key( &sub )             );
+
+	let text = stats.to_prometheus();
+
+	assert!( text.contains( "thespis_peer_unknown_sid_errors_total{peer_id=" ) );
+	assert!( !text.contains( "service=\"remotes::Sub\""                     ) );
+
+	peerb.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+
+	drop( peera );
+	handle.await;
+}
@@ -0,0 +1,329 @@
+// Tests:
+//
+// ✔ receive all items of a streaming response in order.
+// ✔ an empty stream ends without error.
+// ✔ streaming responses and regular calls on the same connection.
+// ✔ a stream that isn't polled doesn't hold up calls on the same peer.
+// ✔ a stream that doesn't start before the timeout yields PeerErr::Timeout.
+// ✔ dropping a stream while chunks are in flight frees the window and the backpressure slot of the remote.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	serde         :: { Serialize, Deserialize      } ,
+	futures_timer :: { Delay                       } ,
+};
+
+
+#[ derive( Serialize, Deserialize, Debug ) ] pub struct Count( pub u64 );
+
+impl Message for Count { type Return = ServiceStream<u64>; }
+
+
+#[ derive(Actor) ] struct Counter;
+
+impl Handler<Count> for Counter
+{
+	#[async_fn] fn handle( &mut self, msg: Count ) -> ServiceStream<u64>
+	{
+		futures::stream::iter( 0..msg.0 ).boxed()
+	}
+}
+
+
+#[ derive(Actor) ] struct SlowCounter;
+
+impl Handler<Count> for SlowCounter
+{
+	#[async_fn] fn handle( &mut self, msg: Count ) -> ServiceStream<u64>
+	{
+		Delay::new( Duration::from_millis(100) ).await;
+
+		futures::stream::iter( 0..msg.0 ).boxed()
+	}
+}
+
+
+service_map!
+(
+	namespace  : streams   ;
+	wire_format: ThesWF    ;
+	services   : Add, Show ;
+	streams    : Count     ;
+);
+
+
+fn counter_sm() -> streams::Services
+{
+	let counter = Addr::builder().start( Counter, &exec() ).expect( "spawn actor mailbox" );
+	let sum     = Addr::builder().start( Sum(0) , &exec() ).expect( "spawn actor mailbox" );
+
+	let mut sm = streams::Services::new();
+
+	sm.register_stream_handler::<Count>( counter.clone_box() );
+	sm.register_handler::<Add >( sum.clone_box() );
+	sm.register_handler::<Show>( sum.clone_box() );
+
+	sm
+}
+
+
+
+// Receive all items of a streaming response in order.
+//
+#[async_std::test]
+//
+async fn stream_basic()
+{
+	// flexi_logger::Logger::with_str( "trace" ).start().unwrap();
+
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let peera = async move
+	{
+		let (_, _, handle) = peer_listen( server, Arc::new( counter_sm() ), exec(), "peera" ).await;
+
+		handle.await;
+
+		trace!( "end of peera" );
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+		let mut addr = streams::RemoteAddr::new( peera.clone() );
+
+		let stream = addr.call_stream( Count(5) ).await.expect( "call_stream" );
+		let items: Vec<u64> = stream.map( |item| item.expect( "stream item" ) ).collect().await;
+
+		assert_eq!( vec![ 0, 1, 2, 3, 4 ], items );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
+
+
+
+// An empty stream ends without error.
+//
+#[async_std::test]
+//
+async fn stream_empty()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let peera = async move
+	{
+		let (_, _, handle) = peer_listen( server, Arc::new( counter_sm() ), exec(), "peera" ).await;
+
+		handle.await;
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+		let mut addr = streams::RemoteAddr::new( peera.clone() );
+
+		let stream = addr.call_stream( Count(0) ).await.expect( "call_stream" );
+		let items: Vec<Result<u64, PeerErr>> = stream.collect().await;
+
+		assert!( items.is_empty() );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
+
+
+
+// Streaming responses and regular calls on the same connection.
+//
+#[async_std::test]
+//
+async fn stream_and_call()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let peera = async move
+	{
+		let (_, _, handle) = peer_listen( server, Arc::new( counter_sm() ), exec(), "peera" ).await;
+
+		handle.await;
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+		let mut addr  = streams::RemoteAddr::new( peera.clone() );
+		let mut addr2 = addr.clone();
+
+		let mut stream = addr.call_stream( Count(3) ).await.expect( "call_stream" );
+
+		assert_eq!( Ok(0), stream.next().await.expect( "first item" ) );
+
+		assert_eq!( Ok(()), addr2.call( Add(5) ).await );
+		assert_eq!( Ok(5) , addr2.call( Show   ).await );
+
+		assert_eq!( Ok(1), stream.next().await.expect( "second item" ) );
+		assert_eq!( Ok(2), stream.next().await.expect( "third item"  ) );
+		assert!   ( stream.next().await.is_none() );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
+
+
+
+// A stream that isn't polled doesn't hold up calls on the same peer. The remote fills the window
+// and then waits for credit, while the peer keeps processing other frames.
+//
+#[async_std::test]
+//
+async fn stream_unpolled()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let peera = async move
+	{
+		let (_, _, handle) = peer_listen( server, Arc::new( counter_sm() ), exec(), "peera" ).await;
+
+		handle.await;
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+		let mut addr  = streams::RemoteAddr::new( peera.clone() );
+		let mut addr2 = addr.clone();
+
+		// Many more items than the window of 16.
+		//
+		let stream = addr.call_stream( Count(100) ).await.expect( "call_stream" );
+
+		// Let the remote fill the window.
+		//
+		Delay::new( Duration::from_millis(50) ).await;
+
+		assert_eq!( Ok(()), addr2.call( Add(5) ).await );
+		assert_eq!( Ok(5) , addr2.call( Show   ).await );
+
+		let items: Vec<u64> = stream.map( |item| item.expect( "stream item" ) ).collect().await;
+
+		assert_eq!( (0..100).collect::<Vec<u64>>(), items );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
+
+
+
+// A stream that doesn't start before the timeout yields PeerErr::Timeout.
+//
+#[async_std::test]
+//
+async fn stream_timeout()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let peera = async move
+	{
+		let slow   = Addr::builder().start( SlowCounter, &exec() ).expect( "spawn actor mailbox" );
+		let mut sm = streams::Services::new();
+
+		sm.register_stream_handler::<Count>( slow.clone_box() );
+
+		let (_, _, handle) = peer_listen( server, Arc::new( sm ), exec(), "peera" ).await;
+
+		handle.await;
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, peer_mb) = Addr::builder().name( "timeout client".into() ).build();
+
+		let mut peer = Peer::from_async_read( peera.clone(), client, 1024, exec(), None, None ).expect( "spawn peer" );
+
+		peer.set_timeout( Duration::from_millis( 10 ) );
+
+		exec().spawn( peer_mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
+
+		let mut addr   = streams::RemoteAddr::new( peera.clone() );
+		let mut stream = addr.call_stream( Count(3) ).await.expect( "call_stream" );
+
+		assert_matches!( stream.next().await, Some( Err( PeerErr::Timeout{..} ) ) );
+		assert!( stream.next().await.is_none() );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
+
+
+
+// Dropping a stream while chunks are in flight frees the window and the backpressure slot of the remote.
+// The chunks that arrive after the drop find no consumer, the remote must still be told to stop.
+//
+#[async_std::test]
+//
+async fn stream_dropped()
+{
+	let server: PeerBuilder = PeerBuilder::new()
+
+		.name        ( "server"                            )
+		.backpressure( Arc::new( BackPressure::new( 4 ) ) )
+		.service_map ( Arc::new( counter_sm() )            )
+	;
+
+	let client: PeerBuilder = PeerBuilder::new().name( "client" );
+
+	let (mut server, mut client) = Peer::pair( server, client, 64, 64, exec() ).expect( "build pair" );
+
+	let mut addr = streams::RemoteAddr::new( client.clone() );
+
+	for _ in 0..8
+	{
+		let mut stream = addr.call_stream( Count(1000) ).await.expect( "call_stream" );
+
+		assert_eq!( Some( Ok(0) ), stream.next().await );
+	}
+
+	// If a window stays open, the slot of it's task is never returned.
+	//
+	let mut available = None;
+
+	for _ in 0..100
+	{
+		available = server.call( GetStats ).await.expect( "get stats" ).backpressure;
+
+		if available == Some(4) { break }
+
+		Delay::new( Duration::from_millis(20) ).await;
+	}
+
+	assert_eq!( Some(4), available );
+
+	// And the remote still processes requests.
+	//
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
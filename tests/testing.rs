@@ -0,0 +1,184 @@
+#![ cfg( feature = "testing" ) ]
+
+// Tests:
+//
+// ✔ a scripted remote answers calls with canned responses and errors, and checks sends.
+// ✔ a scripted remote reports the step at which the peer sent something unexpected.
+// ✔ a mock service map records requests and answers calls.
+// ✔ a mock handler that is too slow makes the caller time out.
+// ✔ a mock handler that panics makes the call fail.
+//
+mod common;
+
+use
+{
+	common                  :: { *, import::{ *, assert_eq }                           } ,
+	serde                   :: { Serialize, Deserialize                                } ,
+	thespis_remote::testing :: { ScriptedRemote, MockServiceMap, MockHandler, Behaviour } ,
+	tsm                     :: { Service                                               } ,
+};
+
+
+#[ derive( Serialize, Deserialize, Debug, Clone, PartialEq ) ] struct Ping( u8     );
+#[ derive( Serialize, Deserialize, Debug, Clone, PartialEq ) ] struct Note( String );
+
+impl Message for Ping { type Return = u8; }
+impl Message for Note { type Return = (); }
+
+
+service_map!
+(
+	namespace  : tsm        ;
+	wire_format: ThesWF     ;
+	services   : Ping, Note ;
+);
+
+
+
+// A scripted remote answers calls with canned responses and errors, and checks sends.
+//
+#[async_std::test]
+//
+async fn scripted_remote_calls()
+{
+	let (peer, mut remote) = ScriptedRemote::connect( PeerBuilder::new(), exec() ).expect( "connect" );
+
+	remote
+
+		.expect_call( Ping::sid(), Ping(1)                   )
+		.reply      ( &2u8                                   )
+		.expect_send( Note::sid(), Note( "hi".to_string() ) )
+		.expect_call( Ping::sid(), Ping(3)                   )
+		.reply_error( ConnectionError::InternalServerError{ sid: None, cid: None } )
+	;
+
+	let mut addr = tsm::RemoteAddr::new( peer );
+
+	let client = async move
+	{
+		assert_eq!( Ok(2) , addr.call( Ping(1)                 ).await );
+		assert_eq!( Ok(()), addr.send( Note( "hi".to_string() ) ).await );
+
+		assert_matches!
+		(
+			addr.call( Ping(3) ).await,
+			Err( PeerErr::Remote{ err: ConnectionError::InternalServerError{..}, .. } )
+		);
+	};
+
+	let (script, _) = join( remote.run(), client ).await;
+
+	assert_eq!( Ok(()), script );
+}
+
+
+
+// A scripted remote reports the step at which the peer sent something unexpected.
+//
+#[async_std::test]
+//
+async fn scripted_remote_mismatch()
+{
+	let (peer, mut remote) = ScriptedRemote::connect( PeerBuilder::new(), exec() ).expect( "connect" );
+
+	remote
+
+		.expect_send( Note::sid(), Note( "a".to_string() ) )
+		.expect_send( Note::sid(), Note( "b".to_string() ) )
+	;
+
+	let mut addr = tsm::RemoteAddr::new( peer );
+
+	addr.send( Note( "a".to_string() ) ).await.expect( "send" );
+	addr.send( Note( "c".to_string() ) ).await.expect( "send" );
+
+	let err = remote.run().await.unwrap_err();
+
+	assert_eq!( 1, err.step );
+	assert!( err.problem.contains( "\"c\"" ) );
+}
+
+
+
+// A mock service map records requests and answers calls.
+//
+#[async_std::test]
+//
+async fn mock_service_map()
+{
+	let mut sm  = MockServiceMap::new( vec![ Ping::sid(), Note::sid() ] );
+	let     log = sm.log();
+
+	sm.reply( Ping::sid(), &7u8 );
+
+	let server: PeerBuilder = PeerBuilder::new().name( "server" ).service_map( Arc::new( sm ) );
+	let client: PeerBuilder = PeerBuilder::new().name( "client" );
+
+	let (_server, client) = Peer::pair( server, client, 8, 8, exec() ).expect( "build pair" );
+
+	let mut addr = tsm::RemoteAddr::new( client );
+
+	addr.send( Note( "x".to_string() ) ).await.expect( "send" );
+
+	assert_eq!( Ok(7), addr.call( Ping(1) ).await );
+
+	let log = log.lock();
+
+	assert_eq!( 2, log.len() );
+
+	assert_eq!( Some( Note( "x".to_string() ) ), log[0].decode() );
+	assert_eq!( Some( Ping(1)                 ), log[1].decode() );
+
+	assert!(  log[0].cid.is_null() );
+	assert!( !log[1].cid.is_null() );
+}
+
+
+
+// Serve a mock handler for Ping and return the client peer.
+//
+fn serve( handler: MockHandler<Ping>, timeout: Duration ) -> Addr<Peer>
+{
+	let handler = Addr::builder().start( handler, &exec() ).expect( "spawn actor mailbox" );
+	let mut sm  = tsm::Services::new();
+
+	sm.register_handler::<Ping>( handler.clone_box() );
+
+	let server: PeerBuilder = PeerBuilder::new().name( "server" ).service_map( Arc::new( sm ) );
+	let client: PeerBuilder = PeerBuilder::new().name( "client" ).timeout( timeout );
+
+	Peer::pair( server, client, 8, 8, exec() ).expect( "build pair" ).1
+}
+
+
+
+// A mock handler that is too slow makes the caller time out.
+//
+#[async_std::test]
+//
+async fn mock_handler_delay()
+{
+	let handler  = MockHandler::new( Behaviour::Delay( Duration::from_millis(200), 1 ) );
+	let received = handler.received();
+
+	let mut addr = tsm::RemoteAddr::new( serve( handler, Duration::from_millis(50) ) );
+
+	assert_matches!( addr.call( Ping(1) ).await, Err( PeerErr::Timeout{..} ) );
+
+	assert_eq!( vec![ Ping(1) ], *received.lock() );
+}
+
+
+
+// A mock handler that panics makes the call fail.
+//
+#[async_std::test]
+//
+async fn mock_handler_panic()
+{
+	let handler = MockHandler::new( Behaviour::Panic );
+
+	let mut addr = tsm::RemoteAddr::new( serve( handler, Duration::from_secs(5) ) );
+
+	assert!( addr.call( Ping(1) ).await.is_err() );
+}
@@ -52,7 +52,7 @@ async fn timeout()
 	{
 		// Create mailbox for our handler
 		//
-		let addr_handler = Addr::builder().start( Slow, &AsyncStd ).expect( "spawn actor mailbox" );
+		let addr_handler = Addr::builder().start( Slow, &exec() ).expect( "spawn actor mailbox" );
 
 		// Create a service map
 		//
@@ -64,7 +64,7 @@ async fn timeout()
 
 		// get a framed connection
 		//
-		let (_, _, handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "peera" ).await;
+		let (_, _, handle) = peer_listen( server, Arc::new( sm ), exec(), "peera" ).await;
 
 		handle.await;
 
@@ -82,7 +82,7 @@ async fn timeout()
 
 		// create peer with stream/sink + service map
 		//
-		let mut peer = Peer::from_async_read( peera.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+		let mut peer = Peer::from_async_read( peera.clone(), client, 1024, exec(), None, None ).expect( "spawn peer" );
 
 
 		// This is the relevant line for this test!
@@ -92,7 +92,7 @@ async fn timeout()
 
 		debug!( "start mailbox for [{}] in peerb", name );
 
-		AsyncStd.spawn( peer_mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
+		exec().spawn( peer_mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
 
 		// Call the service and receive the response
 		//
@@ -0,0 +1,92 @@
+#![ cfg( feature = "tokio" ) ]
+
+// Tests:
+//
+// ✔ peers over a tokio TcpStream, running on a tokio runtime, can call and send.
+// ✔ closing one side over a tokio TcpStream is seen by the other side.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq } } ,
+	tokio  :: { net::{ TcpListener, TcpStream } } ,
+};
+
+
+// Connect a server exposing add_show_sum with Peer::from_tokio_io to a client built with
+// PeerBuilder::build_tokio_io over tcp on localhost. Must be called on the tokio runtime.
+//
+async fn connect() -> (Addr<Peer>, Events<PeerEvent>, Addr<Peer>)
+{
+	let listener = TcpListener::bind( "127.0.0.1:0" ).await.expect( "bind listener" );
+	let local    = listener.local_addr().expect( "local address" );
+
+	let (client_socket, accepted) = futures::join!
+	(
+		TcpStream::connect( local ) ,
+		listener.accept()           ,
+	);
+
+	let client_socket     = client_socket.expect( "connect to listener" );
+	let (server_socket,_) = accepted     .expect( "accept connection"   );
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut server = Peer::from_tokio_io( server_addr.clone(), server_socket, 1024, exec(), None, None ).expect( "create server" );
+
+	server.register_services( Arc::new( add_show_sum() ) );
+
+	let evts = server.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+
+	exec().spawn( async { server_mb.start( server ).await; } ).expect( "start mailbox of server" );
+
+	let client = PeerBuilder::new().name( "client" ).build_tokio_io( client_socket, exec() ).expect( "build client" );
+
+	(server_addr, evts, client)
+}
+
+
+
+// Peers over a tokio TcpStream, running on a tokio runtime, can call and send.
+//
+#[test]
+//
+fn tokio_io_call()
+{
+	exec().block_on( async
+	{
+		let (_server, _evts, mut client) = connect().await;
+
+		let mut addr = remotes::RemoteAddr::new( client.clone() );
+
+		assert_eq!( Ok(()), addr.send( Add(5) ).await );
+		assert_eq!( Ok(()), addr.call( Add(3) ).await );
+		assert_eq!( Ok(8) , addr.call( Show   ).await );
+
+		client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	});
+}
+
+
+
+// Closing one side over a tokio TcpStream is seen by the other side.
+//
+#[test]
+//
+fn tokio_io_close()
+{
+	exec().block_on( async
+	{
+		let (_server, mut evts, mut client) = connect().await;
+
+		client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+		while let Some( evt ) = evts.next().await
+		{
+			if evt == PeerEvent::ClosedByRemote { return }
+		}
+
+		panic!( "server did not see the connection close" );
+	});
+}
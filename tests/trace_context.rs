@@ -0,0 +1,180 @@
+// Tests:
+//
+// ✔ the remote continues the trace sent along with a call.
+// ✔ the remote starts a new trace when none is sent.
+// ✔ requests with a trace context are processed normally.
+// ✔ a trace context is not sent to a remote that doesn't announce that it understands them.
+// ✔ a peer only announces that it understands trace contexts when it opts in.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq }, remotes::Service } ,
+	futures :: { SinkExt, channel::mpsc                      } ,
+	std     :: { io::ErrorKind                               } ,
+};
+
+
+
+// Like peer_listen, but the peer tells the remote that it understands trace contexts.
+//
+async fn traced_listen( socket: Endpoint, name: &str ) -> (Events<PeerEvent>, JoinHandle< MailboxEnd<Peer> >)
+{
+	let (peer_addr, peer_mb) = Addr::builder().name( name.into() ).build();
+
+	let mut peer = Peer::from_async_read( peer_addr, socket, 1024, exec(), None, None ).expect( "spawn peer" );
+
+	peer.enable_trace_context();
+	peer.register_services( Arc::new( add_show_sum() ) );
+
+	let evts   = peer.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+	let handle = exec().spawn_handle( peer_mb.start(peer) ).expect( "start mailbox of Peer" );
+
+	(evts, handle)
+}
+
+
+
+// Call a service peera doesn't expose and return the trace context of the error peera reports.
+//
+async fn remote_trace( trace: Option<TraceContext> ) -> Option<TraceContext>
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut server_evts, handle) = traced_listen( server, "peera" ).await;
+
+	let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	// Peera announces that it understands trace contexts before it answers this call.
+	//
+	assert_eq!( Ok(0), remotes::RemoteAddr::new( peera.clone() ).call( Show ).await );
+
+	let mut addr = remotes::RemoteAddr::new( peera.clone() ).with_trace( trace );
+
+	assert!( addr.call( Sub(1) ).await.is_err() );
+
+	let ctx = match server_evts.next().await
+	{
+		Some( PeerEvent::Error( PeerErr::UnknownService{ ctx } ) ) => ctx,
+		other => panic!( "unexpected event: {:?}", other ),
+	};
+
+	peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+
+	handle.await;
+
+	ctx.trace
+}
+
+
+
+// The remote continues the trace sent along with a call.
+//
+#[async_std::test]
+//
+async fn trace_continued()
+{
+	let trace  = TraceContext::root();
+	let remote = remote_trace( Some( trace ) ).await.expect( "trace context" );
+
+	assert_eq!( trace.trace_id, remote.trace_id  );
+	assert_ne!( trace.parent_id, remote.parent_id );
+}
+
+
+
+// The remote starts a new trace when none is sent.
+//
+#[async_std::test]
+//
+async fn trace_new()
+{
+	assert!( remote_trace( None ).await.is_some() );
+}
+
+
+
+// Requests with a trace context are processed normally.
+//
+#[async_std::test]
+//
+async fn trace_requests()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_, handle) = traced_listen( server, "peera" ).await;
+
+	let (mut peera, _) = peer_connect( client, exec(), "peer_b_to_peera" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( peera.clone() ).with_trace( TraceContext::root() );
+
+	addr.send( Add(2) ).await.expect( "send Add" );
+
+	assert_eq!( Ok(()), addr.call( Add(3) ).await );
+	assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+	peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+
+	handle.await;
+}
+
+
+
+// A trace context is not sent to a remote that doesn't announce that it understands them.
+//
+#[async_std::test]
+//
+async fn trace_unsupported()
+{
+	let (_remote_out, peer_in  ) = mpsc::channel::<ThesWF>( 8 );
+	let (peer_out, mut remote_in) = mpsc::channel::<ThesWF>( 8 );
+
+	let broken = |_| WireErr::Io{ kind: ErrorKind::BrokenPipe };
+
+	let builder: PeerBuilder = PeerBuilder::new().trace_context( true );
+	let mut peer             = builder.build( peer_in.map( Ok ), peer_out.sink_map_err( broken ), exec() ).expect( "build peer" );
+
+	let mut addr = remotes::RemoteAddr::new( peer.clone() ).with_trace( TraceContext::root() );
+
+	addr.send( Add(1) ).await.expect( "send Add" );
+
+	// The peer announces that it understands trace contexts, then sends the request without it's context.
+	//
+	let announce = remote_in.next().await.expect( "announcement" );
+	let request  = remote_in.next().await.expect( "request"      );
+
+	assert_eq!( WireType::TraceContext, announce.kind() );
+	assert_eq!( WireType::IncomingSend, request.kind()  );
+	assert_eq!( Add::sid()            , request.sid()   );
+
+	peer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// A peer only announces that it understands trace contexts when it opts in.
+//
+#[async_std::test]
+//
+async fn trace_opt_in()
+{
+	let (_remote_out, peer_in  ) = mpsc::channel::<ThesWF>( 8 );
+	let (peer_out, mut remote_in) = mpsc::channel::<ThesWF>( 8 );
+
+	let broken = |_| WireErr::Io{ kind: ErrorKind::BrokenPipe };
+
+	let builder: PeerBuilder = PeerBuilder::new();
+	let mut peer             = builder.build( peer_in.map( Ok ), peer_out.sink_map_err( broken ), exec() ).expect( "build peer" );
+
+	remotes::RemoteAddr::new( peer.clone() ).send( Add(1) ).await.expect( "send Add" );
+
+	// The request is the first frame.
+	//
+	let request = remote_in.next().await.expect( "request" );
+
+	assert_eq!( WireType::IncomingSend, request.kind() );
+	assert_eq!( Add::sid()            , request.sid()  );
+
+	peer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,178 @@
+#![ cfg( feature = "websocket" ) ]
+
+// Tests:
+//
+// ✔ peers talk over an in memory WebSocket and closing one is seen by the other.
+// ✔ a close frame from the remote closes the peer.
+// ✔ closing the peer sends a close frame with the reason to the remote.
+// ✔ a reason that doesn't fit in a close frame is cut on a char boundary.
+// ✔ text messages are refused.
+//
+mod common;
+
+use
+{
+	common                         :: { *, import::{ *, assert_eq }                                 } ,
+	futures                        :: { SinkExt                                                     } ,
+	async_tungstenite              :: { accept_async, client_async, WebSocketStream                 } ,
+	async_tungstenite::tungstenite :: { Message, protocol::{ CloseFrame, frame::coding::CloseCode } } ,
+};
+
+
+// A connected pair of WebSockets over an in memory connection.
+//
+async fn ws_pair() -> (WebSocketStream<Endpoint>, WebSocketStream<Endpoint>)
+{
+	let (server, client) = Endpoint::pair( 1024, 1024 );
+
+	let (server, client) = join( accept_async( server ), client_async( "ws://localhost", client ) ).await;
+
+	let server = server.expect( "accept websocket" );
+	let client = client.expect( "connect websocket" ).0;
+
+	(server, client)
+}
+
+
+// A peer on the server side of the WebSocket with the Sum services.
+//
+async fn server( ws: WebSocketStream<Endpoint> ) -> (Addr<Peer>, Events<PeerEvent>)
+{
+	let mut builder: PeerBuilder = PeerBuilder::new()
+
+		.name       ( "server"                   )
+		.service_map( Arc::new( add_show_sum() ) )
+	;
+
+	let evts = builder.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+	let peer = builder.build_websocket( ws, exec() ).expect( "build server peer" );
+
+	(peer, evts)
+}
+
+
+
+// Peers talk over an in memory WebSocket and closing one is seen by the other.
+//
+#[async_std::test]
+//
+async fn websocket_calls()
+{
+	let (server_ws, client_ws) = ws_pair().await;
+
+	let (_server, mut evts) = server( server_ws ).await;
+
+	let mut client = PeerBuilder::new()
+
+		.name( "client" )
+		.build_websocket( client_ws, exec() )
+		.expect( "build client peer" )
+	;
+
+	let mut addr = remotes::RemoteAddr::new( client.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	assert_eq!( Some( PeerEvent::ClosedByRemote ), evts.next().await );
+}
+
+
+
+// A close frame from the remote closes the peer.
+//
+#[async_std::test]
+//
+async fn websocket_close_frame()
+{
+	let (server_ws, mut client_ws) = ws_pair().await;
+
+	let (_server, mut evts) = server( server_ws ).await;
+
+	let frame = CloseFrame{ code: CloseCode::Away, reason: "Going away.".into() };
+
+	client_ws.close( Some( frame ) ).await.expect( "send close frame" );
+
+	assert_eq!( Some( PeerEvent::ClosedByRemote ), evts.next().await );
+}
+
+
+
+// Closing the peer sends a close frame with the reason to the remote.
+//
+#[async_std::test]
+//
+async fn websocket_close_reason()
+{
+	assert_eq!( Some( "Maintenance.".to_string() ), close_reason( "Maintenance." ).await );
+}
+
+
+
+// A reason that doesn't fit in a close frame is cut on a char boundary.
+//
+#[async_std::test]
+//
+async fn websocket_close_reason_long()
+{
+	// 2 bytes per char, so 123 bytes would split a char.
+	//
+	let reason = "é".repeat( 100 );
+
+	assert_eq!( Some( "é".repeat( 61 ) ), close_reason( &reason ).await );
+}
+
+
+
+// Close a peer with reason and return the reason of the close frame the remote receives.
+//
+async fn close_reason( reason: &str ) -> Option<String>
+{
+	let (server_ws, mut client_ws) = ws_pair().await;
+
+	let (mut server, _evts) = server( server_ws ).await;
+
+	server.send( CloseConnection{ remote: false, reason: reason.to_string() } ).await.expect( "close connection" );
+
+	// Skip the frames the peer sent before closing.
+	//
+	while let Some( msg ) = client_ws.next().await
+	{
+		match msg.expect( "receive message" )
+		{
+			Message::Binary(_) => continue,
+
+			Message::Close( frame ) =>
+			{
+				let frame = frame.expect( "close frame" );
+
+				assert_eq!( CloseCode::Normal, frame.code );
+
+				return Some( frame.reason.into_owned() );
+			}
+
+			other => panic!( "unexpected message: {:?}", other ),
+		}
+	}
+
+	None
+}
+
+
+
+// Text messages are refused.
+//
+#[async_std::test]
+//
+async fn websocket_text()
+{
+	let (server_ws, mut client_ws) = ws_pair().await;
+
+	let (_server, mut evts) = server( server_ws ).await;
+
+	client_ws.send( Message::Text( "hello".to_string() ) ).await.expect( "send text" );
+
+	assert_matches!( evts.next().await, Some( PeerEvent::Error( PeerErr::WireFormat{ source: WireErr::Deserialize{..}, .. } ) ) );
+}